@@ -26,6 +26,19 @@ pub struct ExtensionModule {
     pub init_func: unsafe extern "C" fn() -> *mut pyffi::PyObject,
 }
 
+/// Defines an extra frozen module to make available.
+#[derive(Clone, Debug)]
+pub struct FrozenModule<'a> {
+    /// Name of the module.
+    pub name: CString,
+
+    /// Marshalled code object for the module.
+    pub bytecode: &'a [u8],
+
+    /// Whether the module is a package.
+    pub is_package: bool,
+}
+
 /// Configure a Python interpreter.
 ///
 /// This type defines the configuration of a Python interpreter. It is used
@@ -82,11 +95,24 @@ pub struct OxidizedPythonInterpreterConfig<'a> {
     /// meta path importer during interpreter initialization.
     pub packed_resources: Option<&'a [u8]>,
 
+    /// Path to a standalone resources bundle file to load resources from.
+    ///
+    /// `$ORIGIN` is expanded to the directory of the current executable.
+    /// The file begins with a bundle header followed by packed resources data
+    /// in the same format as `packed_resources`. Resources are loaded from it
+    /// in addition to `packed_resources`.
+    pub packed_resources_bundle: Option<String>,
+
     /// Extra extension modules to make available to the interpreter.
     ///
     /// The values will effectively be passed to ``PyImport_ExtendInitTab()``.
     pub extra_extension_modules: Option<Vec<ExtensionModule>>,
 
+    /// Extra frozen modules to make available to the interpreter.
+    ///
+    /// The values will be appended to ``PyImport_FrozenModules``.
+    pub extra_frozen_modules: Option<Vec<FrozenModule<'a>>>,
+
     /// Whether to set sys.argvb with bytes versions of process arguments.
     ///
     /// On Windows, bytes will be UTF-16. On POSIX, bytes will be raw char*
@@ -116,6 +142,29 @@ pub struct OxidizedPythonInterpreterConfig<'a> {
     /// loaded in ``sys.modules``.
     pub write_modules_directory_env: Option<String>,
 
+    /// Whether to detect and dispatch `multiprocessing` process re-execution.
+    ///
+    /// When `multiprocessing` uses the `spawn` start method (or starts its
+    /// resource tracker or fork server), it re-executes `sys.executable` with
+    /// arguments like `--multiprocessing-fork` or `-c 'from multiprocessing...'`.
+    /// If this is set, the interpreter will recognize these command lines during
+    /// initialization and `MainPythonInterpreter.run_as_main()` will run the
+    /// `multiprocessing` entry point instead of the configured `run` mode.
+    pub multiprocessing_auto_dispatch: bool,
+
+    /// File to write `faulthandler` output to instead of stderr.
+    ///
+    /// `$ORIGIN` is expanded to the directory of the current executable.
+    /// The file is opened for appending after interpreter initialization if
+    /// `faulthandler` is enabled. It has no effect otherwise.
+    pub fault_handler_path: Option<String>,
+
+    /// Environment variables to set before interpreter initialization.
+    ///
+    /// Pairs are `(name, value)`. `$ORIGIN` in values is expanded to the
+    /// directory of the current executable.
+    pub environment_variables: Vec<(String, String)>,
+
     /// Defines what code to run by default.
     ///
     pub run: PythonRunMode,
@@ -133,12 +182,17 @@ impl<'a> Default for OxidizedPythonInterpreterConfig<'a> {
             oxidized_importer: false,
             filesystem_importer: true,
             packed_resources: None,
+            packed_resources_bundle: None,
             extra_extension_modules: None,
+            extra_frozen_modules: None,
             argvb: false,
             sys_frozen: false,
             sys_meipass: false,
             terminfo_resolution: TerminfoResolution::Dynamic,
             write_modules_directory_env: None,
+            multiprocessing_auto_dispatch: false,
+            fault_handler_path: None,
+            environment_variables: vec![],
             run: PythonRunMode::Repl,
         }
     }
@@ -179,4 +233,24 @@ impl<'a> OxidizedPythonInterpreterConfig<'a> {
 
         Ok(&self.interpreter_config.module_search_paths)
     }
+
+    /// Resolve the values to use for `program_name` and `executable`.
+    ///
+    /// `$ORIGIN` in either value is expanded to the directory of the
+    /// current executable.
+    pub fn resolve_program_paths(&mut self) -> Result<(), &'static str> {
+        let origin = self.ensure_origin()?;
+        let origin_string = origin.display().to_string();
+
+        let resolve =
+            |p: &PathBuf| PathBuf::from(p.display().to_string().replace("$ORIGIN", &origin_string));
+
+        let program_name = self.interpreter_config.program_name.as_ref().map(resolve);
+        let executable = self.interpreter_config.executable.as_ref().map(resolve);
+
+        self.interpreter_config.program_name = program_name;
+        self.interpreter_config.executable = executable;
+
+        Ok(())
+    }
 }
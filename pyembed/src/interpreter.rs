@@ -14,6 +14,7 @@ use {
     super::interpreter_config::python_interpreter_config_to_py_pre_config,
     super::osutils::resolve_terminfo_dirs,
     super::pyalloc::{make_raw_rust_memory_allocator, RawAllocator},
+    super::python_eval,
     super::python_resources::PythonResourcesState,
     cpython::{
         GILGuard, NoArgs, ObjectProtocol, PyDict, PyErr, PyList, PyObject, PyString, Python,
@@ -21,7 +22,7 @@ use {
     },
     lazy_static::lazy_static,
     python3_sys as pyffi,
-    python_packaging::interpreter::{MemoryAllocatorBackend, TerminfoResolution},
+    python_packaging::interpreter::{MemoryAllocatorBackend, PythonRunMode, TerminfoResolution},
     std::collections::BTreeSet,
     std::convert::TryInto,
     std::env,
@@ -34,6 +35,8 @@ use {
 
 #[cfg(feature = "jemalloc-sys")]
 use super::pyalloc::make_raw_jemalloc_allocator;
+#[cfg(feature = "snmalloc-sys")]
+use super::pyalloc::make_raw_snmalloc_allocator;
 use python3_sys::PyMemAllocatorEx;
 
 lazy_static! {
@@ -50,6 +53,16 @@ fn raw_jemallocator() -> pyffi::PyMemAllocatorEx {
     panic!("jemalloc is not available in this build configuration");
 }
 
+#[cfg(feature = "snmalloc-sys")]
+fn raw_snmallocator() -> pyffi::PyMemAllocatorEx {
+    make_raw_snmalloc_allocator()
+}
+
+#[cfg(not(feature = "snmalloc-sys"))]
+fn raw_snmallocator() -> pyffi::PyMemAllocatorEx {
+    panic!("snmalloc is not available in this build configuration");
+}
+
 /// Format a PyErr in a crude manner.
 ///
 /// This is meant to be called during interpreter initialization. We can't
@@ -281,8 +294,18 @@ impl<'python, 'interpreter, 'resources> MainPythonInterpreter<'python, 'interpre
         self.config
             .resolve_module_search_paths()
             .map_err(|e| NewInterpreterError::Simple(e))?;
+        self.config
+            .resolve_program_paths()
+            .map_err(|e| NewInterpreterError::Simple(e))?;
+
+        // Python reads the environment during initialization, so variables
+        // must be set before then.
+        for (name, value) in &self.config.environment_variables {
+            std::env::set_var(name, value.replace("$ORIGIN", &origin_string));
+        }
 
         set_pyimport_inittab(&self.config);
+        set_pyimport_frozen_modules(&self.config);
 
         // Pre-configure Python.
         let pre_config =
@@ -312,6 +335,9 @@ impl<'python, 'interpreter, 'resources> MainPythonInterpreter<'python, 'interpre
                         make_raw_rust_memory_allocator(),
                     ));
                 }
+                MemoryAllocatorBackend::Snmalloc => {
+                    self.raw_allocator = Some(InterpreterRawAllocator::from(raw_snmallocator()));
+                }
             }
 
             if let Some(allocator) = &self.raw_allocator {
@@ -330,6 +356,19 @@ impl<'python, 'interpreter, 'resources> MainPythonInterpreter<'python, 'interpre
             }
         }
 
+        // A process launched by multiprocessing runs the multiprocessing
+        // entry point instead of the configured run mode.
+        if self.config.multiprocessing_auto_dispatch {
+            let args = env::args_os().collect::<Vec<_>>();
+
+            if let Some(code) = python_eval::multiprocessing_dispatch_code(&args) {
+                self.config.interpreter_config.run_command = None;
+                self.config.interpreter_config.run_filename = None;
+                self.config.interpreter_config.run_module = None;
+                self.config.run = PythonRunMode::Eval { code };
+            }
+        }
+
         let mut py_config: pyffi::PyConfig = (&self.config)
             .try_into()
             .map_err(NewInterpreterError::Dynamic)?;
@@ -353,6 +392,14 @@ impl<'python, 'interpreter, 'resources> MainPythonInterpreter<'python, 'interpre
 
         let py = unsafe { Python::assume_gil_acquired() };
 
+        if !self.config.oxidized_importer
+            && matches!(self.config.run, PythonRunMode::Resource { .. })
+        {
+            return Err(NewInterpreterError::Simple(
+                "PythonRunMode::Resource requires the oxidized importer",
+            ));
+        }
+
         if self.config.oxidized_importer {
             self.resources_state = Some(Box::new(
                 PythonResourcesState::new_from_env()
@@ -360,10 +407,33 @@ impl<'python, 'interpreter, 'resources> MainPythonInterpreter<'python, 'interpre
             ));
 
             if let Some(ref mut resources_state) = self.resources_state {
+                if let Some(path) = &self.config.packed_resources_bundle {
+                    let path = PathBuf::from(path.replace("$ORIGIN", &origin_string));
+
+                    resources_state
+                        .load_bundle(&path)
+                        .map_err(|err| NewInterpreterError::Simple(err))?;
+                }
+
                 resources_state
                     .load(self.config.packed_resources)
                     .map_err(|err| NewInterpreterError::Simple(err))?;
 
+                // Unlike `Module`, `Resource` never falls back to importing
+                // the module from the filesystem.
+                if let PythonRunMode::Resource { name } = &self.config.run {
+                    if !resources_state
+                        .resources
+                        .get(name.as_str())
+                        .map_or(false, |resource| resource.is_module)
+                    {
+                        return Err(NewInterpreterError::Dynamic(format!(
+                            "run mode references Python module {} but it isn't an embedded resource",
+                            name
+                        )));
+                    }
+                }
+
                 let oxidized_importer = py.import(OXIDIZED_IMPORTER_NAME_STR).map_err(|err| {
                     NewInterpreterError::new_from_pyerr(
                         py,
@@ -426,29 +496,33 @@ impl<'python, 'interpreter, 'resources> MainPythonInterpreter<'python, 'interpre
         self.py = Some(py);
         self.interpreter_state = InterpreterState::Initialized;
 
-        // env::args() panics if arguments aren't valid Unicode. But invalid
-        // Unicode arguments are possible and some applications may want to
-        // support them.
-        //
-        // env::args_os() provides access to the raw OsString instances, which
-        // will be derived from wchar_t on Windows and char* on POSIX. We can
-        // convert these to Python str instances using a platform-specific
-        // mechanism.
-        let args_objs = env::args_os()
-            .map(|os_arg| osstr_to_pyobject(py, &os_arg, None))
-            .collect::<Result<Vec<PyObject>, &'static str>>()?;
-
-        // This will steal the pointer to the elements and mem::forget them.
-        let args = PyList::new(py, &args_objs);
-        let argv = b"argv\0";
-
-        let res = args.with_borrowed_ptr(py, |args_ptr| unsafe {
-            pyffi::PySys_SetObject(argv.as_ptr() as *const i8, args_ptr)
-        });
+        // An explicit argv in the config was applied by Python during
+        // initialization. Don't clobber it with process arguments.
+        if self.config.interpreter_config.argv.is_none() {
+            // env::args() panics if arguments aren't valid Unicode. But invalid
+            // Unicode arguments are possible and some applications may want to
+            // support them.
+            //
+            // env::args_os() provides access to the raw OsString instances, which
+            // will be derived from wchar_t on Windows and char* on POSIX. We can
+            // convert these to Python str instances using a platform-specific
+            // mechanism.
+            let args_objs = env::args_os()
+                .map(|os_arg| osstr_to_pyobject(py, &os_arg, None))
+                .collect::<Result<Vec<PyObject>, &'static str>>()?;
+
+            // This will steal the pointer to the elements and mem::forget them.
+            let args = PyList::new(py, &args_objs);
+            let argv = b"argv\0";
 
-        match res {
-            0 => (),
-            _ => return Err(NewInterpreterError::Simple("unable to set sys.argv")),
+            let res = args.with_borrowed_ptr(py, |args_ptr| unsafe {
+                pyffi::PySys_SetObject(argv.as_ptr() as *const i8, args_ptr)
+            });
+
+            match res {
+                0 => (),
+                _ => return Err(NewInterpreterError::Simple("unable to set sys.argv")),
+            }
         }
 
         if self.config.argvb {
@@ -505,6 +579,41 @@ impl<'python, 'interpreter, 'resources> MainPythonInterpreter<'python, 'interpre
             }
         }
 
+        if let Some(path) = &self.config.fault_handler_path {
+            let faulthandler = py.import("faulthandler").map_err(|err| {
+                NewInterpreterError::new_from_pyerr(py, err, "importing faulthandler")
+            })?;
+
+            let enabled = faulthandler
+                .call(py, "is_enabled", NoArgs, None)
+                .and_then(|v| v.is_true(py))
+                .map_err(|err| {
+                    NewInterpreterError::new_from_pyerr(py, err, "faulthandler.is_enabled()")
+                })?;
+
+            if enabled {
+                let path = path.replace("$ORIGIN", &origin_string);
+
+                let io = py
+                    .import("io")
+                    .map_err(|err| NewInterpreterError::new_from_pyerr(py, err, "importing io"))?;
+                let fh = io.call(py, "open", (path, "a"), None).map_err(|err| {
+                    NewInterpreterError::new_from_pyerr(py, err, "opening fault handler file")
+                })?;
+
+                // faulthandler holds a reference to the file, keeping it open.
+                let kwargs = PyDict::new(py);
+                kwargs.set_item(py, "file", fh).map_err(|err| {
+                    NewInterpreterError::new_from_pyerr(py, err, "setting file argument")
+                })?;
+                faulthandler
+                    .call(py, "enable", NoArgs, Some(&kwargs))
+                    .map_err(|err| {
+                        NewInterpreterError::new_from_pyerr(py, err, "faulthandler.enable()")
+                    })?;
+            }
+        }
+
         Ok(())
     }
 
@@ -551,9 +660,10 @@ impl<'python, 'interpreter, 'resources> MainPythonInterpreter<'python, 'interpre
     /// `OxidizedPythonInterpreterConfig.run` and return an integer suitable
     /// for use as a process exit code.
     ///
-    /// The `PythonRunMode::Eval`, `PythonRunMode::File`, and
-    /// `PythonRunMode::Module`, and `PythonRunMode::Repl` run modes are
-    /// evaluated via `Py_RunMain()`. `PythonRunMode::None` simply returns 0.
+    /// The `PythonRunMode::Eval`, `PythonRunMode::File`,
+    /// `PythonRunMode::Module`, `PythonRunMode::Resource`, and
+    /// `PythonRunMode::Repl` run modes are evaluated via `Py_RunMain()`.
+    /// `PythonRunMode::None` simply returns 0.
     ///
     /// `Py_RunMain` is the most robust mechanism to run code, files, or
     /// modules, as `Py_RunMain()` invokes the same APIs that `python` would.
@@ -568,6 +678,11 @@ impl<'python, 'interpreter, 'resources> MainPythonInterpreter<'python, 'interpre
     /// to inspect the return value or handle an uncaught exception. If you want
     /// to keep the interpreter alive or inspect the evaluation result, consider
     /// calling a function in the `python_eval` module.
+    ///
+    /// If `OxidizedPythonInterpreterConfig.multiprocessing_auto_dispatch` is
+    /// set and the process was launched by `multiprocessing` to run a worker,
+    /// resource tracker, or fork server, the `multiprocessing` entry point is
+    /// evaluated via `Py_RunMain()` instead of the configured run mode.
     pub fn run_as_main(&mut self) -> i32 {
         if self.config.uses_py_runmain() {
             let res = unsafe { pyffi::Py_RunMain() };
@@ -655,6 +770,60 @@ fn set_pyimport_inittab(config: &OxidizedPythonInterpreterConfig) {
     }
 }
 
+static mut ORIGINAL_FROZEN_MODULES: Option<Vec<pyffi::_frozen>> = None;
+static mut REPLACED_FROZEN_MODULES: Option<Box<Vec<pyffi::_frozen>>> = None;
+
+/// Set PyImport_FrozenModules from config options.
+///
+/// Like `set_pyimport_inittab()`, we maintain a shadow copy of the array
+/// so repeated interpreter initialization doesn't accumulate entries.
+fn set_pyimport_frozen_modules(config: &OxidizedPythonInterpreterConfig) {
+    unsafe {
+        if ORIGINAL_FROZEN_MODULES.is_none() {
+            let mut entries: Vec<pyffi::_frozen> = Vec::new();
+
+            for i in 0.. {
+                let record = pyffi::PyImport_FrozenModules.offset(i);
+
+                if (*record).name.is_null() {
+                    break;
+                }
+
+                entries.push(*record);
+            }
+
+            ORIGINAL_FROZEN_MODULES = Some(entries);
+        }
+    }
+
+    let mut modules = Box::new(unsafe { ORIGINAL_FROZEN_MODULES.as_ref().unwrap().clone() });
+
+    if let Some(extra_frozen_modules) = &config.extra_frozen_modules {
+        for module in extra_frozen_modules {
+            // A negative size denotes a package.
+            let size = module.bytecode.len() as i32;
+
+            modules.push(pyffi::_frozen {
+                name: module.name.as_ptr(),
+                code: module.bytecode.as_ptr(),
+                size: if module.is_package { -size } else { size },
+            });
+        }
+    }
+
+    // Add sentinel record with NULLs.
+    modules.push(pyffi::_frozen {
+        name: std::ptr::null(),
+        code: std::ptr::null(),
+        size: 0,
+    });
+
+    unsafe {
+        REPLACED_FROZEN_MODULES = Some(modules);
+        pyffi::PyImport_FrozenModules = REPLACED_FROZEN_MODULES.as_ref().unwrap().as_ptr();
+    }
+}
+
 /// Write loaded Python modules to a directory.
 ///
 /// Given a Python interpreter and a path to a directory, this will create a
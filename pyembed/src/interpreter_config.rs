@@ -248,6 +248,7 @@ impl<'a> OxidizedPythonInterpreterConfig<'a> {
                 PythonRunMode::Eval { .. } => true,
                 PythonRunMode::File { .. } => true,
                 PythonRunMode::Module { .. } => true,
+                PythonRunMode::Resource { .. } => true,
                 PythonRunMode::Repl => true,
                 PythonRunMode::None => false,
             }
@@ -643,12 +644,12 @@ impl<'a> TryInto<pyffi::PyConfig> for &'a OxidizedPythonInterpreterConfig<'a> {
                     )?;
                 }
             }
-            PythonRunMode::Module { module } => {
+            PythonRunMode::Module { module: name } | PythonRunMode::Resource { name } => {
                 if self.interpreter_config.run_module.is_none() {
                     set_config_string_from_str(
                         &config,
                         &config.run_module,
-                        module,
+                        name,
                         "setting run_module",
                     )?;
                 }
@@ -41,8 +41,8 @@ as possible.** This is because we want to minimize bloat in produced binaries.
 At this time, we have required direct dependencies on published versions of the
 `anyhow`, `lazy_static`, `libc`, `memmap`, `python-packed-resources`, and `uuid`
 crates. On Windows, this list is extended by `memory-module-sys` and `winapi`,
-which are required to support loading DLLs from memory. We also have an optional
-direct dependency on the `jemalloc-sys` crate.
+which are required to support loading DLLs from memory. We also have optional
+direct dependencies on the `jemalloc-sys`, `snmalloc-sys`, and `zstd` crates.
 
 This crate requires linking against a library providing CPython C symbols.
 (This dependency is via the `python3-sys` crate.) On Windows, this library
@@ -56,6 +56,13 @@ from Python is a run-time configuration option controlled by the
 `OxidizedPythonInterpreterConfig` type and having `jemalloc` compiled into the
 binary does not mean it is being used!
 
+The optional `snmalloc` feature likewise controls support for using
+[snmalloc](https://github.com/microsoft/snmalloc) as Python's memory allocator.
+
+The optional `zstd` feature enables loading packed resources data that was
+compressed with [zstd](https://facebook.github.io/zstd/). Loading compressed
+resources data without this feature results in an error.
+
 There exist mutually exclusive `build-mode-*` features to control how the
 `build.rs` build script works.
 
@@ -116,7 +123,7 @@ mod test;
 
 #[cfg(not(library_mode = "extension"))]
 #[allow(unused_imports)]
-pub use crate::config::{ExtensionModule, OxidizedPythonInterpreterConfig};
+pub use crate::config::{ExtensionModule, FrozenModule, OxidizedPythonInterpreterConfig};
 
 #[cfg(not(library_mode = "extension"))]
 #[allow(unused_imports)]
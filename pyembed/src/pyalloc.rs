@@ -14,6 +14,9 @@ use {
 #[cfg(feature = "jemalloc-sys")]
 use {jemalloc_sys as jemallocffi, std::ptr::null_mut};
 
+#[cfg(feature = "snmalloc-sys")]
+use snmalloc_sys as snmallocffi;
+
 const MIN_ALIGN: usize = 16;
 
 type RawAllocatorState = HashMap<*mut u8, alloc::Layout>;
@@ -220,3 +223,72 @@ pub fn make_raw_jemalloc_allocator() -> pyffi::PyMemAllocatorEx {
         free: Some(raw_jemalloc_free),
     }
 }
+
+// And a raw memory allocator that interfaces directly with snmalloc.
+
+#[cfg(feature = "snmalloc-sys")]
+extern "C" fn raw_snmalloc_malloc(_ctx: *mut c_void, size: size_t) -> *mut c_void {
+    // PyMem_RawMalloc()'s docs say: Requesting zero bytes returns a distinct
+    // non-NULL pointer if possible, as if PyMem_RawMalloc(1) had been called
+    // instead.
+    let size = match size {
+        0 => 1,
+        val => val,
+    };
+
+    unsafe { snmallocffi::sn_malloc(size) }
+}
+
+#[cfg(feature = "snmalloc-sys")]
+extern "C" fn raw_snmalloc_calloc(_ctx: *mut c_void, nelem: size_t, elsize: size_t) -> *mut c_void {
+    // PyMem_RawCalloc()'s docs say: Requesting zero elements or elements of
+    // size zero bytes returns a distinct non-NULL pointer if possible, as if
+    // PyMem_RawCalloc(1, 1) had been called instead.
+    let (nelem, elsize) = match nelem * elsize {
+        0 => (1, 1),
+        _ => (nelem, elsize),
+    };
+
+    unsafe { snmallocffi::sn_calloc(nelem, elsize) }
+}
+
+#[cfg(feature = "snmalloc-sys")]
+extern "C" fn raw_snmalloc_realloc(
+    ctx: *mut c_void,
+    ptr: *mut c_void,
+    new_size: size_t,
+) -> *mut c_void {
+    // PyMem_RawRealloc()'s docs say: If p is NULL, the call is equivalent to
+    // PyMem_RawMalloc(n); else if n is equal to zero, the memory block is
+    // resized but is not freed, and the returned pointer is non-NULL.
+    if ptr.is_null() {
+        return raw_snmalloc_malloc(ctx, new_size);
+    }
+
+    let new_size = match new_size {
+        0 => 1,
+        val => val,
+    };
+
+    unsafe { snmallocffi::sn_realloc(ptr, new_size) }
+}
+
+#[cfg(feature = "snmalloc-sys")]
+extern "C" fn raw_snmalloc_free(_ctx: *mut c_void, ptr: *mut c_void) {
+    if ptr.is_null() {
+        return;
+    }
+
+    unsafe { snmallocffi::sn_free(ptr) }
+}
+
+#[cfg(feature = "snmalloc-sys")]
+pub fn make_raw_snmalloc_allocator() -> pyffi::PyMemAllocatorEx {
+    pyffi::PyMemAllocatorEx {
+        ctx: std::ptr::null_mut(),
+        malloc: Some(raw_snmalloc_malloc),
+        calloc: Some(raw_snmalloc_calloc),
+        realloc: Some(raw_snmalloc_realloc),
+        free: Some(raw_snmalloc_free),
+    }
+}
@@ -13,7 +13,7 @@ use {
     libc::c_char,
     python3_sys as pyffi,
     python_packaging::interpreter::PythonRunMode,
-    std::ffi::CString,
+    std::ffi::{CString, OsString},
     std::io::Write,
     std::path::Path,
 };
@@ -248,6 +248,7 @@ pub fn run(py: Python, run_mode: &PythonRunMode) -> PyResult<PyObject> {
         PythonRunMode::None => Ok(py.None()),
         PythonRunMode::Repl => run_repl(py),
         PythonRunMode::Module { module } => run_module_as_main(py, module),
+        PythonRunMode::Resource { name } => run_module_as_main(py, name),
         PythonRunMode::Eval { code } => run_code(py, code),
         PythonRunMode::File { path } => run_file(py, path),
     }
@@ -338,6 +339,51 @@ pub(crate) fn handle_system_exit(py: Python, err: PyErr) -> Result<i32, &'static
     Ok(1)
 }
 
+/// Prefixes of the code `multiprocessing` passes via `-c` to re-executed processes.
+const MULTIPROCESSING_CODE_PREFIXES: &[&str] = &[
+    "from multiprocessing.spawn import spawn_main; spawn_main(",
+    "from multiprocessing.resource_tracker import main;main(",
+    "from multiprocessing.forkserver import main; main(",
+];
+
+/// Resolve Python code to run for a `multiprocessing` process re-execution.
+///
+/// `multiprocessing` starts child processes by re-executing `sys.executable`.
+/// When `sys.frozen` is set, spawned workers are invoked as
+/// `<exe> --multiprocessing-fork name=value...`. Otherwise, workers, the
+/// resource tracker, and the fork server are invoked as
+/// `<exe> [flags] -c 'from multiprocessing... import ...; main(...)'`.
+///
+/// Only the fixed `-c` prefixes `multiprocessing` emits are matched. The
+/// arguments are left for the interpreter to evaluate: `--multiprocessing-fork`
+/// resolves to `multiprocessing.spawn.freeze_support()`, which reads them from
+/// `sys.argv`, and `-c` resolves to the passed code.
+///
+/// Returns the code to evaluate if the process arguments match one of these
+/// forms or `None` otherwise.
+pub(crate) fn multiprocessing_dispatch_code(args: &[OsString]) -> Option<String> {
+    if args
+        .get(1)
+        .map_or(false, |arg| arg == "--multiprocessing-fork")
+    {
+        return Some(
+            "from multiprocessing.spawn import freeze_support; freeze_support()".to_string(),
+        );
+    }
+
+    let index = args.iter().skip(1).position(|arg| arg == "-c")? + 1;
+    let code = args.get(index + 1)?.to_str()?;
+
+    if MULTIPROCESSING_CODE_PREFIXES
+        .iter()
+        .any(|prefix| code.starts_with(prefix))
+    {
+        Some(code.to_string())
+    } else {
+        None
+    }
+}
+
 /// Represents the results of executing Python code with exception handling.
 #[derive(Debug)]
 pub enum PythonRunResult {
@@ -19,7 +19,7 @@ use {
         PyResult, PyString, PyTuple, Python, PythonObject, ToPyObject,
     },
     python3_sys as pyffi,
-    python_packed_resources::data::Resource,
+    python_packed_resources::data::{Resource, HEADER_ZSTD},
     std::borrow::Cow,
     std::cell::RefCell,
     std::collections::HashMap,
@@ -355,6 +355,17 @@ impl<'a> ImportablePythonModule<'a, u8> {
     }
 }
 
+/// Decompress zstd compressed resources data.
+#[cfg(feature = "zstd")]
+fn decompress_resources(data: &[u8]) -> Result<Vec<u8>, &'static str> {
+    zstd::stream::decode_all(data).map_err(|_| "unable to decompress resources data")
+}
+
+#[cfg(not(feature = "zstd"))]
+fn decompress_resources(_data: &[u8]) -> Result<Vec<u8>, &'static str> {
+    Err("resources data is compressed but zstd support is not available in this build configuration")
+}
+
 /// Defines Python resources available for import.
 #[derive(Debug)]
 pub(crate) struct PythonResourcesState<'a, X>
@@ -412,6 +423,22 @@ impl<'a> PythonResourcesState<'a, u8> {
         Ok(())
     }
 
+    /// Load resources from a standalone resources bundle file.
+    ///
+    /// This must be called before `load()`, as loading of builtin and frozen
+    /// modules needs to see all resources.
+    pub fn load_bundle(&mut self, path: &Path) -> Result<(), &'static str> {
+        let bundle = std::fs::read(path).map_err(|_| "unable to read resources bundle")?;
+
+        // Resources borrow from the bundle data. So, as with decompressed
+        // resources, it is leaked to live for the rest of the process.
+        let data: &'static [u8] = Box::leak(bundle.into_boxed_slice());
+
+        self.load_resources(python_packed_resources::parser::bundle_resources_data(
+            data,
+        )?)
+    }
+
     /// Add a resource to the instance.
     ///
     /// Memory in the resource must live for at least as long as the lifetime of
@@ -503,6 +530,19 @@ impl<'a> PythonResourcesState<'a, u8> {
         }
     }
 
+    /// Obtain file data registered in the directory of a package.
+    ///
+    /// File data resources are named by their `/` delimited relative path.
+    /// Files whose path is within a package's directory are exposed as
+    /// resources of that package.
+    fn package_file_data(&self, package: &str, resource_name: &str) -> Option<&Resource<'a, u8>> {
+        let name = format!("{}/{}", package.replace('.', "/"), resource_name);
+
+        self.resources
+            .get(name.as_str())
+            .filter(|entry| entry.is_file_data)
+    }
+
     /// Obtain a single named resource in a package.
     ///
     /// Err occurs if loading the resource data fails. `Ok(None)` is returned
@@ -514,6 +554,28 @@ impl<'a> PythonResourcesState<'a, u8> {
         package: &str,
         resource_name: &str,
     ) -> PyResult<Option<PyObject>> {
+        if let Some(file) = self.package_file_data(package, resource_name) {
+            let io_module = py.import("io")?;
+
+            if let Some(data) = &file.in_memory_file_data {
+                let bytes_io = io_module.get(py, "BytesIO")?;
+
+                let data = PyBytes::new(py, data);
+                return Ok(Some(bytes_io.call(py, (data,), None)?));
+            }
+
+            if let Some(path) = &file.relative_path_file_data {
+                let path = self.origin.join(path);
+
+                return Ok(Some(io_module.call(
+                    py,
+                    "FileIO",
+                    (path_to_pyobject(py, &path)?, "r"),
+                    None,
+                )?));
+            }
+        }
+
         let entry = match self.resources.get(package) {
             Some(entry) => entry,
             None => return Ok(None),
@@ -548,6 +610,10 @@ impl<'a> PythonResourcesState<'a, u8> {
 
     /// Determines whether a specific package + name pair is a known Python package resource.
     pub fn is_package_resource(&self, package: &str, resource_name: &str) -> bool {
+        if self.package_file_data(package, resource_name).is_some() {
+            return true;
+        }
+
         if let Some(entry) = self.resources.get(package) {
             if let Some(resources) = &entry.in_memory_package_resources {
                 if resources.contains_key(resource_name) {
@@ -569,18 +635,27 @@ impl<'a> PythonResourcesState<'a, u8> {
     ///
     /// The names are returned in sorted order.
     pub fn package_resource_names(&self, py: Python, package: &str) -> PyResult<PyObject> {
-        let entry = match self.resources.get(package) {
-            Some(entry) => entry,
-            None => return Ok(PyList::new(py, &[]).into_object()),
+        let mut names = match self.resources.get(package) {
+            Some(entry) => {
+                if let Some(resources) = &entry.in_memory_package_resources {
+                    resources.keys().map(|x| x.as_ref()).collect()
+                } else if let Some(resources) = &entry.relative_path_package_resources {
+                    resources.keys().map(|x| x.as_ref()).collect()
+                } else {
+                    vec![]
+                }
+            }
+            None => vec![],
         };
 
-        let mut names = if let Some(resources) = &entry.in_memory_package_resources {
-            resources.keys().collect()
-        } else if let Some(resources) = &entry.relative_path_package_resources {
-            resources.keys().collect()
-        } else {
-            vec![]
-        };
+        let prefix = format!("{}/", package.replace('.', "/"));
+        names.extend(self.resources.values().filter_map(|entry| {
+            if entry.is_file_data {
+                entry.name.strip_prefix(&prefix)
+            } else {
+                None
+            }
+        }));
 
         names.sort();
 
@@ -849,7 +924,22 @@ impl<'a> PythonResourcesState<'a, u8> {
     }
 
     /// Load resources by parsing a blob.
+    ///
+    /// The blob may be compressed, in which case it is decompressed first.
     fn load_resources(&mut self, data: &'a [u8]) -> Result<(), &'static str> {
+        let data = if data.starts_with(HEADER_ZSTD) {
+            let decompressed = decompress_resources(&data[HEADER_ZSTD.len()..])?;
+
+            // Resources borrow from the decompressed data, which must therefore
+            // outlive them. Resources are loaded once per process, so leaking
+            // the data to give it a static lifetime is acceptable.
+            let data: &'static [u8] = Box::leak(decompressed.into_boxed_slice());
+
+            data
+        } else {
+            data
+        };
+
         let resources = python_packed_resources::parser::load_resources(data)?;
 
         // Reserve space for expected number of incoming items so we can avoid extra
@@ -978,6 +1068,32 @@ py_class!(pub class OxidizedResource |py| {
         }
     }
 
+    @property def is_file_data(&self) -> PyResult<bool> {
+        Ok(self.resource(py).borrow().is_file_data)
+    }
+
+    @is_file_data.setter def set_is_file_data(&self, value: Option<bool>) -> PyResult<()> {
+        if let Some(value) = value {
+            self.resource(py).borrow_mut().is_file_data = value;
+            Ok(())
+        } else {
+            Err(PyErr::new::<TypeError, _>(py, "cannot delete is_file_data"))
+        }
+    }
+
+    @property def file_executable(&self) -> PyResult<bool> {
+        Ok(self.resource(py).borrow().file_executable)
+    }
+
+    @file_executable.setter def set_file_executable(&self, value: Option<bool>) -> PyResult<()> {
+        if let Some(value) = value {
+            self.resource(py).borrow_mut().file_executable = value;
+            Ok(())
+        } else {
+            Err(PyErr::new::<TypeError, _>(py, "cannot delete file_executable"))
+        }
+    }
+
     @property def name(&self) -> PyResult<String> {
         Ok(self.resource(py).borrow().name.to_string())
     }
@@ -1148,6 +1264,21 @@ py_class!(pub class OxidizedResource |py| {
         }
     }
 
+    @property def in_memory_file_data(&self) -> PyResult<Option<PyBytes>> {
+        Ok(self.resource(py).borrow().in_memory_file_data.as_ref().map(|x| PyBytes::new(py, x)))
+    }
+
+    @in_memory_file_data.setter def set_in_memory_file_data(&self, value: Option<PyObject>) -> PyResult<()> {
+        if let Some(value) = value {
+            self.resource(py).borrow_mut().in_memory_file_data =
+                pyobject_to_owned_bytes_optional(py, &value)?
+                    .map(Cow::Owned);
+            Ok(())
+        } else {
+            Err(PyErr::new::<TypeError, _>(py, "cannot delete in_memory_file_data"))
+        }
+    }
+
     @property def shared_library_dependency_names(&self) -> PyResult<Option<Vec<String>>> {
         Ok(self.resource(py).borrow().shared_library_dependency_names.as_ref().map(|x| {
             Vec::from_iter(x.iter().map(|v| v.to_string()))
@@ -1318,6 +1449,25 @@ py_class!(pub class OxidizedResource |py| {
         }
     }
 
+    @property def relative_path_file_data(&self) -> PyResult<PyObject> {
+        Ok(self.resource(py).borrow().relative_path_file_data.as_ref().map_or_else(
+            || Ok(py.None()),
+            |x| path_to_pathlib_path(py, x)
+        )?)
+    }
+
+    @relative_path_file_data.setter def set_relative_path_file_data(&self, value: Option<PyObject>) -> PyResult<()> {
+        if let Some(value) = value {
+            self.resource(py).borrow_mut().relative_path_file_data =
+                pyobject_to_pathbuf_optional(py, value)?
+                    .map(Cow::Owned);
+
+            Ok(())
+        } else {
+            Err(PyErr::new::<TypeError, _>(py, "cannot delete relative_path_file_data"))
+        }
+    }
+
 });
 
 /// Convert a Resource to an OxidizedResource.
@@ -6,6 +6,7 @@ use {
     crate::{MainPythonInterpreter, OxidizedPythonInterpreterConfig},
     anyhow::{anyhow, Result},
     cpython::{ObjectProtocol, PyObject},
+    python_packaging::interpreter::PythonRunMode,
     std::path::PathBuf,
 };
 
@@ -49,6 +50,37 @@ fn get_importer(interp: &mut MainPythonInterpreter) -> Result<PyObject> {
     Ok(importer)
 }
 
+/// Running a resource requires the module to be an embedded resource.
+#[test]
+fn run_resource_missing() -> Result<()> {
+    let mut config = OxidizedPythonInterpreterConfig::default();
+    config.run = PythonRunMode::Resource {
+        name: "pyembed_missing".to_string(),
+    };
+    assert_eq!(
+        MainPythonInterpreter::new(config)
+            .err()
+            .unwrap()
+            .to_string(),
+        "PythonRunMode::Resource requires the oxidized importer"
+    );
+
+    let mut config = OxidizedPythonInterpreterConfig::default();
+    config.oxidized_importer = true;
+    config.run = PythonRunMode::Resource {
+        name: "pyembed_missing".to_string(),
+    };
+    assert_eq!(
+        MainPythonInterpreter::new(config)
+            .err()
+            .unwrap()
+            .to_string(),
+        "run mode references Python module pyembed_missing but it isn't an embedded resource"
+    );
+
+    Ok(())
+}
+
 /// We can load our oxidized importer with no resources.
 #[test]
 fn no_resources() -> Result<()> {
@@ -97,6 +129,47 @@ fn find_spec_missing() -> Result<()> {
     Ok(())
 }
 
+/// Modules can be imported from compressed resources data.
+#[test]
+#[cfg(feature = "zstd")]
+fn compressed_resources() -> Result<()> {
+    use {
+        python_packed_resources::{
+            data::{Resource, ResourceFlavor, HEADER_ZSTD},
+            writer::write_packed_resources_v2,
+        },
+        std::borrow::Cow,
+    };
+
+    let source = format!("VALUE = 42\n{}", "# compressible\n".repeat(1000));
+
+    let resource = Resource {
+        flavor: ResourceFlavor::Module,
+        name: Cow::Borrowed("compressed_module"),
+        in_memory_source: Some(Cow::Borrowed(source.as_bytes())),
+        ..Resource::default()
+    };
+
+    let mut data = Vec::new();
+    write_packed_resources_v2(&[&resource], &mut data, None)?;
+
+    let mut compressed = HEADER_ZSTD.to_vec();
+    compressed.extend(zstd::stream::encode_all(std::io::Cursor::new(&data), 3)?);
+    assert!(compressed.len() < data.len());
+
+    let mut config = OxidizedPythonInterpreterConfig::default();
+    config.oxidized_importer = true;
+    config.packed_resources = Some(&compressed);
+    let mut interp = MainPythonInterpreter::new(config)?;
+
+    let py = interp.acquire_gil().unwrap();
+    let module = py.import("compressed_module").unwrap();
+    let value = module.get(py, "VALUE").unwrap();
+    assert_eq!(value.extract::<i64>(py).unwrap(), 42);
+
+    Ok(())
+}
+
 /// Run test_importer_builtins.py.
 #[test]
 fn builtins_py() -> Result<()> {
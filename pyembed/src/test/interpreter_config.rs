@@ -84,3 +84,58 @@ fn test_sys_paths_origin() -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn test_environment_variables() -> Result<()> {
+    let mut config = OxidizedPythonInterpreterConfig::default();
+    config.environment_variables = vec![(
+        "PYEMBED_TEST_CERT_FILE".to_string(),
+        "$ORIGIN/cacert.pem".to_string(),
+    )];
+
+    let origin = std::env::current_exe()
+        .unwrap()
+        .parent()
+        .unwrap()
+        .to_path_buf();
+
+    let mut interp = MainPythonInterpreter::new(config)?;
+
+    let py = interp.acquire_gil().unwrap();
+    let os = py.import("os").unwrap();
+    let value = os
+        .get(py, "environ")
+        .unwrap()
+        .get_item(py, "PYEMBED_TEST_CERT_FILE")
+        .unwrap()
+        .extract::<String>(py)
+        .unwrap();
+    assert_eq!(value, format!("{}/cacert.pem", origin.display()));
+
+    Ok(())
+}
+
+#[test]
+fn test_program_paths_origin() -> Result<()> {
+    let mut config = OxidizedPythonInterpreterConfig::default();
+    config.interpreter_config.program_name = Some(PathBuf::from("$ORIGIN/myapp"));
+    config.interpreter_config.executable = Some(PathBuf::from("/usr/bin/python3"));
+
+    let origin = std::env::current_exe()
+        .unwrap()
+        .parent()
+        .unwrap()
+        .to_path_buf();
+
+    config.resolve_program_paths().unwrap();
+    assert_eq!(
+        config.interpreter_config.program_name,
+        Some(PathBuf::from(format!("{}/myapp", origin.display())))
+    );
+    assert_eq!(
+        config.interpreter_config.executable,
+        Some(PathBuf::from("/usr/bin/python3"))
+    );
+
+    Ok(())
+}
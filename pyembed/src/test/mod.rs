@@ -4,3 +4,4 @@
 
 mod importer;
 mod interpreter_config;
+mod python_eval;
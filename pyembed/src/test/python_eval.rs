@@ -0,0 +1,70 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use {crate::python_eval::multiprocessing_dispatch_code, std::ffi::OsString};
+
+fn dispatch(args: &[&str]) -> Option<String> {
+    let args = args.iter().map(OsString::from).collect::<Vec<_>>();
+
+    multiprocessing_dispatch_code(&args)
+}
+
+#[test]
+fn test_multiprocessing_dispatch_unrelated() {
+    assert_eq!(dispatch(&["exe"]), None);
+    assert_eq!(dispatch(&["exe", "--verbose"]), None);
+    assert_eq!(dispatch(&["exe", "-c"]), None);
+    assert_eq!(dispatch(&["exe", "-c", "print('hello')"]), None);
+    assert_eq!(
+        dispatch(&["exe", "-c", "from multiprocessing.util import spawn_main"]),
+        None
+    );
+    assert_eq!(
+        dispatch(&["exe", "script.py", "--multiprocessing-fork"]),
+        None
+    );
+}
+
+#[test]
+fn test_multiprocessing_dispatch_fork() {
+    assert_eq!(
+        dispatch(&[
+            "exe",
+            "--multiprocessing-fork",
+            "tracker_fd=5",
+            "pipe_handle=7"
+        ]),
+        Some("from multiprocessing.spawn import freeze_support; freeze_support()".to_string())
+    );
+}
+
+#[test]
+fn test_multiprocessing_dispatch_code() {
+    for code in &[
+        "from multiprocessing.spawn import spawn_main; spawn_main(tracker_fd=5, pipe_handle=7)",
+        "from multiprocessing.resource_tracker import main;main(9)",
+        "from multiprocessing.forkserver import main; main(3, 4, ['__main__'], **{'sys_path': ['/app']})",
+    ] {
+        assert_eq!(
+            dispatch(&["exe", "-B", "-s", "-c", code, "--multiprocessing-fork"]),
+            Some(code.to_string())
+        );
+    }
+}
+
+#[cfg(unix)]
+#[test]
+fn test_multiprocessing_dispatch_non_unicode() {
+    use std::os::unix::ffi::OsStringExt;
+
+    let args = vec![
+        OsString::from("exe"),
+        OsString::from("-c"),
+        OsString::from_vec(
+            b"from multiprocessing.spawn import spawn_main; spawn_main(\xff)".to_vec(),
+        ),
+    ];
+
+    assert_eq!(multiprocessing_dispatch_code(&args), None);
+}
@@ -10,6 +10,7 @@ import unittest
 
 from oxidized_importer import (
     OxidizedFinder,
+    OxidizedResource,
     OxidizedResourceCollector,
     OxidizedResourceReader,
     find_resources_in_path,
@@ -125,6 +126,35 @@ class TestImporterResourceReading(unittest.TestCase):
         self.assertEqual(r.open_resource("child0/a.txt").getvalue(), b"a")
         self.assertEqual(r.open_resource("child1/b.txt").getvalue(), b"b")
 
+    def test_file_data(self):
+        self._make_package("my_package")
+
+        f = self._finder_from_td()
+
+        resource = OxidizedResource()
+        resource.is_file_data = True
+        resource.name = "my_package/config/template.ini"
+        resource.in_memory_file_data = b"[main]\n"
+        f.add_resource(resource)
+
+        resource = OxidizedResource()
+        resource.is_file_data = True
+        resource.name = "other/file.txt"
+        resource.in_memory_file_data = b"other"
+        f.add_resource(resource)
+
+        r = f.get_resource_reader("my_package")
+
+        self.assertIsInstance(r, OxidizedResourceReader)
+        self.assertTrue(r.is_resource("config/template.ini"))
+        self.assertEqual(r.contents(), ["config/template.ini"])
+        self.assertEqual(
+            r.open_resource("config/template.ini").getvalue(), b"[main]\n"
+        )
+
+        with self.assertRaises(FileNotFoundError):
+            r.open_resource("file.txt")
+
 
 if __name__ == "__main__":
     # Reset command arguments so test runner isn't confused.
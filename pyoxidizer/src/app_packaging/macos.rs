@@ -0,0 +1,159 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+/*!
+Functionality specific to macOS.
+*/
+
+use {
+    anyhow::{anyhow, Context, Result},
+    slog::warn,
+    std::{
+        ffi::OsString,
+        path::{Path, PathBuf},
+    },
+};
+
+/// Whether a Rust target triple targets macOS.
+pub fn is_macos_target(target_triple: &str) -> bool {
+    target_triple.ends_with("-apple-darwin")
+}
+
+/// Describes how to sign binaries with `codesign`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MacOsSigningConfig {
+    /// The signing identity.
+    ///
+    /// This is the common name or SHA-1 hash of a certificate in the
+    /// keychain. `-` performs ad-hoc signing.
+    pub identity: String,
+
+    /// Path to an entitlements plist to embed in the signature.
+    pub entitlements_path: Option<PathBuf>,
+
+    /// Whether to enable the hardened runtime.
+    ///
+    /// Notarization requires the hardened runtime.
+    pub hardened_runtime: bool,
+}
+
+impl MacOsSigningConfig {
+    /// Obtain the arguments to `codesign` to sign the file at `path`.
+    ///
+    /// Existing signatures are replaced.
+    pub fn codesign_args(&self, path: &Path) -> Vec<OsString> {
+        let mut args: Vec<OsString> = vec![
+            "--force".into(),
+            "--sign".into(),
+            self.identity.clone().into(),
+        ];
+
+        if self.hardened_runtime {
+            args.push("--options".into());
+            args.push("runtime".into());
+        }
+
+        if let Some(entitlements_path) = &self.entitlements_path {
+            args.push("--entitlements".into());
+            args.push(entitlements_path.clone().into_os_string());
+        }
+
+        args.push(path.as_os_str().to_os_string());
+
+        args
+    }
+
+    /// Sign the file at `path` by invoking `codesign`.
+    pub fn sign(&self, logger: &slog::Logger, path: &Path) -> Result<()> {
+        let args = self.codesign_args(path);
+
+        warn!(
+            logger,
+            "running codesign {}",
+            args.iter()
+                .map(|x| x.to_string_lossy())
+                .collect::<Vec<_>>()
+                .join(" ")
+        );
+
+        let output = std::process::Command::new("codesign")
+            .args(&args)
+            .output()
+            .context("running codesign")?;
+
+        if !output.status.success() {
+            return Err(anyhow!(
+                "codesign failed to sign {}: {}",
+                path.display(),
+                String::from_utf8_lossy(&output.stderr).trim()
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_macos_target() {
+        assert!(is_macos_target("x86_64-apple-darwin"));
+        assert!(is_macos_target("aarch64-apple-darwin"));
+        assert!(!is_macos_target("x86_64-unknown-linux-gnu"));
+        assert!(!is_macos_target("x86_64-pc-windows-msvc"));
+    }
+
+    #[test]
+    fn test_codesign_args() {
+        let mut config = MacOsSigningConfig {
+            identity: "Developer ID Application: Example".to_string(),
+            entitlements_path: None,
+            hardened_runtime: true,
+        };
+
+        assert_eq!(
+            config.codesign_args(Path::new("/tmp/app")),
+            vec![
+                "--force",
+                "--sign",
+                "Developer ID Application: Example",
+                "--options",
+                "runtime",
+                "/tmp/app"
+            ]
+        );
+
+        config.entitlements_path = Some(PathBuf::from("/tmp/entitlements.plist"));
+
+        assert_eq!(
+            config.codesign_args(Path::new("/tmp/app")),
+            vec![
+                "--force",
+                "--sign",
+                "Developer ID Application: Example",
+                "--options",
+                "runtime",
+                "--entitlements",
+                "/tmp/entitlements.plist",
+                "/tmp/app"
+            ]
+        );
+
+        config.hardened_runtime = false;
+
+        assert_eq!(
+            config.codesign_args(Path::new("/tmp/app")),
+            vec![
+                "--force",
+                "--sign",
+                "Developer ID Application: Example",
+                "--entitlements",
+                "/tmp/entitlements.plist",
+                "/tmp/app"
+            ]
+        );
+    }
+}
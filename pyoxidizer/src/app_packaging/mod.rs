@@ -3,4 +3,6 @@
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.
 
 pub mod glob;
+pub mod macos;
 pub mod resource;
+pub mod windows;
@@ -0,0 +1,184 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+/*!
+Functionality specific to Windows.
+*/
+
+use {
+    anyhow::{anyhow, Result},
+    std::path::PathBuf,
+};
+
+/// Whether a Rust target triple targets Windows.
+pub fn is_windows_target(target_triple: &str) -> bool {
+    target_triple.contains("pc-windows")
+}
+
+/// Parse a version string into the 4 integer components of a Windows version.
+///
+/// Versions have up to 4 dot-delimited components. Missing components are 0.
+pub fn parse_windows_version(version: &str) -> Result<[u16; 4]> {
+    let parts = version.split('.').collect::<Vec<_>>();
+
+    if parts.len() > 4 {
+        return Err(anyhow!("version {} has more than 4 components", version));
+    }
+
+    let mut res = [0; 4];
+
+    for (i, part) in parts.iter().enumerate() {
+        res[i] = part.parse::<u16>().map_err(|_| {
+            anyhow!(
+                "invalid version {}; components must be integers between 0 and 65535",
+                version
+            )
+        })?;
+    }
+
+    Ok(res)
+}
+
+/// Escape a value for use in a resource script string literal.
+fn escape_rc_string(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\"\"")
+}
+
+/// Metadata to embed as resources in Windows executables.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct WindowsResourceMetadata {
+    /// Version of the file.
+    ///
+    /// Must be parseable by `parse_windows_version()`.
+    pub file_version: Option<String>,
+
+    /// Name of the product the file is distributed with.
+    pub product_name: Option<String>,
+
+    /// Path to a `.ico` file to use as the application icon.
+    pub icon_path: Option<PathBuf>,
+}
+
+impl WindowsResourceMetadata {
+    /// Whether no metadata is defined.
+    pub fn is_empty(&self) -> bool {
+        self.file_version.is_none() && self.product_name.is_none() && self.icon_path.is_none()
+    }
+
+    /// Obtain the content of a resource script (`.rc` file) defining this metadata.
+    pub fn to_resource_script(&self) -> Result<String> {
+        let mut lines = vec!["#pragma code_page(65001)".to_string(), "".to_string()];
+
+        if let Some(icon_path) = &self.icon_path {
+            lines.push(format!(
+                "1 ICON \"{}\"",
+                escape_rc_string(&icon_path.display().to_string())
+            ));
+            lines.push("".to_string());
+        }
+
+        if self.file_version.is_some() || self.product_name.is_some() {
+            let version = match &self.file_version {
+                Some(version) => parse_windows_version(version)?,
+                None => [0; 4],
+            };
+            let version = version
+                .iter()
+                .map(|x| x.to_string())
+                .collect::<Vec<_>>()
+                .join(",");
+
+            let mut values = vec![];
+            if let Some(file_version) = &self.file_version {
+                values.push(("FileVersion", file_version.as_str()));
+                values.push(("ProductVersion", file_version.as_str()));
+            }
+            if let Some(product_name) = &self.product_name {
+                values.push(("ProductName", product_name.as_str()));
+            }
+
+            lines.push("1 VERSIONINFO".to_string());
+            lines.push(format!("FILEVERSION {}", version));
+            lines.push(format!("PRODUCTVERSION {}", version));
+            // VOS_NT_WINDOWS32 and VFT_APP.
+            lines.push("FILEOS 0x40004".to_string());
+            lines.push("FILETYPE 0x1".to_string());
+            lines.push("BEGIN".to_string());
+            lines.push("  BLOCK \"StringFileInfo\"".to_string());
+            lines.push("  BEGIN".to_string());
+            // U.S. English, Unicode.
+            lines.push("    BLOCK \"040904b0\"".to_string());
+            lines.push("    BEGIN".to_string());
+            for (key, value) in values {
+                lines.push(format!(
+                    "      VALUE \"{}\", \"{}\"",
+                    key,
+                    escape_rc_string(value)
+                ));
+            }
+            lines.push("    END".to_string());
+            lines.push("  END".to_string());
+            lines.push("  BLOCK \"VarFileInfo\"".to_string());
+            lines.push("  BEGIN".to_string());
+            lines.push("    VALUE \"Translation\", 0x409, 1200".to_string());
+            lines.push("  END".to_string());
+            lines.push("END".to_string());
+        }
+
+        Ok(lines.join("\n") + "\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_windows_target() {
+        assert!(is_windows_target("x86_64-pc-windows-msvc"));
+        assert!(is_windows_target("i686-pc-windows-msvc"));
+        assert!(!is_windows_target("x86_64-unknown-linux-gnu"));
+        assert!(!is_windows_target("x86_64-apple-darwin"));
+    }
+
+    #[test]
+    fn test_parse_windows_version() -> Result<()> {
+        assert_eq!(parse_windows_version("1")?, [1, 0, 0, 0]);
+        assert_eq!(parse_windows_version("1.2.3")?, [1, 2, 3, 0]);
+        assert_eq!(parse_windows_version("1.2.3.4")?, [1, 2, 3, 4]);
+        assert!(parse_windows_version("1.2.3.4.5").is_err());
+        assert!(parse_windows_version("1.2a").is_err());
+        assert!(parse_windows_version("65536").is_err());
+        assert!(parse_windows_version("").is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_resource_script() -> Result<()> {
+        let mut metadata = WindowsResourceMetadata::default();
+        assert!(metadata.is_empty());
+        assert_eq!(
+            metadata.to_resource_script()?,
+            "#pragma code_page(65001)\n\n"
+        );
+
+        metadata.file_version = Some("1.2.3".to_string());
+        metadata.product_name = Some("My \"App\"".to_string());
+        metadata.icon_path = Some(PathBuf::from("C:\\app\\icon.ico"));
+        assert!(!metadata.is_empty());
+
+        let script = metadata.to_resource_script()?;
+        assert!(script.contains("1 ICON \"C:\\\\app\\\\icon.ico\"\n"));
+        assert!(script.contains("FILEVERSION 1,2,3,0\n"));
+        assert!(script.contains("PRODUCTVERSION 1,2,3,0\n"));
+        assert!(script.contains("VALUE \"FileVersion\", \"1.2.3\"\n"));
+        assert!(script.contains("VALUE \"ProductName\", \"My \"\"App\"\"\"\n"));
+
+        metadata.file_version = Some("invalid".to_string());
+        assert!(metadata.to_resource_script().is_err());
+
+        Ok(())
+    }
+}
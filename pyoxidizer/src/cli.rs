@@ -11,6 +11,7 @@ use {
     super::projectmgmt,
     anyhow::{anyhow, Result},
     clap::{App, AppSettings, Arg, SubCommand},
+    std::collections::HashMap,
     std::path::{Path, PathBuf},
 };
 
@@ -72,6 +73,20 @@ This command executes the functionality to derive various artifacts and
 emits special lines that tell the Rust build system how to consume them.
 ";
 
+/// Parse `NAME=VALUE` arguments into config file variables.
+fn parse_vars<'a>(values: impl Iterator<Item = &'a str>) -> Result<HashMap<String, String>> {
+    values
+        .map(|value| {
+            let mut parts = value.splitn(2, '=');
+
+            match (parts.next(), parts.next()) {
+                (Some(name), Some(value)) => Ok((name.to_string(), value.to_string())),
+                _ => Err(anyhow!("invalid variable {}; expected NAME=VALUE", value)),
+            }
+        })
+        .collect()
+}
+
 pub fn run_cli() -> Result<()> {
     let env = crate::environment::resolve_environment()?;
 
@@ -86,6 +101,29 @@ pub fn run_cli() -> Result<()> {
                 .long("verbose")
                 .help("Enable verbose output"),
         )
+        .arg(
+            Arg::with_name("offline")
+                .long("offline")
+                .help("Fail instead of accessing the network"),
+        )
+        .arg(
+            Arg::with_name("allowed_distribution_host")
+                .long("allowed-distribution-host")
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1)
+                .value_name("HOST")
+                .help("Only download Python distributions from this host (can be repeated)"),
+        )
+        .arg(
+            Arg::with_name("var")
+                .long("var")
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1)
+                .value_name("NAME=VALUE")
+                .help("Define a string variable in the config file (can be repeated)"),
+        )
         .subcommand(
             SubCommand::with_name("add")
                 .setting(AppSettings::ArgRequiredElseHelp)
@@ -169,6 +207,23 @@ pub fn run_cli() -> Result<()> {
                         .help("Path to project to evaluate"),
                 ),
         )
+        .subcommand(
+            SubCommand::with_name("validate")
+                .setting(AppSettings::ArgRequiredElseHelp)
+                .about("Check a configuration file for errors without building")
+                .arg(
+                    Arg::with_name("target_triple")
+                        .long("target-triple")
+                        .takes_value(true)
+                        .help("Rust target triple to evaluate for"),
+                )
+                .arg(
+                    Arg::with_name("path")
+                        .default_value(".")
+                        .value_name("PATH")
+                        .help("Path to project to evaluate"),
+                ),
+        )
         .subcommand(
             SubCommand::with_name("build")
                 .setting(AppSettings::ArgRequiredElseHelp)
@@ -193,6 +248,11 @@ pub fn run_cli() -> Result<()> {
                         .value_name("PATH")
                         .help("Directory containing project to build"),
                 )
+                .arg(
+                    Arg::with_name("write_build_result")
+                        .long("write-build-result")
+                        .help("Write a build-result.json file describing each built target"),
+                )
                 .arg(
                     Arg::with_name("targets")
                         .value_name("TARGET")
@@ -269,6 +329,11 @@ pub fn run_cli() -> Result<()> {
         .get_matches();
 
     let verbose = matches.is_present("verbose");
+    let offline = matches.is_present("offline");
+    let allowed_distribution_hosts = matches
+        .values_of("allowed_distribution_host")
+        .map(|values| values.map(|x| x.to_string()).collect::<Vec<_>>());
+    let vars = parse_vars(matches.values_of("var").unwrap_or_default())?;
 
     let log_level = if verbose {
         slog::Level::Info
@@ -297,6 +362,7 @@ pub fn run_cli() -> Result<()> {
             let release = args.is_present("release");
             let target_triple = args.value_of("target_triple");
             let path = args.value_of("path").unwrap();
+            let write_build_result = args.is_present("write_build_result");
             let resolve_targets = if let Some(values) = args.values_of("targets") {
                 Some(values.map(|x| x.to_string()).collect())
             } else {
@@ -310,6 +376,10 @@ pub fn run_cli() -> Result<()> {
                 resolve_targets,
                 release,
                 verbose,
+                offline,
+                allowed_distribution_hosts,
+                write_build_result,
+                &vars,
             )
         }
 
@@ -329,7 +399,26 @@ pub fn run_cli() -> Result<()> {
         ("list-targets", Some(args)) => {
             let path = args.value_of("path").unwrap();
 
-            projectmgmt::list_targets(&logger_context.logger, Path::new(path))
+            projectmgmt::list_targets(
+                &logger_context.logger,
+                Path::new(path),
+                offline,
+                allowed_distribution_hosts,
+                &vars,
+            )
+        }
+
+        ("validate", Some(args)) => {
+            let target_triple = args.value_of("target_triple");
+            let path = args.value_of("path").unwrap();
+
+            projectmgmt::validate(
+                &logger_context.logger,
+                Path::new(path),
+                target_triple,
+                allowed_distribution_hosts,
+                &vars,
+            )
         }
 
         ("init-rust-project", Some(args)) => {
@@ -362,7 +451,7 @@ pub fn run_cli() -> Result<()> {
             let build_script = args.value_of("build-script-name").unwrap();
             let target = args.value_of("target");
 
-            project_building::run_from_build(&logger_context.logger, build_script, target)
+            project_building::run_from_build(&logger_context.logger, build_script, target, &vars)
         }
 
         ("run", Some(args)) => {
@@ -380,6 +469,9 @@ pub fn run_cli() -> Result<()> {
                 target,
                 &extra,
                 verbose,
+                offline,
+                allowed_distribution_hosts,
+                &vars,
             )
         }
 
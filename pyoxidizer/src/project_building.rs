@@ -3,13 +3,18 @@
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.
 
 use {
+    crate::app_packaging::windows::is_windows_target,
     crate::environment::{canonicalize_path, MINIMUM_RUST_VERSION},
     crate::project_layout::initialize_project,
-    crate::py_packaging::binary::{EmbeddedPythonContext, PythonBinaryBuilder},
+    crate::py_packaging::{
+        binary::{EmbeddedPythonContext, PythonBinaryBuilder},
+        distribution::DistributionFlavorRegistry,
+    },
     crate::starlark::eval::{eval_starlark_config_file, EvalResult},
     crate::starlark::target::ResolvedTarget,
     anyhow::{anyhow, Context, Result},
-    slog::warn,
+    slog::{info, warn},
+    std::collections::HashMap,
     std::env,
     std::fs::create_dir_all,
     std::path::{Path, PathBuf},
@@ -83,6 +88,15 @@ pub struct BuiltExecutable {
     pub binary_data: EmbeddedPythonContext,
 }
 
+/// Resolve the filename of an executable named `bin_name` for a target triple.
+pub fn executable_filename(bin_name: &str, target: &str) -> String {
+    if target.contains("pc-windows") {
+        format!("{}.exe", bin_name)
+    } else {
+        bin_name.to_string()
+    }
+}
+
 /// Build an executable embedding Python using an existing Rust project.
 ///
 /// The path to the produced executable is returned.
@@ -152,6 +166,14 @@ pub fn build_executable_with_rust_project(
         features.push("jemalloc");
     }
 
+    if exe.requires_snmalloc() {
+        features.push("snmalloc");
+    }
+
+    if exe.packed_resources_compression_level().is_some() {
+        features.push("zstd");
+    }
+
     let features = features.join(" ");
 
     if !features.is_empty() {
@@ -166,6 +188,25 @@ pub fn build_executable_with_rust_project(
     ));
     envs.push(("PYOXIDIZER_REUSE_ARTIFACTS", "1".to_string()));
 
+    // The project's build script compiles this resource script into the
+    // executable.
+    let windows_metadata = exe.windows_resource_metadata();
+    if is_windows_target(target) && !windows_metadata.is_empty() {
+        let script_path = artifacts_path.join("resources.rc");
+        std::fs::write(&script_path, windows_metadata.to_resource_script()?)
+            .with_context(|| format!("writing {}", script_path.display()))?;
+
+        envs.push((
+            "PYOXIDIZER_WINDOWS_RESOURCE_SCRIPT",
+            script_path.display().to_string(),
+        ));
+
+        // Allows the build script to rebuild when the icon changes.
+        if let Some(icon_path) = &windows_metadata.icon_path {
+            envs.push(("PYOXIDIZER_WINDOWS_ICON", icon_path.display().to_string()));
+        }
+    }
+
     // Set PYTHON_SYS_EXECUTABLE so python3-sys uses our distribution's Python to configure
     // itself.
     // TODO the build environment requiring use of target arch executable prevents
@@ -202,6 +243,9 @@ pub fn build_executable_with_rust_project(
         envs.push(("RUSTC_BOOTSTRAP", "1".to_string()));
     }
 
+    // Variables defined by the configuration come last so they override ours.
+    envs.extend(configured_build_environment(logger, exe));
+
     let status = std::process::Command::new("cargo")
         .args(args)
         .current_dir(&project_path)
@@ -212,13 +256,7 @@ pub fn build_executable_with_rust_project(
         return Err(anyhow!("cargo build failed"));
     }
 
-    let exe_name = if target.contains("pc-windows") {
-        format!("{}.exe", bin_name)
-    } else {
-        bin_name.to_string()
-    };
-
-    let exe_path = target_triple_base_path.join(&exe_name);
+    let exe_path = target_triple_base_path.join(executable_filename(bin_name, target));
 
     if !exe_path.exists() {
         return Err(anyhow!("{} does not exist", exe_path.display()));
@@ -235,6 +273,22 @@ pub fn build_executable_with_rust_project(
     })
 }
 
+/// Obtain the environment variables the configuration defines for `cargo build`.
+///
+/// Only variable names are logged, as values may contain secrets.
+fn configured_build_environment<'a>(
+    logger: &slog::Logger,
+    exe: &'a dyn PythonBinaryBuilder,
+) -> Vec<(&'a str, String)> {
+    exe.build_environment_variables()
+        .iter()
+        .map(|(name, value)| {
+            info!(logger, "setting build environment variable {}", name);
+            (name.as_str(), value.clone())
+        })
+        .collect()
+}
+
 /// Build a Python executable using a temporary Rust project.
 ///
 /// Returns the binary data constituting the built executable.
@@ -280,6 +334,7 @@ pub fn build_python_executable(
 ///
 /// This will resolve `resolve_target` or the default then build it. Built
 /// artifacts (if any) are written to `artifacts_path`.
+#[allow(clippy::too_many_arguments)]
 pub fn build_pyembed_artifacts(
     logger: &slog::Logger,
     config_path: &Path,
@@ -288,6 +343,8 @@ pub fn build_pyembed_artifacts(
     target_triple: &str,
     release: bool,
     verbose: bool,
+    allowed_distribution_hosts: Option<Vec<String>>,
+    vars: &HashMap<String, String>,
 ) -> Result<()> {
     create_dir_all(artifacts_path)?;
 
@@ -309,6 +366,11 @@ pub fn build_pyembed_artifacts(
             None
         },
         true,
+        false,
+        allowed_distribution_hosts,
+        false,
+        vars,
+        &DistributionFlavorRegistry::default(),
     )?;
 
     // TODO should we honor only the specified target if one is given?
@@ -360,8 +422,12 @@ pub fn build_pyembed_artifacts(
 /// interpreter when it is built.
 ///
 /// Receives a logger for receiving log messages, the path to the Rust
-/// build script invoking us, and an optional named target in the config
-/// file to resolve.
+/// build script invoking us, an optional named target in the config
+/// file to resolve, and string variables to define in the config file.
+///
+/// If the `PYOXIDIZER_ALLOWED_DISTRIBUTION_HOSTS` environment variable is
+/// set, Python distributions are only downloaded from the comma delimited
+/// hosts it lists.
 ///
 /// For this to work as expected, the target resolved in the config file must
 /// return a `PythonEmbeddeResources` starlark type.
@@ -369,12 +435,14 @@ pub fn run_from_build(
     logger: &slog::Logger,
     build_script: &str,
     resolve_target: Option<&str>,
+    vars: &HashMap<String, String>,
 ) -> Result<()> {
     // Adding our our rerun-if-changed lines will overwrite the default, so
     // we need to emit the build script name explicitly.
     println!("cargo:rerun-if-changed={}", build_script);
 
     println!("cargo:rerun-if-env-changed=PYOXIDIZER_CONFIG");
+    println!("cargo:rerun-if-env-changed=PYOXIDIZER_ALLOWED_DISTRIBUTION_HOSTS");
 
     // TODO use these variables?
     //let host = env::var("HOST").expect("HOST not defined");
@@ -401,6 +469,17 @@ pub fn run_from_build(
         Err(_) => PathBuf::from(env::var("OUT_DIR").context("OUT_DIR")?),
     };
 
+    let allowed_distribution_hosts =
+        env::var("PYOXIDIZER_ALLOWED_DISTRIBUTION_HOSTS")
+            .ok()
+            .map(|hosts| {
+                hosts
+                    .split(',')
+                    .map(|host| host.trim().to_string())
+                    .filter(|host| !host.is_empty())
+                    .collect::<Vec<_>>()
+            });
+
     build_pyembed_artifacts(
         logger,
         &config_path,
@@ -409,6 +488,8 @@ pub fn run_from_build(
         &target,
         profile == "release",
         false,
+        allowed_distribution_hosts,
+        vars,
     )?;
 
     let cargo_metadata = dest_dir.join("cargo_metadata.txt");
@@ -541,4 +622,68 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_build_environment_variables() -> Result<()> {
+        let logger = get_logger()?;
+        let options = StandalonePythonExecutableBuilderOptions::default();
+        let mut pre_built = options.new_builder()?;
+
+        assert!(configured_build_environment(&logger, pre_built.as_ref()).is_empty());
+
+        pre_built.set_build_environment_variable("RUSTFLAGS", "-C target-cpu=native");
+        pre_built.set_build_environment_variable("CC", "clang");
+
+        assert_eq!(
+            configured_build_environment(&logger, pre_built.as_ref()),
+            vec![
+                ("CC", "clang".to_string()),
+                ("RUSTFLAGS", "-C target-cpu=native".to_string()),
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(windows)]
+    fn test_windows_resource_metadata() -> Result<()> {
+        let logger = get_logger()?;
+        let options = StandalonePythonExecutableBuilderOptions::default();
+        let mut pre_built = options.new_builder()?;
+
+        pre_built.set_windows_resource_metadata(
+            crate::app_packaging::windows::WindowsResourceMetadata {
+                file_version: Some("1.2.3".to_string()),
+                product_name: Some("PyOxidizer Test App".to_string()),
+                icon_path: None,
+            },
+        );
+
+        let build = build_python_executable(
+            &logger,
+            "myapp",
+            pre_built.as_ref(),
+            env!("HOST"),
+            "0",
+            false,
+        )?;
+
+        let utf16 = |s: &str| {
+            s.encode_utf16()
+                .flat_map(|c| c.to_le_bytes().to_vec())
+                .collect::<Vec<u8>>()
+        };
+        let contains = |needle: &[u8]| {
+            build
+                .exe_data
+                .windows(needle.len())
+                .any(|window| window == needle)
+        };
+
+        assert!(contains(&utf16("VS_VERSION_INFO")));
+        assert!(contains(&utf16("PyOxidizer Test App")));
+
+        Ok(())
+    }
 }
@@ -285,6 +285,8 @@ pub fn update_new_cargo_toml(path: &Path, pyembed_location: &PyembedLocation) ->
     content.push_str("[features]\n");
     content.push_str("default = [\"build-mode-pyoxidizer-exe\"]\n");
     content.push_str("jemalloc = [\"jemallocator-global\", \"pyembed/jemalloc\"]\n");
+    content.push_str("snmalloc = [\"pyembed/snmalloc\"]\n");
+    content.push_str("zstd = [\"pyembed/zstd\"]\n");
     content.push_str("build-mode-pyoxidizer-exe = [\"pyembed/build-mode-pyoxidizer-exe\"]\n");
     content
         .push_str("build-mode-prebuilt-artifacts = [\"pyembed/build-mode-prebuilt-artifacts\"]\n");
@@ -293,6 +295,10 @@ pub fn update_new_cargo_toml(path: &Path, pyembed_location: &PyembedLocation) ->
     );
     content.push_str("cpython-link-default = [\"pyembed/cpython-link-default\"]\n");
 
+    content.push_str("\n");
+    content.push_str("[target.'cfg(windows)'.build-dependencies]\n");
+    content.push_str("embed-resource = \"1.3\"\n");
+
     std::fs::write(path, content)?;
 
     Ok(())
@@ -7,9 +7,12 @@
 use {
     crate::project_building::find_pyoxidizer_config_file_env,
     crate::project_layout::{initialize_project, write_new_pyoxidizer_config_file},
-    crate::py_packaging::standalone_distribution::StandaloneDistribution,
+    crate::py_packaging::{
+        distribution::DistributionFlavorRegistry, standalone_distribution::StandaloneDistribution,
+    },
     crate::starlark::eval::{eval_starlark_config_file, EvalResult},
     anyhow::{anyhow, Result},
+    std::collections::HashMap,
     std::fs::create_dir_all,
     std::io::{Cursor, Read},
     std::path::Path,
@@ -37,7 +40,13 @@ pub fn resolve_target(target: Option<&str>) -> Result<String> {
     }
 }
 
-pub fn list_targets(logger: &slog::Logger, project_path: &Path) -> Result<()> {
+pub fn list_targets(
+    logger: &slog::Logger,
+    project_path: &Path,
+    offline: bool,
+    allowed_distribution_hosts: Option<Vec<String>>,
+    vars: &HashMap<String, String>,
+) -> Result<()> {
     let config_path = find_pyoxidizer_config_file_env(logger, project_path).ok_or_else(|| {
         anyhow!(
             "unable to find PyOxidizder config file at {}",
@@ -54,6 +63,11 @@ pub fn list_targets(logger: &slog::Logger, project_path: &Path) -> Result<()> {
         false,
         Some(Vec::new()),
         false,
+        offline,
+        allowed_distribution_hosts,
+        false,
+        vars,
+        &DistributionFlavorRegistry::default(),
     )?;
 
     if res.context.default_target.is_none() {
@@ -73,10 +87,55 @@ pub fn list_targets(logger: &slog::Logger, project_path: &Path) -> Result<()> {
     Ok(())
 }
 
+/// Validate the configuration file of a PyOxidizer project.
+///
+/// The configuration file is evaluated and its targets are resolved without
+/// downloading Python distributions or installing packages.
+pub fn validate(
+    logger: &slog::Logger,
+    project_path: &Path,
+    target_triple: Option<&str>,
+    allowed_distribution_hosts: Option<Vec<String>>,
+    vars: &HashMap<String, String>,
+) -> Result<()> {
+    let config_path = find_pyoxidizer_config_file_env(logger, project_path).ok_or_else(|| {
+        anyhow!(
+            "unable to find PyOxidizer config file at {}",
+            project_path.display()
+        )
+    })?;
+    let target_triple = resolve_target(target_triple)?;
+
+    eval_starlark_config_file(
+        logger,
+        &config_path,
+        &target_triple,
+        false,
+        false,
+        None,
+        false,
+        // Validation never needs network access. Being offline turns any
+        // operation that would reach the network into an error.
+        true,
+        allowed_distribution_hosts,
+        true,
+        vars,
+        &DistributionFlavorRegistry::default(),
+    )?;
+
+    println!("{} is valid", config_path.display());
+
+    Ok(())
+}
+
 /// Build a PyOxidizer enabled project.
 ///
 /// This is a glorified wrapper around `cargo build`. Our goal is to get the
 /// output from repackaging to give the user something for debugging.
+///
+/// If `write_build_result` is true, a JSON file describing each built
+/// target is written into that target's output directory.
+#[allow(clippy::too_many_arguments)]
 pub fn build(
     logger: &slog::Logger,
     project_path: &Path,
@@ -84,6 +143,10 @@ pub fn build(
     resolve_targets: Option<Vec<String>>,
     release: bool,
     verbose: bool,
+    offline: bool,
+    allowed_distribution_hosts: Option<Vec<String>>,
+    write_build_result: bool,
+    vars: &HashMap<String, String>,
 ) -> Result<()> {
     let config_path = find_pyoxidizer_config_file_env(logger, project_path).ok_or_else(|| {
         anyhow!(
@@ -101,15 +164,25 @@ pub fn build(
         verbose,
         resolve_targets,
         false,
+        offline,
+        allowed_distribution_hosts,
+        false,
+        vars,
+        &DistributionFlavorRegistry::default(),
     )?;
 
     for target in res.context.targets_to_resolve() {
-        res.context.build_resolved_target(&target)?;
+        let resolved = res.context.build_resolved_target(&target)?;
+
+        if write_build_result {
+            resolved.write_build_result()?;
+        }
     }
 
     Ok(())
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn run(
     logger: &slog::Logger,
     project_path: &Path,
@@ -118,6 +191,9 @@ pub fn run(
     target: Option<&str>,
     _extra_args: &[&str],
     verbose: bool,
+    offline: bool,
+    allowed_distribution_hosts: Option<Vec<String>>,
+    vars: &HashMap<String, String>,
 ) -> Result<()> {
     let config_path = find_pyoxidizer_config_file_env(logger, project_path).ok_or_else(|| {
         anyhow!(
@@ -141,6 +217,11 @@ pub fn run(
         verbose,
         resolve_targets,
         false,
+        offline,
+        allowed_distribution_hosts,
+        false,
+        vars,
+        &DistributionFlavorRegistry::default(),
     )?;
 
     res.context.run_target(target)
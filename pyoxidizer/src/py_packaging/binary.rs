@@ -7,25 +7,41 @@ Defining and manipulating binaries embedding Python.
 */
 
 use {
-    super::config::EmbeddedPythonConfig,
-    crate::app_packaging::resource::FileManifest,
-    anyhow::Result,
+    super::{
+        config::EmbeddedPythonConfig, distribution::HttpProxyConfig,
+        packaging_tool::PipDownloadOptions,
+    },
+    crate::app_packaging::{resource::FileManifest, windows::WindowsResourceMetadata},
+    anyhow::{anyhow, Context, Result},
     python_packaging::{
+        interpreter::PythonRunMode,
+        package_metadata::{parse_entry_points, EntryPoint, PythonPackageMetadata},
         policy::PythonPackagingPolicy,
         resource::{
-            PythonExtensionModule, PythonModuleSource, PythonPackageDistributionResource,
-            PythonPackageResource, PythonResource,
+            DataLocation, FileData, PythonExtensionModule, PythonModuleSource,
+            PythonPackageDistributionResource, PythonPackageResource, PythonPathExtensionEntry,
+            PythonResource,
+        },
+        resource_collection::{
+            PrePackagedResource, PythonModuleBytecodeProvider, PythonResourceAddCollectionContext,
         },
-        resource_collection::{PrePackagedResource, PythonResourceAddCollectionContext},
     },
+    python_packed_resources::data::HEADER_ZSTD,
+    regex::Regex,
+    serde::Serialize,
+    sha2::{Digest, Sha256},
     std::{
-        collections::HashMap,
+        collections::{BTreeMap, BTreeSet, HashMap},
         fs::File,
         io::Write,
         path::{Path, PathBuf},
+        time::Duration,
     },
 };
 
+/// Origin recorded for resources provided by the Python distribution.
+pub const DISTRIBUTION_RESOURCE_ORIGIN: &str = "Python distribution";
+
 /// How a binary should link against libpython.
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub enum LibpythonLinkMode {
@@ -35,13 +51,37 @@ pub enum LibpythonLinkMode {
     Dynamic,
 }
 
+/// How an extension module is linked into or loaded by a binary.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ExtensionModuleLinkMode {
+    /// The extension module is built into libpython.
+    Builtin,
+    /// The extension module's object files are statically linked into the binary.
+    Static,
+    /// The extension module is a shared library loaded at run-time.
+    Shared,
+}
+
+impl ToString for ExtensionModuleLinkMode {
+    fn to_string(&self) -> String {
+        match self {
+            Self::Builtin => "builtin",
+            Self::Static => "static",
+            Self::Shared => "shared",
+        }
+        .to_string()
+    }
+}
+
 /// A callable that can influence PythonResourceAddCollectionContext.
+///
+/// Returns whether the resource should be added.
 pub type ResourceAddCollectionContextCallback<'a> = Box<
     dyn Fn(
             &PythonPackagingPolicy,
             &PythonResource,
             &mut PythonResourceAddCollectionContext,
-        ) -> Result<()>
+        ) -> Result<bool>
         + 'a,
 >;
 
@@ -89,39 +129,85 @@ pub trait PythonBinaryBuilder {
 
     /// Runs `pip download` using the binary builder's settings.
     ///
+    /// Compatibility tags not set in `options` are derived from the
+    /// distribution being targeted.
+    ///
     /// Returns resources discovered from the Python packages downloaded.
+    /// pip connects through the proxy defined by `proxy`.
     fn pip_download(
         &self,
         logger: &slog::Logger,
         verbose: bool,
+        options: &PipDownloadOptions,
         args: &[String],
+        proxy: &HttpProxyConfig,
     ) -> Result<Vec<PythonResource>>;
 
     /// Runs `pip install` using the binary builder's settings.
     ///
     /// Returns resources discovered as part of performing an install.
+    /// pip runs in `cwd`, connects through the proxy defined by `proxy`, and
+    /// results are cached in `cache_dir`.
+    #[allow(clippy::too_many_arguments)]
     fn pip_install(
         &self,
         logger: &slog::Logger,
         verbose: bool,
         install_args: &[String],
         extra_envs: &HashMap<String, String>,
+        cwd: &Path,
+        cache_dir: &Path,
+        proxy: &HttpProxyConfig,
     ) -> Result<Vec<PythonResource>>;
 
     /// Reads Python resources from the filesystem.
+    ///
+    /// Only resources in `packages` and `namespace_packages` are returned.
+    /// Packages in `namespace_packages` are PEP 420 namespace packages, which
+    /// don't have an `__init__` module.
     fn read_package_root(
         &self,
         logger: &slog::Logger,
         path: &Path,
         packages: &[String],
+        namespace_packages: &[String],
     ) -> Result<Vec<PythonResource>>;
 
     /// Read Python resources from a populated virtualenv directory.
     fn read_virtualenv(&self, logger: &slog::Logger, path: &Path) -> Result<Vec<PythonResource>>;
 
+    /// Read Python resources from a `site-packages` directory.
+    fn read_site_packages(&self, logger: &slog::Logger, path: &Path)
+        -> Result<Vec<PythonResource>>;
+
+    /// Read Python resources from a zip archive.
+    ///
+    /// Entries are read into memory without extracting the archive. If
+    /// `strip_prefix` is defined, only entries under that directory are
+    /// considered and the prefix is removed from their paths.
+    fn read_zip_file(
+        &self,
+        logger: &slog::Logger,
+        path: &Path,
+        strip_prefix: Option<&str>,
+    ) -> Result<Vec<PythonResource>>;
+
+    /// Read an extension module named `name` from a pre-built shared library.
+    ///
+    /// The file name must have an extension module suffix of the target.
+    fn read_extension_module_file(
+        &self,
+        logger: &slog::Logger,
+        name: &str,
+        path: &Path,
+    ) -> Result<PythonExtensionModule>;
+
     /// Runs `python setup.py install` using the binary builder's settings.
     ///
     /// Returns resources discovered as part of performing an install.
+    /// The install is performed in a directory under `work_root`. Downloads
+    /// performed by `setup.py` connect through the proxy defined by `proxy`.
+    #[allow(clippy::too_many_arguments)]
     fn setup_py_install(
         &self,
         logger: &slog::Logger,
@@ -129,6 +215,8 @@ pub trait PythonBinaryBuilder {
         verbose: bool,
         extra_envs: &HashMap<String, String>,
         extra_global_arguments: &[String],
+        work_root: &Path,
+        proxy: &HttpProxyConfig,
     ) -> Result<Vec<PythonResource>>;
 
     /// Add resources from the Python distribution to the builder.
@@ -147,9 +235,11 @@ pub trait PythonBinaryBuilder {
     /// `callback` defines an optional function which can be called between
     /// resource creation and adding that resource to the builder. This
     /// gives the caller an opportunity to influence how resources are added
-    /// to the binary builder.
+    /// to the binary builder. If the callback returns false, the resource
+    /// is not added.
     fn add_distribution_resources(
         &mut self,
+        logger: &slog::Logger,
         callback: Option<ResourceAddCollectionContextCallback>,
     ) -> Result<()>;
 
@@ -200,21 +290,157 @@ pub trait PythonBinaryBuilder {
         add_context: Option<PythonResourceAddCollectionContext>,
     ) -> Result<()>;
 
+    /// Add a `FileData` to make available.
+    ///
+    /// File data is an arbitrary file not tied to a Python package. Its
+    /// `path` is the relative path the file will be addressable at.
+    fn add_file_data(
+        &mut self,
+        file: &FileData,
+        add_context: Option<PythonResourceAddCollectionContext>,
+    ) -> Result<()>;
+
+    /// Whether a resource with the same name but different content has been added.
+    fn is_conflicting_resource(&self, resource: &PythonResource) -> Result<bool>;
+
+    /// Obtain the recorded origin of an added resource, if available.
+    ///
+    /// `name` is the full name of the resource.
+    fn resource_origin(&self, name: &str) -> Option<String>;
+
+    /// Record the origin of an added resource.
+    ///
+    /// Origins are free-form descriptions used in diagnostics.
+    fn set_resource_origin(&mut self, name: &str, origin: &str);
+
+    /// Register a filesystem path to be searched for modules at run-time.
+    ///
+    /// Modules under this path are imported from the filesystem and are not
+    /// embedded in the binary. Registering a path enables the filesystem
+    /// importer.
+    ///
+    /// The path isn't added to the interpreter config directly. See
+    /// `origin_module_search_paths()`.
+    fn add_module_search_path(&mut self, path: &Path);
+
+    /// Resolve paths registered with `add_module_search_path()` against an executable directory.
+    ///
+    /// `origin` is the directory the executable is written to. Paths are
+    /// returned relative to `$ORIGIN` so the binary doesn't reference
+    /// absolute paths on the build machine.
+    fn origin_module_search_paths(&self, origin: &Path) -> Result<Vec<PathBuf>>;
+
     /// Filter embedded resources against names in files.
     ///
     /// `files` is files to read names from.
     ///
     /// `glob_patterns` is file patterns of files to read names from.
+    ///
+    /// `regexes` is regular expressions matched against resource names.
+    ///
+    /// Resources whose name is in any of the files or matches any of the
+    /// regular expressions are retained. All other resources are removed.
     fn filter_resources_from_files(
         &mut self,
         logger: &slog::Logger,
         files: &[&Path],
         glob_patterns: &[&str],
+        regexes: &[Regex],
     ) -> Result<()>;
 
+    /// Remove resources having any of the given names.
+    fn remove_resources(&mut self, logger: &slog::Logger, names: &BTreeSet<String>) -> Result<()>;
+
     /// Whether the binary requires the jemalloc library.
     fn requires_jemalloc(&self) -> bool;
 
+    /// Whether the binary requires the snmalloc library.
+    fn requires_snmalloc(&self) -> bool;
+
+    /// Obtain metadata to embed as resources in Windows executables.
+    fn windows_resource_metadata(&self) -> &WindowsResourceMetadata;
+
+    /// Set metadata to embed as resources in Windows executables.
+    ///
+    /// The metadata is ignored when building for other targets.
+    fn set_windows_resource_metadata(&mut self, metadata: WindowsResourceMetadata);
+
+    /// Obtain environment variables to set when compiling the binary.
+    fn build_environment_variables(&self) -> &BTreeMap<String, String>;
+
+    /// Set an environment variable for the Rust compilation of the binary.
+    ///
+    /// e.g. `RUSTFLAGS` or `CARGO_TARGET_<TRIPLE>_LINKER`. These take
+    /// precedence over variables PyOxidizer sets on its own.
+    fn set_build_environment_variable(&mut self, name: &str, value: &str);
+
+    /// Obtain the zstd level packed resources data is compressed with.
+    ///
+    /// `None` means resources data isn't compressed.
+    fn packed_resources_compression_level(&self) -> Option<i32>;
+
+    /// Set the zstd level to compress packed resources data with.
+    ///
+    /// `None` disables compression.
+    fn set_packed_resources_compression_level(&mut self, level: Option<i32>);
+
+    /// Obtain the maximum number of bytecode compilers to run concurrently.
+    fn bytecode_compiler_jobs(&self) -> usize;
+
+    /// Set the maximum number of bytecode compilers to run concurrently.
+    ///
+    /// Must be at least 1.
+    fn set_bytecode_compiler_jobs(&mut self, jobs: usize);
+
+    /// Obtain what code the embedded interpreter runs by default.
+    fn run_mode(&self) -> &PythonRunMode;
+
+    /// Set what code the embedded interpreter runs by default.
+    fn set_run_mode(&mut self, run_mode: PythonRunMode);
+
+    /// Obtain the filename packed resources are written to as a standalone bundle.
+    ///
+    /// `None` means resources data is embedded in the binary.
+    fn resources_bundle_filename(&self) -> Option<String>;
+
+    /// Set the filename to write packed resources to as a standalone bundle.
+    ///
+    /// The bundle is installed next to the binary, which loads resources
+    /// from it at run-time instead of embedding them.
+    fn set_resources_bundle_filename(&mut self, filename: Option<String>);
+
+    /// Compile Python source and register it as a frozen module.
+    ///
+    /// The bytecode is added to the frozen modules table the embedded
+    /// interpreter installs during initialization.
+    fn add_frozen_module(&mut self, name: &str, source: &[u8], is_package: bool) -> Result<()>;
+
+    /// Replace the source code of an added Python module.
+    ///
+    /// Bytecode derived from the module's source is compiled from the new
+    /// source. Errors if the module's source hasn't been added.
+    fn replace_module_source(&mut self, name: &str, source: &[u8]) -> Result<()>;
+
+    /// Describe how each added extension module will be linked.
+    ///
+    /// Entries are sorted by extension module name.
+    fn extension_module_link_modes(&self) -> Vec<(String, ExtensionModuleLinkMode)>;
+
+    /// Compute a fingerprint of the state an `EmbeddedPythonContext` derives from.
+    ///
+    /// The fingerprint is a hex encoded SHA-256 digest covering the builder's
+    /// settings and the content of all resources, including files they
+    /// reference. Unlike `EmbeddedPythonContext::fingerprint()`, computing it
+    /// doesn't require compiling bytecode or serializing resources.
+    fn input_fingerprint(&self) -> Result<String>;
+
+    /// Verify that Python modules the interpreter is configured to run are packaged.
+    ///
+    /// Errors if a module that must be packaged isn't. Returns descriptions
+    /// of missing modules that may still be importable from the filesystem
+    /// at run-time.
+    fn validate_run_modules(&self) -> Result<Vec<String>>;
+
     /// Obtain an `EmbeddedPythonContext` instance from this one.
     fn to_embedded_python_context(
         &self,
@@ -267,6 +493,530 @@ pub struct EmbeddedPythonPaths {
     pub cargo_metadata: PathBuf,
 }
 
+/// Estimated size of a binary embedding Python, derived without building it.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct EmbeddedPythonSizeEstimate {
+    /// Number of resources that would be embedded in the binary.
+    pub resource_count: usize,
+
+    /// Size in bytes of the packed resources data.
+    pub resources_size: u64,
+
+    /// Size in bytes of libraries providing Python that would be linked.
+    pub libpython_size: u64,
+
+    /// Number of extra files that would be installed next to the binary.
+    pub extra_files_count: usize,
+
+    /// Size in bytes of extra files that would be installed next to the binary.
+    pub extra_files_size: u64,
+}
+
+impl EmbeddedPythonSizeEstimate {
+    /// Approximate size in bytes of the produced executable.
+    ///
+    /// This does not account for Rust code or linker behavior, such as
+    /// dead code elimination, so the actual size will differ.
+    pub fn executable_size(&self) -> u64 {
+        self.resources_size + self.libpython_size
+    }
+
+    /// Approximate size in bytes of all files that would be written.
+    pub fn total_size(&self) -> u64 {
+        self.executable_size() + self.extra_files_size
+    }
+}
+
+/// Describes a single resource in a resource manifest.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct ResourceManifestEntry {
+    /// Name of the resource.
+    pub name: String,
+
+    /// Types of data associated with the resource.
+    ///
+    /// A single entry can carry multiple types. e.g. a package's module
+    /// source and its package resources.
+    pub types: Vec<&'static str>,
+
+    /// Size in bytes of the data backing the resource.
+    ///
+    /// Bytecode that will be compiled from source is not counted.
+    pub size: u64,
+
+    /// Recorded origin of the resource, if available.
+    pub origin: Option<String>,
+
+    /// Whether module source code is included.
+    pub source: bool,
+
+    /// Whether module bytecode is included.
+    pub bytecode: bool,
+}
+
+/// Derive a manifest describing every resource added to a binary builder.
+pub fn resource_manifest(builder: &dyn PythonBinaryBuilder) -> Result<Vec<ResourceManifestEntry>> {
+    let location_size = |location: &DataLocation| -> Result<u64> {
+        Ok(match location {
+            DataLocation::Memory(data) => data.len() as u64,
+            DataLocation::Path(path) => std::fs::metadata(path)?.len(),
+        })
+    };
+    let bytecode_size = |provider: &PythonModuleBytecodeProvider| -> Result<u64> {
+        match provider {
+            PythonModuleBytecodeProvider::Provided(location) => location_size(location),
+            PythonModuleBytecodeProvider::FromSource(_) => Ok(0),
+        }
+    };
+
+    builder
+        .iter_resources()
+        .map(|(name, resource)| {
+            let mut types = vec![];
+            let mut size = 0;
+
+            if resource.is_module {
+                types.push("module");
+            }
+            if resource.is_builtin_extension_module || resource.is_extension_module {
+                types.push("extension_module");
+            }
+            if resource.is_frozen_module {
+                types.push("frozen_module");
+            }
+            if resource.in_memory_resources.is_some()
+                || resource.relative_path_package_resources.is_some()
+            {
+                types.push("package_resources");
+            }
+            if resource.in_memory_distribution_resources.is_some()
+                || resource.relative_path_distribution_resources.is_some()
+            {
+                types.push("distribution_resources");
+            }
+            if resource.is_shared_library {
+                types.push("shared_library");
+            }
+            if resource.is_file_data {
+                types.push("file_data");
+            }
+
+            for location in resource
+                .in_memory_source
+                .iter()
+                .chain(resource.relative_path_module_source.iter().map(|(_, l)| l))
+                .chain(resource.in_memory_extension_module_shared_library.iter())
+                .chain(
+                    resource
+                        .relative_path_extension_module_shared_library
+                        .iter()
+                        .map(|(_, l)| l),
+                )
+                .chain(resource.in_memory_resources.iter().flat_map(|x| x.values()))
+                .chain(
+                    resource
+                        .in_memory_distribution_resources
+                        .iter()
+                        .flat_map(|x| x.values()),
+                )
+                .chain(
+                    resource
+                        .relative_path_package_resources
+                        .iter()
+                        .flat_map(|x| x.values().map(|(_, l)| l)),
+                )
+                .chain(
+                    resource
+                        .relative_path_distribution_resources
+                        .iter()
+                        .flat_map(|x| x.values().map(|(_, l)| l)),
+                )
+                .chain(resource.in_memory_shared_library.iter())
+                .chain(
+                    resource
+                        .relative_path_shared_library
+                        .iter()
+                        .map(|(_, _, l)| l),
+                )
+                .chain(resource.in_memory_file_data.iter())
+                .chain(resource.relative_path_file_data.iter().map(|(_, l)| l))
+            {
+                size += location_size(location)?;
+            }
+
+            let bytecode = resource
+                .in_memory_bytecode
+                .iter()
+                .chain(resource.in_memory_bytecode_opt1.iter())
+                .chain(resource.in_memory_bytecode_opt2.iter())
+                .chain(
+                    resource
+                        .relative_path_bytecode
+                        .iter()
+                        .chain(resource.relative_path_bytecode_opt1.iter())
+                        .chain(resource.relative_path_bytecode_opt2.iter())
+                        .map(|(_, _, provider)| provider),
+                )
+                .collect::<Vec<_>>();
+
+            for provider in &bytecode {
+                size += bytecode_size(provider)?;
+            }
+
+            Ok(ResourceManifestEntry {
+                name: name.clone(),
+                types,
+                size,
+                origin: builder.resource_origin(name),
+                source: resource.in_memory_source.is_some()
+                    || resource.relative_path_module_source.is_some(),
+                bytecode: !bytecode.is_empty(),
+            })
+        })
+        .collect()
+}
+
+/// Counts of resources added to a binary builder, by type.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ResourceStats {
+    /// Number of modules having source code.
+    pub module_sources: usize,
+
+    /// Number of modules having bytecode.
+    pub module_bytecodes: usize,
+
+    /// Number of extension modules, including built-in extension modules.
+    pub extension_modules: usize,
+
+    /// Number of frozen modules.
+    pub frozen_modules: usize,
+
+    /// Number of individual package resource files.
+    pub package_resources: usize,
+
+    /// Number of individual package distribution resource files.
+    pub distribution_resources: usize,
+
+    /// Number of shared libraries.
+    pub shared_libraries: usize,
+
+    /// Number of files.
+    pub file_data: usize,
+
+    /// Size in bytes of the data backing all resources.
+    ///
+    /// Bytecode that will be compiled from source is not counted.
+    pub total_bytes: u64,
+}
+
+/// Count resources added to a binary builder and sum their sizes.
+pub fn resource_stats(builder: &dyn PythonBinaryBuilder) -> Result<ResourceStats> {
+    let mut stats = ResourceStats {
+        total_bytes: resource_manifest(builder)?
+            .iter()
+            .map(|entry| entry.size)
+            .sum(),
+        ..ResourceStats::default()
+    };
+
+    for (_, resource) in builder.iter_resources() {
+        if resource.in_memory_source.is_some() || resource.relative_path_module_source.is_some() {
+            stats.module_sources += 1;
+        }
+        if resource.in_memory_bytecode.is_some()
+            || resource.in_memory_bytecode_opt1.is_some()
+            || resource.in_memory_bytecode_opt2.is_some()
+            || resource.relative_path_bytecode.is_some()
+            || resource.relative_path_bytecode_opt1.is_some()
+            || resource.relative_path_bytecode_opt2.is_some()
+        {
+            stats.module_bytecodes += 1;
+        }
+        if resource.is_builtin_extension_module || resource.is_extension_module {
+            stats.extension_modules += 1;
+        }
+        if resource.is_frozen_module {
+            stats.frozen_modules += 1;
+        }
+        stats.package_resources += resource.in_memory_resources.as_ref().map_or(0, |x| x.len())
+            + resource
+                .relative_path_package_resources
+                .as_ref()
+                .map_or(0, |x| x.len());
+        stats.distribution_resources += resource
+            .in_memory_distribution_resources
+            .as_ref()
+            .map_or(0, |x| x.len())
+            + resource
+                .relative_path_distribution_resources
+                .as_ref()
+                .map_or(0, |x| x.len());
+        if resource.is_shared_library {
+            stats.shared_libraries += 1;
+        }
+        if resource.is_file_data {
+            stats.file_data += 1;
+        }
+    }
+
+    Ok(stats)
+}
+
+/// Derive pinned requirements for package distributions added to a binary builder.
+///
+/// The name and version of each distribution come from its `METADATA` or
+/// `PKG-INFO` distribution resource. Returned `(name, version)` pairs are
+/// sorted by name.
+pub fn installed_requirements(builder: &dyn PythonBinaryBuilder) -> Result<Vec<(String, String)>> {
+    let mut requirements = vec![];
+
+    for (name, resource) in builder.iter_resources() {
+        let location = resource
+            .in_memory_distribution_resources
+            .iter()
+            .flat_map(|x| x.iter())
+            .chain(
+                resource
+                    .relative_path_distribution_resources
+                    .iter()
+                    .flat_map(|x| x.iter().map(|(k, (_, l))| (k, l))),
+            )
+            .find(|(k, _)| k.as_str() == "METADATA" || k.as_str() == "PKG-INFO")
+            .map(|(_, location)| location);
+
+        let location = match location {
+            Some(location) => location,
+            None => continue,
+        };
+
+        let data = location
+            .resolve()
+            .with_context(|| format!("reading distribution metadata of {}", name))?;
+        let metadata = PythonPackageMetadata::from_metadata(&data)
+            .with_context(|| format!("parsing distribution metadata of {}", name))?;
+
+        let package = metadata
+            .name()
+            .ok_or_else(|| anyhow!("distribution metadata of {} does not define a Name", name))?;
+        let version = metadata.version().ok_or_else(|| {
+            anyhow!(
+                "distribution metadata of {} does not define a Version",
+                name
+            )
+        })?;
+
+        requirements.push((package.to_string(), version.to_string()));
+    }
+
+    requirements.sort();
+    requirements.dedup();
+
+    Ok(requirements)
+}
+
+/// Express a path relative to `$ORIGIN`.
+///
+/// `origin` is the directory `$ORIGIN` expands to. Both paths are
+/// canonicalized if they exist. Returns `None` if no relative path leads
+/// from `origin` to `path`, e.g. because they are on different drives.
+pub fn origin_relative_path(path: &Path, origin: &Path) -> Option<PathBuf> {
+    let path = std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    let origin = std::fs::canonicalize(origin).unwrap_or_else(|_| origin.to_path_buf());
+
+    let path_components = path.components().collect::<Vec<_>>();
+    let origin_components = origin.components().collect::<Vec<_>>();
+
+    if !path.is_absolute()
+        || !origin.is_absolute()
+        || path_components.first() != origin_components.first()
+    {
+        return None;
+    }
+
+    let common = path_components
+        .iter()
+        .zip(origin_components.iter())
+        .take_while(|(a, b)| a == b)
+        .count();
+
+    let mut relative = PathBuf::from("$ORIGIN");
+    for _ in common..origin_components.len() {
+        relative.push("..");
+    }
+    for component in &path_components[common..] {
+        relative.push(component);
+    }
+
+    Some(relative)
+}
+
+/// Resolve resources referenced by .pth files among collected resources.
+///
+/// The embedded interpreter doesn't process .pth files. If `apply` is true,
+/// the resources collected from the directories they reference are returned
+/// so they can be packaged like any other resource. Code lines are dropped.
+///
+/// Returns the resources to add and warnings describing content that is dropped.
+pub fn path_extension_resources(
+    resources: &[PythonResource],
+    apply: bool,
+) -> Result<(Vec<PythonResource<'static>>, Vec<String>)> {
+    let mut path_resources = vec![];
+    let mut warnings = vec![];
+
+    for resource in resources {
+        let pth = match resource {
+            PythonResource::PathExtension(pth) => pth,
+            _ => continue,
+        };
+
+        let origin = match &pth.data {
+            DataLocation::Path(path) => path.display().to_string(),
+            DataLocation::Memory(_) => "in-memory .pth file".to_string(),
+        };
+
+        if !apply {
+            warnings.push(format!(
+                "dropping {}: .pth files are not processed by the embedded interpreter; set apply_pth_files on the packaging policy to package the modules it references",
+                origin
+            ));
+            continue;
+        }
+
+        let mut has_paths = false;
+
+        for entry in pth
+            .entries()
+            .with_context(|| format!("parsing {}", origin))?
+        {
+            match entry {
+                PythonPathExtensionEntry::Path(_) => {
+                    has_paths = true;
+                }
+                PythonPathExtensionEntry::Code(line) => {
+                    warnings.push(format!(
+                        "dropping `{}` from {}: code in .pth files is not executed",
+                        line, origin
+                    ));
+                }
+            }
+        }
+
+        if has_paths && pth.path_resources.is_empty() {
+            warnings.push(format!(
+                "no resources found in directories referenced by {}",
+                origin
+            ));
+        }
+
+        path_resources.extend(pth.path_resources.iter().cloned());
+    }
+
+    Ok((path_resources, warnings))
+}
+
+/// Find a `console_scripts` entry point of a distribution added to a binary builder.
+///
+/// `distribution` is matched against the `Name` in distribution metadata,
+/// ignoring case and treating `-`, `_`, and `.` as equivalent.
+pub fn console_script_entry_point(
+    builder: &dyn PythonBinaryBuilder,
+    distribution: &str,
+    name: &str,
+) -> Result<EntryPoint> {
+    let normalize = |value: &str| value.to_lowercase().replace(|c| c == '_' || c == '.', "-");
+    let wanted = normalize(distribution);
+
+    for (resource_name, resource) in builder.iter_resources() {
+        let files = resource
+            .in_memory_distribution_resources
+            .iter()
+            .flat_map(|x| x.iter())
+            .chain(
+                resource
+                    .relative_path_distribution_resources
+                    .iter()
+                    .flat_map(|x| x.iter().map(|(k, (_, l))| (k, l))),
+            )
+            .map(|(k, l)| (k.as_str(), l))
+            .collect::<BTreeMap<_, _>>();
+
+        let location = match files.get("METADATA").or_else(|| files.get("PKG-INFO")) {
+            Some(location) => location,
+            None => continue,
+        };
+
+        let data = location
+            .resolve()
+            .with_context(|| format!("reading distribution metadata of {}", resource_name))?;
+        let metadata = PythonPackageMetadata::from_metadata(&data)
+            .with_context(|| format!("parsing distribution metadata of {}", resource_name))?;
+
+        match metadata.name() {
+            Some(value) if normalize(value) == wanted => {}
+            _ => continue,
+        }
+
+        let data = files
+            .get("entry_points.txt")
+            .ok_or_else(|| anyhow!("distribution {} does not define entry points", distribution))?
+            .resolve()
+            .with_context(|| format!("reading entry points of {}", resource_name))?;
+
+        return parse_entry_points(&data)
+            .with_context(|| format!("parsing entry points of {}", resource_name))?
+            .into_iter()
+            .find(|entry_point| entry_point.group == "console_scripts" && entry_point.name == name)
+            .ok_or_else(|| {
+                anyhow!(
+                    "distribution {} does not define a console_scripts entry point named {}",
+                    distribution,
+                    name
+                )
+            });
+    }
+
+    Err(anyhow!(
+        "distribution {} not found in executable resources",
+        distribution
+    ))
+}
+
+/// Find resources added to a binary builder that belong to test packages.
+///
+/// A resource belongs to a test package if any component of its dotted
+/// name is in `package_names`. e.g. `foo.tests.test_bar` belongs to `tests`.
+pub fn test_package_resource_names(
+    builder: &dyn PythonBinaryBuilder,
+    package_names: &[String],
+) -> BTreeSet<String> {
+    builder
+        .iter_resources()
+        .filter(|(name, _)| {
+            name.split('.')
+                .any(|component| package_names.iter().any(|p| p == component))
+        })
+        .map(|(name, _)| name.clone())
+        .collect()
+}
+
+/// Range of zstd compression levels accepted for packed resources data.
+pub const PACKED_RESOURCES_COMPRESSION_LEVELS: std::ops::RangeInclusive<i32> = 1..=22;
+
+/// Compress packed resources data with zstd.
+///
+/// The returned data has a header identifying it as compressed, allowing
+/// `pyembed` to transparently decompress it when loading resources.
+pub fn compress_packed_resources(data: &[u8], level: i32) -> Result<Vec<u8>> {
+    let mut compressed = HEADER_ZSTD.to_vec();
+    compressed.extend(
+        zstd::stream::encode_all(std::io::Cursor::new(data), level)
+            .context("compressing packed resources data")?,
+    );
+
+    Ok(compressed)
+}
+
 /// Holds context necessary to embed Python in a binary.
 pub struct EmbeddedPythonContext {
     /// The configuration for the embedded interpreter.
@@ -289,9 +1039,109 @@ pub struct EmbeddedPythonContext {
 
     /// Rust target triple for the target we are building for.
     pub target_triple: String,
+
+    /// How long it took to compile bytecode for resources.
+    ///
+    /// This does not influence the built binary.
+    pub bytecode_compile_duration: Duration,
 }
 
 impl EmbeddedPythonContext {
+    /// Estimate the size of a binary built from this context.
+    pub fn size_estimate(&self) -> EmbeddedPythonSizeEstimate {
+        let resource_count = self
+            .module_names
+            .split(|c| *c == b'\n')
+            .filter(|name| !name.is_empty())
+            .count();
+
+        let libpython_size = self.linking_info.libpythonxy_data.len() as u64
+            + self
+                .linking_info
+                .libpyembeddedconfig_data
+                .as_ref()
+                .map_or(0, |data| data.len() as u64);
+
+        let (extra_files_count, extra_files_size) = self
+            .extra_files
+            .entries()
+            .fold((0, 0), |(count, size), (_, content)| {
+                (count + 1, size + content.data.len() as u64)
+            });
+
+        EmbeddedPythonSizeEstimate {
+            resource_count,
+            resources_size: self.resources.len() as u64,
+            libpython_size,
+            extra_files_count,
+            extra_files_size,
+        }
+    }
+
+    /// Compute a fingerprint of the content that would be embedded in a binary.
+    ///
+    /// The fingerprint is a hex encoded SHA-256 digest of the interpreter
+    /// configuration, resources, linking info, extra files, and target
+    /// triples. It changes whenever any of these change.
+    pub fn fingerprint(&self) -> Result<String> {
+        let mut hasher = Sha256::new();
+
+        // Prefix each field with its length so adjacent fields can't be
+        // confused with one another.
+        let mut update = |data: &[u8]| {
+            hasher.update(&(data.len() as u64).to_le_bytes());
+            hasher.update(data);
+        };
+
+        update(
+            self.config
+                .to_oxidized_python_interpreter_config_rs(None)?
+                .as_bytes(),
+        );
+        update(&self.module_names);
+        update(&self.resources);
+        update(
+            self.linking_info
+                .libpythonxy_filename
+                .display()
+                .to_string()
+                .as_bytes(),
+        );
+        update(&self.linking_info.libpythonxy_data);
+        update(
+            self.linking_info
+                .libpython_filename
+                .as_ref()
+                .map_or(String::new(), |p| p.display().to_string())
+                .as_bytes(),
+        );
+        update(
+            self.linking_info
+                .libpyembeddedconfig_filename
+                .as_ref()
+                .map_or(String::new(), |p| p.display().to_string())
+                .as_bytes(),
+        );
+        update(
+            self.linking_info
+                .libpyembeddedconfig_data
+                .as_ref()
+                .map_or(&[][..], |data| data.as_slice()),
+        );
+        update(self.linking_info.cargo_metadata.join("\n").as_bytes());
+
+        for (path, content) in self.extra_files.entries() {
+            update(path.display().to_string().as_bytes());
+            update(&content.data);
+            update(&[content.executable as u8]);
+        }
+
+        update(self.host_triple.as_bytes());
+        update(self.target_triple.as_bytes());
+
+        Ok(hex::encode(hasher.finalize()))
+    }
+
     /// Write out files needed to link a binary.
     pub fn write_files(&self, dest_dir: &Path) -> Result<EmbeddedPythonPaths> {
         let module_names = dest_dir.join("py-module-names");
@@ -7,7 +7,8 @@ Configuring a Python interpreter.
 */
 
 use {
-    anyhow::Result,
+    crate::app_packaging::windows::is_windows_target,
+    anyhow::{anyhow, Result},
     itertools::Itertools,
     python_packaging::{
         interpreter::{
@@ -17,6 +18,8 @@ use {
         resource::BytecodeOptimizationLevel,
     },
     std::{
+        collections::BTreeMap,
+        ffi::OsString,
         io::Write,
         path::{Path, PathBuf},
     },
@@ -44,14 +47,62 @@ fn optional_bool_to_string(value: &Option<bool>) -> String {
 
 fn optional_string_to_string(value: &Option<String>) -> String {
     match value {
-        Some(value) => format_args!("Some(\"{}\")", value).to_string(),
+        Some(value) => format_args!("Some(r#\"{}\"#.to_string())", value).to_string(),
         None => "None".to_string(),
     }
 }
 
 fn optional_pathbuf_to_string(value: &Option<PathBuf>) -> String {
     match value {
-        Some(value) => format_args!("Some(PathBuf::from(\"{}\"", value.display()).to_string(),
+        Some(value) => {
+            format_args!("Some(std::path::PathBuf::from(r#\"{}\"#))", value.display()).to_string()
+        }
+        None => "None".to_string(),
+    }
+}
+
+fn environment_variables_to_string(value: &[(String, String)]) -> String {
+    format!(
+        "vec![{}]",
+        value
+            .iter()
+            .map(|(name, value)| format!("({:?}.to_string(), {:?}.to_string())", name, value))
+            .collect::<Vec<_>>()
+            .join(", ")
+    )
+}
+
+fn frozen_modules_to_string(value: &BTreeMap<String, FrozenModuleBytecode>) -> String {
+    if value.is_empty() {
+        return "None".to_string();
+    }
+
+    format!(
+        "Some(vec![{}])",
+        value
+            .iter()
+            .map(|(name, module)| {
+                format!(
+                    "pyembed::FrozenModule {{ name: std::ffi::CString::new(r#\"{}\"#).unwrap(), bytecode: &[{}], is_package: {} }}",
+                    name,
+                    module.bytecode.iter().map(|b| b.to_string()).join(", "),
+                    module.is_package
+                )
+            })
+            .join(", ")
+    )
+}
+
+fn optional_vec_os_string_to_string(value: &Option<Vec<OsString>>) -> String {
+    match value {
+        Some(value) => format!(
+            "Some(vec![{}])",
+            value
+                .iter()
+                .map(|x| format!("std::ffi::OsString::from({:?})", x.to_string_lossy()))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
         None => "None".to_string(),
     }
 }
@@ -59,10 +110,10 @@ fn optional_pathbuf_to_string(value: &Option<PathBuf>) -> String {
 fn optional_vec_string_to_string(value: &Option<Vec<String>>) -> String {
     match value {
         Some(value) => format!(
-            "Some({})",
+            "Some(vec![{}])",
             value
                 .iter()
-                .map(|x| format_args!("\"{}\"", x).to_string())
+                .map(|x| format!("{:?}.to_string()", x))
                 .collect::<Vec<_>>()
                 .join(", ")
         ),
@@ -70,6 +121,16 @@ fn optional_vec_string_to_string(value: &Option<Vec<String>>) -> String {
     }
 }
 
+/// Bytecode for a module registered in the interpreter's frozen modules table.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FrozenModuleBytecode {
+    /// Marshalled code object for the module.
+    pub bytecode: Vec<u8>,
+
+    /// Whether the module is a package.
+    pub is_package: bool,
+}
+
 /// Represents the run-time configuration of a Python interpreter.
 ///
 /// This type mirrors `pyembed::OxidizedPythonInterpreterConfig`. We can't
@@ -87,6 +148,11 @@ pub struct EmbeddedPythonConfig {
     pub sys_meipass: bool,
     pub terminfo_resolution: TerminfoResolution,
     pub write_modules_directory_env: Option<String>,
+    pub multiprocessing_auto_dispatch: bool,
+    pub fault_handler_path: Option<String>,
+    pub packed_resources_bundle: Option<String>,
+    pub environment_variables: Vec<(String, String)>,
+    pub frozen_modules: BTreeMap<String, FrozenModuleBytecode>,
     pub run_mode: PythonRunMode,
 }
 
@@ -105,17 +171,142 @@ impl Default for EmbeddedPythonConfig {
             sys_meipass: false,
             terminfo_resolution: TerminfoResolution::None,
             write_modules_directory_env: None,
+            multiprocessing_auto_dispatch: false,
+            fault_handler_path: None,
+            packed_resources_bundle: None,
+            environment_variables: vec![],
+            frozen_modules: BTreeMap::new(),
             run_mode: PythonRunMode::Repl,
         }
     }
 }
 
 impl EmbeddedPythonConfig {
+    /// Obtain descriptions of settings that contradict the interpreter profile.
+    ///
+    /// The `python` profile is meant to behave like a `python` executable and
+    /// honor the user's environment. Explicitly enabling isolation, disabling
+    /// use of the environment, or disabling the signal handlers `python`
+    /// installs undermines that. Conversely, the `isolated` profile is meant to
+    /// ignore the user's environment, which enabling both `site_import` and
+    /// `user_site_directory` undermines.
+    pub fn profile_conflicts(&self) -> Vec<String> {
+        let mut conflicts = vec![];
+
+        if self.config.profile == PythonInterpreterProfile::Python {
+            if self.config.isolated == Some(true) {
+                conflicts.push(
+                    "isolated is enabled but the python profile is meant to honor the user environment"
+                        .to_string(),
+                );
+            }
+
+            if self.config.use_environment == Some(false) {
+                conflicts.push(
+                    "use_environment is disabled but the python profile is meant to honor the user environment"
+                        .to_string(),
+                );
+            }
+
+            if self.config.install_signal_handlers == Some(false) {
+                conflicts.push(
+                    "install_signal_handlers is disabled but the python profile is meant to install signal handlers like python does"
+                        .to_string(),
+                );
+            }
+        } else if self.config.site_import == Some(true)
+            && self.config.user_site_directory == Some(true)
+        {
+            conflicts.push(
+                "site_import and user_site_directory are enabled but the isolated profile is meant to ignore the user environment; the user site-packages directory will be added to sys.path"
+                    .to_string(),
+            );
+        }
+
+        conflicts
+    }
+
+    /// Describe module search paths that won't be used for importing.
+    ///
+    /// Module search paths are consumed by the filesystem importer. If it is
+    /// disabled, paths that remain registered are likely a mistake.
+    pub fn filesystem_importer_conflicts(&self) -> Vec<String> {
+        match &self.config.module_search_paths {
+            Some(paths) if !paths.is_empty() && !self.filesystem_importer => vec![format!(
+                "module_search_paths has {} entries but filesystem_importer is disabled; modules won't be imported from them",
+                paths.len()
+            )],
+            _ => vec![],
+        }
+    }
+
+    /// Describe settings that only apply to the interactive interpreter.
+    ///
+    /// `inspect` and `interactive` influence Python's REPL. Other run modes
+    /// never start one, so enabling these settings with them has no effect.
+    pub fn run_mode_conflicts(&self) -> Vec<String> {
+        if self.run_mode == PythonRunMode::Repl {
+            return vec![];
+        }
+
+        let mut conflicts = vec![];
+
+        if self.config.inspect == Some(true) {
+            conflicts.push(format!(
+                "inspect is enabled but run_mode is {}; the interactive interpreter is only started by the repl run mode",
+                self.run_mode.to_string()
+            ));
+        }
+
+        if self.config.interactive == Some(true) {
+            conflicts.push(format!(
+                "interactive is enabled but run_mode is {}; the interactive interpreter is only started by the repl run mode",
+                self.run_mode.to_string()
+            ));
+        }
+
+        conflicts
+    }
+
+    /// Describe settings that have no effect on the given target triple.
+    ///
+    /// The legacy Windows settings are ignored by interpreters on other
+    /// platforms.
+    pub fn target_conflicts(&self, target_triple: &str) -> Vec<String> {
+        if is_windows_target(target_triple) {
+            return vec![];
+        }
+
+        let mut conflicts = vec![];
+
+        if self.config.legacy_windows_fs_encoding.is_some() {
+            conflicts.push(format!(
+                "legacy_windows_fs_encoding is set but has no effect on non-Windows target {}",
+                target_triple
+            ));
+        }
+
+        if self.config.legacy_windows_stdio.is_some() {
+            conflicts.push(format!(
+                "legacy_windows_stdio is set but has no effect on non-Windows target {}",
+                target_triple
+            ));
+        }
+
+        conflicts
+    }
+
     /// Convert the instance to Rust code that constructs a `pyembed::OxidizedPythonInterpreterConfig`.
     pub fn to_oxidized_python_interpreter_config_rs(
         &self,
         packed_resources_path: Option<&Path>,
     ) -> Result<String> {
+        if self.multiprocessing_auto_dispatch && self.run_mode == PythonRunMode::None {
+            return Err(anyhow!(
+                "multiprocessing_auto_dispatch cannot be used with a run mode of none"
+            ));
+        }
+
         let code = format!(
             "pyembed::OxidizedPythonInterpreterConfig {{\n    \
             origin: None,\n    \
@@ -131,7 +322,7 @@ impl EmbeddedPythonConfig {
             parse_argv: {},\n        \
             use_environment: {},\n        \
             utf8_mode: {},\n        \
-            argv: None,\n        \
+            argv: {},\n        \
             base_exec_prefix: {},\n        \
             base_executable: {},\n        \
             base_prefix: {},\n        \
@@ -182,12 +373,17 @@ impl EmbeddedPythonConfig {
             oxidized_importer: {},\n    \
             filesystem_importer: {},\n    \
             packed_resources: {},\n    \
+            packed_resources_bundle: {},\n    \
             extra_extension_modules: None,\n    \
+            extra_frozen_modules: {},\n    \
             argvb: {},\n    \
             sys_frozen: {},\n    \
             sys_meipass: {},\n    \
             terminfo_resolution: {},\n    \
             write_modules_directory_env: {},\n    \
+            multiprocessing_auto_dispatch: {},\n    \
+            fault_handler_path: {},\n    \
+            environment_variables: {},\n    \
             run: {},\n\
             }}\n\
             ",
@@ -218,6 +414,7 @@ impl EmbeddedPythonConfig {
             optional_bool_to_string(&self.config.parse_argv),
             optional_bool_to_string(&self.config.use_environment),
             optional_bool_to_string(&self.config.utf8_mode),
+            optional_vec_os_string_to_string(&self.config.argv),
             optional_pathbuf_to_string(&self.config.base_exec_prefix),
             optional_pathbuf_to_string(&self.config.base_executable),
             optional_pathbuf_to_string(&self.config.base_prefix),
@@ -255,10 +452,10 @@ impl EmbeddedPythonConfig {
             match &self.config.module_search_paths {
                 Some(paths) => {
                     format!(
-                        "Some({})",
+                        "Some(vec![{}])",
                         paths
                             .iter()
-                            .map(|p| format_args!("\"{}\"", p.display()).to_string())
+                            .map(|p| format!("std::path::PathBuf::from({:?})", p.to_string_lossy()))
                             .collect::<Vec<String>>()
                             .join(", ")
                     )
@@ -299,15 +496,20 @@ impl EmbeddedPythonConfig {
             match self.raw_allocator {
                 MemoryAllocatorBackend::Jemalloc => "pyembed::PythonRawAllocator::jemalloc()",
                 MemoryAllocatorBackend::Rust => "pyembed::PythonRawAllocator::rust()",
+                MemoryAllocatorBackend::Snmalloc => "pyembed::PythonRawAllocator::snmalloc()",
                 MemoryAllocatorBackend::System => "pyembed::PythonRawAllocator::system()",
             },
             self.oxidized_importer,
             self.filesystem_importer,
-            if let Some(path) = packed_resources_path {
-                format!("Some(include_bytes!(r#\"{}\"#))", path.display())
-            } else {
-                "None".to_string()
+            // Resources are loaded from the bundle instead of being embedded.
+            match packed_resources_path {
+                Some(path) if self.packed_resources_bundle.is_none() => {
+                    format!("Some(include_bytes!(r#\"{}\"#))", path.display())
+                }
+                _ => "None".to_string(),
             },
+            optional_string_to_string(&self.packed_resources_bundle),
+            frozen_modules_to_string(&self.frozen_modules),
             self.argvb,
             self.sys_frozen,
             self.sys_meipass,
@@ -315,10 +517,13 @@ impl EmbeddedPythonConfig {
                 TerminfoResolution::Dynamic => "pyembed::TerminfoResolution::Dynamic".to_string(),
                 TerminfoResolution::None => "pyembed::TerminfoResolution::None".to_string(),
                 TerminfoResolution::Static(ref v) => {
-                    format!("pyembed::TerminfoResolution::Static(r###\"{}\"###", v)
+                    format!("pyembed::TerminfoResolution::Static({:?}.to_string())", v)
                 }
             },
             optional_string_to_string(&self.write_modules_directory_env),
+            self.multiprocessing_auto_dispatch,
+            optional_string_to_string(&self.fault_handler_path),
+            environment_variables_to_string(&self.environment_variables),
             match self.run_mode {
                 PythonRunMode::None => "pyembed::PythonRunMode::None".to_owned(),
                 PythonRunMode::Repl => "pyembed::PythonRunMode::Repl".to_owned(),
@@ -336,6 +541,10 @@ impl EmbeddedPythonConfig {
                     format!("pyembed::PythonRunMode::File {{ path: std::path::PathBuf::new(r###\"{}\"###) }}",
                     path.display())
                 }
+                PythonRunMode::Resource { ref name } => format!(
+                    "pyembed::PythonRunMode::Resource {{ name: {:?}.to_string() }}",
+                    name
+                ),
             },
         );
 
@@ -369,3 +578,241 @@ impl EmbeddedPythonConfig {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_raw_allocator_snmalloc() -> Result<()> {
+        let config = EmbeddedPythonConfig {
+            raw_allocator: MemoryAllocatorBackend::Snmalloc,
+            ..EmbeddedPythonConfig::default()
+        };
+
+        let code = config.to_oxidized_python_interpreter_config_rs(None)?;
+        assert!(code.contains("raw_allocator: Some(pyembed::PythonRawAllocator::snmalloc()),"));
+
+        // snmalloc is only used when explicitly requested.
+        for triple in &[
+            "x86_64-unknown-linux-gnu",
+            "x86_64-apple-darwin",
+            "x86_64-pc-windows-msvc",
+        ] {
+            assert_ne!(
+                default_raw_allocator(triple),
+                MemoryAllocatorBackend::Snmalloc
+            );
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_profile_conflicts() {
+        let mut config = EmbeddedPythonConfig::default();
+        config.config.isolated = Some(true);
+        config.config.use_environment = Some(false);
+        config.config.install_signal_handlers = Some(false);
+        assert!(config.profile_conflicts().is_empty());
+
+        config.config.profile = PythonInterpreterProfile::Python;
+        assert_eq!(config.profile_conflicts().len(), 3);
+
+        config.config.isolated = None;
+        config.config.use_environment = Some(true);
+        config.config.install_signal_handlers = None;
+        assert!(config.profile_conflicts().is_empty());
+
+        config.config.site_import = Some(true);
+        config.config.user_site_directory = Some(true);
+        assert!(config.profile_conflicts().is_empty());
+
+        config.config.profile = PythonInterpreterProfile::Isolated;
+        assert_eq!(config.profile_conflicts().len(), 1);
+
+        config.config.site_import = Some(false);
+        assert!(config.profile_conflicts().is_empty());
+    }
+
+    #[test]
+    fn test_filesystem_importer_conflicts() {
+        let mut config = EmbeddedPythonConfig::default();
+        assert!(config.filesystem_importer_conflicts().is_empty());
+
+        config.config.module_search_paths = Some(vec![]);
+        assert!(config.filesystem_importer_conflicts().is_empty());
+
+        config.config.module_search_paths = Some(vec![PathBuf::from("$ORIGIN/lib")]);
+        assert_eq!(config.filesystem_importer_conflicts().len(), 1);
+
+        config.filesystem_importer = true;
+        assert!(config.filesystem_importer_conflicts().is_empty());
+    }
+
+    #[test]
+    fn test_module_search_paths_escaped() -> Result<()> {
+        let mut config = EmbeddedPythonConfig::default();
+        config.filesystem_importer = true;
+        config.config.module_search_paths = Some(vec![
+            PathBuf::from("$ORIGIN/lib"),
+            PathBuf::from("C:\\Program Files\\\"app\""),
+        ]);
+
+        let code = config.to_oxidized_python_interpreter_config_rs(None)?;
+        assert!(code.contains(
+            "module_search_paths: Some(vec![std::path::PathBuf::from(\"$ORIGIN/lib\"), std::path::PathBuf::from(\"C:\\\\Program Files\\\\\\\"app\\\"\")]),"
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_run_mode_conflicts() {
+        let mut config = EmbeddedPythonConfig::default();
+        config.config.inspect = Some(true);
+        config.config.interactive = Some(true);
+        assert_eq!(config.run_mode, PythonRunMode::Repl);
+        assert!(config.run_mode_conflicts().is_empty());
+
+        config.run_mode = PythonRunMode::Module {
+            module: "foo".to_string(),
+        };
+        assert_eq!(config.run_mode_conflicts().len(), 2);
+
+        config.config.inspect = Some(false);
+        config.config.interactive = None;
+        assert!(config.run_mode_conflicts().is_empty());
+    }
+
+    #[test]
+    fn test_target_conflicts() {
+        let mut config = EmbeddedPythonConfig::default();
+        assert!(config
+            .target_conflicts("x86_64-unknown-linux-gnu")
+            .is_empty());
+
+        config.config.legacy_windows_fs_encoding = Some(true);
+        config.config.legacy_windows_stdio = Some(false);
+        assert_eq!(config.target_conflicts("x86_64-unknown-linux-gnu").len(), 2);
+        assert!(config.target_conflicts("x86_64-pc-windows-msvc").is_empty());
+    }
+
+    #[test]
+    fn test_multiprocessing_auto_dispatch() -> Result<()> {
+        let config = EmbeddedPythonConfig {
+            multiprocessing_auto_dispatch: true,
+            ..EmbeddedPythonConfig::default()
+        };
+
+        let code = config.to_oxidized_python_interpreter_config_rs(None)?;
+        assert!(code.contains("multiprocessing_auto_dispatch: true,"));
+
+        let config = EmbeddedPythonConfig {
+            multiprocessing_auto_dispatch: true,
+            run_mode: PythonRunMode::None,
+            ..EmbeddedPythonConfig::default()
+        };
+        assert!(config
+            .to_oxidized_python_interpreter_config_rs(None)
+            .is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_environment_variables() -> Result<()> {
+        let config = EmbeddedPythonConfig::default();
+
+        let code = config.to_oxidized_python_interpreter_config_rs(None)?;
+        assert!(code.contains("environment_variables: vec![],"));
+
+        let config = EmbeddedPythonConfig {
+            environment_variables: vec![
+                (
+                    "SSL_CERT_FILE".to_string(),
+                    "$ORIGIN/cacert.pem".to_string(),
+                ),
+                ("MYAPP_MODE".to_string(), "\"#frozen\\".to_string()),
+            ],
+            ..EmbeddedPythonConfig::default()
+        };
+
+        let code = config.to_oxidized_python_interpreter_config_rs(None)?;
+        assert!(code.contains(
+            "environment_variables: vec![(\"SSL_CERT_FILE\".to_string(), \"$ORIGIN/cacert.pem\".to_string()), (\"MYAPP_MODE\".to_string(), \"\\\"#frozen\\\\\".to_string())],"
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_packed_resources_bundle() -> Result<()> {
+        let resources_path = Path::new("packed-resources");
+
+        let config = EmbeddedPythonConfig::default();
+
+        let code = config.to_oxidized_python_interpreter_config_rs(Some(resources_path))?;
+        assert!(code.contains("packed_resources: Some(include_bytes!(r#\"packed-resources\"#)),"));
+        assert!(code.contains("packed_resources_bundle: None,"));
+
+        let config = EmbeddedPythonConfig {
+            packed_resources_bundle: Some("$ORIGIN/myapp.pyoxidizer".to_string()),
+            ..EmbeddedPythonConfig::default()
+        };
+
+        let code = config.to_oxidized_python_interpreter_config_rs(Some(resources_path))?;
+        assert!(code.contains("packed_resources: None,"));
+        assert!(code.contains(
+            "packed_resources_bundle: Some(r#\"$ORIGIN/myapp.pyoxidizer\"#.to_string()),"
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_frozen_modules() -> Result<()> {
+        let config = EmbeddedPythonConfig::default();
+
+        let code = config.to_oxidized_python_interpreter_config_rs(None)?;
+        assert!(code.contains("extra_frozen_modules: None,"));
+
+        let mut frozen_modules = BTreeMap::new();
+        frozen_modules.insert(
+            "foo.bar".to_string(),
+            FrozenModuleBytecode {
+                bytecode: vec![0xe3, 0x00, 0x01],
+                is_package: true,
+            },
+        );
+
+        let config = EmbeddedPythonConfig {
+            frozen_modules,
+            ..EmbeddedPythonConfig::default()
+        };
+
+        let code = config.to_oxidized_python_interpreter_config_rs(None)?;
+        assert!(code.contains(
+            "extra_frozen_modules: Some(vec![pyembed::FrozenModule { name: std::ffi::CString::new(r#\"foo.bar\"#).unwrap(), bytecode: &[227, 0, 1], is_package: true }]),"
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_run_mode_resource() -> Result<()> {
+        let config = EmbeddedPythonConfig {
+            run_mode: PythonRunMode::Resource {
+                name: "myapp.main".to_string(),
+            },
+            ..EmbeddedPythonConfig::default()
+        };
+
+        let code = config.to_oxidized_python_interpreter_config_rs(None)?;
+        assert!(code.contains(
+            "run: pyembed::PythonRunMode::Resource { name: \"myapp.main\".to_string() },"
+        ));
+
+        Ok(())
+    }
+}
@@ -12,9 +12,13 @@ use {
         config::EmbeddedPythonConfig,
         standalone_distribution::StandaloneDistribution,
     },
-    crate::python_distributions::PYTHON_DISTRIBUTIONS,
+    crate::python_distributions::{
+        PythonDistributionCollection, DEFAULT_PYTHON_VERSION, PYTHON_DISTRIBUTIONS,
+    },
     anyhow::{anyhow, Context, Result},
     fs2::FileExt,
+    itertools::Itertools,
+    lazy_static::lazy_static,
     python_packaging::{
         bytecode::PythonBytecodeCompiler,
         module_util::PythonModuleSuffixes,
@@ -24,13 +28,15 @@ use {
     sha2::{Digest, Sha256},
     slog::warn,
     std::{
-        collections::HashMap,
+        collections::{BTreeMap, HashMap},
         convert::TryFrom,
         fs,
         fs::{create_dir_all, File},
         io::Read,
+        ops::Deref,
         path::{Path, PathBuf},
-        sync::Arc,
+        process::Command,
+        sync::{Arc, Mutex},
     },
     url::Url,
     uuid::Uuid,
@@ -77,8 +83,19 @@ pub enum BinaryLibpythonLinkMode {
 
 #[derive(Clone, Debug, Eq, Hash, PartialEq)]
 pub enum PythonDistributionLocation {
-    Local { local_path: String, sha256: String },
-    Url { url: String, sha256: String },
+    Local {
+        local_path: String,
+        sha256: String,
+    },
+    Url {
+        url: String,
+        sha256: String,
+    },
+    /// Mirror URLs, tried in order until one provides a matching archive.
+    Urls {
+        urls: Vec<String>,
+        sha256: String,
+    },
 }
 
 /// Describes an obtainable Python distribution.
@@ -87,6 +104,9 @@ pub struct PythonDistributionRecord {
     /// X.Y major.minor version of Python.
     pub python_major_minor_version: String,
 
+    /// X.Y.Z full version of Python.
+    pub python_version: String,
+
     /// Where the distribution can be obtained from.
     pub location: PythonDistributionLocation,
 
@@ -270,36 +290,181 @@ fn sha256_path(path: &PathBuf) -> Vec<u8> {
     hasher.finalize().to_vec()
 }
 
-pub fn get_http_client() -> reqwest::Result<reqwest::blocking::Client> {
-    let mut builder = reqwest::blocking::ClientBuilder::new();
+/// Proxy settings for HTTP requests.
+///
+/// Follows the semantics of the `HTTP_PROXY`, `HTTPS_PROXY`, and `NO_PROXY`
+/// environment variables.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct HttpProxyConfig {
+    /// Proxy URL to use for `http://` URLs.
+    pub http_proxy: Option<String>,
 
-    for (key, value) in std::env::vars() {
-        let key = key.to_lowercase();
-        if key.ends_with("_proxy") {
-            let end = key.len() - "_proxy".len();
-            let schema = &key[..end];
+    /// Proxy URL to use for `https://` URLs.
+    pub https_proxy: Option<String>,
 
-            if let Ok(url) = Url::parse(&value) {
-                if let Some(proxy) = match schema {
-                    "http" => Some(reqwest::Proxy::http(url.as_str())),
-                    "https" => Some(reqwest::Proxy::https(url.as_str())),
-                    _ => None,
-                } {
-                    if let Ok(proxy) = proxy {
-                        builder = builder.proxy(proxy);
-                    }
-                }
-            }
+    /// Hosts that should be connected to directly, bypassing any proxy.
+    ///
+    /// An entry matches the host itself and all of its subdomains. `*`
+    /// matches all hosts.
+    pub no_proxy: Vec<String>,
+}
+
+impl HttpProxyConfig {
+    /// Construct an instance from `HTTP_PROXY`, `HTTPS_PROXY`, and `NO_PROXY`.
+    ///
+    /// Lowercase variants of the environment variables take precedence.
+    /// Proxy values that aren't valid URLs are ignored.
+    pub fn from_env() -> Self {
+        let var = |name: &str| {
+            std::env::var(name.to_lowercase())
+                .or_else(|_| std::env::var(name))
+                .ok()
+                .filter(|value| !value.is_empty())
+        };
+
+        Self {
+            http_proxy: var("HTTP_PROXY").filter(|value| Url::parse(value).is_ok()),
+            https_proxy: var("HTTPS_PROXY").filter(|value| Url::parse(value).is_ok()),
+            no_proxy: var("NO_PROXY")
+                .map(|value| Self::parse_no_proxy(&value))
+                .unwrap_or_default(),
+        }
+    }
+
+    /// Parse a comma delimited `NO_PROXY` value into a list of hosts.
+    pub fn parse_no_proxy(value: &str) -> Vec<String> {
+        value
+            .split(',')
+            .map(|entry| entry.trim())
+            .filter(|entry| !entry.is_empty())
+            .map(|entry| entry.to_string())
+            .collect()
+    }
+
+    /// Whether connections to `host` should bypass the proxy.
+    pub fn is_excluded(&self, host: &str) -> bool {
+        let host = host.to_lowercase();
+
+        self.no_proxy.iter().any(|entry| {
+            let entry = entry.trim_start_matches('.').to_lowercase();
+
+            entry == "*" || host == entry || host.ends_with(&format!(".{}", entry))
+        })
+    }
+
+    /// Resolve the proxy URL to use for a request with a scheme and host.
+    pub fn proxy_for(&self, scheme: &str, host: &str) -> Option<&str> {
+        if self.is_excluded(host) {
+            return None;
+        }
+
+        match scheme {
+            "http" => self.http_proxy.as_deref(),
+            "https" => self.https_proxy.as_deref(),
+            _ => None,
+        }
+    }
+
+    /// Environment variables conveying these settings to a child process.
+    ///
+    /// Uppercase and lowercase variants are defined. Variables without a
+    /// value should be removed from the child's environment, so it doesn't
+    /// use a proxy inherited from our environment that these settings don't
+    /// define.
+    pub fn environment_variables(&self) -> Vec<(String, Option<String>)> {
+        let no_proxy = if self.no_proxy.is_empty() {
+            None
+        } else {
+            Some(self.no_proxy.join(","))
+        };
+
+        let mut res = vec![];
+
+        for (name, value) in &[
+            ("HTTP_PROXY", &self.http_proxy),
+            ("HTTPS_PROXY", &self.https_proxy),
+            ("NO_PROXY", &no_proxy),
+        ] {
+            res.push((name.to_string(), (*value).clone()));
+            res.push((name.to_lowercase(), (*value).clone()));
+        }
+
+        res
+    }
+
+    /// Configure a command to run with these proxy settings.
+    pub fn apply_to_command(&self, command: &mut Command) {
+        for (name, value) in self.environment_variables() {
+            match value {
+                Some(value) => command.env(name, value),
+                None => command.env_remove(name),
+            };
         }
     }
+}
+
+/// Obtain an HTTP client that honors proxy settings.
+pub fn get_http_client(proxy: &HttpProxyConfig) -> reqwest::Result<reqwest::blocking::Client> {
+    let proxy = proxy.clone();
+
+    // Registering a proxy disables reqwest's own environment variable handling,
+    // so `proxy` is the sole source of proxy settings.
+    reqwest::blocking::ClientBuilder::new()
+        .proxy(reqwest::Proxy::custom(move |url| {
+            proxy
+                .proxy_for(url.scheme(), url.host_str().unwrap_or(""))
+                .and_then(|value| reqwest::Url::parse(value).ok())
+        }))
+        .build()
+}
+
+/// Whether a Python distribution location can be resolved without network access.
+///
+/// Local distributions are always available. URL distributions are only
+/// available if a file with a matching hash is already present in `cache_dir`.
+pub fn distribution_available_offline(
+    location: &PythonDistributionLocation,
+    cache_dir: &Path,
+) -> bool {
+    let url_available = |url: &str, sha256: &str| -> bool {
+        let expected_hash = match hex::decode(sha256) {
+            Ok(h) => h,
+            Err(_) => return false,
+        };
+
+        let u = match Url::parse(url) {
+            Ok(u) => u,
+            Err(_) => return false,
+        };
+
+        let basename = match u.path_segments().and_then(|s| s.last()) {
+            Some(name) if !name.is_empty() => name.to_string(),
+            _ => return false,
+        };
+
+        let cache_path = cache_dir.join(basename);
+
+        cache_path.exists() && sha256_path(&cache_path) == expected_hash
+    };
 
-    builder.build()
+    match location {
+        PythonDistributionLocation::Local { .. } => true,
+        PythonDistributionLocation::Url { url, sha256 } => url_available(url, sha256),
+        PythonDistributionLocation::Urls { urls, sha256 } => {
+            urls.iter().any(|url| url_available(url, sha256))
+        }
+    }
 }
 
 /// Ensure a Python distribution at a URL is available in a local directory.
 ///
 /// The path to the downloaded and validated file is returned.
-pub fn download_distribution(url: &str, sha256: &str, cache_dir: &Path) -> Result<PathBuf> {
+pub fn download_distribution(
+    url: &str,
+    sha256: &str,
+    cache_dir: &Path,
+    proxy: &HttpProxyConfig,
+) -> Result<PathBuf> {
     let expected_hash = hex::decode(sha256)?;
     let u = Url::parse(url)?;
 
@@ -324,7 +489,7 @@ pub fn download_distribution(url: &str, sha256: &str, cache_dir: &Path) -> Resul
     let mut data: Vec<u8> = Vec::new();
 
     println!("downloading {}", u);
-    let client = get_http_client()?;
+    let client = get_http_client(proxy)?;
     let mut response = client.get(u.as_str()).send()?;
     response.read_to_end(&mut data)?;
 
@@ -347,7 +512,7 @@ pub fn download_distribution(url: &str, sha256: &str, cache_dir: &Path) -> Resul
                 .context("unable to remove temporary distribution file")?;
 
             if cache_path.exists() {
-                download_distribution(url, sha256, cache_dir)?;
+                download_distribution(url, sha256, cache_dir, proxy)?;
                 return Ok(());
             }
 
@@ -358,6 +523,41 @@ pub fn download_distribution(url: &str, sha256: &str, cache_dir: &Path) -> Resul
     Ok(cache_path)
 }
 
+/// Ensure a Python distribution available from several mirror URLs is available in a local directory.
+///
+/// URLs are tried in order until one provides content matching `sha256`.
+/// The path to the downloaded and validated file is returned.
+pub fn download_distribution_from_mirrors(
+    logger: &slog::Logger,
+    urls: &[String],
+    sha256: &str,
+    cache_dir: &Path,
+    proxy: &HttpProxyConfig,
+) -> Result<PathBuf> {
+    let mut failures = vec![];
+
+    for url in urls {
+        match download_distribution(url, sha256, cache_dir, proxy) {
+            Ok(path) => {
+                warn!(logger, "Python distribution obtained from mirror {}", url);
+                return Ok(path);
+            }
+            Err(e) => {
+                warn!(
+                    logger,
+                    "unable to obtain Python distribution from mirror {}: {}", url, e
+                );
+                failures.push(format!("{}: {}", url, e));
+            }
+        }
+    }
+
+    Err(anyhow!(
+        "unable to obtain Python distribution from any mirror ({})",
+        failures.join("; ")
+    ))
+}
+
 pub fn copy_local_distribution(path: &PathBuf, sha256: &str, cache_dir: &Path) -> Result<PathBuf> {
     let expected_hash = hex::decode(sha256)?;
     let basename = path.file_name().unwrap().to_str().unwrap().to_string();
@@ -397,8 +597,10 @@ pub fn copy_local_distribution(path: &PathBuf, sha256: &str, cache_dir: &Path) -
 ///
 /// Local filesystem paths are preferred over remote URLs if both are defined.
 pub fn resolve_python_distribution_archive(
+    logger: &slog::Logger,
     dist: &PythonDistributionLocation,
     cache_dir: &Path,
+    proxy: &HttpProxyConfig,
 ) -> Result<PathBuf> {
     if !cache_dir.exists() {
         create_dir_all(cache_dir).unwrap();
@@ -410,7 +612,10 @@ pub fn resolve_python_distribution_archive(
             copy_local_distribution(&p, sha256, cache_dir)
         }
         PythonDistributionLocation::Url { url, sha256 } => {
-            download_distribution(url, sha256, cache_dir)
+            download_distribution(url, sha256, cache_dir, proxy)
+        }
+        PythonDistributionLocation::Urls { urls, sha256 } => {
+            download_distribution_from_mirrors(logger, urls, sha256, cache_dir, proxy)
         }
     }
 }
@@ -422,9 +627,10 @@ pub fn resolve_python_distribution_from_location(
     logger: &slog::Logger,
     location: &PythonDistributionLocation,
     distributions_dir: &Path,
+    proxy: &HttpProxyConfig,
 ) -> Result<(PathBuf, PathBuf)> {
     warn!(logger, "resolving Python distribution {:?}", location);
-    let path = resolve_python_distribution_archive(location, distributions_dir)?;
+    let path = resolve_python_distribution_archive(logger, location, distributions_dir, proxy)?;
     warn!(
         logger,
         "Python distribution available at {}",
@@ -434,6 +640,7 @@ pub fn resolve_python_distribution_from_location(
     let distribution_hash = match location {
         PythonDistributionLocation::Local { sha256, .. } => sha256,
         PythonDistributionLocation::Url { sha256, .. } => sha256,
+        PythonDistributionLocation::Urls { sha256, .. } => sha256,
     };
 
     let distribution_path = distributions_dir.join(format!("python.{}", &distribution_hash[0..12]));
@@ -452,6 +659,9 @@ pub enum DistributionFlavor {
 
     /// Dynamically linked distributions coming from the `python-build-standalone` project.
     StandaloneDynamic,
+
+    /// Distributions of a flavor registered with a `DistributionFlavorRegistry`.
+    Custom(String),
 }
 
 impl Default for DistributionFlavor {
@@ -460,53 +670,349 @@ impl Default for DistributionFlavor {
     }
 }
 
+impl ToString for DistributionFlavor {
+    fn to_string(&self) -> String {
+        match self {
+            Self::Standalone => "standalone",
+            Self::StandaloneStatic => "standalone_static",
+            Self::StandaloneDynamic => "standalone_dynamic",
+            Self::Custom(name) => name.as_str(),
+        }
+        .to_string()
+    }
+}
+
 impl TryFrom<&str> for DistributionFlavor {
     type Error = String;
 
     fn try_from(value: &str) -> Result<Self, Self::Error> {
+        DistributionFlavorRegistry::default().resolve_flavor(value)
+    }
+}
+
+impl DistributionFlavor {
+    /// Resolve the name of a flavor built in to PyOxidizer.
+    fn builtin_from_name(value: &str) -> Option<Self> {
         match value {
-            "standalone" => Ok(Self::Standalone),
-            "standalone_static" | "standalone-static" => Ok(Self::StandaloneStatic),
-            "standalone_dynamic" | "standalone-dynamic" => Ok(Self::StandaloneDynamic),
-            _ => Err(format!("distribution flavor {} not recognized", value)),
+            "standalone" => Some(Self::Standalone),
+            "standalone_static" | "standalone-static" => Some(Self::StandaloneStatic),
+            "standalone_dynamic" | "standalone-dynamic" => Some(Self::StandaloneDynamic),
+            _ => None,
+        }
+    }
+
+    /// All distribution flavors built in to PyOxidizer.
+    pub fn all() -> Vec<Self> {
+        vec![
+            Self::Standalone,
+            Self::StandaloneStatic,
+            Self::StandaloneDynamic,
+        ]
+    }
+
+    /// Whether a distribution record provides this flavor.
+    pub fn matches_record(&self, record: &PythonDistributionRecord) -> bool {
+        match self {
+            Self::Standalone => true,
+            Self::StandaloneStatic => !record.supports_prebuilt_extension_modules,
+            Self::StandaloneDynamic => record.supports_prebuilt_extension_modules,
+            // Registered flavors have their own records.
+            Self::Custom(_) => true,
+        }
+    }
+}
+
+/// Holds distribution flavors registered in addition to the built-in flavors.
+///
+/// Each flavor is backed by its own distribution records, keyed by flavor name.
+#[derive(Clone, Default)]
+pub struct DistributionFlavorRegistry {
+    flavors: BTreeMap<String, Arc<PythonDistributionCollection>>,
+}
+
+impl std::fmt::Debug for DistributionFlavorRegistry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_set().entries(self.flavors.keys()).finish()
+    }
+}
+
+impl DistributionFlavorRegistry {
+    /// Register a distribution flavor backed by custom distribution records.
+    ///
+    /// Once registered, `name` is recognized as a flavor and distributions of
+    /// that flavor are found in `dists` instead of the built-in distributions.
+    /// `dists` should be ordered by preference. Registering a name again
+    /// replaces its records. Names of built-in flavors can't be registered.
+    pub fn register(&mut self, name: &str, dists: Vec<PythonDistributionRecord>) -> Result<()> {
+        if name.is_empty() {
+            return Err(anyhow!("distribution flavor name cannot be empty"));
+        }
+
+        if DistributionFlavor::builtin_from_name(name).is_some() {
+            return Err(anyhow!(
+                "distribution flavor {} conflicts with a built-in flavor",
+                name
+            ));
+        }
+
+        if dists.is_empty() {
+            return Err(anyhow!(
+                "distribution flavor {} must have at least 1 distribution",
+                name
+            ));
+        }
+
+        self.flavors.insert(
+            name.to_string(),
+            Arc::new(PythonDistributionCollection::new(dists)),
+        );
+
+        Ok(())
+    }
+
+    /// Resolve a flavor name to a built-in or registered flavor.
+    pub fn resolve_flavor(&self, value: &str) -> Result<DistributionFlavor, String> {
+        if let Some(flavor) = DistributionFlavor::builtin_from_name(value) {
+            Ok(flavor)
+        } else if self.flavors.contains_key(value) {
+            Ok(DistributionFlavor::Custom(value.to_string()))
+        } else {
+            Err(format!(
+                "distribution flavor {} not recognized; valid flavors: {}",
+                value,
+                DistributionFlavor::all()
+                    .iter()
+                    .map(|flavor| flavor.to_string())
+                    .chain(self.flavors.keys().cloned())
+                    .join(", ")
+            ))
+        }
+    }
+
+    /// Obtain the distribution records of a flavor.
+    ///
+    /// Built-in flavors resolve to the built-in distributions.
+    pub fn distributions(
+        &self,
+        flavor: &DistributionFlavor,
+    ) -> Result<&PythonDistributionCollection> {
+        match flavor {
+            DistributionFlavor::Custom(name) => self
+                .flavors
+                .get(name)
+                .map(|dists| dists.as_ref())
+                .ok_or_else(|| anyhow!("distribution flavor {} is not registered", name)),
+            _ => Ok(&*PYTHON_DISTRIBUTIONS),
+        }
+    }
+
+    /// Resolve the location of the default Python distribution of a flavor.
+    ///
+    /// Like `default_distribution_location()` but also handles registered flavors.
+    pub fn default_distribution_location(
+        &self,
+        flavor: &DistributionFlavor,
+        target: &str,
+        python_version: Option<&str>,
+    ) -> Result<PythonDistributionLocation> {
+        if let DistributionFlavor::Custom(name) = flavor {
+            let dists = self.distributions(flavor)?;
+
+            return dists
+                .find_distribution(target, flavor, python_version)
+                .map(|dist| dist.location)
+                .ok_or_else(|| {
+                    anyhow!(
+                        "could not find {} Python distribution for {} with version {}; available versions: {}",
+                        name,
+                        target,
+                        python_version.unwrap_or(DEFAULT_PYTHON_VERSION),
+                        dists.python_versions(target, flavor, None).join(", ")
+                    )
+                });
         }
+
+        default_distribution_location(flavor, target, python_version)
     }
 }
 
+/// Key identifying a resolved distribution in a `DistributionCache`.
+///
+/// Consists of the archive's SHA-256, the distribution flavor, and the
+/// directory the distribution is written to.
+type DistributionCacheKey = (String, String, PathBuf);
+
+/// Holds distributions that have already been resolved.
+///
+/// Resolving a distribution entails validating the archive, extracting it,
+/// and analyzing the extracted files. This can take a few seconds. Caching
+/// resolved distributions means this work is only performed once per process.
+#[derive(Debug, Default)]
+pub struct DistributionCache {
+    distributions:
+        Mutex<HashMap<DistributionCacheKey, Arc<Mutex<Option<Arc<StandaloneDistribution>>>>>>,
+}
+
+impl DistributionCache {
+    /// Resolve a distribution, reusing a previously resolved instance if available.
+    ///
+    /// Concurrent requests for the same distribution wait for the first request
+    /// to finish instead of extracting the archive again. Requests for different
+    /// distributions proceed in parallel.
+    pub fn resolve_distribution(
+        &self,
+        logger: &slog::Logger,
+        flavor: &DistributionFlavor,
+        location: &PythonDistributionLocation,
+        dest_dir: &Path,
+        proxy: &HttpProxyConfig,
+    ) -> Result<Arc<StandaloneDistribution>> {
+        let sha256 = match location {
+            PythonDistributionLocation::Local { sha256, .. } => sha256,
+            PythonDistributionLocation::Url { sha256, .. } => sha256,
+            PythonDistributionLocation::Urls { sha256, .. } => sha256,
+        };
+
+        let key = (sha256.clone(), flavor.to_string(), dest_dir.to_path_buf());
+
+        let entry = self
+            .distributions
+            .lock()
+            .unwrap()
+            .entry(key)
+            .or_insert_with(|| Arc::new(Mutex::new(None)))
+            .clone();
+
+        let mut entry = entry.lock().unwrap();
+
+        if let Some(dist) = entry.deref() {
+            return Ok(dist.clone());
+        }
+
+        let dist = Arc::new(StandaloneDistribution::from_location(
+            logger, location, dest_dir, proxy,
+        )?);
+        *entry = Some(dist.clone());
+
+        Ok(dist)
+    }
+}
+
+lazy_static! {
+    /// Process-wide cache of resolved distributions.
+    pub static ref DISTRIBUTION_CACHE: DistributionCache = DistributionCache::default();
+}
+
+/// Ensure a distribution location is permitted by an allowed hosts policy.
+///
+/// `allowed_hosts` of `None` permits every host. Otherwise URL locations must
+/// have a host in the list (compared case insensitively). Local paths are
+/// always permitted.
+pub fn ensure_location_host_allowed(
+    location: &PythonDistributionLocation,
+    allowed_hosts: Option<&[String]>,
+) -> Result<()> {
+    let allowed = match allowed_hosts {
+        Some(hosts) => hosts,
+        None => return Ok(()),
+    };
+
+    let urls = match location {
+        PythonDistributionLocation::Url { url, .. } => vec![url],
+        PythonDistributionLocation::Urls { urls, .. } => urls.iter().collect(),
+        PythonDistributionLocation::Local { .. } => return Ok(()),
+    };
+
+    for url in urls {
+        let host = Url::parse(url)
+            .ok()
+            .and_then(|url| url.host_str().map(|host| host.to_lowercase()))
+            .ok_or_else(|| {
+                anyhow!(
+                    "unable to determine host of Python distribution URL {}",
+                    url
+                )
+            })?;
+
+        if !allowed.iter().any(|x| x.to_lowercase() == host) {
+            return Err(anyhow!(
+                "host {} of Python distribution URL {} is not an allowed distribution host (allowed: {})",
+                host,
+                url,
+                allowed.join(", ")
+            ));
+        }
+    }
+
+    Ok(())
+}
+
 /// Obtain a `PythonDistribution` implementation of a flavor and from a location.
 ///
-/// The distribution will be written to `dest_dir`.
+/// The distribution will be written to `dest_dir`. Distributions are cached
+/// in `DISTRIBUTION_CACHE`, so resolving the same distribution again does not
+/// extract it again. Locations not permitted by `allowed_hosts` are refused
+/// before anything is downloaded.
 pub fn resolve_distribution(
     logger: &slog::Logger,
     flavor: &DistributionFlavor,
     location: &PythonDistributionLocation,
     dest_dir: &Path,
+    proxy: &HttpProxyConfig,
+    allowed_hosts: Option<&[String]>,
 ) -> Result<Box<dyn PythonDistribution>> {
-    // TODO is there a way we can define PythonDistribution::from_location()
-    Ok(match flavor {
-        DistributionFlavor::Standalone => Box::new(StandaloneDistribution::from_location(
-            logger, &location, dest_dir,
-        )?) as Box<dyn PythonDistribution>,
+    ensure_location_host_allowed(location, allowed_hosts)?;
 
-        DistributionFlavor::StandaloneStatic => Box::new(StandaloneDistribution::from_location(
-            logger, &location, dest_dir,
-        )?) as Box<dyn PythonDistribution>,
+    // TODO is there a way we can define PythonDistribution::from_location()
+    // All flavors are currently backed by StandaloneDistribution.
+    let dist =
+        DISTRIBUTION_CACHE.resolve_distribution(logger, flavor, location, dest_dir, proxy)?;
 
-        DistributionFlavor::StandaloneDynamic => Box::new(StandaloneDistribution::from_location(
-            logger, &location, dest_dir,
-        )?) as Box<dyn PythonDistribution>,
-    })
+    Ok(Box::new(dist.deref().clone()) as Box<dyn PythonDistribution>)
 }
 
 /// Resolve the location of the default Python distribution of a given flavor and build target.
+///
+/// `python_version` can be an `X.Y` or `X.Y.Z` version string.
 pub fn default_distribution_location(
     flavor: &DistributionFlavor,
     target: &str,
-    python_major_minor_version: Option<&str>,
+    python_version: Option<&str>,
 ) -> Result<PythonDistributionLocation> {
+    if let DistributionFlavor::Custom(name) = flavor {
+        return Err(anyhow!(
+            "distribution flavor {} must be resolved through its registry",
+            name
+        ));
+    }
+
     let dist = PYTHON_DISTRIBUTIONS
-        .find_distribution(target, flavor, python_major_minor_version)
-        .ok_or_else(|| anyhow!("could not find default Python distribution for {}", target))?;
+        .find_distribution(target, flavor, python_version)
+        .ok_or_else(|| {
+            let version = python_version.unwrap_or(DEFAULT_PYTHON_VERSION);
+
+            if version.split('.').count() > 2 {
+                let major_minor = version.split('.').take(2).join(".");
+                let versions = PYTHON_DISTRIBUTIONS.python_versions(target, flavor, Some(&major_minor));
+
+                if !versions.is_empty() {
+                    return anyhow!(
+                        "could not find default Python distribution for {} with version {}; available versions: {}",
+                        target,
+                        version,
+                        versions.join(", ")
+                    );
+                }
+            }
+
+            anyhow!(
+                "could not find default {} Python distribution for {} with version {}; {}",
+                flavor.to_string(),
+                target,
+                version,
+                PYTHON_DISTRIBUTIONS.describe_available(target)
+            )
+        })?;
 
     Ok(dist.location)
 }
@@ -523,10 +1029,12 @@ pub fn default_distribution(
     flavor: &DistributionFlavor,
     target: &str,
     dest_dir: &Path,
+    proxy: &HttpProxyConfig,
+    allowed_hosts: Option<&[String]>,
 ) -> Result<Box<dyn PythonDistribution>> {
     let location = default_distribution_location(flavor, target, None)?;
 
-    resolve_distribution(logger, flavor, &location, dest_dir)
+    resolve_distribution(logger, flavor, &location, dest_dir, proxy, allowed_hosts)
 }
 
 #[cfg(test)]
@@ -545,7 +1053,418 @@ mod tests {
             &DistributionFlavor::Standalone,
             target,
             temp_dir.path(),
+            &HttpProxyConfig::from_env(),
+            None,
+        )?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_default_distribution_host_denied() -> Result<()> {
+        let logger = get_logger()?;
+        let target = env!("HOST");
+
+        let temp_dir = tempdir::TempDir::new("pyoxidizer-test")?;
+
+        let err = default_distribution(
+            &logger,
+            &DistributionFlavor::Standalone,
+            target,
+            temp_dir.path(),
+            &HttpProxyConfig::from_env(),
+            Some(&["mirror.example.com".to_string()]),
+        )
+        .err()
+        .unwrap();
+        assert!(err
+            .to_string()
+            .contains("is not an allowed distribution host (allowed: mirror.example.com)"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_ensure_location_host_allowed() -> Result<()> {
+        let allowed = vec!["mirror.example.com".to_string()];
+
+        let location = PythonDistributionLocation::Url {
+            url: "https://Mirror.Example.com/python.tar.zst".to_string(),
+            sha256: "00".to_string(),
+        };
+        ensure_location_host_allowed(&location, Some(&allowed))?;
+
+        let location = PythonDistributionLocation::Urls {
+            urls: vec![
+                "https://mirror.example.com/python.tar.zst".to_string(),
+                "https://example.com/python.tar.zst".to_string(),
+            ],
+            sha256: "00".to_string(),
+        };
+        assert!(ensure_location_host_allowed(&location, Some(&allowed))
+            .unwrap_err()
+            .to_string()
+            .starts_with("host example.com of Python distribution URL"));
+        ensure_location_host_allowed(&location, None)?;
+
+        let location = PythonDistributionLocation::Local {
+            local_path: "some_path".to_string(),
+            sha256: "00".to_string(),
+        };
+        ensure_location_host_allowed(&location, Some(&allowed))?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_http_proxy_config() {
+        let proxy = HttpProxyConfig {
+            http_proxy: Some("http://proxy.example.com:3128".to_string()),
+            https_proxy: Some("http://secure-proxy.example.com:3128".to_string()),
+            no_proxy: HttpProxyConfig::parse_no_proxy(" localhost, .internal.example.com ,,"),
+        };
+
+        assert_eq!(
+            proxy.no_proxy,
+            vec!["localhost".to_string(), ".internal.example.com".to_string()]
+        );
+
+        assert_eq!(
+            proxy.proxy_for("http", "github.com"),
+            Some("http://proxy.example.com:3128")
+        );
+        assert_eq!(
+            proxy.proxy_for("https", "github.com"),
+            Some("http://secure-proxy.example.com:3128")
+        );
+        assert_eq!(proxy.proxy_for("ftp", "github.com"), None);
+        assert_eq!(proxy.proxy_for("https", "localhost"), None);
+        assert_eq!(proxy.proxy_for("https", "internal.example.com"), None);
+        assert_eq!(proxy.proxy_for("https", "pypi.INTERNAL.example.com"), None);
+        assert_eq!(
+            proxy.proxy_for("https", "notinternal.example.com"),
+            Some("http://secure-proxy.example.com:3128")
+        );
+
+        let proxy = HttpProxyConfig {
+            no_proxy: vec!["*".to_string()],
+            ..proxy
+        };
+        assert_eq!(proxy.proxy_for("http", "github.com"), None);
+
+        let proxy = HttpProxyConfig {
+            http_proxy: Some("http://proxy.example.com:3128".to_string()),
+            https_proxy: None,
+            no_proxy: vec!["localhost".to_string(), ".example.com".to_string()],
+        };
+        assert_eq!(
+            proxy.environment_variables(),
+            vec![
+                (
+                    "HTTP_PROXY".to_string(),
+                    Some("http://proxy.example.com:3128".to_string())
+                ),
+                (
+                    "http_proxy".to_string(),
+                    Some("http://proxy.example.com:3128".to_string())
+                ),
+                ("HTTPS_PROXY".to_string(), None),
+                ("https_proxy".to_string(), None),
+                (
+                    "NO_PROXY".to_string(),
+                    Some("localhost,.example.com".to_string())
+                ),
+                (
+                    "no_proxy".to_string(),
+                    Some("localhost,.example.com".to_string())
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_download_distribution_proxy() -> Result<()> {
+        use std::io::{BufRead, BufReader, Write};
+
+        let temp_dir = tempdir::TempDir::new("pyoxidizer-test")?;
+        let body: &[u8] = b"distribution content";
+
+        // A minimal proxy that records the request line and serves a fixed body.
+        let listener = std::net::TcpListener::bind("127.0.0.1:0")?;
+        let address = listener.local_addr()?;
+        let handle = std::thread::spawn(move || -> std::io::Result<String> {
+            let (mut stream, _) = listener.accept()?;
+            let mut reader = BufReader::new(stream.try_clone()?);
+
+            let mut request_line = String::new();
+            reader.read_line(&mut request_line)?;
+
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line)?;
+                if line.trim_end().is_empty() {
+                    break;
+                }
+            }
+
+            stream.write_all(
+                format!(
+                    "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                    body.len()
+                )
+                .as_bytes(),
+            )?;
+            stream.write_all(body)?;
+
+            Ok(request_line)
+        });
+
+        let proxy = HttpProxyConfig {
+            http_proxy: Some(format!("http://{}", address)),
+            https_proxy: None,
+            no_proxy: vec![],
+        };
+
+        let path = download_distribution(
+            "http://pyoxidizer.invalid/python.tar.zst",
+            &hex::encode(Sha256::digest(body)),
+            temp_dir.path(),
+            &proxy,
+        )?;
+        assert_eq!(fs::read(&path)?, body);
+
+        let request_line = handle.join().unwrap()?;
+        assert_eq!(
+            request_line.trim_end(),
+            "GET http://pyoxidizer.invalid/python.tar.zst HTTP/1.1"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_download_distribution_from_mirrors() -> Result<()> {
+        use std::io::{BufRead, BufReader, Write};
+
+        let logger = get_logger()?;
+        let temp_dir = tempdir::TempDir::new("pyoxidizer-test")?;
+        let body: &[u8] = b"distribution content";
+
+        // A minimal proxy that fails requests to the first mirror and serves
+        // a fixed body for everything else.
+        let listener = std::net::TcpListener::bind("127.0.0.1:0")?;
+        let address = listener.local_addr()?;
+        let handle = std::thread::spawn(move || -> std::io::Result<Vec<String>> {
+            let mut request_lines = vec![];
+
+            for _ in 0..2 {
+                let (mut stream, _) = listener.accept()?;
+                let mut reader = BufReader::new(stream.try_clone()?);
+
+                let mut request_line = String::new();
+                reader.read_line(&mut request_line)?;
+
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line)?;
+                    if line.trim_end().is_empty() {
+                        break;
+                    }
+                }
+
+                let (status, content) = if request_line.contains("mirror1.invalid") {
+                    ("404 Not Found", b"not found".as_ref())
+                } else {
+                    ("200 OK", body)
+                };
+
+                stream.write_all(
+                    format!(
+                        "HTTP/1.1 {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                        status,
+                        content.len()
+                    )
+                    .as_bytes(),
+                )?;
+                stream.write_all(content)?;
+
+                request_lines.push(request_line.trim_end().to_string());
+            }
+
+            Ok(request_lines)
+        });
+
+        let proxy = HttpProxyConfig {
+            http_proxy: Some(format!("http://{}", address)),
+            https_proxy: None,
+            no_proxy: vec![],
+        };
+
+        let path = download_distribution_from_mirrors(
+            &logger,
+            &[
+                "http://mirror1.invalid/python.tar.zst".to_string(),
+                "http://mirror2.invalid/python.tar.zst".to_string(),
+            ],
+            &hex::encode(Sha256::digest(body)),
+            temp_dir.path(),
+            &proxy,
+        )?;
+        assert_eq!(fs::read(&path)?, body);
+
+        let request_lines = handle.join().unwrap()?;
+        assert_eq!(
+            request_lines,
+            vec![
+                "GET http://mirror1.invalid/python.tar.zst HTTP/1.1",
+                "GET http://mirror2.invalid/python.tar.zst HTTP/1.1",
+            ]
+        );
+
+        let err = download_distribution_from_mirrors(
+            &logger,
+            &["http://mirror1.invalid/other.tar.zst".to_string()],
+            &hex::encode(Sha256::digest(b"other")),
+            temp_dir.path(),
+            &HttpProxyConfig {
+                http_proxy: Some("http://127.0.0.1:1".to_string()),
+                https_proxy: None,
+                no_proxy: vec![],
+            },
+        )
+        .unwrap_err();
+        assert!(err
+            .to_string()
+            .starts_with("unable to obtain Python distribution from any mirror"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_distribution_cache() -> Result<()> {
+        let logger = get_logger()?;
+        let temp_dir = tempdir::TempDir::new("pyoxidizer-test")?;
+
+        let location =
+            default_distribution_location(&DistributionFlavor::Standalone, env!("HOST"), None)?;
+        let (_, extract_dir) = resolve_python_distribution_from_location(
+            &logger,
+            &location,
+            temp_dir.path(),
+            &HttpProxyConfig::from_env(),
+        )?;
+
+        let cache = DistributionCache::default();
+
+        let dist1 = cache.resolve_distribution(
+            &logger,
+            &DistributionFlavor::Standalone,
+            &location,
+            temp_dir.path(),
+            &HttpProxyConfig::from_env(),
+        )?;
+        assert!(extract_dir.exists());
+
+        // If the distribution were resolved again, it would be re-extracted.
+        std::fs::remove_dir_all(&extract_dir)?;
+
+        let dist2 = cache.resolve_distribution(
+            &logger,
+            &DistributionFlavor::Standalone,
+            &location,
+            temp_dir.path(),
+            &HttpProxyConfig::from_env(),
+        )?;
+        assert!(Arc::ptr_eq(&dist1, &dist2));
+        assert!(!extract_dir.exists());
+
+        // A different flavor is a different cache entry.
+        let dist3 = cache.resolve_distribution(
+            &logger,
+            &DistributionFlavor::StandaloneStatic,
+            &location,
+            temp_dir.path(),
+            &HttpProxyConfig::from_env(),
+        )?;
+        assert!(!Arc::ptr_eq(&dist1, &dist3));
+        assert!(extract_dir.exists());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_default_distribution_location_patch_version() -> Result<()> {
+        let target = "x86_64-unknown-linux-gnu";
+
+        assert_eq!(
+            default_distribution_location(&DistributionFlavor::Standalone, target, Some("3.9"))?,
+            default_distribution_location(&DistributionFlavor::Standalone, target, Some("3.9.0"))?
+        );
+
+        let err =
+            default_distribution_location(&DistributionFlavor::Standalone, target, Some("3.9.99"))
+                .unwrap_err();
+        assert!(err.to_string().ends_with("available versions: 3.9.0"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_register_distribution_flavor() -> Result<()> {
+        let logger = get_logger()?;
+        let temp_dir = tempdir::TempDir::new("pyoxidizer-test")?;
+
+        let record = PYTHON_DISTRIBUTIONS
+            .find_distribution(env!("HOST"), &DistributionFlavor::Standalone, None)
+            .unwrap();
+
+        let mut registry = DistributionFlavorRegistry::default();
+        assert!(registry.resolve_flavor("test-custom").is_err());
+
+        for name in &["", "standalone", "standalone-static", "standalone_dynamic"] {
+            assert!(registry.register(name, vec![record.clone()]).is_err());
+        }
+        assert!(registry.register("test-custom", vec![]).is_err());
+
+        registry.register("test-custom", vec![record.clone()])?;
+
+        let flavor = registry.resolve_flavor("test-custom").unwrap();
+        assert_eq!(
+            flavor,
+            DistributionFlavor::Custom("test-custom".to_string())
+        );
+        assert_eq!(flavor.to_string(), "test-custom");
+        assert!(registry
+            .resolve_flavor("test-other")
+            .unwrap_err()
+            .contains("test-custom"));
+
+        // Registrations are local to the registry.
+        assert!(DistributionFlavor::try_from("test-custom").is_err());
+        assert!(DistributionFlavorRegistry::default()
+            .default_distribution_location(&flavor, env!("HOST"), None)
+            .is_err());
+        assert!(default_distribution_location(&flavor, env!("HOST"), None).is_err());
+
+        let location = registry.default_distribution_location(&flavor, env!("HOST"), None)?;
+        assert_eq!(location, record.location);
+
+        let err = registry
+            .default_distribution_location(&flavor, "no-such-target", None)
+            .unwrap_err();
+        assert!(err
+            .to_string()
+            .starts_with("could not find test-custom Python distribution for no-such-target"));
+
+        let dist = resolve_distribution(
+            &logger,
+            &flavor,
+            &location,
+            temp_dir.path(),
+            &HttpProxyConfig::from_env(),
+            None,
         )?;
+        assert_eq!(dist.python_version(), record.python_version);
 
         Ok(())
     }
@@ -65,15 +65,14 @@ pub fn resolve_resource_names_from_files(
     Ok(include_names)
 }
 
-pub fn filter_btreemap<V>(
-    logger: &slog::Logger,
-    m: &mut BTreeMap<String, V>,
-    f: &BTreeSet<String>,
-) {
+pub fn filter_btreemap<V, F>(logger: &slog::Logger, m: &mut BTreeMap<String, V>, keep: F)
+where
+    F: Fn(&str) -> bool,
+{
     let keys: Vec<String> = m.keys().cloned().collect();
 
     for key in keys {
-        if !f.contains(&key) {
+        if !keep(&key) {
             warn!(logger, "removing {}", key);
             m.remove(&key);
         }
@@ -0,0 +1,175 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+/*!
+Detecting resources that are likely to misbehave once packaged.
+*/
+
+use {
+    super::binary::{PythonBinaryBuilder, DISTRIBUTION_RESOURCE_ORIGIN},
+    anyhow::Result,
+    python_packaging::{
+        resource::DataLocation,
+        resource_collection::{PrePackagedResource, PythonModuleBytecodeProvider},
+    },
+    std::collections::HashSet,
+};
+
+/// How severe a lint finding is.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LintSeverity {
+    /// The resource may not work as intended.
+    Warning,
+    /// The resource will not work as intended.
+    Error,
+}
+
+impl ToString for LintSeverity {
+    fn to_string(&self) -> String {
+        match self {
+            Self::Warning => "warning",
+            Self::Error => "error",
+        }
+        .to_string()
+    }
+}
+
+/// A problem detected with a resource.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LintFinding {
+    pub severity: LintSeverity,
+
+    /// Name of the resource the finding applies to.
+    pub resource: String,
+
+    pub message: String,
+}
+
+/// Obtain the source code of a module, if available.
+pub fn module_source(resource: &PrePackagedResource) -> Option<&DataLocation> {
+    if let Some(location) = &resource.in_memory_source {
+        return Some(location);
+    }
+    if let Some((_, location)) = &resource.relative_path_module_source {
+        return Some(location);
+    }
+
+    resource
+        .in_memory_bytecode
+        .iter()
+        .chain(resource.in_memory_bytecode_opt1.iter())
+        .chain(resource.in_memory_bytecode_opt2.iter())
+        .chain(
+            resource
+                .relative_path_bytecode
+                .iter()
+                .chain(resource.relative_path_bytecode_opt1.iter())
+                .chain(resource.relative_path_bytecode_opt2.iter())
+                .map(|(_, _, provider)| provider),
+        )
+        .find_map(|provider| match provider {
+            PythonModuleBytecodeProvider::FromSource(location) => Some(location),
+            PythonModuleBytecodeProvider::Provided(_) => None,
+        })
+}
+
+/// Whether a module will be imported from memory.
+fn is_in_memory_module(resource: &PrePackagedResource) -> bool {
+    resource.in_memory_source.is_some()
+        || resource.in_memory_bytecode.is_some()
+        || resource.in_memory_bytecode_opt1.is_some()
+        || resource.in_memory_bytecode_opt2.is_some()
+}
+
+/// Examine resources added to a binary builder for likely problems.
+///
+/// This uses heuristics and can report false positives. Resources provided
+/// by the Python distribution are known to work and aren't examined.
+pub fn lint_resources(builder: &dyn PythonBinaryBuilder) -> Result<Vec<LintFinding>> {
+    let shared_libraries = builder
+        .iter_resources()
+        .filter(|(_, resource)| resource.is_shared_library)
+        .map(|(name, _)| name.as_str())
+        .collect::<HashSet<_>>();
+
+    let mut findings = vec![];
+
+    for (name, resource) in builder.iter_resources() {
+        if builder.resource_origin(name).as_deref() == Some(DISTRIBUTION_RESOURCE_ORIGIN) {
+            continue;
+        }
+
+        let mut finding = |severity, message: String| {
+            findings.push(LintFinding {
+                severity,
+                resource: name.clone(),
+                message,
+            })
+        };
+
+        if resource.is_module && is_in_memory_module(resource) {
+            if let Some(location) = module_source(resource) {
+                let source = location.resolve()?;
+                let contains = |needle: &[u8]| source.windows(needle.len()).any(|w| w == needle);
+
+                if contains(b"__file__") {
+                    finding(
+                        LintSeverity::Warning,
+                        "module references __file__, which is not defined when importing from memory"
+                            .to_string(),
+                    );
+                }
+
+                if contains(b"pkg_resources") {
+                    finding(
+                        LintSeverity::Warning,
+                        "module references pkg_resources, which cannot load modules or resources from memory"
+                            .to_string(),
+                    );
+                }
+            }
+        }
+
+        let pth_names = resource
+            .in_memory_resources
+            .iter()
+            .flat_map(|x| x.keys())
+            .chain(
+                resource
+                    .relative_path_package_resources
+                    .iter()
+                    .flat_map(|x| x.keys()),
+            )
+            .chain(std::iter::once(name).filter(|_| resource.is_file_data))
+            .filter(|x| x.ends_with(".pth"))
+            .cloned()
+            .collect::<Vec<_>>();
+
+        for pth in pth_names {
+            finding(
+                LintSeverity::Warning,
+                format!(
+                    "{} is a .pth file, which is not processed by the embedded interpreter",
+                    pth
+                ),
+            );
+        }
+
+        if let Some(depends) = &resource.shared_library_dependency_names {
+            for depend in depends {
+                if !shared_libraries.contains(depend.as_str()) {
+                    finding(
+                        LintSeverity::Error,
+                        format!(
+                            "extension module depends on missing shared library {}",
+                            depend
+                        ),
+                    );
+                }
+            }
+        }
+    }
+
+    Ok(findings)
+}
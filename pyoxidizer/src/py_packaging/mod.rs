@@ -14,7 +14,9 @@ pub mod distribution;
 pub mod distutils;
 pub mod filtering;
 pub mod libpython;
+pub mod lint;
 pub mod packaging_tool;
+pub mod pruning;
 pub mod resource;
 pub mod standalone_builder;
 pub mod standalone_distribution;
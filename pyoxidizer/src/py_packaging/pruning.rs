@@ -0,0 +1,164 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+/*!
+Removing standard library modules an application cannot import.
+*/
+
+use {
+    super::{
+        binary::{PythonBinaryBuilder, DISTRIBUTION_RESOURCE_ORIGIN},
+        lint::module_source,
+    },
+    anyhow::{anyhow, Result},
+    python_packaging::{python_source::find_imports, resource_collection::PrePackagedResource},
+    std::collections::{BTreeMap, BTreeSet, VecDeque},
+};
+
+/// Modules imported by the interpreter or by extension modules.
+///
+/// These imports aren't visible when scanning Python source code.
+const ALWAYS_REACHABLE_MODULES: &[&str] = &[
+    "__main__",
+    "_bootlocale",
+    "_collections_abc",
+    "_sitebuiltins",
+    "_strptime",
+    "_weakrefset",
+    "abc",
+    "codecs",
+    "collections",
+    "copyreg",
+    "encodings",
+    "genericpath",
+    "importlib",
+    "importlib._bootstrap",
+    "importlib._bootstrap_external",
+    "importlib.machinery",
+    "io",
+    "linecache",
+    "ntpath",
+    "numbers",
+    "os",
+    "posixpath",
+    "runpy",
+    "site",
+    "stat",
+    "traceback",
+    "warnings",
+    "xml.etree.ElementPath",
+    "zipimport",
+];
+
+/// Packages whose modules are imported by names computed at run-time.
+///
+/// e.g. codecs are located by `encodings.search_function()`. Every module
+/// in these packages is retained.
+const DYNAMIC_IMPORT_PACKAGES: &[&str] = &["encodings"];
+
+fn is_distribution_resource(builder: &dyn PythonBinaryBuilder, name: &str) -> bool {
+    builder.resource_origin(name).as_deref() == Some(DISTRIBUTION_RESOURCE_ORIGIN)
+}
+
+fn in_dynamic_import_package(name: &str) -> bool {
+    DYNAMIC_IMPORT_PACKAGES
+        .iter()
+        .any(|package| name == *package || name.starts_with(&format!("{}.", package)))
+}
+
+/// Whether a module only has bytecode, which can't be scanned for imports.
+fn has_bytecode_only(resource: &PrePackagedResource) -> bool {
+    module_source(resource).is_none()
+        && (resource.in_memory_bytecode.is_some()
+            || resource.in_memory_bytecode_opt1.is_some()
+            || resource.in_memory_bytecode_opt2.is_some()
+            || resource.relative_path_bytecode.is_some()
+            || resource.relative_path_bytecode_opt1.is_some()
+            || resource.relative_path_bytecode_opt2.is_some())
+}
+
+/// Find standard library modules that can't be imported from entry modules.
+///
+/// Reachability is determined by statically scanning module source for
+/// imports, starting from `entry_modules`, every module not provided by the
+/// Python distribution and modules the interpreter imports on its own.
+/// See `find_imports()` for the limitations of this analysis.
+///
+/// Only pure Python modules provided by the Python distribution are
+/// returned. Extension modules, shared libraries and file data are never
+/// considered unreachable.
+pub fn unreachable_stdlib_modules(
+    builder: &dyn PythonBinaryBuilder,
+    entry_modules: &[String],
+) -> Result<BTreeSet<String>> {
+    let resources = builder
+        .iter_resources()
+        .map(|(name, resource)| (name.as_str(), resource))
+        .collect::<BTreeMap<_, _>>();
+
+    for name in entry_modules {
+        if !resources.get(name.as_str()).map_or(false, |r| r.is_module) {
+            return Err(anyhow!("entry module {} does not exist", name));
+        }
+    }
+
+    let mut queue = entry_modules.iter().cloned().collect::<VecDeque<_>>();
+    queue.extend(ALWAYS_REACHABLE_MODULES.iter().map(|x| x.to_string()));
+    queue.extend(
+        resources
+            .iter()
+            .filter(|(name, resource)| {
+                resource.is_module
+                    && (!is_distribution_resource(builder, name) || in_dynamic_import_package(name))
+            })
+            .map(|(name, _)| name.to_string()),
+    );
+
+    let mut reachable = BTreeSet::new();
+
+    while let Some(name) = queue.pop_front() {
+        if reachable.contains(&name) {
+            continue;
+        }
+
+        // Importing a module imports its parent packages.
+        let mut parent = name.as_str();
+        while let Some(pos) = parent.rfind('.') {
+            parent = &parent[0..pos];
+            queue.push_back(parent.to_string());
+        }
+
+        if let Some(resource) = resources.get(name.as_str()) {
+            if let Some(location) = module_source(resource) {
+                queue.extend(find_imports(
+                    &location.resolve()?,
+                    &name,
+                    resource.is_package,
+                ));
+            } else if has_bytecode_only(resource) {
+                return Err(anyhow!(
+                    "cannot determine modules imported by {}: source code is not available",
+                    name
+                ));
+            }
+        }
+
+        reachable.insert(name);
+    }
+
+    Ok(resources
+        .iter()
+        .filter(|(name, resource)| {
+            resource.is_module
+                && !resource.is_builtin_extension_module
+                && !resource.is_frozen_module
+                && !resource.is_extension_module
+                && !resource.is_shared_library
+                && !resource.is_file_data
+                && is_distribution_resource(builder, name)
+                && !reachable.contains(**name)
+        })
+        .map(|(name, _)| name.to_string())
+        .collect())
+}
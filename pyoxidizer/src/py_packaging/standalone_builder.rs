@@ -5,41 +5,55 @@
 use {
     super::{
         binary::{
-            EmbeddedPythonContext, LibpythonLinkMode, PythonBinaryBuilder, PythonLinkingInfo,
-            ResourceAddCollectionContextCallback,
+            compress_packed_resources, origin_relative_path, EmbeddedPythonContext,
+            ExtensionModuleLinkMode, LibpythonLinkMode, PythonBinaryBuilder, PythonLinkingInfo,
+            ResourceAddCollectionContextCallback, DISTRIBUTION_RESOURCE_ORIGIN,
         },
-        config::EmbeddedPythonConfig,
-        distribution::{BinaryLibpythonLinkMode, PythonDistribution},
+        config::{EmbeddedPythonConfig, FrozenModuleBytecode},
+        distribution::{BinaryLibpythonLinkMode, HttpProxyConfig, PythonDistribution},
         filtering::{filter_btreemap, resolve_resource_names_from_files},
         libpython::link_libpython,
         packaging_tool::{
-            find_resources, pip_download, pip_install, read_virtualenv, setup_py_install,
+            pip_download, pip_install, read_extension_module_file, read_site_packages,
+            read_virtualenv, setup_py_install, PipDownloadOptions,
         },
         standalone_distribution::StandaloneDistribution,
     },
-    crate::app_packaging::resource::{FileContent, FileManifest},
-    anyhow::{anyhow, Result},
+    crate::app_packaging::{
+        resource::{FileContent, FileManifest},
+        windows::WindowsResourceMetadata,
+    },
+    anyhow::{anyhow, Context, Result},
     lazy_static::lazy_static,
     python_packaging::{
-        bytecode::BytecodeCompiler,
-        interpreter::MemoryAllocatorBackend,
+        bytecode::{BytecodeCompiler, CompileMode, PythonBytecodeCompiler},
+        filesystem_scanning::find_python_resources,
+        interpreter::{MemoryAllocatorBackend, PythonRunMode},
         libpython::LibPythonBuildContext,
         location::AbstractResourceLocation,
         policy::PythonPackagingPolicy,
         resource::{
-            DataLocation, PythonExtensionModule, PythonModuleSource,
-            PythonPackageDistributionResource, PythonPackageResource, PythonResource,
+            BytecodeOptimizationLevel, DataLocation, FileData, PythonExtensionModule,
+            PythonModuleSource, PythonPackageDistributionResource, PythonPackageResource,
+            PythonResource,
         },
         resource_collection::{
-            PrePackagedResource, PythonResourceAddCollectionContext, PythonResourceCollector,
+            CompiledResourcesCollection, PrePackagedResource, PythonModuleBytecodeProvider,
+            PythonResourceAddCollectionContext, PythonResourceCollector,
         },
+        zip_scanning::find_python_resources_in_zip,
     },
-    slog::warn,
+    python_packed_resources::data::BUNDLE_HEADER_V1,
+    regex::Regex,
+    sha2::{Digest, Sha256},
+    slog::{info, warn},
     std::{
         collections::{BTreeMap, BTreeSet, HashMap},
+        fmt::Write as FmtWrite,
         io::Write,
         path::{Path, PathBuf},
         sync::Arc,
+        time::Instant,
     },
     tempdir::TempDir,
 };
@@ -63,6 +77,102 @@ fn ignored_libraries_for_target(target_triple: &str) -> Vec<&'static str> {
     }
 }
 
+/// Count Python modules lacking bytecode for an optimization level.
+///
+/// Only modules having bytecode for some other optimization level are
+/// counted, since modules without any bytecode are loaded some other way.
+fn modules_missing_bytecode_level<'a>(
+    resources: impl Iterator<Item = (&'a String, &'a PrePackagedResource)>,
+    level: BytecodeOptimizationLevel,
+) -> usize {
+    resources
+        .filter(|(_, resource)| resource.is_module)
+        .filter(|(_, resource)| {
+            let levels = [
+                resource.in_memory_bytecode.is_some() || resource.relative_path_bytecode.is_some(),
+                resource.in_memory_bytecode_opt1.is_some()
+                    || resource.relative_path_bytecode_opt1.is_some(),
+                resource.in_memory_bytecode_opt2.is_some()
+                    || resource.relative_path_bytecode_opt2.is_some(),
+            ];
+
+            let index = match level {
+                BytecodeOptimizationLevel::Zero => 0,
+                BytecodeOptimizationLevel::One => 1,
+                BytecodeOptimizationLevel::Two => 2,
+            };
+
+            !levels[index] && levels.iter().any(|x| *x)
+        })
+        .count()
+}
+
+/// Compute the size of Python module source replaced by level 2 bytecode.
+///
+/// Returns the size of source for modules packaged only as level 2 bytecode
+/// compiled from that source and the size of the bytecode.
+fn stripped_docstrings_sizes<'a>(
+    resources: impl Iterator<Item = (&'a String, &'a PrePackagedResource)>,
+    compiled: &CompiledResourcesCollection,
+) -> Result<(usize, usize)> {
+    let extra_files = compiled
+        .extra_files
+        .iter()
+        .map(|(path, location, _)| (path.as_path(), location))
+        .collect::<HashMap<_, _>>();
+
+    let mut source_size = 0;
+    let mut bytecode_size = 0;
+
+    for (name, resource) in resources {
+        if !resource.is_module
+            || resource.in_memory_source.is_some()
+            || resource.relative_path_module_source.is_some()
+        {
+            continue;
+        }
+
+        let provider = match (
+            &resource.in_memory_bytecode_opt2,
+            &resource.relative_path_bytecode_opt2,
+        ) {
+            (Some(provider), _) => provider,
+            (None, Some((_, _, provider))) => provider,
+            (None, None) => continue,
+        };
+
+        if let PythonModuleBytecodeProvider::FromSource(location) = provider {
+            source_size += location.resolve()?.len();
+        } else {
+            continue;
+        }
+
+        if let Some(entry) = compiled.resources.get(name) {
+            if let Some(data) = &entry.in_memory_bytecode_opt2 {
+                bytecode_size += data.len();
+            }
+            if let Some(path) = &entry.relative_path_module_bytecode_opt2 {
+                let path: &Path = path;
+                if let Some(location) = extra_files.get(path) {
+                    bytecode_size += location.resolve()?.len();
+                }
+            }
+        }
+    }
+
+    Ok((source_size, bytecode_size))
+}
+
+/// Adapts a digest to `std::fmt::Write`.
+struct DigestWriter<'a>(&'a mut Sha256);
+
+impl<'a> std::fmt::Write for DigestWriter<'a> {
+    fn write_str(&mut self, s: &str) -> std::fmt::Result {
+        self.0.update(s.as_bytes());
+        Ok(())
+    }
+}
+
 /// A self-contained Python executable before it is compiled.
 #[derive(Clone)]
 pub struct StandalonePythonExecutableBuilder {
@@ -108,6 +218,26 @@ pub struct StandalonePythonExecutableBuilder {
 
     /// Path to python executable that can be invoked at build time.
     host_python_exe: PathBuf,
+
+    /// Metadata to embed as resources when building for Windows.
+    windows_resource_metadata: WindowsResourceMetadata,
+
+    /// Environment variables to set when compiling the binary.
+    build_environment_variables: BTreeMap<String, String>,
+
+    /// Maximum number of bytecode compilers to run concurrently.
+    bytecode_compiler_jobs: usize,
+
+    /// Filename to write packed resources to instead of embedding them.
+    resources_bundle_filename: Option<String>,
+
+    /// zstd level to compress packed resources data with.
+    packed_resources_compression_level: Option<i32>,
+
+    /// Absolute paths to search for modules at run-time.
+    ///
+    /// These are made relative to the executable when it is built.
+    origin_module_search_paths: Vec<PathBuf>,
 }
 
 impl StandalonePythonExecutableBuilder {
@@ -201,6 +331,12 @@ impl StandalonePythonExecutableBuilder {
             extension_build_contexts: BTreeMap::new(),
             config,
             host_python_exe,
+            windows_resource_metadata: WindowsResourceMetadata::default(),
+            build_environment_variables: BTreeMap::new(),
+            bytecode_compiler_jobs: num_cpus::get(),
+            resources_bundle_filename: None,
+            packed_resources_compression_level: None,
+            origin_module_search_paths: vec![],
         });
 
         builder.add_distribution_core_state()?;
@@ -389,31 +525,43 @@ impl PythonBinaryBuilder for StandalonePythonExecutableBuilder {
         &self,
         logger: &slog::Logger,
         verbose: bool,
+        options: &PipDownloadOptions,
         args: &[String],
+        proxy: &HttpProxyConfig,
     ) -> Result<Vec<PythonResource>> {
         pip_download(
             logger,
             &**self.host_distribution,
             &**self.target_distribution,
             verbose,
+            options,
             args,
+            proxy,
         )
     }
 
+    #[allow(clippy::too_many_arguments)]
     fn pip_install(
         &self,
         logger: &slog::Logger,
         verbose: bool,
         install_args: &[String],
         extra_envs: &HashMap<String, String>,
+        cwd: &Path,
+        cache_dir: &Path,
+        proxy: &HttpProxyConfig,
     ) -> Result<Vec<PythonResource>> {
         pip_install(
             logger,
+            &**self.host_distribution,
             &**self.target_distribution,
             self.link_mode,
             verbose,
             install_args,
             extra_envs,
+            cwd,
+            cache_dir,
+            proxy,
         )
     }
 
@@ -422,23 +570,80 @@ impl PythonBinaryBuilder for StandalonePythonExecutableBuilder {
         _logger: &slog::Logger,
         path: &Path,
         packages: &[String],
+        namespace_packages: &[String],
     ) -> Result<Vec<PythonResource>> {
-        Ok(find_resources(&**self.target_distribution, path, None)?
-            .iter()
-            .filter_map(|x| {
-                if x.is_in_packages(packages) {
-                    Some(x.clone())
-                } else {
-                    None
-                }
-            })
-            .collect::<Vec<_>>())
+        for package in namespace_packages {
+            let package_path = package
+                .split('.')
+                .fold(path.to_path_buf(), |acc, part| acc.join(part));
+
+            if !package_path.is_dir() {
+                return Err(anyhow!(
+                    "namespace package {} not found at {}",
+                    package,
+                    package_path.display()
+                ));
+            }
+        }
+
+        let mut resources = Vec::new();
+
+        for resource in find_python_resources(
+            path,
+            &self.target_distribution.cache_tag,
+            &self.target_distribution.python_module_suffixes()?,
+        )
+        .with_namespace_packages(namespace_packages)
+        {
+            let resource = resource?.to_memory()?;
+
+            if resource.is_in_packages(packages) || resource.is_in_packages(namespace_packages) {
+                resources.push(resource);
+            }
+        }
+
+        Ok(resources)
     }
 
     fn read_virtualenv(&self, _logger: &slog::Logger, path: &Path) -> Result<Vec<PythonResource>> {
         read_virtualenv(&**self.target_distribution, path)
     }
 
+    fn read_site_packages(
+        &self,
+        _logger: &slog::Logger,
+        path: &Path,
+    ) -> Result<Vec<PythonResource>> {
+        read_site_packages(&**self.target_distribution, path)
+    }
+
+    fn read_zip_file(
+        &self,
+        _logger: &slog::Logger,
+        path: &Path,
+        strip_prefix: Option<&str>,
+    ) -> Result<Vec<PythonResource>> {
+        let fh = std::fs::File::open(path)
+            .with_context(|| format!("opening {} for zip reading", path.display()))?;
+
+        find_python_resources_in_zip(
+            std::io::BufReader::new(fh),
+            strip_prefix,
+            &self.target_distribution.cache_tag,
+            &self.target_distribution.python_module_suffixes()?,
+        )
+    }
+
+    fn read_extension_module_file(
+        &self,
+        _logger: &slog::Logger,
+        name: &str,
+        path: &Path,
+    ) -> Result<PythonExtensionModule> {
+        read_extension_module_file(&**self.target_distribution, name, path)
+    }
+
+    #[allow(clippy::too_many_arguments)]
     fn setup_py_install(
         &self,
         logger: &slog::Logger,
@@ -446,6 +651,8 @@ impl PythonBinaryBuilder for StandalonePythonExecutableBuilder {
         verbose: bool,
         extra_envs: &HashMap<String, String>,
         extra_global_arguments: &[String],
+        work_root: &Path,
+        proxy: &HttpProxyConfig,
     ) -> Result<Vec<PythonResource>> {
         setup_py_install(
             logger,
@@ -455,13 +662,31 @@ impl PythonBinaryBuilder for StandalonePythonExecutableBuilder {
             verbose,
             extra_envs,
             extra_global_arguments,
+            work_root,
+            proxy,
         )
     }
 
     fn add_distribution_resources(
         &mut self,
+        logger: &slog::Logger,
         callback: Option<ResourceAddCollectionContextCallback>,
     ) -> Result<()> {
+        let excluded_extensions = self
+            .target_distribution
+            .extension_modules
+            .keys()
+            .filter(|name| self.packaging_policy.is_extension_module_excluded(name))
+            .cloned()
+            .collect::<Vec<_>>();
+
+        for name in &excluded_extensions {
+            info!(
+                logger,
+                "excluding extension module {} per packaging policy", name
+            );
+        }
+
         for ext in self.packaging_policy.resolve_python_extension_modules(
             self.target_distribution.extension_modules.values(),
             &self.target_triple,
@@ -472,10 +697,14 @@ impl PythonBinaryBuilder for StandalonePythonExecutableBuilder {
                 .derive_add_collection_context(&resource);
 
             if let Some(callback) = &callback {
-                callback(&self.packaging_policy, &resource, &mut add_context)?;
+                if !callback(&self.packaging_policy, &resource, &mut add_context)? {
+                    continue;
+                }
             }
 
             self.add_python_extension_module(&ext, Some(add_context))?;
+            self.resources_collector
+                .set_resource_origin(&resource.full_name(), DISTRIBUTION_RESOURCE_ORIGIN);
         }
 
         for source in self.target_distribution.source_modules()? {
@@ -485,10 +714,14 @@ impl PythonBinaryBuilder for StandalonePythonExecutableBuilder {
                 .derive_add_collection_context(&resource);
 
             if let Some(callback) = &callback {
-                callback(&self.packaging_policy, &resource, &mut add_context)?;
+                if !callback(&self.packaging_policy, &resource, &mut add_context)? {
+                    continue;
+                }
             }
 
             self.add_python_module_source(&source, Some(add_context))?;
+            self.resources_collector
+                .set_resource_origin(&resource.full_name(), DISTRIBUTION_RESOURCE_ORIGIN);
         }
 
         for data in self.target_distribution.resource_datas()? {
@@ -498,10 +731,29 @@ impl PythonBinaryBuilder for StandalonePythonExecutableBuilder {
                 .derive_add_collection_context(&resource);
 
             if let Some(callback) = &callback {
-                callback(&self.packaging_policy, &resource, &mut add_context)?;
+                if !callback(&self.packaging_policy, &resource, &mut add_context)? {
+                    continue;
+                }
             }
 
             self.add_python_package_resource(&data, Some(add_context))?;
+            self.resources_collector
+                .set_resource_origin(&resource.full_name(), DISTRIBUTION_RESOURCE_ORIGIN);
+        }
+
+        // Excluded extension modules can't be imported. So nothing may be
+        // added underneath them.
+        for (name, _) in self.resources_collector.iter_resources() {
+            if let Some(excluded) = excluded_extensions
+                .iter()
+                .find(|excluded| name.starts_with(&format!("{}.", excluded)))
+            {
+                return Err(anyhow!(
+                    "extension module {} cannot be excluded because {} depends on it",
+                    excluded,
+                    name
+                ));
+            }
         }
 
         Ok(())
@@ -512,11 +764,17 @@ impl PythonBinaryBuilder for StandalonePythonExecutableBuilder {
         module: &PythonModuleSource,
         add_context: Option<PythonResourceAddCollectionContext>,
     ) -> Result<()> {
-        let add_context = add_context.unwrap_or_else(|| {
+        let mut add_context = add_context.unwrap_or_else(|| {
             self.packaging_policy
                 .derive_add_collection_context(&module.into())
         });
 
+        // Bytecode only and docstring stripping modes override per-resource settings.
+        self.packaging_policy
+            .enforce_bytecode_only(&mut add_context);
+        self.packaging_policy
+            .enforce_strip_docstrings(&mut add_context);
+
         self.resources_collector
             .add_python_module_source_with_context(module, &add_context)
     }
@@ -554,6 +812,13 @@ impl PythonBinaryBuilder for StandalonePythonExecutableBuilder {
         extension_module: &PythonExtensionModule,
         add_context: Option<PythonResourceAddCollectionContext>,
     ) -> Result<()> {
+        if self
+            .packaging_policy
+            .is_extension_module_excluded(&extension_module.name)
+        {
+            return Ok(());
+        }
+
         let add_context = add_context.unwrap_or_else(|| {
             self.packaging_policy
                 .derive_add_collection_context(&extension_module.into())
@@ -589,18 +854,71 @@ impl PythonBinaryBuilder for StandalonePythonExecutableBuilder {
         Ok(())
     }
 
+    fn add_file_data(
+        &mut self,
+        file: &FileData,
+        add_context: Option<PythonResourceAddCollectionContext>,
+    ) -> Result<()> {
+        let add_context = add_context.unwrap_or_else(|| {
+            self.packaging_policy
+                .derive_add_collection_context(&file.into())
+        });
+
+        self.resources_collector
+            .add_file_data_with_context(file, &add_context)
+    }
+
+    fn is_conflicting_resource(&self, resource: &PythonResource) -> Result<bool> {
+        self.resources_collector.is_conflicting_resource(resource)
+    }
+
+    fn resource_origin(&self, name: &str) -> Option<String> {
+        self.resources_collector
+            .resource_origin(name)
+            .map(|x| x.to_string())
+    }
+
+    fn set_resource_origin(&mut self, name: &str, origin: &str) {
+        self.resources_collector.set_resource_origin(name, origin);
+    }
+
+    fn add_module_search_path(&mut self, path: &Path) {
+        self.origin_module_search_paths.push(path.to_path_buf());
+        self.config.filesystem_importer = true;
+    }
+
+    fn origin_module_search_paths(&self, origin: &Path) -> Result<Vec<PathBuf>> {
+        self.origin_module_search_paths
+            .iter()
+            .map(|path| {
+                origin_relative_path(path, origin).ok_or_else(|| {
+                    anyhow!(
+                        "module search path {} cannot be expressed relative to {}",
+                        path.display(),
+                        origin.display()
+                    )
+                })
+            })
+            .collect()
+    }
+
     fn filter_resources_from_files(
         &mut self,
         logger: &slog::Logger,
         files: &[&Path],
         glob_patterns: &[&str],
+        regexes: &[Regex],
     ) -> Result<()> {
         let resource_names = resolve_resource_names_from_files(files, glob_patterns)?;
 
+        let keep = |name: &str| {
+            resource_names.contains(name) || regexes.iter().any(|re| re.is_match(name))
+        };
+
         warn!(logger, "filtering module entries");
 
         self.resources_collector.filter_resources_mut(|resource| {
-            if !resource_names.contains(&resource.name) {
+            if !keep(&resource.name) {
                 warn!(logger, "removing {}", resource.name);
                 false
             } else {
@@ -609,7 +927,24 @@ impl PythonBinaryBuilder for StandalonePythonExecutableBuilder {
         })?;
 
         warn!(logger, "filtering embedded extension modules");
-        filter_btreemap(logger, &mut self.extension_build_contexts, &resource_names);
+        filter_btreemap(logger, &mut self.extension_build_contexts, keep);
+
+        Ok(())
+    }
+
+    fn remove_resources(&mut self, logger: &slog::Logger, names: &BTreeSet<String>) -> Result<()> {
+        self.resources_collector.filter_resources_mut(|resource| {
+            if names.contains(&resource.name) {
+                warn!(logger, "removing {}", resource.name);
+                false
+            } else {
+                true
+            }
+        })?;
+
+        filter_btreemap(logger, &mut self.extension_build_contexts, |name| {
+            !names.contains(name)
+        });
 
         Ok(())
     }
@@ -618,17 +953,226 @@ impl PythonBinaryBuilder for StandalonePythonExecutableBuilder {
         self.config.raw_allocator == MemoryAllocatorBackend::Jemalloc
     }
 
+    fn requires_snmalloc(&self) -> bool {
+        self.config.raw_allocator == MemoryAllocatorBackend::Snmalloc
+    }
+
+    fn windows_resource_metadata(&self) -> &WindowsResourceMetadata {
+        &self.windows_resource_metadata
+    }
+
+    fn set_windows_resource_metadata(&mut self, metadata: WindowsResourceMetadata) {
+        self.windows_resource_metadata = metadata;
+    }
+
+    fn build_environment_variables(&self) -> &BTreeMap<String, String> {
+        &self.build_environment_variables
+    }
+
+    fn set_build_environment_variable(&mut self, name: &str, value: &str) {
+        self.build_environment_variables
+            .insert(name.to_string(), value.to_string());
+    }
+
+    fn packed_resources_compression_level(&self) -> Option<i32> {
+        self.packed_resources_compression_level
+    }
+
+    fn set_packed_resources_compression_level(&mut self, level: Option<i32>) {
+        self.packed_resources_compression_level = level;
+    }
+
+    fn bytecode_compiler_jobs(&self) -> usize {
+        self.bytecode_compiler_jobs
+    }
+
+    fn set_bytecode_compiler_jobs(&mut self, jobs: usize) {
+        self.bytecode_compiler_jobs = jobs;
+    }
+
+    fn run_mode(&self) -> &PythonRunMode {
+        &self.config.run_mode
+    }
+
+    fn set_run_mode(&mut self, run_mode: PythonRunMode) {
+        self.config.run_mode = run_mode;
+    }
+
+    fn resources_bundle_filename(&self) -> Option<String> {
+        self.resources_bundle_filename.clone()
+    }
+
+    fn set_resources_bundle_filename(&mut self, filename: Option<String>) {
+        self.resources_bundle_filename = filename;
+    }
+
+    fn extension_module_link_modes(&self) -> Vec<(String, ExtensionModuleLinkMode)> {
+        self.iter_resources()
+            .filter_map(|(name, resource)| {
+                if resource.is_builtin_extension_module {
+                    // Extensions already in libpython don't contribute object files.
+                    let has_object_files = self
+                        .extension_build_contexts
+                        .get(name)
+                        .map_or(false, |context| !context.object_files.is_empty());
+
+                    Some((
+                        name.clone(),
+                        if has_object_files {
+                            ExtensionModuleLinkMode::Static
+                        } else {
+                            ExtensionModuleLinkMode::Builtin
+                        },
+                    ))
+                } else if resource.is_extension_module {
+                    Some((name.clone(), ExtensionModuleLinkMode::Shared))
+                } else {
+                    None
+                }
+            })
+            .collect()
+    }
+
+    fn add_frozen_module(&mut self, name: &str, source: &[u8], is_package: bool) -> Result<()> {
+        let mut compiler = BytecodeCompiler::new(self.host_python_exe_path())?;
+        let bytecode = compiler
+            .compile(
+                source,
+                name,
+                BytecodeOptimizationLevel::Zero,
+                CompileMode::Bytecode,
+            )
+            .with_context(|| format!("compiling frozen module {}", name))?;
+
+        self.config.frozen_modules.insert(
+            name.to_string(),
+            FrozenModuleBytecode {
+                bytecode,
+                is_package,
+            },
+        );
+
+        Ok(())
+    }
+
+    fn replace_module_source(&mut self, name: &str, source: &[u8]) -> Result<()> {
+        self.resources_collector
+            .replace_python_module_source(name, source.to_vec())
+    }
+
+    fn input_fingerprint(&self) -> Result<String> {
+        let mut hasher = Sha256::new();
+
+        // Debug representations are streamed into the hasher to avoid
+        // materializing them.
+        writeln!(
+            DigestWriter(&mut hasher),
+            "{:?}\n{:?}\n{:?}\n{:?}\n{:?}\n{:?}\n{:?}\n{:?}\n{:?}\n{:?}\n{:?}\n{:?}\n{:?}\n{:?}\n{:?}\n{:?}",
+            self.host_triple,
+            self.target_triple,
+            self.exe_name,
+            self.target_distribution,
+            self.link_mode,
+            self.supports_in_memory_dynamically_linked_extension_loading,
+            self.packaging_policy,
+            self.core_build_context,
+            self.extension_build_contexts,
+            self.config,
+            self.host_python_exe,
+            self.windows_resource_metadata,
+            self.build_environment_variables,
+            self.resources_bundle_filename,
+            self.packed_resources_compression_level,
+            self.origin_module_search_paths,
+        )?;
+        writeln!(DigestWriter(&mut hasher), "{:?}", self.resources_collector)?;
+
+        // Resources may reference files whose content isn't captured above.
+        for (_, resource) in self.resources_collector.iter_resources() {
+            for location in resource.data_locations() {
+                if let DataLocation::Path(path) = location {
+                    let data = location.resolve()?;
+                    hasher.update(path.display().to_string().as_bytes());
+                    hasher.update(&(data.len() as u64).to_le_bytes());
+                    hasher.update(&data);
+                }
+            }
+        }
+
+        Ok(hex::encode(hasher.finalize()))
+    }
+
+    fn validate_run_modules(&self) -> Result<Vec<String>> {
+        // Modules may be importable from the filesystem at run-time unless
+        // they are run via a `resource:` run mode.
+        let mut modules = vec![];
+
+        match &self.config.run_mode {
+            PythonRunMode::Resource { name } => modules.push(("run_mode", name, true)),
+            PythonRunMode::Module { module } => {
+                modules.push(("run_mode", module, !self.config.filesystem_importer))
+            }
+            _ => {}
+        }
+
+        if let Some(module) = &self.config.config.run_module {
+            modules.push(("run_module", module, !self.config.filesystem_importer));
+        }
+
+        let mut warnings = vec![];
+
+        for (setting, module, required) in modules {
+            if self
+                .iter_resources()
+                .any(|(name, resource)| name == module && resource.is_module)
+            {
+                continue;
+            }
+
+            let message = format!(
+                "{} references Python module {} but no such module is packaged in the executable",
+                setting, module
+            );
+
+            if required {
+                return Err(anyhow!(message));
+            }
+
+            warnings.push(format!(
+                "{}; it must be importable from the filesystem at run-time",
+                message
+            ));
+        }
+
+        Ok(warnings)
+    }
+
     fn to_embedded_python_context(
         &self,
         logger: &slog::Logger,
         opt_level: &str,
     ) -> Result<EmbeddedPythonContext> {
+        for warning in self.validate_run_modules()? {
+            warn!(logger, "warning: {}", warning);
+        }
+
         let mut file_seen = false;
         for module in self.resources_collector.find_dunder_file()? {
             file_seen = true;
             warn!(logger, "warning: {} contains __file__", module);
         }
 
+        for conflict in self
+            .config
+            .profile_conflicts()
+            .into_iter()
+            .chain(self.config.filesystem_importer_conflicts())
+            .chain(self.config.run_mode_conflicts())
+            .chain(self.config.target_conflicts(&self.target_triple))
+        {
+            warn!(logger, "warning: {}", conflict);
+        }
+
         if file_seen {
             warn!(logger, "__file__ was encountered in some embedded modules");
             warn!(
@@ -641,10 +1185,72 @@ impl PythonBinaryBuilder for StandalonePythonExecutableBuilder {
             );
         }
 
+        let mut config = self.config.clone();
+
+        // Level 2 bytecode is only loaded by interpreters running at that level.
+        if self.packaging_policy.strip_docstrings() {
+            match config.config.optimization_level {
+                None => {
+                    warn!(
+                        logger,
+                        "strip_docstrings is set; setting interpreter optimization_level to 2"
+                    );
+                    config.config.optimization_level = Some(BytecodeOptimizationLevel::Two);
+                }
+                Some(BytecodeOptimizationLevel::Two) => {}
+                Some(level) => {
+                    return Err(anyhow!(
+                        "strip_docstrings packages optimization level 2 bytecode but the interpreter optimization_level is {:?}",
+                        level
+                    ));
+                }
+            }
+        }
+
+        // The interpreter only loads bytecode matching its optimization level.
+        if let Some(level) = config.config.optimization_level {
+            let missing = modules_missing_bytecode_level(self.iter_resources(), level);
+
+            if missing > 0 {
+                warn!(
+                    logger,
+                    "interpreter optimization_level is {:?} but {} modules do not have bytecode for that level; they will need to be compiled from source at run-time or will fail to import",
+                    level,
+                    missing
+                );
+            }
+        }
+
+        let compile_start = Instant::now();
         let compiled_resources = {
-            let mut compiler = BytecodeCompiler::new(self.host_python_exe_path())?;
-            self.resources_collector.compile_resources(&mut compiler)?
+            let python_exe = self.host_python_exe_path().to_path_buf();
+
+            self.resources_collector.compile_resources_parallel(
+                self.bytecode_compiler_jobs,
+                move || -> Result<Box<dyn PythonBytecodeCompiler + Send>> {
+                    Ok(Box::new(BytecodeCompiler::new(&python_exe)?))
+                },
+            )?
         };
+        let bytecode_compile_duration = compile_start.elapsed();
+        info!(
+            logger,
+            "bytecode_compilation took {:.3}s",
+            bytecode_compile_duration.as_secs_f64()
+        );
+
+        if self.packaging_policy.strip_docstrings() {
+            let (source_size, bytecode_size) =
+                stripped_docstrings_sizes(self.iter_resources(), &compiled_resources)?;
+
+            warn!(
+                logger,
+                "stripping docstrings removed {} bytes of Python source and added {} bytes of bytecode ({} bytes saved)",
+                source_size,
+                bytecode_size,
+                source_size as i64 - bytecode_size as i64
+            );
+        }
 
         let mut extra_files = FileManifest::default();
 
@@ -668,6 +1274,34 @@ impl PythonBinaryBuilder for StandalonePythonExecutableBuilder {
         let mut resources = Vec::new();
         compiled_resources.write_packed_resources_v1(&mut resources)?;
 
+        if let Some(level) = self.packed_resources_compression_level {
+            let original_size = resources.len();
+            resources = compress_packed_resources(&resources, level)?;
+
+            info!(
+                logger,
+                "compressed packed resources from {} to {} bytes",
+                original_size,
+                resources.len()
+            );
+        }
+
+        if let Some(filename) = &self.resources_bundle_filename {
+            let mut data = BUNDLE_HEADER_V1.to_vec();
+            data.extend_from_slice(&resources);
+
+            extra_files.add_file(
+                Path::new(filename),
+                &FileContent {
+                    data,
+                    executable: false,
+                },
+            )?;
+
+            config.packed_resources_bundle = Some(format!("$ORIGIN/{}", filename));
+            resources = Vec::new();
+        }
+
         let linking_info = self.resolve_python_linking_info(logger, opt_level)?;
 
         if self.link_mode == LibpythonLinkMode::Dynamic {
@@ -683,13 +1317,14 @@ impl PythonBinaryBuilder for StandalonePythonExecutableBuilder {
         }
 
         Ok(EmbeddedPythonContext {
-            config: self.config.clone(),
+            config,
             linking_info,
             module_names,
             resources,
             extra_files,
             host_triple: self.host_triple.clone(),
             target_triple: self.target_triple.clone(),
+            bytecode_compile_duration,
         })
     }
 }
@@ -702,7 +1337,11 @@ pub mod tests {
         crate::python_distributions::PYTHON_DISTRIBUTIONS,
         crate::testutil::*,
         lazy_static::lazy_static,
-        python_packaging::{location::ConcreteResourceLocation, policy::ExtensionModuleFilter},
+        python_packaging::{
+            location::ConcreteResourceLocation, policy::ExtensionModuleFilter,
+            resource::PythonExtensionModuleVariants,
+        },
+        python_packed_resources::data::HEADER_ZSTD,
         std::collections::BTreeSet,
         std::iter::FromIterator,
         std::ops::DerefMut,
@@ -847,7 +1486,7 @@ pub mod tests {
                 config,
             )?;
 
-            builder.add_distribution_resources(None)?;
+            builder.add_distribution_resources(&get_logger()?, None)?;
 
             Ok(builder)
         }
@@ -962,6 +1601,72 @@ pub mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_packed_resources_compression() -> Result<()> {
+        let logger = get_logger()?;
+        let options = StandalonePythonExecutableBuilderOptions::default();
+        let mut builder = options.new_builder()?;
+
+        let uncompressed = builder.to_embedded_python_context(&logger, "0")?;
+
+        builder.set_packed_resources_compression_level(Some(3));
+        let compressed = builder.to_embedded_python_context(&logger, "0")?;
+
+        assert!(compressed.resources.starts_with(HEADER_ZSTD));
+        assert!(compressed.resources.len() < uncompressed.resources.len());
+
+        let decompressed = zstd::stream::decode_all(std::io::Cursor::new(
+            &compressed.resources[HEADER_ZSTD.len()..],
+        ))?;
+        assert_eq!(decompressed, uncompressed.resources);
+
+        let names = python_packed_resources::parser::load_resources(&decompressed)
+            .map_err(|e| anyhow!(e))?
+            .map(|resource| resource.map(|r| r.name.to_string()).map_err(|e| anyhow!(e)))
+            .collect::<Result<Vec<_>>>()?;
+        assert!(names.contains(&"json".to_string()));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_resources_bundle() -> Result<()> {
+        let logger = get_logger()?;
+        let options = StandalonePythonExecutableBuilderOptions::default();
+        let mut builder = options.new_builder()?;
+
+        let embedded = builder.to_embedded_python_context(&logger, "0")?;
+        assert!(embedded.config.packed_resources_bundle.is_none());
+
+        builder.set_resources_bundle_filename(Some("myapp.pyoxidizer".to_string()));
+        let bundled = builder.to_embedded_python_context(&logger, "0")?;
+
+        assert!(bundled.resources.is_empty());
+        assert_eq!(
+            bundled.config.packed_resources_bundle,
+            Some("$ORIGIN/myapp.pyoxidizer".to_string())
+        );
+
+        let (_, content) = bundled
+            .extra_files
+            .entries()
+            .find(|(path, _)| path == &&PathBuf::from("myapp.pyoxidizer"))
+            .unwrap();
+        assert!(content.data.starts_with(BUNDLE_HEADER_V1));
+
+        let data = python_packed_resources::parser::bundle_resources_data(&content.data)
+            .map_err(|e| anyhow!(e))?;
+        assert_eq!(data, embedded.resources.as_slice());
+
+        let names = python_packed_resources::parser::load_resources(data)
+            .map_err(|e| anyhow!(e))?
+            .map(|resource| resource.map(|r| r.name.to_string()).map_err(|e| anyhow!(e)))
+            .collect::<Result<Vec<_>>>()?;
+        assert!(names.contains(&"json".to_string()));
+
+        Ok(())
+    }
+
     #[test]
     fn test_minimal_extensions_present() -> Result<()> {
         let options = StandalonePythonExecutableBuilderOptions::default();
@@ -993,6 +1698,39 @@ pub mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_excluded_extension_module_dependents() -> Result<()> {
+        let options = StandalonePythonExecutableBuilderOptions::default();
+        let mut builder = options.new_builder()?;
+
+        let mut distribution = (**builder.target_distribution).clone();
+        for name in &["_testpkg", "_testpkg.child"] {
+            let mut em = distribution.extension_modules["_sqlite3"]
+                .default_variant()
+                .clone();
+            em.name = name.to_string();
+            em.is_package = *name == "_testpkg";
+            distribution.extension_modules.insert(
+                name.to_string(),
+                PythonExtensionModuleVariants::from_iter(vec![em]),
+            );
+        }
+        builder.target_distribution = Arc::new(Box::new(distribution));
+        builder
+            .packaging_policy
+            .set_excluded_extension_modules(vec!["_testpkg".to_string()])?;
+
+        let err = builder
+            .add_distribution_resources(&get_logger()?, None)
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "extension module _testpkg cannot be excluded because _testpkg.child depends on it"
+        );
+
+        Ok(())
+    }
+
     #[test]
     fn test_linux_distribution_extensions() -> Result<()> {
         for libpython_link_mode in vec![
@@ -10,7 +10,7 @@ use {
         config::{default_raw_allocator, EmbeddedPythonConfig},
         distribution::{
             is_stdlib_test_package, resolve_python_distribution_from_location,
-            BinaryLibpythonLinkMode, DistributionExtractLock, PythonDistribution,
+            BinaryLibpythonLinkMode, DistributionExtractLock, HttpProxyConfig, PythonDistribution,
             PythonDistributionLocation,
         },
         distutils::prepare_hacked_distutils,
@@ -456,13 +456,67 @@ pub struct StandaloneDistribution {
 }
 
 impl StandaloneDistribution {
+    /// Construct a distribution only describing its target and Python version.
+    ///
+    /// The distribution has no files, modules, or libraries. It stands in
+    /// for a distribution that isn't resolved, such as when validating
+    /// configuration files.
+    pub fn stub(target_triple: &str, version: &str) -> Self {
+        let major_minor = version.split('.').take(2).collect::<Vec<_>>().concat();
+        let extension_suffix = if target_triple.contains("pc-windows") {
+            ".pyd"
+        } else {
+            ".so"
+        };
+
+        Self {
+            base_dir: PathBuf::new(),
+            target_triple: target_triple.to_string(),
+            python_implementation: "cpython".to_string(),
+            python_tag: format!("cp{}", major_minor),
+            python_abi_tag: None,
+            python_platform_tag: String::new(),
+            version: version.to_string(),
+            python_exe: PathBuf::new(),
+            stdlib_path: PathBuf::new(),
+            link_mode: StandaloneDistributionLinkMode::Static,
+            python_symbol_visibility: "global-default".to_string(),
+            extension_module_loading: vec![],
+            licenses: None,
+            license_path: None,
+            tcl_library_path: None,
+            objs_core: BTreeMap::new(),
+            links_core: vec![],
+            libpython_shared_library: None,
+            extension_modules: BTreeMap::new(),
+            frozen_c: vec![],
+            includes: BTreeMap::new(),
+            libraries: BTreeMap::new(),
+            py_modules: BTreeMap::new(),
+            resources: BTreeMap::new(),
+            license_infos: BTreeMap::new(),
+            venv_base: PathBuf::new(),
+            inittab_object: PathBuf::new(),
+            inittab_cflags: vec![],
+            cache_tag: format!("cpython-{}", major_minor),
+            module_suffixes: PythonModuleSuffixes {
+                source: vec![".py".to_string()],
+                bytecode: vec![".pyc".to_string()],
+                debug_bytecode: vec![],
+                optimized_bytecode: vec![],
+                extension: vec![extension_suffix.to_string()],
+            },
+        }
+    }
+
     pub fn from_location(
         logger: &slog::Logger,
         location: &PythonDistributionLocation,
         distributions_dir: &Path,
+        proxy: &HttpProxyConfig,
     ) -> Result<Self> {
         let (archive_path, extract_path) =
-            resolve_python_distribution_from_location(logger, location, distributions_dir)?;
+            resolve_python_distribution_from_location(logger, location, distributions_dir, proxy)?;
 
         Self::from_tar_zst_file(logger, &archive_path, &extract_path)
     }
@@ -1321,7 +1375,11 @@ impl PythonDistribution for StandaloneDistribution {
 
 #[cfg(test)]
 pub mod tests {
-    use {super::*, crate::testutil::*};
+    use {
+        super::*,
+        crate::testutil::*,
+        python_packaging::{bytecode::CompileMode, resource::BytecodeOptimizationLevel},
+    };
 
     #[test]
     fn test_stdlib_annotations() -> Result<()> {
@@ -1344,4 +1402,49 @@ pub mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_compile_bytecode_levels() -> Result<()> {
+        let distribution = get_default_distribution()?;
+        let mut compiler = distribution.create_bytecode_compiler()?;
+
+        let source = b"\"\"\"docstring\"\"\"\nassert True\nif __debug__:\n    print('debug')\n";
+        let levels = [
+            BytecodeOptimizationLevel::Zero,
+            BytecodeOptimizationLevel::One,
+            BytecodeOptimizationLevel::Two,
+        ];
+
+        for mode in &[
+            CompileMode::Bytecode,
+            CompileMode::PycCheckedHash,
+            CompileMode::PycUncheckedHash,
+        ] {
+            let compiled = compiler.compile_levels(source, "foo.py", &levels, *mode)?;
+            assert_eq!(compiled.len(), 3);
+
+            for level in &levels {
+                assert_eq!(
+                    compiled.get(level),
+                    Some(&compiler.compile(source, "foo.py", *level, *mode)?)
+                );
+            }
+
+            // Optimization levels should produce distinct bytecode for this source.
+            assert_ne!(
+                compiled[&BytecodeOptimizationLevel::Zero],
+                compiled[&BytecodeOptimizationLevel::One]
+            );
+            assert_ne!(
+                compiled[&BytecodeOptimizationLevel::One],
+                compiled[&BytecodeOptimizationLevel::Two]
+            );
+        }
+
+        assert!(compiler
+            .compile_levels(source, "foo.py", &[], CompileMode::Bytecode)?
+            .is_empty());
+
+        Ok(())
+    }
 }
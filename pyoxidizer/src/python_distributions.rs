@@ -12,6 +12,9 @@ use {
     lazy_static::lazy_static,
 };
 
+/// `X.Y` Python version used when a version isn't requested.
+pub const DEFAULT_PYTHON_VERSION: &str = "3.8";
+
 /// Describes a Python distribution available at a URL.
 pub struct HostedDistribution {
     pub url: String,
@@ -22,32 +25,96 @@ pub struct PythonDistributionCollection {
     dists: Vec<PythonDistributionRecord>,
 }
 
+/// Obtain the patch component of an `X.Y.Z` version string.
+fn python_patch_version(version: &str) -> u32 {
+    version
+        .split('.')
+        .nth(2)
+        .and_then(|v| v.parse::<u32>().ok())
+        .unwrap_or(0)
+}
+
 impl PythonDistributionCollection {
+    /// Construct an instance from distribution records.
+    ///
+    /// Records should be ordered by preference.
+    pub fn new(dists: Vec<PythonDistributionRecord>) -> Self {
+        Self { dists }
+    }
+
     /// Find a Python distribution given requirements.
     ///
     /// `target_triple` is the Rust machine triple the distribution is built for.
     /// `flavor` is the type of Python distribution.
-    /// `python_major_minor_version` is an optional `X.Y` version string being
-    /// requested. If `None`, `3.8` is assumed.
+    /// `python_version` is an optional `X.Y` or `X.Y.Z` version string being
+    /// requested. If `None`, `DEFAULT_PYTHON_VERSION` is assumed. An `X.Y`
+    /// version resolves to the latest available patch release of that
+    /// version. An `X.Y.Z` version must match exactly.
     pub fn find_distribution(
         &self,
         target_triple: &str,
         flavor: &DistributionFlavor,
-        python_major_minor_version: Option<&str>,
+        python_version: Option<&str>,
     ) -> Option<PythonDistributionRecord> {
-        let python_major_minor_version = python_major_minor_version.unwrap_or("3.8");
+        let python_version = python_version.unwrap_or(DEFAULT_PYTHON_VERSION);
+        let exact = python_version.split('.').count() > 2;
+
+        let candidates = self
+            .dists
+            .iter()
+            .filter(|dist| {
+                if exact {
+                    dist.python_version == python_version
+                } else {
+                    dist.python_major_minor_version == python_version
+                }
+            })
+            .filter(|dist| dist.target_triple == target_triple)
+            .filter(|dist| flavor.matches_record(dist))
+            .collect::<Vec<_>>();
 
+        // Records are ordered by preference. So pick the first record having
+        // the latest patch version.
+        let latest = candidates
+            .iter()
+            .map(|dist| python_patch_version(&dist.python_version))
+            .max()?;
+
+        candidates
+            .into_iter()
+            .find(|dist| python_patch_version(&dist.python_version) == latest)
+            .cloned()
+    }
+
+    /// Obtain the `X.Y.Z` Python versions available for a target and flavor.
+    ///
+    /// `python_major_minor_version` optionally restricts results to versions
+    /// of a specific `X.Y` Python version.
+    ///
+    /// Versions are sorted in ascending version order, not string order.
+    pub fn python_versions(
+        &self,
+        target_triple: &str,
+        flavor: &DistributionFlavor,
+        python_major_minor_version: Option<&str>,
+    ) -> Vec<String> {
         self.dists
             .iter()
-            .filter(|dist| dist.python_major_minor_version == python_major_minor_version)
+            .filter(|dist| match python_major_minor_version {
+                Some(version) => dist.python_major_minor_version == version,
+                None => true,
+            })
             .filter(|dist| dist.target_triple == target_triple)
-            .filter(|dist| match flavor {
-                DistributionFlavor::Standalone => true,
-                DistributionFlavor::StandaloneStatic => !dist.supports_prebuilt_extension_modules,
-                DistributionFlavor::StandaloneDynamic => dist.supports_prebuilt_extension_modules,
+            .filter(|dist| flavor.matches_record(dist))
+            .map(|dist| dist.python_version.clone())
+            .sorted_by_key(|version| {
+                version
+                    .split('.')
+                    .map(|part| part.parse::<u32>().unwrap_or(0))
+                    .collect::<Vec<_>>()
             })
-            .cloned()
-            .next()
+            .dedup()
+            .collect::<Vec<_>>()
     }
 
     /// Obtain records for all registered distributions.
@@ -56,8 +123,45 @@ impl PythonDistributionCollection {
         self.dists.iter()
     }
 
+    /// Describe the flavors and `X.Y` Python versions available for a target.
+    ///
+    /// If no distributions are available for the target, the available
+    /// targets are described instead.
+    pub fn describe_available(&self, target_triple: &str) -> String {
+        let flavors = DistributionFlavor::all()
+            .iter()
+            .filter_map(|flavor| {
+                let versions = self
+                    .python_versions(target_triple, flavor, None)
+                    .iter()
+                    .map(|version| version.split('.').take(2).join("."))
+                    .dedup()
+                    .collect::<Vec<_>>();
+
+                if versions.is_empty() {
+                    None
+                } else {
+                    Some(format!("{} ({})", flavor.to_string(), versions.join(", ")))
+                }
+            })
+            .collect::<Vec<_>>();
+
+        if flavors.is_empty() {
+            format!(
+                "no distributions are available for {}; available targets: {}",
+                target_triple,
+                self.all_target_triples().join(", ")
+            )
+        } else {
+            format!(
+                "available distributions for {}: {}",
+                target_triple,
+                flavors.join("; ")
+            )
+        }
+    }
+
     /// All target triples of distributions in this collection.
-    #[allow(unused)]
     pub fn all_target_triples(&self) -> impl Iterator<Item = &str> {
         self.dists
             .iter()
@@ -73,6 +177,7 @@ lazy_static! {
             // Linux glibc linked.
             PythonDistributionRecord {
                 python_major_minor_version: "3.8".to_string(),
+                python_version: "3.8.6".to_string(),
                 location: PythonDistributionLocation::Url {
                     url: "https://github.com/indygreg/python-build-standalone/releases/download/20201003/cpython-3.8.6-x86_64-unknown-linux-gnu-pgo-20201003T2016.tar.zst".to_string(),
                     sha256: "897bb37257a2181b64785c4688bc0b29454ddce7a634bbd491d7b59709f11531".to_string(),
@@ -82,6 +187,7 @@ lazy_static! {
             },
             PythonDistributionRecord {
                 python_major_minor_version: "3.9".to_string(),
+                python_version: "3.9.0".to_string(),
                 location: PythonDistributionLocation::Url {
                     url: "https://github.com/indygreg/python-build-standalone/releases/download/20201006/cpython-3.9.0-x86_64-unknown-linux-gnu-pgo-20201007T0146.tar.zst".to_string(),
                     sha256: "ae2019fc4870f77a4eda9f9597b506347ea0cfa05edfea4698c716c31faddca3".to_string(),
@@ -93,6 +199,7 @@ lazy_static! {
             // Linux musl.
             PythonDistributionRecord {
                 python_major_minor_version: "3.8".to_string(),
+                python_version: "3.8.6".to_string(),
                 location: PythonDistributionLocation::Url {
                     url: "https://github.com/indygreg/python-build-standalone/releases/download/20201003/cpython-3.8.6-x86_64-unknown-linux-musl-noopt-20201003T2016.tar.zst".to_string(),
                     sha256: "7bace9a729eb823bc952554ee5dcb91b0e48b6e9717d52b0f44165335546b8df".to_string(),
@@ -102,6 +209,7 @@ lazy_static! {
             },
             PythonDistributionRecord {
                 python_major_minor_version: "3.9".to_string(),
+                python_version: "3.9.0".to_string(),
                 location: PythonDistributionLocation::Url {
                     url: "https://github.com/indygreg/python-build-standalone/releases/download/20201006/cpython-3.9.0-x86_64-unknown-linux-musl-noopt-20201007T0146.tar.zst".to_string(),
                     sha256: "00f32615b9fa3f804d8b283a158d55addf236dd2b03272ab90549cb801740b27".to_string(),
@@ -120,6 +228,7 @@ lazy_static! {
             // Windows shared.
             PythonDistributionRecord {
                 python_major_minor_version: "3.8".to_string(),
+                python_version: "3.8.6".to_string(),
                 location: PythonDistributionLocation::Url {
                     url: "https://github.com/indygreg/python-build-standalone/releases/download/20201003/cpython-3.8.6-i686-pc-windows-msvc-shared-pgo-20201003T2039.tar.zst".to_string(),
                     sha256: "acefe8125a33338b8825c715c9dc49c5a80aa2c9742b1d9b576118ed1852adf8".to_string(),
@@ -129,6 +238,7 @@ lazy_static! {
             },
             PythonDistributionRecord {
                 python_major_minor_version: "3.9".to_string(),
+                python_version: "3.9.0".to_string(),
                 location: PythonDistributionLocation::Url {
                     url: "https://github.com/indygreg/python-build-standalone/releases/download/20201005/cpython-3.9.0-i686-pc-windows-msvc-shared-pgo-20201006T0241.tar.zst".to_string(),
                     sha256: "753df81eb5d5cf50866e2fa023bd3b070ac1569e6fd70e2267267b8dfbd0530f".to_string(),
@@ -138,6 +248,7 @@ lazy_static! {
             },
             PythonDistributionRecord {
                 python_major_minor_version: "3.8".to_string(),
+                python_version: "3.8.6".to_string(),
                 location: PythonDistributionLocation::Url {
                     url: "https://github.com/indygreg/python-build-standalone/releases/download/20201003/cpython-3.8.6-x86_64-pc-windows-msvc-shared-pgo-20201003T2021.tar.zst".to_string(),
                     sha256: "671122d910e57230df4fe3aae024e8a56613a1786d53567553adc8e31d2490f1".to_string(),
@@ -147,6 +258,7 @@ lazy_static! {
             },
             PythonDistributionRecord {
                 python_major_minor_version: "3.9".to_string(),
+                python_version: "3.9.0".to_string(),
                 location: PythonDistributionLocation::Url {
                     url: "https://github.com/indygreg/python-build-standalone/releases/download/20201005/cpython-3.9.0-x86_64-pc-windows-msvc-shared-pgo-20201006T0240.tar.zst".to_string(),
                     sha256: "471878204860800c6272eb74354aae274bf97ac345401155443e42a3e0788df1".to_string(),
@@ -158,6 +270,7 @@ lazy_static! {
             // Windows static.
             PythonDistributionRecord {
                 python_major_minor_version: "3.8".to_string(),
+                python_version: "3.8.6".to_string(),
                 location: PythonDistributionLocation::Url {
                     url: "https://github.com/indygreg/python-build-standalone/releases/download/20201003/cpython-3.8.6-i686-pc-windows-msvc-static-noopt-20201003T2034.tar.zst".to_string(),
                     sha256: "12a2ea07b3875228dae67582e97a721e865b1a924efa4dc79aaec1043986ef0b".to_string(),
@@ -167,6 +280,7 @@ lazy_static! {
             },
             PythonDistributionRecord {
                 python_major_minor_version: "3.9".to_string(),
+                python_version: "3.9.0".to_string(),
                 location: PythonDistributionLocation::Url {
                     url: "https://github.com/indygreg/python-build-standalone/releases/download/20201005/cpython-3.9.0-i686-pc-windows-msvc-static-noopt-20201006T0236.tar.zst".to_string(),
                     sha256: "4e722bf60b34212fc24e674f8b981790433a1c367190f99201875126fedc9692".to_string(),
@@ -176,6 +290,7 @@ lazy_static! {
             },
             PythonDistributionRecord {
                 python_major_minor_version: "3.8".to_string(),
+                python_version: "3.8.6".to_string(),
                 location: PythonDistributionLocation::Url {
                     url: "https://github.com/indygreg/python-build-standalone/releases/download/20201003/cpython-3.8.6-x86_64-pc-windows-msvc-static-noopt-20201003T2015.tar.zst".to_string(),
                     sha256: "4bcbbfc41ca03bb1a6edc1435406f9e27e02f64fc0a0248578a9c26e891c1e39".to_string(),
@@ -185,6 +300,7 @@ lazy_static! {
             },
             PythonDistributionRecord {
                 python_major_minor_version: "3.9".to_string(),
+                python_version: "3.9.0".to_string(),
                 location: PythonDistributionLocation::Url {
                     url: "https://github.com/indygreg/python-build-standalone/releases/download/20201005/cpython-3.9.0-x86_64-pc-windows-msvc-static-noopt-20201006T0232.tar.zst".to_string(),
                     sha256: "79fa7ff6e9e729d22175507826648ab7490d25b5968ad5ad01090497eb4255d8".to_string(),
@@ -196,6 +312,7 @@ lazy_static! {
             // macOS.
             PythonDistributionRecord {
                 python_major_minor_version: "3.8".to_string(),
+                python_version: "3.8.6".to_string(),
                 location: PythonDistributionLocation::Url {
                     url: "https://github.com/indygreg/python-build-standalone/releases/download/20201003/cpython-3.8.6-x86_64-apple-darwin-pgo-20201003T2017.tar.zst".to_string(),
                     sha256: "aa1b61cceedf3e6661e25de40cc366c91af98a6c2d5a02334d665b59682b02e3".to_string(),
@@ -205,6 +322,7 @@ lazy_static! {
             },
             PythonDistributionRecord {
                 python_major_minor_version: "3.9".to_string(),
+                python_version: "3.9.0".to_string(),
                 location: PythonDistributionLocation::Url {
                     url: "https://github.com/indygreg/python-build-standalone/releases/download/20201006/cpython-3.9.0-x86_64-apple-darwin-pgo-20201007T0154.tar.zst".to_string(),
                     sha256: "31e00721a776b604b1d4de82a9e8e8a02b09dcb351d51720869301157e67911b".to_string(),
@@ -247,4 +365,33 @@ mod tests {
             ]
         );
     }
+
+    #[test]
+    fn test_find_distribution_python_version() {
+        let triple = "x86_64-unknown-linux-gnu";
+
+        let dist = PYTHON_DISTRIBUTIONS
+            .find_distribution(triple, &DistributionFlavor::Standalone, Some("3.9"))
+            .unwrap();
+        assert_eq!(dist.python_major_minor_version, "3.9");
+        assert_eq!(dist.python_version, "3.9.0");
+
+        let dist = PYTHON_DISTRIBUTIONS
+            .find_distribution(triple, &DistributionFlavor::Standalone, Some("3.8.6"))
+            .unwrap();
+        assert_eq!(dist.python_version, "3.8.6");
+
+        assert!(PYTHON_DISTRIBUTIONS
+            .find_distribution(triple, &DistributionFlavor::Standalone, Some("3.8.0"))
+            .is_none());
+
+        assert_eq!(
+            PYTHON_DISTRIBUTIONS.python_versions(
+                triple,
+                &DistributionFlavor::Standalone,
+                Some("3.8")
+            ),
+            vec!["3.8.6".to_string()]
+        );
+    }
 }
@@ -7,9 +7,15 @@ use {
         file_resource::FileManifestValue,
         python_embedded_resources::PythonEmbeddedResources,
         python_executable::PythonExecutable,
+        python_executable_tarball::PythonExecutableTarball,
+        python_resources_bundle::PythonResourcesBundle,
         target::{BuildContext, BuildTarget, ResolvedTarget},
-        util::{optional_list_arg, required_bool_arg, required_str_arg, required_type_arg},
+        util::{
+            optional_list_arg, optional_str_arg, required_bool_arg, required_str_arg,
+            required_type_arg,
+        },
     },
+    crate::py_packaging::distribution::{DistributionFlavorRegistry, HttpProxyConfig},
     anyhow::{anyhow, Context, Result},
     linked_hash_map::LinkedHashMap,
     path_dedot::ParseDot,
@@ -18,7 +24,9 @@ use {
         environment::{Environment, EnvironmentError, TypeValues},
         eval::call_stack::CallStack,
         values::{
-            error::{RuntimeError, ValueError},
+            error::{
+                RuntimeError, UnsupportedOperation, ValueError, INCORRECT_PARAMETER_TYPE_ERROR_CODE,
+            },
             none::NoneType,
             {Mutable, TypedValue, Value, ValueResult},
         },
@@ -31,6 +39,7 @@ use {
         collections::BTreeMap,
         path::{Path, PathBuf},
     },
+    url::Url,
 };
 
 /// Represents a registered target in the Starlark environment.
@@ -85,6 +94,9 @@ pub struct EnvironmentContext {
     /// Path where Python distributions are written.
     pub python_distributions_path: PathBuf,
 
+    /// Path where results of `pip install` are cached.
+    pub pip_install_cache_path: PathBuf,
+
     /// Registered build targets.
     ///
     /// A target consists of a name and a Starlark callable.
@@ -96,6 +108,9 @@ pub struct EnvironmentContext {
     /// Name of default target.
     pub default_target: Option<String>,
 
+    /// Targets being resolved by `resolve_target()`, outermost first.
+    pub resolving_targets: Vec<String>,
+
     /// Name of default target to resolve in build script mode.
     pub default_build_script_target: Option<String>,
 
@@ -106,6 +121,32 @@ pub struct EnvironmentContext {
     ///
     /// This will change the default target to resolve.
     pub build_script_mode: bool,
+
+    /// Whether network access is forbidden.
+    ///
+    /// When set, operations that would download a resource fail instead.
+    pub offline: bool,
+
+    /// Hosts Python distributions may be downloaded from.
+    ///
+    /// If defined, downloading a distribution from a URL whose host isn't in
+    /// this list fails. Local distributions are not affected.
+    pub allowed_distribution_hosts: Option<Vec<String>>,
+
+    /// Whether evaluation only validates the configuration.
+    ///
+    /// When set, target functions aren't called and Python distributions
+    /// aren't downloaded or extracted.
+    pub validate_only: bool,
+
+    /// Proxy settings for HTTP requests, such as Python distribution downloads.
+    ///
+    /// Defaults to settings from the `HTTP_PROXY`, `HTTPS_PROXY`, and
+    /// `NO_PROXY` environment variables.
+    pub http_proxy: HttpProxyConfig,
+
+    /// Distribution flavors available in addition to the built-in flavors.
+    pub distribution_flavors: DistributionFlavorRegistry,
 }
 
 impl EnvironmentContext {
@@ -120,6 +161,9 @@ impl EnvironmentContext {
         build_opt_level: &str,
         resolve_targets: Option<Vec<String>>,
         build_script_mode: bool,
+        offline: bool,
+        allowed_distribution_hosts: Option<Vec<String>>,
+        validate_only: bool,
     ) -> Result<EnvironmentContext> {
         let parent = config_path
             .parent()
@@ -144,12 +188,19 @@ impl EnvironmentContext {
             build_opt_level: build_opt_level.to_string(),
             build_path: build_path.clone(),
             python_distributions_path: build_path.join("python_distributions"),
+            pip_install_cache_path: build_path.join("pip_install_cache"),
             targets: BTreeMap::new(),
             targets_order: Vec::new(),
             default_target: None,
+            resolving_targets: Vec::new(),
             default_build_script_target: None,
             resolve_targets,
             build_script_mode,
+            offline,
+            allowed_distribution_hosts,
+            validate_only,
+            http_proxy: HttpProxyConfig::from_env(),
+            distribution_flavors: DistributionFlavorRegistry::default(),
         })
     }
 
@@ -164,6 +215,29 @@ impl EnvironmentContext {
 
         self.build_path = path.clone();
         self.python_distributions_path = path.join("python_distributions");
+        self.pip_install_cache_path = path.join("pip_install_cache");
+
+        Ok(())
+    }
+
+    /// Set the directory relative paths are resolved against.
+    ///
+    /// A relative `path` is resolved against the current value. The
+    /// directory must exist.
+    pub fn set_cwd(&mut self, path: &Path) -> Result<()> {
+        let path = if path.is_relative() {
+            self.cwd.join(path)
+        } else {
+            path.to_path_buf()
+        }
+        .parse_dot()?
+        .to_path_buf();
+
+        if !path.is_dir() {
+            return Err(anyhow!("{} is not a directory", path.display()));
+        }
+
+        self.cwd = path;
 
         Ok(())
     }
@@ -216,6 +290,54 @@ impl EnvironmentContext {
         }
     }
 
+    /// Order targets so each target follows the targets it depends on.
+    ///
+    /// The returned list contains `targets` and all their transitive
+    /// dependencies. An error occurs if a target is not registered or if
+    /// dependencies are cyclic.
+    pub fn targets_in_dependency_order(&self, targets: &[String]) -> Result<Vec<String>> {
+        fn visit(
+            context: &EnvironmentContext,
+            target: &str,
+            visiting: &mut Vec<String>,
+            order: &mut Vec<String>,
+        ) -> Result<()> {
+            if order.iter().any(|t| t == target) {
+                return Ok(());
+            }
+
+            if let Some(index) = visiting.iter().position(|t| t == target) {
+                let mut cycle = visiting[index..].to_vec();
+                cycle.push(target.to_string());
+
+                return Err(anyhow!("target dependency cycle: {}", cycle.join(" -> ")));
+            }
+
+            let entry = context
+                .targets
+                .get(target)
+                .ok_or_else(|| anyhow!("target {} does not exist", target))?;
+
+            visiting.push(target.to_string());
+            for depend in &entry.depends {
+                visit(context, depend, visiting, order)?;
+            }
+            visiting.pop();
+
+            order.push(target.to_string());
+
+            Ok(())
+        }
+
+        let mut order = vec![];
+
+        for target in targets {
+            visit(self, target, &mut vec![], &mut order)?;
+        }
+
+        Ok(order)
+    }
+
     /// Build a resolved target.
     pub fn build_resolved_target(&mut self, target: &str) -> Result<ResolvedTarget> {
         let resolved_value = if let Some(t) = self.targets.get(target) {
@@ -244,6 +366,11 @@ impl EnvironmentContext {
 
         std::fs::create_dir_all(&output_path).context("creating output path")?;
 
+        // Building happens after evaluation, so we need a fresh environment for
+        // calling into Starlark.
+        let (_, type_values) = global_environment(self)
+            .map_err(|e| anyhow!("error creating Starlark environment: {:?}", e))?;
+
         let context = BuildContext {
             logger: self.logger.clone(),
             host_triple: self.build_host_triple.clone(),
@@ -251,6 +378,7 @@ impl EnvironmentContext {
             release: self.build_release,
             opt_level: self.build_opt_level.clone(),
             output_path,
+            type_values: &type_values,
         };
 
         // TODO surely this can use dynamic dispatch.
@@ -270,6 +398,16 @@ impl EnvironmentContext {
                 .map_err(|_| anyhow!("object isn't mutable"))?
                 .ok_or_else(|| anyhow!("invalid cast"))?
                 .build(&context),
+            "PythonExecutableTarball" => resolved_value
+                .downcast_mut::<PythonExecutableTarball>()
+                .map_err(|_| anyhow!("object isn't mutable"))?
+                .ok_or_else(|| anyhow!("invalid cast"))?
+                .build(&context),
+            "PythonResourcesBundle" => resolved_value
+                .downcast_mut::<PythonResourcesBundle>()
+                .map_err(|_| anyhow!("object isn't mutable"))?
+                .ok_or_else(|| anyhow!("invalid cast"))?
+                .build(&context),
             _ => Err(anyhow!("could not determine type of target")),
         }?;
 
@@ -320,6 +458,21 @@ impl TypedValue for EnvironmentContext {
     fn values_for_descendant_check_and_freeze(&self) -> Box<dyn Iterator<Item = Value>> {
         Box::new(std::iter::empty())
     }
+
+    fn get_attr(&self, attribute: &str) -> ValueResult {
+        match attribute {
+            "cwd" => Ok(Value::from(self.cwd.display().to_string())),
+            attr => Err(ValueError::OperationNotSupported {
+                op: UnsupportedOperation::GetAttr(attr.to_string()),
+                left: Self::TYPE.to_string(),
+                right: None,
+            }),
+        }
+    }
+
+    fn has_attr(&self, attribute: &str) -> Result<bool, ValueError> {
+        Ok(attribute == "cwd")
+    }
 }
 
 /// Starlark type holding context for PyOxidizer.
@@ -417,8 +570,8 @@ fn starlark_resolve_target(
     // into Starlark or we can get double borrows. Hence the block here.
     let target_entry = {
         let raw_context = get_context(type_values)?;
-        let context = raw_context
-            .downcast_ref::<EnvironmentContext>()
+        let mut context = raw_context
+            .downcast_mut::<EnvironmentContext>()?
             .ok_or(ValueError::IncorrectParameterType)?;
 
         // If we have a resolved value for this target, return it.
@@ -434,19 +587,79 @@ fn starlark_resolve_target(
             return Ok(v);
         }
 
+        // A target depending on itself, directly or indirectly, would
+        // otherwise recurse until the stack overflows.
+        if let Some(index) = context.resolving_targets.iter().position(|t| t == &target) {
+            let mut cycle = context.resolving_targets[index..].to_vec();
+            cycle.push(target.clone());
+
+            return Err(ValueError::from(RuntimeError {
+                code: "PYOXIDIZER_BUILD",
+                message: format!("target dependency cycle: {}", cycle.join(" -> ")),
+                label: "resolve_target()".to_string(),
+            }));
+        }
+
         warn!(&context.logger, "resolving target {}", target);
 
-        match &context.targets.get(&target) {
+        let entry = match &context.targets.get(&target) {
             Some(v) => Ok((*v).clone()),
             None => Err(ValueError::from(RuntimeError {
                 code: "PYOXIDIZER_BUILD",
                 message: format!("target {} does not exist", target),
                 label: "resolve_target()".to_string(),
             })),
-        }?
+        }?;
+
+        context.resolving_targets.push(target.clone());
+
+        entry
     };
 
-    // Resolve target dependencies.
+    let res = resolve_target_entry(type_values, call_stack, target_entry);
+
+    // We can't obtain a mutable reference to the context above because it
+    // would create multiple borrows.
+    let raw_context = get_context(type_values)?;
+    let mut context = raw_context
+        .downcast_mut::<EnvironmentContext>()?
+        .ok_or(ValueError::IncorrectParameterType)?;
+
+    context.resolving_targets.pop();
+
+    let res = res?;
+
+    // Run modes referencing modules that aren't packaged are reported when
+    // the configuration is evaluated instead of when the executable is built.
+    // Executables aren't populated with packages when validating.
+    if !context.validate_only {
+        if let Some(exe) = res.downcast_ref::<PythonExecutable>() {
+            exe.exe.validate_run_modules().map_err(|e| {
+                ValueError::from(RuntimeError {
+                    code: "PYOXIDIZER_BUILD",
+                    message: format!("target {}: {}", target, e),
+                    label: "resolve_target()".to_string(),
+                })
+            })?;
+        }
+    }
+
+    // TODO consider replacing the target's callable with a new function that returns the
+    // resolved value. This will ensure a target function is only ever called once.
+
+    if let Some(target_entry) = context.targets.get_mut(&target) {
+        target_entry.resolved_value = Some(res.clone());
+    }
+
+    Ok(res)
+}
+
+/// Resolve the dependencies of a target and call its function.
+fn resolve_target_entry(
+    type_values: &TypeValues,
+    call_stack: &mut CallStack,
+    target_entry: Target,
+) -> ValueResult {
     let mut args = Vec::new();
 
     for depend_target in target_entry.depends {
@@ -458,30 +671,14 @@ fn starlark_resolve_target(
         )?);
     }
 
-    let res = target_entry.callable.call(
+    target_entry.callable.call(
         call_stack,
         type_values,
         args,
         LinkedHashMap::new(),
         None,
         None,
-    )?;
-
-    // TODO consider replacing the target's callable with a new function that returns the
-    // resolved value. This will ensure a target function is only ever called once.
-
-    // We can't obtain a mutable reference to the context above because it
-    // would create multiple borrows.
-    let raw_context = get_context(type_values)?;
-    let mut context = raw_context
-        .downcast_mut::<EnvironmentContext>()?
-        .ok_or(ValueError::IncorrectParameterType)?;
-
-    if let Some(target_entry) = context.targets.get_mut(&target) {
-        target_entry.resolved_value = Some(res.clone());
-    }
-
-    Ok(res)
+    )
 }
 
 /// resolve_targets()
@@ -506,7 +703,16 @@ fn starlark_resolve_targets(type_values: &TypeValues, call_stack: &mut CallStack
             .downcast_ref::<EnvironmentContext>()
             .ok_or(ValueError::IncorrectParameterType)?;
 
-        context.targets_to_resolve()
+        // Dependencies are resolved before the targets depending on them.
+        context
+            .targets_in_dependency_order(&context.targets_to_resolve())
+            .map_err(|e| {
+                ValueError::from(RuntimeError {
+                    code: "PYOXIDIZER_BUILD",
+                    message: e.to_string(),
+                    label: "resolve_targets()".to_string(),
+                })
+            })?
     };
 
     println!("resolving {} targets", targets.len());
@@ -544,6 +750,86 @@ fn starlark_set_build_path(type_values: &TypeValues, path: &Value) -> ValueResul
     Ok(Value::new(NoneType::None))
 }
 
+/// set_cwd(path)
+fn starlark_set_cwd(type_values: &TypeValues, path: &Value) -> ValueResult {
+    let path = required_str_arg("path", &path)?;
+
+    let raw_context = get_context(type_values)?;
+    let mut context = raw_context
+        .downcast_mut::<EnvironmentContext>()?
+        .ok_or(ValueError::IncorrectParameterType)?;
+
+    context.set_cwd(&PathBuf::from(&path)).map_err(|e| {
+        ValueError::from(RuntimeError {
+            code: "PYOXIDIZER_BUILD",
+            message: e.to_string(),
+            label: "set_cwd()".to_string(),
+        })
+    })?;
+
+    Ok(Value::new(NoneType::None))
+}
+
+/// set_http_proxy(http_proxy=None, https_proxy=None, no_proxy=None)
+fn starlark_set_http_proxy(
+    type_values: &TypeValues,
+    http_proxy: &Value,
+    https_proxy: &Value,
+    no_proxy: &Value,
+) -> ValueResult {
+    let http_proxy = optional_str_arg("http_proxy", &http_proxy)?;
+    let https_proxy = optional_str_arg("https_proxy", &https_proxy)?;
+    optional_list_arg("no_proxy", "string", &no_proxy)?;
+
+    for value in http_proxy.iter().chain(https_proxy.iter()) {
+        Url::parse(value).map_err(|e| {
+            ValueError::from(RuntimeError {
+                code: INCORRECT_PARAMETER_TYPE_ERROR_CODE,
+                message: format!("invalid proxy URL {}: {}", value, e),
+                label: "set_http_proxy()".to_string(),
+            })
+        })?;
+    }
+
+    let no_proxy = match no_proxy.get_type() {
+        "list" => no_proxy.iter()?.iter().map(|x| x.to_string()).collect(),
+        _ => Vec::new(),
+    };
+
+    let raw_context = get_context(type_values)?;
+    let mut context = raw_context
+        .downcast_mut::<EnvironmentContext>()?
+        .ok_or(ValueError::IncorrectParameterType)?;
+
+    context.http_proxy = HttpProxyConfig {
+        http_proxy,
+        https_proxy,
+        no_proxy,
+    };
+
+    Ok(Value::new(NoneType::None))
+}
+
+/// build_target_triple()
+fn starlark_build_target_triple(type_values: &TypeValues) -> ValueResult {
+    let raw_context = get_context(type_values)?;
+    let context = raw_context
+        .downcast_ref::<EnvironmentContext>()
+        .ok_or(ValueError::IncorrectParameterType)?;
+
+    Ok(Value::from(context.build_target_triple.clone()))
+}
+
+/// build_host_triple()
+fn starlark_build_host_triple(type_values: &TypeValues) -> ValueResult {
+    let raw_context = get_context(type_values)?;
+    let context = raw_context
+        .downcast_ref::<EnvironmentContext>()
+        .ok_or(ValueError::IncorrectParameterType)?;
+
+    Ok(Value::from(context.build_host_triple.clone()))
+}
+
 starlark_module! { global_module =>
     #[allow(clippy::ptr_arg)]
     register_target(
@@ -578,6 +864,29 @@ starlark_module! { global_module =>
     set_build_path(env env, path) {
         starlark_set_build_path(&env, &path)
     }
+
+    #[allow(clippy::ptr_arg)]
+    set_cwd(env env, path) {
+        starlark_set_cwd(&env, &path)
+    }
+
+    #[allow(clippy::ptr_arg)]
+    set_http_proxy(
+        env env,
+        http_proxy=NoneType::None,
+        https_proxy=NoneType::None,
+        no_proxy=NoneType::None
+    ) {
+        starlark_set_http_proxy(&env, &http_proxy, &https_proxy, &no_proxy)
+    }
+
+    build_target_triple(env env) {
+        starlark_build_target_triple(&env)
+    }
+
+    build_host_triple(env env) {
+        starlark_build_host_triple(&env)
+    }
 }
 
 /// Obtain a Starlark environment for evaluating PyOxidizer configurations.
@@ -589,6 +898,7 @@ pub fn global_environment(
     super::file_resource::file_resource_env(&mut env, &mut type_values);
     super::python_distribution::python_distribution_module(&mut env, &mut type_values);
     super::python_executable::python_executable_env(&mut env, &mut type_values);
+    super::python_interpreter_config::python_interpreter_config_module(&mut env, &mut type_values);
     super::python_packaging_policy::python_packaging_policy_module(&mut env, &mut type_values);
 
     env.set("CONTEXT", Value::new(context.clone()))?;
@@ -612,6 +922,7 @@ pub fn global_environment(
         "resolve_target",
         "resolve_targets",
         "set_build_path",
+        "set_cwd",
         "CONTEXT",
         "CWD",
         "CONFIG_PATH",
@@ -635,12 +946,103 @@ pub mod tests {
         assert_eq!(cwd.to_str(), pwd.display().to_string());
     }
 
+    #[test]
+    fn test_set_cwd() -> Result<()> {
+        let temp_dir = tempdir::TempDir::new("pyoxidizer-test")?;
+        let sub = temp_dir.path().join("sub");
+        std::fs::create_dir(&sub)?;
+
+        let mut env = StarlarkEnvironment::new()?;
+        let pwd = std::env::current_dir()?;
+        env.eval_assert(&format!("CONTEXT.cwd == {:?}", pwd.display().to_string()))?;
+
+        env.eval(&format!(
+            "set_cwd({:?})",
+            temp_dir.path().display().to_string()
+        ))?;
+        env.eval_assert(&format!(
+            "CONTEXT.cwd == {:?}",
+            temp_dir.path().display().to_string()
+        ))?;
+
+        env.eval("set_cwd('sub')")?;
+        env.eval_assert(&format!("CONTEXT.cwd == {:?}", sub.display().to_string()))?;
+
+        assert!(env.eval("set_cwd('missing')").is_err());
+        env.eval_assert(&format!("CONTEXT.cwd == {:?}", sub.display().to_string()))?;
+
+        // CWD retains its initial value.
+        env.eval_assert(&format!("CWD == {:?}", pwd.display().to_string()))?;
+
+        Ok(())
+    }
+
     #[test]
     fn test_build_target() {
         let target = starlark_ok("BUILD_TARGET_TRIPLE");
         assert_eq!(target.to_str(), crate::project_building::HOST);
     }
 
+    #[test]
+    fn test_build_target_triple() -> Result<()> {
+        let mut env = StarlarkEnvironment::new()?;
+
+        let target = env.eval("build_target_triple()")?;
+        assert_eq!(target.get_type(), "string");
+        assert!(!target.to_str().is_empty());
+        assert_eq!(target.to_str(), crate::project_building::HOST);
+
+        env.set_target_triple("x86_64-pc-windows-msvc")?;
+        let target = env.eval("build_target_triple()")?;
+        assert_eq!(target.to_str(), "x86_64-pc-windows-msvc");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_set_http_proxy() -> Result<()> {
+        let mut env = StarlarkEnvironment::new()?;
+
+        env.eval("set_http_proxy(http_proxy='http://proxy:3128', https_proxy='http://proxy:3129', no_proxy=['localhost', '.internal'])")?;
+
+        let raw_context = env.eval("CONTEXT")?;
+        let context = raw_context
+            .downcast_ref::<EnvironmentContext>()
+            .ok_or(ValueError::IncorrectParameterType)
+            .unwrap();
+        assert_eq!(
+            context.http_proxy,
+            HttpProxyConfig {
+                http_proxy: Some("http://proxy:3128".to_string()),
+                https_proxy: Some("http://proxy:3129".to_string()),
+                no_proxy: vec!["localhost".to_string(), ".internal".to_string()],
+            }
+        );
+        drop(context);
+
+        env.eval("set_http_proxy()")?;
+        let raw_context = env.eval("CONTEXT")?;
+        let context = raw_context
+            .downcast_ref::<EnvironmentContext>()
+            .ok_or(ValueError::IncorrectParameterType)
+            .unwrap();
+        assert_eq!(context.http_proxy, HttpProxyConfig::default());
+        drop(context);
+
+        assert!(env.eval("set_http_proxy(http_proxy='not a url')").is_err());
+        assert!(env.eval("set_http_proxy(no_proxy='localhost')").is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_build_host_triple() {
+        let host = starlark_ok("build_host_triple()");
+        assert_eq!(host.get_type(), "string");
+        assert!(!host.to_str().is_empty());
+        assert_eq!(host.to_str(), crate::project_building::HOST);
+    }
+
     #[test]
     fn test_register_target() -> Result<()> {
         let mut env = StarlarkEnvironment::new()?;
@@ -687,4 +1089,109 @@ pub mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_targets_in_dependency_order() -> Result<()> {
+        let mut env = StarlarkEnvironment::new()?;
+        env.eval("def noop(*args): pass")?;
+        env.eval("register_target('app', noop, depends=['lib', 'config'])")?;
+        env.eval("register_target('lib', noop, depends=['config'])")?;
+        env.eval("register_target('config', noop)")?;
+        env.eval("register_target('a', noop, depends=['b'])")?;
+        env.eval("register_target('b', noop, depends=['a'])")?;
+        env.eval("register_target('missing', noop, depends=['unknown'])")?;
+
+        let raw_context = env.eval("CONTEXT")?;
+        let context = raw_context
+            .downcast_ref::<EnvironmentContext>()
+            .ok_or(ValueError::IncorrectParameterType)
+            .unwrap();
+
+        assert_eq!(
+            context.targets_in_dependency_order(&["app".to_string()])?,
+            vec!["config".to_string(), "lib".to_string(), "app".to_string()]
+        );
+        assert_eq!(
+            context.targets_in_dependency_order(&["lib".to_string(), "app".to_string()])?,
+            vec!["config".to_string(), "lib".to_string(), "app".to_string()]
+        );
+
+        let err = context
+            .targets_in_dependency_order(&["a".to_string()])
+            .unwrap_err();
+        assert_eq!(err.to_string(), "target dependency cycle: a -> b -> a");
+
+        let err = context
+            .targets_in_dependency_order(&["missing".to_string()])
+            .unwrap_err();
+        assert_eq!(err.to_string(), "target unknown does not exist");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_resolve_targets_dependency_order() -> Result<()> {
+        let drain = CapturingDrain::default();
+
+        let mut env = StarlarkEnvironment::new()?;
+        env.set_logger(slog::Logger::root(drain.clone(), slog::o!()))?;
+        env.eval("def noop(*args): pass")?;
+        env.eval("register_target('app', noop, depends=['lib'], default=True)")?;
+        env.eval("register_target('lib', noop)")?;
+        env.eval("resolve_targets()")?;
+
+        let resolved = drain
+            .messages
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|m| m.starts_with("resolving target "))
+            .cloned()
+            .collect::<Vec<_>>();
+        assert_eq!(
+            resolved,
+            vec![
+                "resolving target lib".to_string(),
+                "resolving target app".to_string()
+            ]
+        );
+
+        let mut env = StarlarkEnvironment::new()?;
+        env.eval("def noop(*args): pass")?;
+        env.eval("register_target('a', noop, depends=['b'], default=True)")?;
+        env.eval("register_target('b', noop, depends=['a'])")?;
+
+        let err = env.eval("resolve_targets()").unwrap_err();
+        assert!(err
+            .to_string()
+            .as_str()
+            .contains("target dependency cycle: a -> b -> a"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_resolve_target_cycle() -> Result<()> {
+        let mut env = StarlarkEnvironment::new()?;
+        env.eval("def noop(*args): pass")?;
+        env.eval("register_target('a', noop, depends=['b'])")?;
+        env.eval("register_target('b', noop, depends=['c'])")?;
+        env.eval("register_target('c', noop, depends=['a'])")?;
+
+        let err = env.eval("resolve_target('a')").unwrap_err();
+        assert!(err
+            .to_string()
+            .as_str()
+            .contains("target dependency cycle: a -> b -> c -> a"));
+
+        // The failed resolution doesn't leave targets marked as in progress.
+        let raw_context = env.eval("CONTEXT")?;
+        let context = raw_context
+            .downcast_ref::<EnvironmentContext>()
+            .ok_or(ValueError::IncorrectParameterType)
+            .unwrap();
+        assert!(context.resolving_targets.is_empty());
+
+        Ok(())
+    }
 }
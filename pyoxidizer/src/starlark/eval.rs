@@ -4,16 +4,312 @@
 
 use {
     super::env::{global_environment, EnvironmentContext},
-    anyhow::{anyhow, Result},
+    crate::py_packaging::distribution::DistributionFlavorRegistry,
     codemap::CodeMap,
     codemap_diagnostic::{Diagnostic, Level},
-    starlark::{environment::Environment, syntax::dialect::Dialect},
+    starlark::{
+        environment::Environment,
+        syntax::{
+            dialect::Dialect,
+            lexer::{Lexer, Token},
+        },
+        values::Value,
+    },
     std::{
+        collections::HashMap,
+        fmt,
         path::Path,
         sync::{Arc, Mutex},
     },
 };
 
+/// Keywords of the Starlark language, which can't be used as variable names.
+const STARLARK_KEYWORDS: &[&str] = &[
+    "and", "break", "continue", "def", "elif", "else", "for", "if", "in", "lambda", "load", "not",
+    "or", "pass", "return",
+];
+
+/// Whether a string is usable as the name of a Starlark global variable.
+fn is_valid_variable_name(name: &str) -> bool {
+    let valid = match name.chars().next() {
+        Some(c) => {
+            (c.is_ascii_alphabetic() || c == '_')
+                && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+        }
+        None => false,
+    };
+
+    valid && !STARLARK_KEYWORDS.contains(&name)
+}
+
+/// Define string global variables in an environment.
+///
+/// Variables can't replace existing globals, such as built-in functions.
+fn define_variables(env: &mut Environment, vars: &HashMap<String, String>) -> Result<(), String> {
+    let mut names = vars.keys().collect::<Vec<_>>();
+    names.sort();
+
+    for name in names {
+        if !is_valid_variable_name(name) {
+            return Err(format!(
+                "invalid variable name {}; must consist of letters, digits, and underscores and not start with a digit",
+                name
+            ));
+        }
+
+        if env.get(name).is_ok() {
+            return Err(format!(
+                "variable {} conflicts with an existing global",
+                name
+            ));
+        }
+
+        env.set(name, Value::from(vars[name].clone()))
+            .map_err(|e| format!("unable to define variable {}: {:?}", name, e))?;
+    }
+
+    Ok(())
+}
+
+/// Whether a token is an assignment operator.
+fn is_assignment_token(token: &Token) -> bool {
+    matches!(
+        token,
+        Token::Equal
+            | Token::PlusEqual
+            | Token::MinusEqual
+            | Token::StarEqual
+            | Token::SlashEqual
+            | Token::DoubleSlashEqual
+            | Token::PercentEqual
+    )
+}
+
+/// Find names bound by a module level statement.
+///
+/// Returns each bound name along with its byte offset in the source.
+fn statement_bindings(statement: &[&(u64, Token, u64)]) -> Vec<(String, u64)> {
+    let mut res = vec![];
+
+    match statement.first().map(|(_, token, _)| token) {
+        Some(Token::Def) => {
+            if let Some((offset, Token::Identifier(name), _)) = statement.get(1) {
+                res.push((name.clone(), *offset));
+            }
+        }
+        // load("file.bzl", "symbol", alias = "symbol")
+        Some(Token::Load) => {
+            for (i, (offset, token, _)) in statement.iter().enumerate().skip(3) {
+                let next = statement.get(i + 1).map(|(_, token, _)| token);
+                let previous = statement.get(i - 1).map(|(_, token, _)| token);
+
+                match token {
+                    Token::Identifier(name) if next == Some(&Token::Equal) => {
+                        res.push((name.clone(), *offset));
+                    }
+                    Token::StringLiteral(name) if previous != Some(&Token::Equal) => {
+                        res.push((name.clone(), *offset));
+                    }
+                    _ => {}
+                }
+            }
+        }
+        _ => {
+            // Keyword arguments also use `=`, so only consider operators
+            // outside brackets.
+            let mut depth = 0;
+            let end = match statement.iter().position(|(_, token, _)| {
+                match token {
+                    Token::OpeningBracket
+                    | Token::OpeningCurlyBracket
+                    | Token::OpeningParenthesis => depth += 1,
+                    Token::ClosingBracket
+                    | Token::ClosingCurlyBracket
+                    | Token::ClosingParenthesis => depth -= 1,
+                    _ => {}
+                }
+
+                depth == 0 && is_assignment_token(token)
+            }) {
+                Some(end) => end,
+                None => return res,
+            };
+
+            // Identifiers in brackets are only bound if the brackets group
+            // targets, e.g. `(a, b) = ...`, rather than index or call
+            // something, e.g. `a[b] = ...`.
+            let mut groups = vec![];
+
+            for (i, (offset, token, _)) in statement[0..end].iter().enumerate() {
+                let previous = if i > 0 {
+                    Some(&statement[i - 1].1)
+                } else {
+                    None
+                };
+                let next = statement.get(i + 1).map(|(_, token, _)| token);
+
+                match token {
+                    Token::OpeningBracket | Token::OpeningParenthesis => {
+                        groups.push(!matches!(
+                            previous,
+                            Some(Token::Identifier(_))
+                                | Some(Token::StringLiteral(_))
+                                | Some(Token::ClosingBracket)
+                                | Some(Token::ClosingParenthesis)
+                                | Some(Token::ClosingCurlyBracket)
+                        ));
+                    }
+                    Token::OpeningCurlyBracket => groups.push(false),
+                    Token::ClosingBracket
+                    | Token::ClosingParenthesis
+                    | Token::ClosingCurlyBracket => {
+                        groups.pop();
+                    }
+                    Token::Identifier(name)
+                        if groups.iter().all(|group| *group)
+                            && previous != Some(&Token::Dot)
+                            && next != Some(&Token::Dot)
+                            && next != Some(&Token::OpeningBracket)
+                            && next != Some(&Token::OpeningParenthesis) =>
+                    {
+                        res.push((name.clone(), *offset));
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    res
+}
+
+/// Find a module level assignment to one of `names` in Starlark source.
+///
+/// Returns the name and byte offset of the first assignment found. Names
+/// bound in functions are local to them and aren't considered. Source that
+/// can't be tokenized is ignored, leaving the error to evaluation.
+fn find_global_assignment(content: &str, names: &[&String]) -> Option<(String, u64)> {
+    let tokens = Lexer::new(content).collect::<Result<Vec<_>, _>>().ok()?;
+
+    let mut indent = 0;
+    let mut depth = 0;
+    let mut statement = vec![];
+    let mut statements = vec![];
+
+    for token in &tokens {
+        match &token.1 {
+            Token::Indent => indent += 1,
+            Token::Dedent => indent -= 1,
+            _ if indent > 0 => {}
+            Token::Newline | Token::Semicolon if depth == 0 => {
+                statements.push(std::mem::replace(&mut statement, vec![]));
+            }
+            t => {
+                match t {
+                    Token::OpeningBracket
+                    | Token::OpeningCurlyBracket
+                    | Token::OpeningParenthesis => depth += 1,
+                    Token::ClosingBracket
+                    | Token::ClosingCurlyBracket
+                    | Token::ClosingParenthesis => depth -= 1,
+                    _ => {}
+                }
+
+                statement.push(token);
+            }
+        }
+    }
+    statements.push(statement);
+
+    statements
+        .iter()
+        .flat_map(|statement| statement_bindings(statement))
+        .find(|(name, _)| names.contains(&name))
+}
+
+/// A location in a Starlark source file referenced by an evaluation error.
+///
+/// Lines and columns are 1-based.
+#[derive(Clone, Debug, PartialEq)]
+pub struct EvalErrorSpan {
+    /// Name of the file containing the span.
+    pub file: String,
+
+    /// Line the span begins on.
+    pub begin_line: usize,
+
+    /// Column the span begins at.
+    pub begin_column: usize,
+
+    /// Line the span ends on.
+    pub end_line: usize,
+
+    /// Column the span ends at.
+    pub end_column: usize,
+
+    /// Text describing the span, if available.
+    pub label: Option<String>,
+}
+
+/// Represents an error evaluating a Starlark configuration file.
+///
+/// Unlike the `Diagnostic` it wraps, this type has spans resolved to
+/// file names, lines, and columns, so it can be rendered without access to
+/// the `CodeMap` used during evaluation.
+#[derive(Clone, Debug)]
+pub struct EvalError {
+    /// The diagnostic emitted by the Starlark evaluator.
+    pub diagnostic: Diagnostic,
+
+    /// Resolved locations of `diagnostic`'s spans.
+    pub spans: Vec<EvalErrorSpan>,
+}
+
+impl EvalError {
+    fn from_diagnostic(diagnostic: Diagnostic, map: &CodeMap) -> Self {
+        let spans = diagnostic
+            .spans
+            .iter()
+            .map(|span_label| {
+                let loc = map.look_up_span(span_label.span);
+
+                EvalErrorSpan {
+                    file: loc.file.name().to_string(),
+                    begin_line: loc.begin.line + 1,
+                    begin_column: loc.begin.column + 1,
+                    end_line: loc.end.line + 1,
+                    end_column: loc.end.column + 1,
+                    label: span_label.label.clone(),
+                }
+            })
+            .collect();
+
+        Self { diagnostic, spans }
+    }
+
+    /// The severity of the error.
+    pub fn level(&self) -> Level {
+        self.diagnostic.level
+    }
+}
+
+impl From<Diagnostic> for EvalError {
+    fn from(diagnostic: Diagnostic) -> Self {
+        Self {
+            diagnostic,
+            spans: vec![],
+        }
+    }
+}
+
+impl fmt::Display for EvalError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.diagnostic.message)
+    }
+}
+
+impl std::error::Error for EvalError {}
+
 /// Represents the result of evaluating a Starlark environment.
 pub struct EvalResult {
     pub env: Environment,
@@ -22,6 +318,11 @@ pub struct EvalResult {
 }
 
 /// Evaluate a Starlark configuration file, returning a low-level result.
+///
+/// `vars` defines additional string global variables available to the file.
+/// `distribution_flavors` defines distribution flavors available in addition
+/// to the built-in flavors.
+#[allow(clippy::too_many_arguments)]
 pub fn evaluate_file(
     logger: &slog::Logger,
     config_path: &Path,
@@ -30,8 +331,13 @@ pub fn evaluate_file(
     verbose: bool,
     resolve_targets: Option<Vec<String>>,
     build_script_mode: bool,
-) -> Result<EvalResult, Diagnostic> {
-    let context = EnvironmentContext::new(
+    offline: bool,
+    allowed_distribution_hosts: Option<Vec<String>>,
+    validate_only: bool,
+    vars: &HashMap<String, String>,
+    distribution_flavors: &DistributionFlavorRegistry,
+) -> Result<EvalResult, EvalError> {
+    let mut context = EnvironmentContext::new(
         logger,
         verbose,
         config_path,
@@ -42,6 +348,9 @@ pub fn evaluate_file(
         "0",
         resolve_targets,
         build_script_mode,
+        offline,
+        allowed_distribution_hosts,
+        validate_only,
     )
     .map_err(|e| Diagnostic {
         level: Level::Error,
@@ -49,6 +358,7 @@ pub fn evaluate_file(
         code: Some("environment".to_string()),
         spans: vec![],
     })?;
+    context.distribution_flavors = distribution_flavors.clone();
 
     let (mut env, type_values) = global_environment(&context).map_err(|_| Diagnostic {
         level: Level::Error,
@@ -57,6 +367,37 @@ pub fn evaluate_file(
         spans: vec![],
     })?;
 
+    define_variables(&mut env, vars).map_err(|message| Diagnostic {
+        level: Level::Error,
+        message,
+        code: Some("environment".to_string()),
+        spans: vec![],
+    })?;
+
+    // Starlark lets modules rebind any global. Variables are constants, so
+    // reject files assigning to them before anything is evaluated. Unreadable
+    // files are reported by evaluation.
+    if let Ok(content) = std::fs::read_to_string(config_path) {
+        let names = vars.keys().collect::<Vec<_>>();
+
+        if let Some((name, offset)) = find_global_assignment(&content, &names) {
+            let line = content[0..offset as usize].matches('\n').count() + 1;
+
+            return Err(Diagnostic {
+                level: Level::Error,
+                message: format!(
+                    "{}:{}: variable {} is read-only and can't be assigned",
+                    config_path.display(),
+                    line,
+                    name
+                ),
+                code: Some("environment".to_string()),
+                spans: vec![],
+            }
+            .into());
+        }
+    }
+
     let map = Arc::new(Mutex::new(CodeMap::new()));
     let file_loader_env = env.clone();
     starlark::eval::simple::eval_file(
@@ -77,7 +418,7 @@ pub fn evaluate_file(
 
         slog::error!(logger, "{}", String::from_utf8_lossy(&msg));
 
-        e
+        EvalError::from_diagnostic(e, &raw_map)
     })?;
 
     // The EnvironmentContext is cloned as part of evaluation, which is a bit wonky.
@@ -103,6 +444,19 @@ pub fn evaluate_file(
 }
 
 /// Evaluate a Starlark configuration file and return its result.
+///
+/// If `validate_only` is set, the file is evaluated and its targets are
+/// resolved, but Python distributions aren't downloaded and packages aren't
+/// installed. A stub stands in for each Python distribution. This is useful
+/// for quickly catching errors in a configuration file.
+///
+/// Each entry in `vars` is defined as a string global variable before the
+/// file is evaluated. Names must be valid identifiers and can't conflict
+/// with existing globals.
+///
+/// Flavors registered in `distribution_flavors` can be used in addition to
+/// the built-in distribution flavors.
+#[allow(clippy::too_many_arguments)]
 pub fn eval_starlark_config_file(
     logger: &slog::Logger,
     path: &Path,
@@ -111,7 +465,12 @@ pub fn eval_starlark_config_file(
     verbose: bool,
     resolve_targets: Option<Vec<String>>,
     build_script_mode: bool,
-) -> Result<EvalResult> {
+    offline: bool,
+    allowed_distribution_hosts: Option<Vec<String>>,
+    validate_only: bool,
+    vars: &HashMap<String, String>,
+    distribution_flavors: &DistributionFlavorRegistry,
+) -> Result<EvalResult, EvalError> {
     crate::starlark::eval::evaluate_file(
         logger,
         path,
@@ -120,6 +479,236 @@ pub fn eval_starlark_config_file(
         verbose,
         resolve_targets,
         build_script_mode,
+        offline,
+        allowed_distribution_hosts,
+        validate_only,
+        vars,
+        distribution_flavors,
     )
-    .map_err(|d| anyhow!(d.message))
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, anyhow::Result};
+
+    #[test]
+    fn test_syntax_error_spans() -> Result<()> {
+        let temp_dir = tempdir::TempDir::new("pyoxidizer-test")?;
+        let config_path = temp_dir.path().join("pyoxidizer.bzl");
+        std::fs::write(&config_path, "x = 1\ny = (\n")?;
+
+        let err = match eval_starlark_config_file(
+            &crate::testutil::get_logger()?,
+            &config_path,
+            crate::project_building::HOST,
+            false,
+            false,
+            None,
+            false,
+            true,
+            None,
+            false,
+            &HashMap::new(),
+            &DistributionFlavorRegistry::default(),
+        ) {
+            Ok(_) => panic!("evaluation should have failed"),
+            Err(err) => err,
+        };
+
+        assert_eq!(err.to_string(), err.diagnostic.message);
+        assert_eq!(err.level(), Level::Error);
+        assert!(!err.spans.is_empty());
+
+        let span = &err.spans[0];
+        assert_eq!(span.file, config_path.display().to_string());
+        assert!(span.begin_line >= 2);
+        assert!(span.begin_column >= 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_validate_only() -> Result<()> {
+        let temp_dir = tempdir::TempDir::new("pyoxidizer-test")?;
+        let config_path = temp_dir.path().join("pyoxidizer.bzl");
+        let build_path = temp_dir.path().join("build");
+
+        let config = |depends: &str| {
+            format!(
+                "set_build_path({:?})\n\
+                 dist = default_python_distribution()\n\
+                 info = dist.info()\n\
+                 def make_exe():\n    \
+                     exe = dist.to_python_executable('myapp')\n    \
+                     exe.add_python_resources(exe.pip_install(['pyflakes']))\n    \
+                     return exe\n\
+                 register_target('exe', make_exe, depends={})\n\
+                 resolve_targets()\n",
+                build_path.display().to_string(),
+                depends
+            )
+        };
+
+        let eval = || -> Result<EvalResult> {
+            Ok(eval_starlark_config_file(
+                &crate::testutil::get_logger()?,
+                &config_path,
+                crate::project_building::HOST,
+                false,
+                false,
+                None,
+                false,
+                true,
+                None,
+                true,
+                &HashMap::new(),
+                &DistributionFlavorRegistry::default(),
+            )?)
+        };
+
+        // The distribution isn't available locally, so this would fail with an
+        // offline error if it were resolved.
+        std::fs::write(&config_path, config("[]"))?;
+        let res = eval()?;
+        assert!(res.context.targets.contains_key("exe"));
+        assert_eq!(
+            res.context.targets["exe"]
+                .resolved_value
+                .as_ref()
+                .map(|v| v.get_type()),
+            Some("PythonExecutable")
+        );
+        assert!(
+            !build_path.join("python_distributions").exists()
+                || std::fs::read_dir(build_path.join("python_distributions"))?
+                    .next()
+                    .is_none()
+        );
+
+        std::fs::write(&config_path, config("['missing']"))?;
+        assert!(eval().is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_vars() -> Result<()> {
+        let temp_dir = tempdir::TempDir::new("pyoxidizer-test")?;
+        let config_path = temp_dir.path().join("pyoxidizer.bzl");
+        std::fs::write(
+            &config_path,
+            "def make_version():\n    \
+                 if VERSION != '1.2.3':\n        \
+                     fail('unexpected VERSION')\n    \
+                 return VERSION\n\
+             register_target('version', make_version, default=True)\n\
+             resolve_targets()\n",
+        )?;
+
+        let eval = |vars: &[(&str, &str)]| -> Result<EvalResult> {
+            let vars = vars
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect::<HashMap<_, _>>();
+
+            Ok(eval_starlark_config_file(
+                &crate::testutil::get_logger()?,
+                &config_path,
+                crate::project_building::HOST,
+                false,
+                false,
+                None,
+                false,
+                true,
+                None,
+                false,
+                &vars,
+                &DistributionFlavorRegistry::default(),
+            )?)
+        };
+
+        let res = eval(&[("VERSION", "1.2.3")])?;
+        assert_eq!(
+            res.env.get("VERSION").unwrap().to_str(),
+            "1.2.3".to_string()
+        );
+        assert_eq!(
+            res.context.targets["version"]
+                .resolved_value
+                .as_ref()
+                .unwrap()
+                .to_str(),
+            "1.2.3".to_string()
+        );
+
+        // Undefined variables are an error.
+        assert!(eval(&[]).is_err());
+        assert!(eval(&[("VERSION", "1.0")]).is_err());
+
+        // Names must be valid identifiers that don't shadow existing globals.
+        for name in &[
+            "",
+            "1VERSION",
+            "MY-VERSION",
+            "def",
+            "CWD",
+            "default_python_distribution",
+        ] {
+            assert!(eval(&[("VERSION", "1.2.3"), (name, "x")]).is_err());
+        }
+
+        // Variables are read-only.
+        std::fs::write(&config_path, "x = 1\nVERSION = '2.0'\nresolve_targets()\n")?;
+        let err = eval(&[("VERSION", "1.2.3")]).err().unwrap();
+        assert!(err
+            .to_string()
+            .ends_with(":2: variable VERSION is read-only and can't be assigned"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_find_global_assignment() {
+        let version = "VERSION".to_string();
+        let names = vec![&version];
+
+        for content in &[
+            "VERSION = '1'",
+            "x = 1; VERSION = '1'",
+            "VERSION += '1'",
+            "a, VERSION = 1, 2",
+            "(a, (b, VERSION)) = 1, (2, 3)",
+            "[a, VERSION] = [1, 2]",
+            "def VERSION():\n    pass\n",
+            "load('other.bzl', 'VERSION')",
+            "load('other.bzl', VERSION = 'other')",
+        ] {
+            assert_eq!(
+                find_global_assignment(content, &names).map(|(name, _)| name),
+                Some("VERSION".to_string()),
+                "{}",
+                content
+            );
+        }
+
+        for content in &[
+            "x = VERSION",
+            "x[VERSION] = 1",
+            "VERSION.x = 1",
+            "VERSION[0] = 1",
+            "f(VERSION = 1)",
+            "x = f(VERSION = 1)",
+            "x == VERSION",
+            "def f(VERSION):\n    VERSION = 1\n    return VERSION\n",
+            "load('other.bzl', x = 'VERSION')",
+            "VERSIONS = 1",
+        ] {
+            assert_eq!(find_global_assignment(content, &names), None, "{}", content);
+        }
+
+        assert_eq!(
+            find_global_assignment("x = 1\n\nVERSION = 2\n", &names),
+            Some(("VERSION".to_string(), 7))
+        );
+    }
 }
@@ -10,7 +10,7 @@ use {
             PythonExtensionModuleValue, PythonModuleSourceValue,
             PythonPackageDistributionResourceValue, PythonPackageResourceValue,
         },
-        target::{BuildContext, BuildTarget, ResolvedTarget, RunMode},
+        target::{BuildContext, BuildTarget, BuildTimings, ResolvedTarget, RunMode},
         util::{
             optional_list_arg, optional_str_arg, required_bool_arg, required_list_arg,
             required_str_arg, required_type_arg,
@@ -40,7 +40,7 @@ use {
         },
     },
     std::{
-        collections::HashSet,
+        collections::BTreeSet,
         convert::TryFrom,
         ops::Deref,
         path::{Path, PathBuf},
@@ -141,6 +141,7 @@ impl BuildTarget for FileManifestValue {
         Ok(ResolvedTarget {
             run_mode,
             output_path: context.output_path.clone(),
+            timings: BuildTimings::new(),
         })
     }
 }
@@ -374,17 +375,15 @@ impl FileManifestValue {
     }
 }
 
-/// glob(include, exclude=None, relative_to=None)
-fn starlark_glob(
-    type_values: &TypeValues,
+/// Resolve files matching `include` but not `exclude` glob patterns.
+///
+/// Both arguments have already been validated as lists of strings or `None`.
+fn resolve_glob_paths(
+    cwd: &Path,
     include: &Value,
     exclude: &Value,
-    strip_prefix: &Value,
-) -> ValueResult {
-    required_list_arg("include", "string", include)?;
-    optional_list_arg("exclude", "string", exclude)?;
-    let strip_prefix = optional_str_arg("strip_prefix", strip_prefix)?;
-
+    label: &str,
+) -> Result<BTreeSet<PathBuf>, ValueError> {
     let include = include
         .iter()?
         .iter()
@@ -396,39 +395,53 @@ fn starlark_glob(
         _ => Vec::new(),
     };
 
-    let raw_context = get_context(type_values)?;
-    let context = raw_context
-        .downcast_ref::<EnvironmentContext>()
-        .ok_or(ValueError::IncorrectParameterType)?;
-
-    let mut result = HashSet::new();
-
-    // Evaluate all the includes first.
-    for v in include {
-        for p in evaluate_glob(&context.cwd, &v).map_err(|e| {
+    let evaluate = |pattern: &str| {
+        evaluate_glob(cwd, pattern).map_err(|e| {
             ValueError::from(RuntimeError {
                 code: "PYOXIDIZER_BUILD",
                 message: e.to_string(),
-                label: "glob()".to_string(),
+                label: label.to_string(),
             })
-        })? {
+        })
+    };
+
+    let mut result = BTreeSet::new();
+
+    // Evaluate all the includes first.
+    for v in include {
+        for p in evaluate(&v)? {
             result.insert(p);
         }
     }
 
     // Then apply excludes.
     for v in exclude {
-        for p in evaluate_glob(&context.cwd, &v).map_err(|e| {
-            ValueError::from(RuntimeError {
-                code: "PYOXIDIZER_BUILD",
-                message: e.to_string(),
-                label: "glob()".to_string(),
-            })
-        })? {
+        for p in evaluate(&v)? {
             result.remove(&p);
         }
     }
 
+    Ok(result)
+}
+
+/// glob(include, exclude=None, relative_to=None)
+fn starlark_glob(
+    type_values: &TypeValues,
+    include: &Value,
+    exclude: &Value,
+    strip_prefix: &Value,
+) -> ValueResult {
+    required_list_arg("include", "string", include)?;
+    optional_list_arg("exclude", "string", exclude)?;
+    let strip_prefix = optional_str_arg("strip_prefix", strip_prefix)?;
+
+    let raw_context = get_context(type_values)?;
+    let context = raw_context
+        .downcast_ref::<EnvironmentContext>()
+        .ok_or(ValueError::IncorrectParameterType)?;
+
+    let result = resolve_glob_paths(&context.cwd, include, exclude, "glob()")?;
+
     let mut manifest = FileManifest::default();
 
     for path in result {
@@ -469,12 +482,46 @@ fn starlark_glob(
     }))
 }
 
+/// glob_paths(include, exclude=None, root=None)
+fn starlark_glob_paths(
+    type_values: &TypeValues,
+    include: &Value,
+    exclude: &Value,
+    root: &Value,
+) -> ValueResult {
+    required_list_arg("include", "string", include)?;
+    optional_list_arg("exclude", "string", exclude)?;
+    let root = optional_str_arg("root", root)?;
+
+    let raw_context = get_context(type_values)?;
+    let context = raw_context
+        .downcast_ref::<EnvironmentContext>()
+        .ok_or(ValueError::IncorrectParameterType)?;
+
+    let root = match root {
+        Some(root) => context.cwd.join(root),
+        None => context.cwd.clone(),
+    };
+
+    let paths = resolve_glob_paths(&root, include, exclude, "glob_paths()")?
+        .into_iter()
+        .map(|p| Value::from(p.display().to_string()))
+        .collect::<Vec<_>>();
+
+    Ok(Value::from(paths))
+}
+
 starlark_module! { file_resource_env =>
     #[allow(clippy::ptr_arg)]
     glob(env env, include, exclude=NoneType::None, strip_prefix=NoneType::None) {
         starlark_glob(&env, &include, &exclude, &strip_prefix)
     }
 
+    #[allow(clippy::ptr_arg)]
+    glob_paths(env env, include, exclude=NoneType::None, root=NoneType::None) {
+        starlark_glob_paths(&env, &include, &exclude, &root)
+    }
+
     #[allow(non_snake_case, clippy::ptr_arg)]
     FileManifest(env _env) {
         FileManifestValue::new_from_args()
@@ -533,6 +580,81 @@ mod tests {
         assert_eq!(m.manifest, FileManifest::default());
     }
 
+    #[test]
+    fn test_glob_paths() -> Result<()> {
+        let temp_dir = tempdir::TempDir::new("pyoxidizer-test")?;
+        let root = temp_dir.path();
+
+        std::fs::create_dir_all(root.join("templates").join("partials"))?;
+        std::fs::write(root.join("templates").join("index.html"), "index")?;
+        std::fs::write(root.join("templates").join("README.txt"), "readme")?;
+        std::fs::write(
+            root.join("templates").join("partials").join("header.html"),
+            "header",
+        )?;
+
+        let mut env = StarlarkEnvironment::new()?;
+        env.eval(&format!("root = {:?}", root.display().to_string()))?;
+
+        assert!(env.eval("glob_paths('*')").is_err());
+        assert!(env.eval("glob_paths(['*'], exclude='*')").is_err());
+
+        let paths = env.eval("glob_paths(['templates/**/*'], root=root)")?;
+        assert_eq!(paths.get_type(), "list");
+        assert_eq!(
+            paths
+                .iter()
+                .unwrap()
+                .iter()
+                .map(|x| x.to_string())
+                .collect::<Vec<_>>(),
+            vec![
+                root.join("templates").join("README.txt"),
+                root.join("templates").join("index.html"),
+                root.join("templates").join("partials").join("header.html"),
+            ]
+            .iter()
+            .map(|p| p.display().to_string())
+            .collect::<Vec<_>>()
+        );
+
+        // Excludes are applied after includes. Directories aren't returned.
+        let paths = env.eval(
+            "glob_paths(['templates/**/*'], exclude=['templates/partials/*', '**/*.txt'], root=root)",
+        )?;
+        assert_eq!(
+            paths
+                .iter()
+                .unwrap()
+                .iter()
+                .map(|x| x.to_string())
+                .collect::<Vec<_>>(),
+            vec![root
+                .join("templates")
+                .join("index.html")
+                .display()
+                .to_string()]
+        );
+
+        // Absolute patterns ignore the root.
+        env.eval_assert(&format!(
+            "glob_paths([{:?}], root='/nonexistent') == [{:?}]",
+            root.join("templates")
+                .join("index.html")
+                .display()
+                .to_string(),
+            root.join("templates")
+                .join("index.html")
+                .display()
+                .to_string()
+        ))?;
+
+        // No matches yields an empty list.
+        env.eval_assert("glob_paths(['*.nonexistent'], root=root) == []")?;
+
+        Ok(())
+    }
+
     #[test]
     fn test_add_python_source_module() -> Result<()> {
         let m = Value::new(FileManifestValue {
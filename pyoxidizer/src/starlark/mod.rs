@@ -14,9 +14,11 @@ pub mod file_resource;
 pub mod python_distribution;
 pub mod python_embedded_resources;
 pub mod python_executable;
+pub mod python_executable_tarball;
 pub mod python_interpreter_config;
 pub mod python_packaging_policy;
 pub mod python_resource;
+pub mod python_resources_bundle;
 pub mod target;
 #[cfg(test)]
 mod testutil;
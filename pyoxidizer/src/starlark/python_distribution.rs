@@ -9,32 +9,44 @@ use {
         python_interpreter_config::PythonInterpreterConfigValue,
         python_packaging_policy::PythonPackagingPolicyValue,
         python_resource::{
-            add_context_for_value, python_resource_to_value, PythonExtensionModuleValue,
-            PythonModuleSourceValue, PythonPackageResourceValue,
+            add_context_for_value, python_resource_to_value, python_resource_type_name,
+            PythonExtensionModuleValue, PythonModuleSourceValue, PythonPackageResourceValue,
+        },
+        target::log_phase_duration,
+        util::{
+            optional_list_arg, optional_str_arg, optional_type_arg, required_bool_arg,
+            required_str_arg,
         },
-        util::{optional_str_arg, optional_type_arg, required_bool_arg, required_str_arg},
     },
-    crate::py_packaging::{
-        distribution::BinaryLibpythonLinkMode,
-        distribution::{
-            default_distribution_location, is_stdlib_test_package, resolve_distribution,
-            DistributionFlavor, PythonDistribution as PythonDistributionTrait,
-            PythonDistributionLocation,
+    crate::{
+        py_packaging::{
+            binary::DISTRIBUTION_RESOURCE_ORIGIN,
+            distribution::BinaryLibpythonLinkMode,
+            distribution::{
+                default_distribution_location, distribution_available_offline,
+                is_stdlib_test_package, resolve_distribution, DistributionFlavor, HttpProxyConfig,
+                PythonDistribution as PythonDistributionTrait, PythonDistributionLocation,
+            },
+            standalone_distribution::StandaloneDistribution,
         },
+        python_distributions::PYTHON_DISTRIBUTIONS,
     },
-    anyhow::{anyhow, Result},
+    anyhow::{anyhow, Context, Result},
     itertools::Itertools,
+    linked_hash_map::LinkedHashMap,
     python_packaging::{
         bytecode::{CompileMode, PythonBytecodeCompiler},
-        policy::PythonPackagingPolicy,
-        resource::{BytecodeOptimizationLevel, PythonResource},
+        policy::{ExtensionModuleFilter, PythonPackagingPolicy},
+        resource::{BytecodeOptimizationLevel, DataLocation, PythonResource},
         resource_collection::PythonResourceAddCollectionContext,
     },
     starlark::{
         environment::TypeValues,
         eval::call_stack::CallStack,
         values::{
-            error::{RuntimeError, ValueError, INCORRECT_PARAMETER_TYPE_ERROR_CODE},
+            error::{
+                RuntimeError, UnsupportedOperation, ValueError, INCORRECT_PARAMETER_TYPE_ERROR_CODE,
+            },
             none::NoneType,
             {Mutable, TypedValue, Value, ValueResult},
         },
@@ -44,9 +56,11 @@ use {
         },
     },
     std::{
+        collections::BTreeMap,
         convert::TryFrom,
         path::{Path, PathBuf},
         sync::Arc,
+        time::{Duration, Instant},
     },
 };
 
@@ -56,9 +70,124 @@ pub struct PythonDistribution {
 
     dest_dir: PathBuf,
 
+    http_proxy: HttpProxyConfig,
+
+    /// Hosts the distribution may be downloaded from.
+    ///
+    /// `None` means any host is allowed.
+    allowed_hosts: Option<Vec<String>>,
+
+    /// Whether the configuration is only being validated.
+    ///
+    /// The distribution is never resolved when set. A stub describing the
+    /// distribution is used instead.
+    validate_only: bool,
+
     pub distribution: Option<Arc<Box<dyn PythonDistributionTrait>>>,
 
+    /// How long it took to resolve `distribution`.
+    resolution_duration: Option<Duration>,
+
     compiler: Option<Box<dyn PythonBytecodeCompiler>>,
+
+    /// Maximum number of bytecode compilers to run concurrently.
+    ///
+    /// `None` means to use the number of CPU cores.
+    bytecode_compiler_jobs: Option<usize>,
+}
+
+/// Ensure a distribution location can be resolved given the network policy.
+///
+/// Returns an `OFFLINE_ERROR` if the context forbids network access and the
+/// distribution would need to be downloaded.
+fn ensure_location_offline_compatible(
+    context: &EnvironmentContext,
+    location: &PythonDistributionLocation,
+    dest_dir: &Path,
+    label: &str,
+) -> Result<(), ValueError> {
+    // Distributions aren't resolved when validating, so nothing is downloaded.
+    if !context.offline
+        || context.validate_only
+        || distribution_available_offline(location, dest_dir)
+    {
+        return Ok(());
+    }
+
+    let url = match location {
+        PythonDistributionLocation::Url { url, .. } => url.clone(),
+        PythonDistributionLocation::Urls { urls, .. } => urls.join(", "),
+        PythonDistributionLocation::Local { local_path, .. } => local_path.clone(),
+    };
+
+    Err(ValueError::from(RuntimeError {
+        code: "OFFLINE_ERROR",
+        message: format!(
+            "offline mode forbids downloading Python distribution {}",
+            url
+        ),
+        label: label.to_string(),
+    }))
+}
+
+/// Ensure a distribution location is permitted by the allowed hosts policy.
+///
+/// Returns a `DISTRIBUTION_HOST_DENIED` error if the context restricts the
+/// hosts distributions may be downloaded from and the location is a URL
+/// whose host isn't allowed. Local paths are always permitted.
+///
+/// `resolve_distribution()` enforces the same policy. Checking here as well
+/// surfaces the error where the distribution is defined.
+fn ensure_location_host_allowed(
+    context: &EnvironmentContext,
+    location: &PythonDistributionLocation,
+    label: &str,
+) -> Result<(), ValueError> {
+    crate::py_packaging::distribution::ensure_location_host_allowed(
+        location,
+        context.allowed_distribution_hosts.as_deref(),
+    )
+    .map_err(|e| {
+        ValueError::from(RuntimeError {
+            code: "DISTRIBUTION_HOST_DENIED",
+            message: e.to_string(),
+            label: label.to_string(),
+        })
+    })
+}
+
+/// Resolve the directory a distribution is downloaded and extracted to.
+///
+/// Defaults to the context's Python distributions path. Custom paths are
+/// resolved relative to the current working directory and created if
+/// missing. They must be writable.
+fn resolve_extract_path(
+    context: &EnvironmentContext,
+    extract_path: Option<String>,
+    label: &str,
+) -> Result<PathBuf, ValueError> {
+    let path = match extract_path {
+        Some(path) => context.cwd.join(path),
+        None => return Ok(context.python_distributions_path.clone()),
+    };
+
+    let error = |message: String| {
+        ValueError::from(RuntimeError {
+            code: "PYOXIDIZER_BUILD",
+            message,
+            label: label.to_string(),
+        })
+    };
+
+    std::fs::create_dir_all(&path)
+        .map_err(|e| error(format!("unable to create {}: {}", path.display(), e)))?;
+
+    // Permission bits don't reliably indicate whether we can write, so
+    // attempt to create something.
+    tempdir::TempDir::new_in(&path, "pyoxidizer-write-check")
+        .map_err(|e| error(format!("{} is not writable: {}", path.display(), e)))?;
+
+    Ok(path)
 }
 
 impl PythonDistribution {
@@ -66,13 +195,21 @@ impl PythonDistribution {
         flavor: DistributionFlavor,
         location: PythonDistributionLocation,
         dest_dir: &Path,
+        http_proxy: &HttpProxyConfig,
+        allowed_hosts: Option<Vec<String>>,
+        validate_only: bool,
     ) -> PythonDistribution {
         PythonDistribution {
             flavor,
             source: location,
             dest_dir: dest_dir.to_path_buf(),
+            http_proxy: http_proxy.clone(),
+            allowed_hosts,
+            validate_only,
             distribution: None,
+            resolution_duration: None,
             compiler: None,
+            bytecode_compiler_jobs: None,
         }
     }
 
@@ -81,10 +218,36 @@ impl PythonDistribution {
             return Ok(());
         }
 
-        let dist = resolve_distribution(logger, &self.flavor, &self.source, &self.dest_dir)?;
-        //warn!(logger, "distribution info: {:#?}", dist.as_minimal_info());
+        if self.validate_only {
+            let record = PYTHON_DISTRIBUTIONS
+                .iter()
+                .find(|record| record.location == self.source)
+                .ok_or_else(|| {
+                    anyhow!("only known Python distributions can be described when validating configuration")
+                })?;
+
+            self.distribution = Some(Arc::new(Box::new(StandaloneDistribution::stub(
+                &record.target_triple,
+                &record.python_version,
+            ))));
+
+            return Ok(());
+        }
+
+        let start = Instant::now();
+
+        let dist = resolve_distribution(
+            logger,
+            &self.flavor,
+            &self.source,
+            &self.dest_dir,
+            &self.http_proxy,
+            self.allowed_hosts.as_deref(),
+        )?;
 
         self.distribution = Some(Arc::new(dist));
+        self.resolution_duration =
+            Some(log_phase_duration(logger, "distribution_resolution", start));
 
         Ok(())
     }
@@ -116,6 +279,32 @@ impl PythonDistribution {
             Err(anyhow!("bytecode compiler should exist"))
         }
     }
+
+    /// Compile bytecode at multiple optimization levels using this distribution.
+    ///
+    /// Source is only parsed once. Returns bytecode keyed by optimization level.
+    pub fn compile_bytecode_levels(
+        &mut self,
+        logger: &slog::Logger,
+        source: &[u8],
+        filename: &str,
+        levels: &[BytecodeOptimizationLevel],
+        output_mode: CompileMode,
+    ) -> Result<BTreeMap<BytecodeOptimizationLevel, Vec<u8>>> {
+        self.ensure_distribution_resolved(logger)?;
+
+        if let Some(dist) = &self.distribution {
+            if self.compiler.is_none() {
+                self.compiler = Some(dist.create_bytecode_compiler()?);
+            }
+        }
+
+        if let Some(compiler) = &mut self.compiler {
+            compiler.compile_levels(source, filename, levels, output_mode)
+        } else {
+            Err(anyhow!("bytecode compiler should exist"))
+        }
+    }
 }
 
 impl TypedValue for PythonDistribution {
@@ -129,20 +318,76 @@ impl TypedValue for PythonDistribution {
     fn to_str(&self) -> String {
         format!("PythonDistribution<{:#?}>", self.source)
     }
+
+    fn get_attr(&self, attribute: &str) -> ValueResult {
+        match attribute {
+            "bytecode_compiler_jobs" => Ok(Value::from(
+                self.bytecode_compiler_jobs.unwrap_or_else(num_cpus::get) as i64,
+            )),
+            attr => Err(ValueError::OperationNotSupported {
+                op: UnsupportedOperation::GetAttr(attr.to_string()),
+                left: "PythonDistribution".to_string(),
+                right: None,
+            }),
+        }
+    }
+
+    fn has_attr(&self, attribute: &str) -> Result<bool, ValueError> {
+        Ok(match attribute {
+            "bytecode_compiler_jobs" => true,
+            _ => false,
+        })
+    }
+
+    fn set_attr(&mut self, attribute: &str, value: Value) -> Result<(), ValueError> {
+        match attribute {
+            "bytecode_compiler_jobs" => {
+                let jobs = match value.get_type() {
+                    "int" => value.to_int()?,
+                    t => {
+                        return Err(ValueError::from(RuntimeError {
+                            code: INCORRECT_PARAMETER_TYPE_ERROR_CODE,
+                            message: format!("bytecode_compiler_jobs must be an int; got {}", t),
+                            label: "bytecode_compiler_jobs".to_string(),
+                        }))
+                    }
+                };
+
+                if jobs < 1 {
+                    return Err(ValueError::from(RuntimeError {
+                        code: INCORRECT_PARAMETER_TYPE_ERROR_CODE,
+                        message: format!("bytecode_compiler_jobs must be at least 1; got {}", jobs),
+                        label: "bytecode_compiler_jobs".to_string(),
+                    }));
+                }
+
+                self.bytecode_compiler_jobs = Some(jobs as usize);
+
+                Ok(())
+            }
+            attr => Err(ValueError::OperationNotSupported {
+                op: UnsupportedOperation::SetAttr(attr.to_string()),
+                left: "PythonDistribution".to_string(),
+                right: None,
+            }),
+        }
+    }
 }
 
 // Starlark functions.
 impl PythonDistribution {
-    /// default_python_distribution(flavor, build_target=None, python_version=None)
+    /// default_python_distribution(flavor, build_target=None, python_version=None, extract_path=None)
     fn default_python_distribution(
         type_values: &TypeValues,
         flavor: &Value,
         build_target: &Value,
         python_version: &Value,
+        extract_path: &Value,
     ) -> ValueResult {
         let flavor = required_str_arg("flavor", flavor)?;
         let build_target = optional_str_arg("build_target", build_target)?;
         let python_version = optional_str_arg("python_version", &python_version)?;
+        let extract_path = optional_str_arg("extract_path", &extract_path)?;
 
         let raw_context = get_context(type_values)?;
         let context = raw_context
@@ -154,20 +399,25 @@ impl PythonDistribution {
             None => context.build_target_triple.clone(),
         };
 
-        let flavor = DistributionFlavor::try_from(flavor.as_str()).map_err(|e| {
-            ValueError::from(RuntimeError {
-                code: "PYOXIDIZER_BUILD",
-                message: e,
-                label: "default_python_distribution()".to_string(),
-            })
-        })?;
+        let flavor = context
+            .distribution_flavors
+            .resolve_flavor(flavor.as_str())
+            .map_err(|e| {
+                ValueError::from(RuntimeError {
+                    code: "PYOXIDIZER_BUILD",
+                    message: e,
+                    label: "default_python_distribution()".to_string(),
+                })
+            })?;
 
         let python_version_str = match &python_version {
             Some(x) => Some(x.as_str()),
             None => None,
         };
 
-        let location = default_distribution_location(&flavor, &build_target, python_version_str)
+        let location = context
+            .distribution_flavors
+            .default_distribution_location(&flavor, &build_target, python_version_str)
             .map_err(|e| {
                 ValueError::from(RuntimeError {
                     code: "PYOXIDIZER_BUILD",
@@ -181,25 +431,100 @@ impl PythonDistribution {
             .downcast_ref::<EnvironmentContext>()
             .ok_or(ValueError::IncorrectParameterType)?;
 
+        let dest_dir =
+            resolve_extract_path(&context, extract_path, "default_python_distribution()")?;
+
+        ensure_location_offline_compatible(
+            &context,
+            &location,
+            &dest_dir,
+            "default_python_distribution()",
+        )?;
+
+        ensure_location_host_allowed(&context, &location, "default_python_distribution()")?;
+
         Ok(Value::new(PythonDistribution::from_location(
             flavor,
             location,
-            &context.python_distributions_path,
+            &dest_dir,
+            &context.http_proxy,
+            context.allowed_distribution_hosts.clone(),
+            context.validate_only,
         )))
     }
 
+    /// available_python_versions(flavor="standalone", build_target=None)
+    fn available_python_versions(
+        type_values: &TypeValues,
+        flavor: &Value,
+        build_target: &Value,
+    ) -> ValueResult {
+        let flavor = required_str_arg("flavor", flavor)?;
+        let build_target = optional_str_arg("build_target", build_target)?;
+
+        let raw_context = get_context(type_values)?;
+        let context = raw_context
+            .downcast_ref::<EnvironmentContext>()
+            .ok_or(ValueError::IncorrectParameterType)?;
+
+        let build_target = match build_target {
+            Some(t) => t,
+            None => context.build_target_triple.clone(),
+        };
+
+        let flavor_value = context
+            .distribution_flavors
+            .resolve_flavor(flavor.as_str())
+            .map_err(|e| {
+                ValueError::from(RuntimeError {
+                    code: "PYOXIDIZER_BUILD",
+                    message: e,
+                    label: "available_python_versions()".to_string(),
+                })
+            })?;
+
+        let versions = context
+            .distribution_flavors
+            .distributions(&flavor_value)
+            .map_err(|e| {
+                ValueError::from(RuntimeError {
+                    code: "PYOXIDIZER_BUILD",
+                    message: e.to_string(),
+                    label: "available_python_versions()".to_string(),
+                })
+            })?
+            .python_versions(&build_target, &flavor_value, None);
+
+        if versions.is_empty() {
+            return Err(ValueError::from(RuntimeError {
+                code: "PYOXIDIZER_BUILD",
+                message: format!(
+                    "no {} Python distributions available for {}",
+                    flavor, build_target
+                ),
+                label: "available_python_versions()".to_string(),
+            }));
+        }
+
+        Ok(Value::from(versions))
+    }
+
     /// PythonDistribution()
     fn from_args(
         type_values: &TypeValues,
         sha256: &Value,
         local_path: &Value,
         url: &Value,
+        urls: &Value,
         flavor: &Value,
+        extract_path: &Value,
     ) -> ValueResult {
         required_str_arg("sha256", sha256)?;
         optional_str_arg("local_path", local_path)?;
         optional_str_arg("url", url)?;
+        optional_list_arg("urls", "string", urls)?;
         let flavor = required_str_arg("flavor", flavor)?;
+        let extract_path = optional_str_arg("extract_path", extract_path)?;
 
         if local_path.get_type() != "NoneType" && url.get_type() != "NoneType" {
             return Err(ValueError::from(RuntimeError {
@@ -209,11 +534,34 @@ impl PythonDistribution {
             }));
         }
 
+        if urls.get_type() != "NoneType" {
+            if local_path.get_type() != "NoneType" || url.get_type() != "NoneType" {
+                return Err(ValueError::from(RuntimeError {
+                    code: INCORRECT_PARAMETER_TYPE_ERROR_CODE,
+                    message: "cannot define urls with local_path or url".to_string(),
+                    label: "cannot define urls with local_path or url".to_string(),
+                }));
+            }
+
+            if urls.length()? == 0 {
+                return Err(ValueError::from(RuntimeError {
+                    code: INCORRECT_PARAMETER_TYPE_ERROR_CODE,
+                    message: "urls must not be empty".to_string(),
+                    label: "urls must not be empty".to_string(),
+                }));
+            }
+        }
+
         let distribution = if local_path.get_type() != "NoneType" {
             PythonDistributionLocation::Local {
                 local_path: local_path.to_string(),
                 sha256: sha256.to_string(),
             }
+        } else if urls.get_type() != "NoneType" {
+            PythonDistributionLocation::Urls {
+                urls: urls.iter()?.iter().map(|x| x.to_string()).collect(),
+                sha256: sha256.to_string(),
+            }
         } else {
             PythonDistributionLocation::Url {
                 url: url.to_string(),
@@ -237,10 +585,24 @@ impl PythonDistribution {
             .downcast_ref::<EnvironmentContext>()
             .ok_or(ValueError::IncorrectParameterType)?;
 
+        let dest_dir = resolve_extract_path(&context, extract_path, "PythonDistribution()")?;
+
+        ensure_location_offline_compatible(
+            &context,
+            &distribution,
+            &dest_dir,
+            "PythonDistribution()",
+        )?;
+
+        ensure_location_host_allowed(&context, &distribution, "PythonDistribution()")?;
+
         Ok(Value::new(PythonDistribution::from_location(
             flavor,
             distribution,
-            &context.python_distributions_path,
+            &dest_dir,
+            &context.http_proxy,
+            context.allowed_distribution_hosts.clone(),
+            context.validate_only,
         )))
     }
 
@@ -272,6 +634,40 @@ impl PythonDistribution {
         Ok(Value::new(PythonPackagingPolicyValue::new(policy)))
     }
 
+    /// PythonDistribution.make_stdlib_only_policy()
+    fn make_stdlib_only_policy_starlark(&mut self, type_values: &TypeValues) -> ValueResult {
+        let raw_context = get_context(type_values)?;
+        let context = raw_context
+            .downcast_ref::<EnvironmentContext>()
+            .ok_or(ValueError::IncorrectParameterType)?;
+
+        self.ensure_distribution_resolved(&context.logger)
+            .map_err(|e| {
+                ValueError::from(RuntimeError {
+                    code: "PYOXIDIZER_BUILD",
+                    message: e.to_string(),
+                    label: "resolve_distribution()".to_string(),
+                })
+            })?;
+        let dist = self.distribution.as_ref().unwrap().clone();
+
+        let mut policy = dist.create_packaging_policy().map_err(|e| {
+            ValueError::from(RuntimeError {
+                code: "PYOXIDIZER_BUILD",
+                message: e.to_string(),
+                label: "make_stdlib_only_policy()".to_string(),
+            })
+        })?;
+
+        policy.set_extension_module_filter(ExtensionModuleFilter::All);
+        policy.set_include_distribution_sources(true);
+        policy.set_include_distribution_resources(true);
+        policy.set_include_non_distribution_sources(false);
+        policy.set_include_test(false);
+
+        Ok(Value::new(PythonPackagingPolicyValue::new(policy)))
+    }
+
     /// PythonDistribution.make_python_interpreter_config()
     fn make_python_interpreter_config_starlark(&mut self, type_values: &TypeValues) -> ValueResult {
         let raw_context = get_context(type_values)?;
@@ -304,6 +700,7 @@ impl PythonDistribution {
     ///     name,
     ///     packaging_policy=None,
     ///     config=None,
+    ///     resource_filter=None,
     /// )
     #[allow(
         clippy::ptr_arg,
@@ -317,6 +714,7 @@ impl PythonDistribution {
         name: &Value,
         packaging_policy: &Value,
         config: &Value,
+        resource_filter: &Value,
     ) -> ValueResult {
         let name = required_str_arg("name", &name)?;
         optional_type_arg(
@@ -325,6 +723,7 @@ impl PythonDistribution {
             &packaging_policy,
         )?;
         optional_type_arg("config", "PythonInterpreterConfig", &config)?;
+        optional_type_arg("resource_filter", "function", &resource_filter)?;
 
         let raw_context = get_context(type_values)?;
         let context = raw_context
@@ -375,9 +774,12 @@ impl PythonDistribution {
             }
         }?;
 
-        let host_distribution = if dist
-            .compatible_host_triples()
-            .contains(&context.build_host_triple)
+        // The stub distribution used when validating stands in for the host
+        // distribution too, as resolving one would download it.
+        let host_distribution = if self.validate_only
+            || dist
+                .compatible_host_triples()
+                .contains(&context.build_host_triple)
         {
             Some(dist.clone())
         } else {
@@ -395,12 +797,23 @@ impl PythonDistribution {
                 })
             })?;
 
+            ensure_location_offline_compatible(
+                &context,
+                &location,
+                &context.python_distributions_path,
+                "to_python_executable()",
+            )?;
+
+            ensure_location_host_allowed(&context, &location, "to_python_executable()")?;
+
             Some(Arc::new(
                 resolve_distribution(
                     &context.logger,
                     &flavor,
                     &location,
                     &context.python_distributions_path,
+                    &context.http_proxy,
+                    context.allowed_distribution_hosts.as_deref(),
                 )
                 .map_err(|e| {
                     ValueError::from(RuntimeError {
@@ -432,47 +845,165 @@ impl PythonDistribution {
                 })
             })?;
 
+        if let Some(jobs) = self.bytecode_compiler_jobs {
+            builder.set_bytecode_compiler_jobs(jobs);
+        }
+
+        let target_triple = context.build_target_triple.clone();
+
         let callback = Box::new(
             |_policy: &PythonPackagingPolicy,
              resource: &PythonResource,
              add_context: &mut PythonResourceAddCollectionContext|
-             -> Result<()> {
+             -> Result<bool> {
                 // Callback is declared Fn, so we can't take a mutable reference.
                 // A copy should be fine.
                 let mut cs = call_stack.clone();
 
-                // There is a PythonPackagingPolicy passed into this callback
-                // and one passed into the outer function as a &Value. The
-                // former is derived from the latter. And the latter has Starlark
-                // callbacks registered on it.
-                //
-                // When we call python_resource_to_value(), the Starlark
-                // callbacks are automatically called.
-
-                let value = python_resource_to_value(&type_values, &mut cs, resource, &policy)
+                let mut process = || -> Result<bool> {
+                    // There is a PythonPackagingPolicy passed into this callback
+                    // and one passed into the outer function as a &Value. The
+                    // former is derived from the latter. And the latter has Starlark
+                    // callbacks registered on it.
+                    //
+                    // When we call python_resource_to_value(), the Starlark
+                    // callbacks are automatically called.
+
+                    let value = python_resource_to_value(
+                        &type_values,
+                        &mut cs,
+                        resource,
+                        &policy,
+                        DISTRIBUTION_RESOURCE_ORIGIN,
+                    )
                     .map_err(|e| anyhow!("error converting PythonResource to Value: {:?}", e))?;
 
-                let new_add_context = add_context_for_value(&value, "to_python_executable")
-                    .map_err(|e| anyhow!("error obtaining add context from Value: {:?}", e))?
-                    .expect("add context should have been populated as part of Value conversion");
+                    let new_add_context = add_context_for_value(&value, "to_python_executable")
+                        .map_err(|e| anyhow!("error obtaining add context from Value: {:?}", e))?
+                        .expect(
+                            "add context should have been populated as part of Value conversion",
+                        );
 
-                add_context.replace(&new_add_context);
+                    add_context.replace(&new_add_context);
 
-                Ok(())
+                    if resource_filter.get_type() == "NoneType" {
+                        return Ok(true);
+                    }
+
+                    let include = resource_filter
+                        .call(
+                            &mut cs,
+                            type_values,
+                            vec![value, Value::from(target_triple.as_str())],
+                            LinkedHashMap::new(),
+                            None,
+                            None,
+                        )
+                        .map_err(|e| anyhow!("error calling resource_filter: {:?}", e))?;
+
+                    if include.get_type() != "bool" {
+                        return Err(anyhow!(
+                            "resource_filter must return a bool; got {}",
+                            include.get_type()
+                        ));
+                    }
+
+                    Ok(include.to_bool())
+                };
+
+                // Name the resource so failures in Starlark callbacks can be
+                // traced back to what triggered them.
+                process().with_context(|| {
+                    format!(
+                        "resource callback failed for {} {}",
+                        python_resource_type_name(resource),
+                        resource.full_name()
+                    )
+                })
             },
         );
 
         builder
-            .add_distribution_resources(Some(callback))
+            .add_distribution_resources(&context.logger, Some(callback))
             .map_err(|e| {
                 ValueError::from(RuntimeError {
                     code: "PYOXIDIZER_BUILD",
-                    message: e.to_string(),
+                    message: format!("{:#}", e),
                     label: "to_python_executable()".to_string(),
                 })
             })?;
 
-        Ok(Value::new(PythonExecutable::new(builder, policy)))
+        let mut exe = PythonExecutable::new(builder, policy);
+        exe.distribution_resolution_duration = self.resolution_duration;
+
+        Ok(Value::new(exe))
+    }
+
+    /// PythonDistribution.info()
+    pub fn info_starlark(&mut self, type_values: &TypeValues) -> ValueResult {
+        let raw_context = get_context(type_values)?;
+        let context = raw_context
+            .downcast_ref::<EnvironmentContext>()
+            .ok_or(ValueError::IncorrectParameterType)?;
+
+        let location = match &self.source {
+            PythonDistributionLocation::Local { local_path, .. } => local_path.clone(),
+            PythonDistributionLocation::Url { url, .. } => url.clone(),
+            PythonDistributionLocation::Urls { urls, .. } => urls.join(", "),
+        };
+
+        let mut info = LinkedHashMap::new();
+        info.insert("flavor".to_string(), Value::from(self.flavor.to_string()));
+        info.insert("location".to_string(), Value::from(location));
+
+        // Describe the distribution from what is known without resolving it.
+        if self.validate_only {
+            let record = PYTHON_DISTRIBUTIONS
+                .iter()
+                .find(|record| record.location == self.source);
+
+            info.insert(
+                "python_version".to_string(),
+                match record {
+                    Some(record) => Value::from(record.python_version.clone()),
+                    None => Value::from(NoneType::None),
+                },
+            );
+            info.insert(
+                "target_triple".to_string(),
+                match record {
+                    Some(record) => Value::from(record.target_triple.clone()),
+                    None => Value::from(NoneType::None),
+                },
+            );
+
+            return Value::try_from(info);
+        }
+
+        self.ensure_distribution_resolved(&context.logger)
+            .map_err(|e| {
+                ValueError::from(RuntimeError {
+                    code: "PYOXIDIZER_BUILD",
+                    message: e.to_string(),
+                    label: "resolve_distribution()".to_string(),
+                })
+            })?;
+        let dist = self.distribution.as_ref().unwrap().clone();
+
+        info.insert(
+            "python_version".to_string(),
+            Value::from(dist.python_version()),
+        );
+        info.insert(
+            "target_triple".to_string(),
+            Value::from(dist.target_triple()),
+        );
+        info.insert(
+            "extension_module_count".to_string(),
+            Value::from(dist.iter_extension_modules().count() as i64),
+        );
+
+        Value::try_from(info)
     }
 
     /// PythonDistribution.extension_modules()
@@ -496,11 +1027,99 @@ impl PythonDistribution {
                 .as_ref()
                 .unwrap()
                 .iter_extension_modules()
-                .map(|em| Value::new(PythonExtensionModuleValue::new(em.clone())))
+                .map(|em| {
+                    let mut value = PythonExtensionModuleValue::new(em.clone());
+                    value.origin = Some(DISTRIBUTION_RESOURCE_ORIGIN.to_string());
+                    Value::new(value)
+                })
                 .collect_vec(),
         ))
     }
 
+    /// PythonDistribution.extension_module_dependencies(name)
+    pub fn extension_module_dependencies(
+        &mut self,
+        type_values: &TypeValues,
+        name: &Value,
+    ) -> ValueResult {
+        let name = required_str_arg("name", &name)?;
+
+        let raw_context = get_context(type_values)?;
+        let context = raw_context
+            .downcast_ref::<EnvironmentContext>()
+            .ok_or(ValueError::IncorrectParameterType)?;
+
+        self.ensure_distribution_resolved(&context.logger)
+            .map_err(|e| {
+                ValueError::from(RuntimeError {
+                    code: "PYOXIDIZER_BUILD",
+                    message: e.to_string(),
+                    label: "resolve_distribution()".to_string(),
+                })
+            })?;
+
+        let mut variants = vec![];
+
+        for em in self
+            .distribution
+            .as_ref()
+            .unwrap()
+            .iter_extension_modules()
+            .filter(|em| em.name == name)
+        {
+            let mut link_libraries = vec![];
+
+            for library in &em.link_libraries {
+                let mut value = LinkedHashMap::new();
+                value.insert("name".to_string(), Value::from(library.name.clone()));
+                value.insert("system".to_string(), Value::from(library.system));
+                value.insert("framework".to_string(), Value::from(library.framework));
+                value.insert(
+                    "static_library".to_string(),
+                    Value::from(library.static_library.is_some()),
+                );
+                value.insert(
+                    "dynamic_library".to_string(),
+                    Value::from(library.dynamic_library.is_some()),
+                );
+
+                link_libraries.push(Value::try_from(value)?);
+            }
+
+            let object_files = em
+                .object_file_data
+                .iter()
+                .filter_map(|location| match location {
+                    DataLocation::Path(path) => Some(Value::from(path.display().to_string())),
+                    DataLocation::Memory(_) => None,
+                })
+                .collect_vec();
+
+            let mut value = LinkedHashMap::new();
+            value.insert(
+                "variant".to_string(),
+                match &em.variant {
+                    Some(variant) => Value::from(variant.clone()),
+                    None => Value::from(NoneType::None),
+                },
+            );
+            value.insert("link_libraries".to_string(), Value::from(link_libraries));
+            value.insert("object_files".to_string(), Value::from(object_files));
+
+            variants.push(Value::try_from(value)?);
+        }
+
+        if variants.is_empty() {
+            return Err(ValueError::from(RuntimeError {
+                code: "PYTHON_DISTRIBUTION",
+                message: format!("extension module {} not found in distribution", name),
+                label: "extension_module_dependencies()".to_string(),
+            }));
+        }
+
+        Ok(Value::from(variants))
+    }
+
     /// PythonDistribution.package_resources(include_test=false)
     pub fn package_resources(
         &mut self,
@@ -543,7 +1162,9 @@ impl PythonDistribution {
                     if !include_test && is_stdlib_test_package(&data.leaf_package) {
                         None
                     } else {
-                        Some(Value::new(PythonPackageResourceValue::new(data.clone())))
+                        let mut value = PythonPackageResourceValue::new(data.clone());
+                        value.origin = Some(DISTRIBUTION_RESOURCE_ORIGIN.to_string());
+                        Some(Value::new(value))
                     }
                 })
                 .collect_vec(),
@@ -582,16 +1203,93 @@ impl PythonDistribution {
         Ok(Value::from(
             modules
                 .iter()
-                .map(|module| Value::new(PythonModuleSourceValue::new(module.clone())))
+                .map(|module| {
+                    let mut value = PythonModuleSourceValue::new(module.clone());
+                    value.origin = Some(DISTRIBUTION_RESOURCE_ORIGIN.to_string());
+                    Value::new(value)
+                })
                 .collect_vec(),
         ))
     }
+
+    /// PythonDistribution.collect_resources(include_test=false)
+    pub fn collect_resources(
+        &mut self,
+        type_values: &TypeValues,
+        include_test: &Value,
+    ) -> ValueResult {
+        let include_test = required_bool_arg("include_test", &include_test)?;
+
+        let raw_context = get_context(type_values)?;
+        let context = raw_context
+            .downcast_ref::<EnvironmentContext>()
+            .ok_or(ValueError::IncorrectParameterType)?;
+
+        self.ensure_distribution_resolved(&context.logger)
+            .map_err(|e| {
+                ValueError::from(RuntimeError {
+                    code: "PYOXIDIZER_BUILD",
+                    message: e.to_string(),
+                    label: "resolve_distribution()".to_string(),
+                })
+            })?;
+
+        let dist = self.distribution.as_ref().unwrap();
+
+        let to_error = |e: anyhow::Error| {
+            ValueError::from(RuntimeError {
+                code: "PYTHON_DISTRIBUTION",
+                message: e.to_string(),
+                label: "collect_resources()".to_string(),
+            })
+        };
+
+        let modules = dist.source_modules().map_err(to_error)?;
+        let resources = dist.resource_datas().map_err(to_error)?;
+
+        let mut values = modules
+            .into_iter()
+            .filter(|module| include_test || !module.is_test)
+            .map(|module| {
+                let mut value = PythonModuleSourceValue::new(module);
+                value.origin = Some(DISTRIBUTION_RESOURCE_ORIGIN.to_string());
+                Value::new(value)
+            })
+            .collect_vec();
+
+        values.extend(dist.iter_extension_modules().map(|em| {
+            let mut value = PythonExtensionModuleValue::new(em.clone());
+            value.origin = Some(DISTRIBUTION_RESOURCE_ORIGIN.to_string());
+            Value::new(value)
+        }));
+
+        values.extend(
+            resources
+                .into_iter()
+                .filter(|resource| include_test || !resource.is_test)
+                .map(|resource| {
+                    let mut value = PythonPackageResourceValue::new(resource);
+                    value.origin = Some(DISTRIBUTION_RESOURCE_ORIGIN.to_string());
+                    Value::new(value)
+                }),
+        );
+
+        Ok(Value::from(values))
+    }
 }
 
 starlark_module! { python_distribution_module =>
     #[allow(non_snake_case, clippy::ptr_arg)]
-    PythonDistribution(env env, sha256, local_path=NoneType::None, url=NoneType::None, flavor="standalone") {
-        PythonDistribution::from_args(&env, &sha256, &local_path, &url, &flavor)
+    PythonDistribution(
+        env env,
+        sha256,
+        local_path=NoneType::None,
+        url=NoneType::None,
+        urls=NoneType::None,
+        flavor="standalone",
+        extract_path=NoneType::None
+    ) {
+        PythonDistribution::from_args(&env, &sha256, &local_path, &url, &urls, &flavor, &extract_path)
     }
 
     PythonDistribution.make_python_packaging_policy(env env, this) {
@@ -601,6 +1299,13 @@ starlark_module! { python_distribution_module =>
         }
     }
 
+    PythonDistribution.make_stdlib_only_policy(env env, this) {
+        match this.clone().downcast_mut::<PythonDistribution>()? {
+            Some(mut dist) => dist.make_stdlib_only_policy_starlark(&env),
+            None => Err(ValueError::IncorrectParameterType),
+        }
+    }
+
     PythonDistribution.make_python_interpreter_config(env env, this) {
         match this.clone().downcast_mut::<PythonDistribution>()? {
             Some(mut dist) => dist.make_python_interpreter_config_starlark(&env),
@@ -608,6 +1313,14 @@ starlark_module! { python_distribution_module =>
         }
     }
 
+    #[allow(clippy::ptr_arg)]
+    PythonDistribution.info(env env, this) {
+        match this.clone().downcast_mut::<PythonDistribution>()? {
+            Some(mut dist) => dist.info_starlark(&env),
+            None => Err(ValueError::IncorrectParameterType),
+        }
+    }
+
     #[allow(clippy::ptr_arg)]
     PythonDistribution.extension_modules(env env, this) {
         match this.clone().downcast_mut::<PythonDistribution>()? {
@@ -616,6 +1329,14 @@ starlark_module! { python_distribution_module =>
         }
     }
 
+    #[allow(clippy::ptr_arg)]
+    PythonDistribution.extension_module_dependencies(env env, this, name) {
+        match this.clone().downcast_mut::<PythonDistribution>()? {
+            Some(mut dist) => dist.extension_module_dependencies(&env, &name),
+            None => Err(ValueError::IncorrectParameterType),
+        }
+    }
+
     #[allow(clippy::ptr_arg)]
     PythonDistribution.source_modules(env env, this) {
         match this.clone().downcast_mut::<PythonDistribution>()? {
@@ -632,6 +1353,14 @@ starlark_module! { python_distribution_module =>
         }
     }
 
+    #[allow(clippy::ptr_arg)]
+    PythonDistribution.collect_resources(env env, this, include_test=false) {
+        match this.clone().downcast_mut::<PythonDistribution>()? {
+            Some(mut dist) => dist.collect_resources(&env, &include_test),
+            None => Err(ValueError::IncorrectParameterType),
+        }
+    }
+
     #[allow(non_snake_case, clippy::ptr_arg)]
     PythonDistribution.to_python_executable(
         env env,
@@ -639,7 +1368,8 @@ starlark_module! { python_distribution_module =>
         this,
         name,
         packaging_policy=NoneType::None,
-        config=NoneType::None
+        config=NoneType::None,
+        resource_filter=NoneType::None
     ) {
         match this.clone().downcast_mut::<PythonDistribution>()? {
             Some(mut dist) =>dist.to_python_executable_starlark(
@@ -648,6 +1378,7 @@ starlark_module! { python_distribution_module =>
                 &name,
                 &packaging_policy,
                 &config,
+                &resource_filter,
             ),
             None => Err(ValueError::IncorrectParameterType),
         }
@@ -658,16 +1389,28 @@ starlark_module! { python_distribution_module =>
         env env,
         flavor="standalone",
         build_target=NoneType::None,
-        python_version=NoneType::None
+        python_version=NoneType::None,
+        extract_path=NoneType::None
     ) {
-        PythonDistribution::default_python_distribution(&env, &flavor, &build_target, &python_version)
+        PythonDistribution::default_python_distribution(&env, &flavor, &build_target, &python_version, &extract_path)
+    }
+
+    #[allow(clippy::ptr_arg)]
+    available_python_versions(
+        env env,
+        flavor="standalone",
+        build_target=NoneType::None
+    ) {
+        PythonDistribution::available_python_versions(&env, &flavor, &build_target)
     }
 }
 
 #[cfg(test)]
 mod tests {
     use {
-        super::super::testutil::*, super::*, crate::py_packaging::distribution::DistributionFlavor,
+        super::super::testutil::*,
+        super::*,
+        crate::py_packaging::distribution::{DistributionFlavor, DistributionFlavorRegistry},
         crate::python_distributions::PYTHON_DISTRIBUTIONS,
     };
 
@@ -697,6 +1440,83 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_available_python_versions() -> Result<()> {
+        let mut env = StarlarkEnvironment::new()?;
+
+        let versions = env.eval("available_python_versions()")?;
+        assert_eq!(versions.get_type(), "list");
+
+        let versions = versions
+            .iter()
+            .unwrap()
+            .iter()
+            .map(|v| v.to_string())
+            .collect::<Vec<_>>();
+        assert!(!versions.is_empty());
+        assert!(versions.contains(&"3.9.0".to_string()));
+
+        let parsed = versions
+            .iter()
+            .map(|v| {
+                v.split('.')
+                    .map(|part| part.parse::<u32>().unwrap())
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        assert!(parsed.iter().all(|v| v.len() == 3));
+        assert!(parsed.windows(2).all(|w| w[0] < w[1]));
+
+        assert!(env
+            .eval("available_python_versions(build_target='unknown-triple')")
+            .is_err());
+        assert!(env
+            .eval("available_python_versions(flavor='unknown')")
+            .is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_registered_distribution_flavor() -> Result<()> {
+        let record = PYTHON_DISTRIBUTIONS
+            .find_distribution(
+                crate::project_building::HOST,
+                &DistributionFlavor::Standalone,
+                None,
+            )
+            .unwrap();
+
+        let mut flavors = DistributionFlavorRegistry::default();
+        flavors.register("test-custom", vec![record.clone()])?;
+
+        let mut env = StarlarkEnvironment::new()?;
+        env.set_distribution_flavors(flavors)?;
+
+        let dist = env.eval("default_python_distribution(flavor='test-custom')")?;
+        let x = dist.downcast_ref::<PythonDistribution>().unwrap();
+        assert_eq!(x.source, record.location);
+
+        let versions = env.eval("available_python_versions(flavor='test-custom')")?;
+        assert_eq!(
+            versions
+                .iter()
+                .unwrap()
+                .iter()
+                .map(|v| v.to_string())
+                .collect::<Vec<_>>(),
+            vec![record.python_version.clone()]
+        );
+
+        // Flavors registered with one environment aren't visible to others.
+        let mut env = StarlarkEnvironment::new()?;
+        assert!(env
+            .eval("default_python_distribution(flavor='test-custom')")
+            .is_err());
+
+        Ok(())
+    }
+
     #[test]
     fn test_default_python_distribution_python_38() -> Result<()> {
         let mut env = StarlarkEnvironment::new()?;
@@ -739,6 +1559,74 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_default_python_distribution_python_patch_version() -> Result<()> {
+        let mut env = StarlarkEnvironment::new()?;
+
+        let dist = env.eval("default_python_distribution(python_version='3.9.0')")?;
+        assert_eq!(dist.get_type(), "PythonDistribution");
+
+        let wanted = PYTHON_DISTRIBUTIONS
+            .find_distribution(
+                crate::project_building::HOST,
+                &DistributionFlavor::Standalone,
+                Some("3.9"),
+            )
+            .unwrap();
+
+        let x = dist.downcast_ref::<PythonDistribution>().unwrap();
+        assert_eq!(x.source, wanted.location);
+
+        let err = env
+            .eval("default_python_distribution(python_version='3.9.99')")
+            .unwrap_err();
+        assert!(err
+            .to_string()
+            .as_str()
+            .contains("available versions: 3.9.0"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_default_python_distribution_unavailable() -> Result<()> {
+        let mut env = StarlarkEnvironment::new()?;
+
+        let err = env
+            .eval("default_python_distribution(flavor='standalone_static', build_target='x86_64-unknown-linux-gnu')")
+            .unwrap_err();
+        assert!(err.to_string().as_str().contains(
+            "could not find default standalone_static Python distribution for x86_64-unknown-linux-gnu with version 3.8; available distributions for x86_64-unknown-linux-gnu: standalone (3.8, 3.9); standalone_dynamic (3.8, 3.9)"
+        ));
+
+        let err = env
+            .eval("default_python_distribution(build_target='x86_64-unknown-linux-gnu', python_version='3.7')")
+            .unwrap_err();
+        assert!(err.to_string().as_str().contains(
+            "available distributions for x86_64-unknown-linux-gnu: standalone (3.8, 3.9)"
+        ));
+
+        let err = env
+            .eval("default_python_distribution(build_target='armv7-unknown-linux-gnueabihf')")
+            .unwrap_err();
+        assert!(err.to_string().as_str().contains(
+            "no distributions are available for armv7-unknown-linux-gnueabihf; available targets: "
+        ));
+        assert!(err
+            .to_string()
+            .as_str()
+            .contains("x86_64-unknown-linux-gnu"));
+
+        let err = env
+            .eval("default_python_distribution(flavor='standalone-typo')")
+            .unwrap_err();
+        assert!(err.to_string().as_str().contains(
+            "distribution flavor standalone-typo not recognized; valid flavors: standalone, standalone_static, standalone_dynamic"
+        ));
+
+        Ok(())
+    }
+
     #[test]
     #[cfg(windows)]
     fn test_default_python_distribution_dynamic_windows() {
@@ -784,6 +1672,28 @@ mod tests {
         assert_eq!(x.flavor, DistributionFlavor::Standalone);
     }
 
+    #[test]
+    fn test_python_distribution_urls() {
+        let dist = starlark_ok("PythonDistribution('sha256', urls=['url1', 'url2'])");
+        let wanted = PythonDistributionLocation::Urls {
+            urls: vec!["url1".to_string(), "url2".to_string()],
+            sha256: "sha256".to_string(),
+        };
+
+        let x = dist.downcast_ref::<PythonDistribution>().unwrap();
+        assert_eq!(x.source, wanted);
+        assert_eq!(x.flavor, DistributionFlavor::Standalone);
+
+        let err = starlark_nok("PythonDistribution('sha256', urls=[])");
+        assert_eq!(err.message, "urls must not be empty");
+
+        let err = starlark_nok("PythonDistribution('sha256', url='url1', urls=['url2'])");
+        assert_eq!(err.message, "cannot define urls with local_path or url");
+
+        let err = starlark_nok("PythonDistribution('sha256', urls='url1')");
+        assert!(err.message.starts_with("function expects a list for urls"));
+    }
+
     #[test]
     fn test_python_distribution_local_path() {
         let dist = starlark_ok("PythonDistribution('sha256', local_path='some_path')");
@@ -797,12 +1707,212 @@ mod tests {
         assert_eq!(x.flavor, DistributionFlavor::Standalone);
     }
 
+    #[test]
+    fn test_python_distribution_offline() -> Result<()> {
+        let mut env = StarlarkEnvironment::new()?;
+        env.set_offline(true)?;
+
+        let err = env
+            .eval("PythonDistribution('00', url='https://example.com/python.tar.zst')")
+            .unwrap_err()
+            .to_string();
+        assert!(err.as_str().contains("OFFLINE_ERROR"));
+        assert!(err.as_str().contains("https://example.com/python.tar.zst"));
+
+        let dist = env.eval("PythonDistribution('00', local_path='some_path')")?;
+        assert_eq!(dist.get_type(), "PythonDistribution");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_python_distribution_allowed_hosts() -> Result<()> {
+        let mut env = StarlarkEnvironment::new()?;
+        env.set_allowed_distribution_hosts(Some(vec!["mirror.example.com".to_string()]))?;
+
+        let err = env
+            .eval("PythonDistribution('00', url='https://example.com/python.tar.zst')")
+            .unwrap_err()
+            .to_string();
+        assert!(err.as_str().contains("DISTRIBUTION_HOST_DENIED"));
+        assert!(err.as_str().contains("host example.com"));
+
+        let dist =
+            env.eval("PythonDistribution('00', url='https://Mirror.Example.com/python.tar.zst')")?;
+        assert_eq!(dist.get_type(), "PythonDistribution");
+
+        let err = env
+            .eval("PythonDistribution('00', urls=['https://mirror.example.com/python.tar.zst', 'https://example.com/python.tar.zst'])")
+            .unwrap_err()
+            .to_string();
+        assert!(err.as_str().contains("DISTRIBUTION_HOST_DENIED"));
+        assert!(err.as_str().contains("host example.com"));
+
+        let dist = env.eval("PythonDistribution('00', local_path='some_path')")?;
+        assert_eq!(dist.get_type(), "PythonDistribution");
+
+        env.set_allowed_distribution_hosts(None)?;
+        env.eval("PythonDistribution('00', url='https://example.com/python.tar.zst')")?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_extract_path() -> Result<()> {
+        let temp_dir = tempdir::TempDir::new("pyoxidizer-test")?;
+        let extract_path = temp_dir.path().join("distributions");
+
+        let mut env = StarlarkEnvironment::new()?;
+        env.eval(&format!(
+            "dist = default_python_distribution(extract_path={:?})",
+            extract_path.display().to_string()
+        ))?;
+        assert!(extract_path.is_dir());
+
+        let dist = env.eval("dist")?;
+        let mut dist = dist.downcast_mut::<PythonDistribution>().unwrap().unwrap();
+        assert_eq!(dist.dest_dir, extract_path);
+
+        dist.ensure_distribution_resolved(&crate::testutil::get_logger()?)?;
+
+        let sha256 = match &dist.source {
+            PythonDistributionLocation::Url { sha256, .. } => sha256.clone(),
+            PythonDistributionLocation::Urls { sha256, .. } => sha256.clone(),
+            PythonDistributionLocation::Local { sha256, .. } => sha256.clone(),
+        };
+        assert!(extract_path
+            .join(format!("python.{}", &sha256[0..12]))
+            .join("python")
+            .join("PYTHON.json")
+            .exists());
+
+        // Relative paths are resolved against the current working directory.
+        env.eval(&format!(
+            "set_cwd({:?})",
+            temp_dir.path().display().to_string()
+        ))?;
+        let dist =
+            env.eval("PythonDistribution('00', local_path='some_path', extract_path='rel')")?;
+        let dist = dist.downcast_ref::<PythonDistribution>().unwrap();
+        assert_eq!(dist.dest_dir, temp_dir.path().join("rel"));
+        assert!(temp_dir.path().join("rel").is_dir());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_info() -> Result<()> {
+        let mut env = StarlarkEnvironment::new()?;
+        env.eval("dist = default_python_distribution()")?;
+        let info = env.eval("dist.info()")?;
+        assert_eq!(info.get_type(), "dict");
+
+        let dist = env.eval("dist")?;
+        let dist = dist.downcast_ref::<PythonDistribution>().unwrap();
+        let resolved = dist.distribution.as_ref().unwrap();
+
+        let python_version = info.at(Value::from("python_version")).unwrap();
+        assert_eq!(python_version.to_str(), resolved.python_version());
+        assert_eq!(
+            info.at(Value::from("target_triple")).unwrap().to_str(),
+            resolved.target_triple()
+        );
+        assert_eq!(
+            info.at(Value::from("flavor")).unwrap().to_str(),
+            "standalone"
+        );
+        assert!(
+            info.at(Value::from("extension_module_count"))
+                .unwrap()
+                .to_int()
+                .unwrap()
+                > 0
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_info_validate_only() -> Result<()> {
+        let temp_dir = tempdir::TempDir::new("pyoxidizer-test")?;
+
+        let mut env = StarlarkEnvironment::new()?;
+        env.set_validate_only(true)?;
+        env.eval(&format!(
+            "dist = default_python_distribution(extract_path={:?})",
+            temp_dir.path().display().to_string()
+        ))?;
+        env.eval("info = dist.info()")?;
+        env.eval_assert("info['flavor'] == 'standalone'")?;
+        env.eval_assert("info['python_version'] != None")?;
+        env.eval_assert("info['target_triple'] != None")?;
+        env.eval_assert("'extension_module_count' not in info")?;
+
+        // A stub distribution stands in for the real one.
+        env.eval("exe = dist.to_python_executable('myapp')")?;
+        env.eval_assert("type(exe) == 'PythonExecutable'")?;
+        env.eval_assert("len(dist.source_modules()) == 0")?;
+
+        let dist = env.eval("dist")?;
+        let dist = dist.downcast_ref::<PythonDistribution>().unwrap();
+        assert!(dist.distribution.is_some());
+        assert!(std::fs::read_dir(temp_dir.path())?.next().is_none());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_bytecode_compiler_jobs() -> Result<()> {
+        let mut env = StarlarkEnvironment::new()?;
+        env.eval("dist = default_python_distribution()")?;
+        env.eval_assert(&format!(
+            "dist.bytecode_compiler_jobs == {}",
+            num_cpus::get()
+        ))?;
+
+        assert!(env.eval("dist.bytecode_compiler_jobs = 0").is_err());
+        assert!(env.eval("dist.bytecode_compiler_jobs = -1").is_err());
+        assert!(env.eval("dist.bytecode_compiler_jobs = '2'").is_err());
+
+        env.eval("dist.bytecode_compiler_jobs = 2")?;
+        env.eval_assert("dist.bytecode_compiler_jobs == 2")?;
+
+        let exe = env.eval("dist.to_python_executable('testapp')")?;
+        let exe = exe.downcast_ref::<PythonExecutable>().unwrap();
+        assert_eq!(exe.exe.bytecode_compiler_jobs(), 2);
+
+        Ok(())
+    }
+
     #[test]
     fn test_make_python_packaging_policy() {
         let policy = starlark_ok("default_python_distribution().make_python_packaging_policy()");
         assert_eq!(policy.get_type(), "PythonPackagingPolicy");
     }
 
+    #[test]
+    fn test_make_stdlib_only_policy() -> Result<()> {
+        let mut env = StarlarkEnvironment::new()?;
+
+        env.eval("dist = default_python_distribution()")?;
+        env.eval("policy = dist.make_stdlib_only_policy()")?;
+        env.eval_assert("policy.include_distribution_sources == True")?;
+        env.eval_assert("policy.include_distribution_resources == True")?;
+        env.eval_assert("policy.include_non_distribution_sources == False")?;
+        env.eval_assert("policy.include_test == False")?;
+
+        let exe = env.eval("dist.to_python_executable('testapp', packaging_policy=policy)")?;
+        let exe = exe.downcast_ref::<PythonExecutable>().unwrap();
+
+        assert!(exe.exe.iter_resources().any(|(name, _)| name == "json"));
+        assert!(exe
+            .exe
+            .iter_resources()
+            .all(|(name, _)| !is_stdlib_test_package(name)));
+
+        Ok(())
+    }
+
     #[test]
     fn test_make_python_interpreter_config() {
         let config = starlark_ok("default_python_distribution().make_python_interpreter_config()");
@@ -837,6 +1947,45 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_collect_resources() -> Result<()> {
+        let mut env = StarlarkEnvironment::new()?;
+        env.eval("dist = default_python_distribution()")?;
+
+        let resources = env.eval("dist.collect_resources()")?;
+        assert_eq!(resources.get_type(), "list");
+
+        let types = resources
+            .iter()
+            .unwrap()
+            .iter()
+            .map(|r| r.get_type())
+            .collect::<std::collections::BTreeSet<_>>();
+        assert!(types.contains(PythonModuleSourceValue::TYPE));
+        assert!(types.contains(PythonExtensionModuleValue::TYPE));
+        assert!(types.contains(PythonPackageResourceValue::TYPE));
+
+        for r in resources.iter().unwrap().iter() {
+            assert!(r.get_attr("is_stdlib").unwrap().to_bool());
+
+            if let Some(m) = r.downcast_ref::<PythonModuleSourceValue>() {
+                assert!(!m.inner.is_test);
+            }
+            if let Some(data) = r.downcast_ref::<PythonPackageResourceValue>() {
+                assert!(!data.inner.is_test);
+            }
+        }
+
+        env.eval_assert(
+            "len(dist.collect_resources()) < len(dist.collect_resources(include_test=True))",
+        )?;
+        env.eval_assert(
+            "len(dist.collect_resources(include_test=True)) == len(dist.source_modules()) + len(dist.extension_modules()) + len(dist.package_resources(include_test=True))",
+        )?;
+
+        Ok(())
+    }
+
     #[test]
     fn test_extension_modules() {
         let mods = starlark_ok("default_python_distribution().extension_modules()");
@@ -847,4 +1996,39 @@ mod tests {
             assert!(m.get_attr("is_stdlib").unwrap().to_bool());
         }
     }
+
+    #[test]
+    fn test_extension_module_dependencies() -> Result<()> {
+        let mut env = StarlarkEnvironment::new()?;
+        env.eval("dist = default_python_distribution()")?;
+
+        assert!(env
+            .eval("dist.extension_module_dependencies('does_not_exist')")
+            .is_err());
+
+        let variants = env.eval("dist.extension_module_dependencies('_ssl')")?;
+        assert_eq!(variants.get_type(), "list");
+        assert!(variants.length().unwrap() > 0);
+
+        env.eval_assert(
+            "type(dist.extension_module_dependencies('_ssl')[0]['object_files']) == 'list'",
+        )?;
+
+        let mut names = vec![];
+        for variant in variants.iter().unwrap().iter() {
+            for library in variant
+                .at(Value::from("link_libraries"))
+                .unwrap()
+                .iter()
+                .unwrap()
+                .iter()
+            {
+                names.push(library.at(Value::from("name")).unwrap().to_string());
+            }
+        }
+        assert!(names.iter().any(|n| n.as_str().contains("ssl")));
+        assert!(names.iter().any(|n| n.as_str().contains("crypto")));
+
+        Ok(())
+    }
 }
@@ -3,9 +3,9 @@
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.
 
 use {
-    super::target::{BuildContext, BuildTarget, ResolvedTarget, RunMode},
+    super::target::{BuildContext, BuildTarget, BuildTimings, ResolvedTarget, RunMode},
     crate::py_packaging::binary::PythonBinaryBuilder,
-    anyhow::Result,
+    anyhow::{anyhow, Result},
     slog::warn,
     starlark::values::{Mutable, TypedValue, Value},
 };
@@ -31,6 +31,18 @@ impl BuildTarget for PythonEmbeddedResources {
             context.output_path.display()
         );
 
+        // The location of the executable embedding these resources isn't
+        // known. So search paths relative to it can't be resolved.
+        if !self
+            .exe
+            .origin_module_search_paths(&context.output_path)?
+            .is_empty()
+        {
+            return Err(anyhow!(
+                "module search paths from pip_install_editable() require building a PythonExecutable"
+            ));
+        }
+
         let embedded = self
             .exe
             .to_embedded_python_context(&context.logger, &context.opt_level)?;
@@ -40,6 +52,7 @@ impl BuildTarget for PythonEmbeddedResources {
         Ok(ResolvedTarget {
             run_mode: RunMode::None,
             output_path: context.output_path.clone(),
+            timings: BuildTimings::new(),
         })
     }
 }
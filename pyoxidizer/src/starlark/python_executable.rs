@@ -6,21 +6,52 @@ use {
     super::{
         env::{get_context, EnvironmentContext},
         python_embedded_resources::PythonEmbeddedResources,
+        python_executable_tarball::PythonExecutableTarball,
         python_packaging_policy::PythonPackagingPolicyValue,
         python_resource::{
             is_resource_starlark_compatible, python_resource_to_value, PythonExtensionModuleValue,
             PythonModuleSourceValue, PythonPackageDistributionResourceValue,
             PythonPackageResourceValue, ResourceCollectionContext,
         },
-        target::{BuildContext, BuildTarget, ResolvedTarget, RunMode},
+        python_resources_bundle::PythonResourcesBundle,
+        target::{
+            log_phase_duration, BuildContext, BuildTarget, BuildTimings, ResolvedTarget, RunMode,
+        },
         util::{
-            optional_dict_arg, optional_list_arg, required_bool_arg, required_list_arg,
-            required_str_arg,
+            optional_bool_arg, optional_dict_arg, optional_list_arg, optional_str_arg,
+            required_bool_arg, required_env_name_arg, required_list_arg, required_str_arg,
+            required_type_arg,
+        },
+    },
+    crate::{
+        app_packaging::{
+            macos::{is_macos_target, MacOsSigningConfig},
+            resource::FileManifest,
+            windows::{is_windows_target, parse_windows_version, WindowsResourceMetadata},
+        },
+        project_building::{build_python_executable, executable_filename},
+        py_packaging::{
+            binary::{
+                console_script_entry_point, installed_requirements, path_extension_resources,
+                resource_manifest, resource_stats as binary_resource_stats,
+                test_package_resource_names, PythonBinaryBuilder,
+                PACKED_RESOURCES_COMPRESSION_LEVELS,
+            },
+            lint::lint_resources,
+            packaging_tool::{pipfile_lock_requirements, redact_credentials, PipDownloadOptions},
+            pruning::unreachable_stdlib_modules,
         },
     },
-    crate::{project_building::build_python_executable, py_packaging::binary::PythonBinaryBuilder},
-    anyhow::{Context, Result},
-    python_packaging::resource::{DataLocation, PythonModuleSource},
+    anyhow::{anyhow, Context, Result},
+    linked_hash_map::LinkedHashMap,
+    python_packaging::{
+        interpreter::PythonRunMode,
+        resource::{
+            DataLocation, FileData, PythonModuleSource, PythonPackageResource, PythonResource,
+        },
+    },
+    regex::Regex,
+    sha2::{Digest, Sha256},
     slog::{info, warn},
     starlark::{
         environment::TypeValues,
@@ -36,13 +67,18 @@ use {
         },
     },
     std::{
-        collections::HashMap,
+        collections::{BTreeSet, HashMap},
+        convert::TryFrom,
         io::Write,
         ops::Deref,
-        path::{Path, PathBuf},
+        path::{Component, Path, PathBuf},
+        time::{Duration, Instant},
     },
 };
 
+/// Package holding resources added by `PythonExecutable.add_metadata_resource()`.
+pub const METADATA_RESOURCES_PACKAGE: &str = "_pyoxidizer_metadata";
+
 /// Represents a builder for a Python executable.
 pub struct PythonExecutable {
     pub exe: Box<dyn PythonBinaryBuilder>,
@@ -52,6 +88,21 @@ pub struct PythonExecutable {
     // values_for_descendant_check_and_freeze() without the borrow checker
     // complaining due to a temporary vec/array.
     policy: Vec<Value>,
+
+    /// Starlark functions to call after the executable is built.
+    post_build_callbacks: Vec<Value>,
+
+    /// How to sign the executable when building for macOS.
+    macos_signing: Option<MacOsSigningConfig>,
+
+    /// Whether building refuses to replace an existing executable.
+    overwrite_protection: bool,
+
+    /// Whether to replace an existing executable despite `overwrite_protection`.
+    force_overwrite: bool,
+
+    /// How long it took to resolve the Python distribution this was derived from.
+    pub distribution_resolution_duration: Option<Duration>,
 }
 
 impl PythonExecutable {
@@ -59,6 +110,11 @@ impl PythonExecutable {
         Self {
             exe,
             policy: vec![Value::new(policy)],
+            post_build_callbacks: vec![],
+            macos_signing: None,
+            overwrite_protection: false,
+            force_overwrite: false,
+            distribution_resolution_duration: None,
         }
     }
 
@@ -71,6 +127,20 @@ impl PythonExecutable {
     }
 }
 
+impl Clone for PythonExecutable {
+    fn clone(&self) -> Self {
+        Self {
+            exe: self.exe.clone_box(),
+            policy: self.policy.clone(),
+            post_build_callbacks: self.post_build_callbacks.clone(),
+            macos_signing: self.macos_signing.clone(),
+            overwrite_protection: self.overwrite_protection,
+            force_overwrite: self.force_overwrite,
+            distribution_resolution_duration: self.distribution_resolution_duration,
+        }
+    }
+}
+
 impl TypedValue for PythonExecutable {
     type Holder = Mutable<PythonExecutable>;
     const TYPE: &'static str = "PythonExecutable";
@@ -78,14 +148,221 @@ impl TypedValue for PythonExecutable {
     fn values_for_descendant_check_and_freeze<'a>(
         &'a self,
     ) -> Box<dyn Iterator<Item = Value> + 'a> {
-        Box::new(self.policy.iter().cloned())
+        Box::new(
+            self.policy
+                .iter()
+                .chain(self.post_build_callbacks.iter())
+                .cloned(),
+        )
     }
 }
 
 impl BuildTarget for PythonExecutable {
     fn build(&mut self, context: &BuildContext) -> Result<ResolvedTarget> {
+        let (target, _) = self.build_with_extra_files(context)?;
+
+        Ok(target)
+    }
+}
+
+impl PythonExecutable {
+    /// Build the executable into the build context's output directory.
+    ///
+    /// Also returns the files that need to be installed next to the
+    /// executable for it to run. These are not written by this function.
+    pub fn build_with_extra_files(
+        &self,
+        context: &BuildContext,
+    ) -> Result<(ResolvedTarget, FileManifest)> {
+        let mut timings = BuildTimings::new();
+
+        if let Some(duration) = self.distribution_resolution_duration {
+            timings.insert(
+                "distribution_resolution".to_string(),
+                duration.as_secs_f64(),
+            );
+        }
+
+        let (dest_path, extra_files) = self.write_executable(context, &mut timings)?;
+
+        // Callbacks receive the path even if an existing executable was reused,
+        // as they may need to operate on files next to it.
+        let mut call_stack = CallStack::default();
+
+        for func in &self.post_build_callbacks {
+            func.call(
+                &mut call_stack,
+                context.type_values,
+                vec![Value::from(dest_path.display().to_string())],
+                LinkedHashMap::new(),
+                None,
+                None,
+            )
+            .map_err(|e| {
+                anyhow!(
+                    "error calling post-build callback {}: {:?}",
+                    func.to_str(),
+                    e
+                )
+            })?;
+        }
+
+        Ok((
+            ResolvedTarget {
+                run_mode: RunMode::Path { path: dest_path },
+                output_path: context.output_path.clone(),
+                timings,
+            },
+            extra_files,
+        ))
+    }
+
+    /// Write the executable into the build context's output directory.
+    ///
+    /// Durations of build phases are recorded in `timings`.
+    ///
+    /// Returns the path to the written executable and the extra files
+    /// it requires.
+    fn write_executable(
+        &self,
+        context: &BuildContext,
+        timings: &mut BuildTimings,
+    ) -> Result<(PathBuf, FileManifest)> {
+        let exe_name = executable_filename(&self.exe.name(), &context.target_triple);
+        let dest_path = context.output_path.join(&exe_name);
+        let fingerprint_path = context
+            .output_path
+            .join(format!(".{}.fingerprint", exe_name));
+
+        // The fingerprint captures everything that influences the built binary.
+        // It is computed from inputs so a previous build can be reused without
+        // collecting resources or compiling bytecode.
+        let mut fingerprint = format!(
+            "pyoxidizer {}\ntarget {}\nopt-level {}\nrelease {}\ninputs {}\n",
+            env!("CARGO_PKG_VERSION"),
+            context.target_triple,
+            context.opt_level,
+            context.release,
+            self.exe.input_fingerprint()?
+        );
+
+        let signing = match &self.macos_signing {
+            Some(signing) if is_macos_target(&context.target_triple) => Some(signing),
+            _ => None,
+        };
+
+        if let Some(signing) = signing {
+            fingerprint.push_str(&format!("codesign {:?}\n", signing));
+            if let Some(path) = &signing.entitlements_path {
+                fingerprint.push_str(&format!("entitlements {}\n", file_sha256(path)?));
+            }
+        }
+
+        let windows_metadata = self.exe.windows_resource_metadata();
+        if is_windows_target(&context.target_triple) && !windows_metadata.is_empty() {
+            fingerprint.push_str(&format!("windows-resources {:?}\n", windows_metadata));
+            if let Some(path) = &windows_metadata.icon_path {
+                fingerprint.push_str(&format!("icon {}\n", file_sha256(path)?));
+            }
+        }
+
+        for (name, value) in self.exe.build_environment_variables() {
+            fingerprint.push_str(&format!("build-env {:?}={:?}\n", name, value));
+        }
+
+        // The previous build is only reused if the executable it wrote is
+        // still intact.
+        let previous = if dest_path.exists() {
+            match PreviousBuild::read(&fingerprint_path, &fingerprint) {
+                Some(previous) if file_sha256(&dest_path)? == previous.exe_digest => Some(previous),
+                _ => None,
+            }
+        } else {
+            None
+        };
+
+        if let Some(previous) = &previous {
+            // Extra files aren't persisted, so they have to be derived again.
+            if !previous.has_extra_files {
+                warn!(
+                    &context.logger,
+                    "{} is up to date; reusing existing executable",
+                    dest_path.display()
+                );
+
+                write_checksums(
+                    &context.logger,
+                    &dest_path,
+                    &previous.exe_digest,
+                    &previous.resources_digest,
+                )?;
+
+                return Ok((dest_path, FileManifest::default()));
+            }
+        }
+
+        // Bytecode is compiled as part of collecting resources. Its duration
+        // is reported separately.
+        let start = Instant::now();
+        let mut embedded = self
+            .exe
+            .to_embedded_python_context(&context.logger, &context.opt_level)?;
+        let origin_search_paths = self.exe.origin_module_search_paths(&context.output_path)?;
+        if !origin_search_paths.is_empty() {
+            embedded
+                .config
+                .config
+                .module_search_paths
+                .get_or_insert_with(Vec::new)
+                .extend(origin_search_paths);
+        }
+        let collection_duration = start
+            .elapsed()
+            .checked_sub(embedded.bytecode_compile_duration)
+            .unwrap_or_default();
+        info!(
+            &context.logger,
+            "resource_collection took {:.3}s",
+            collection_duration.as_secs_f64()
+        );
+        timings.insert(
+            "resource_collection".to_string(),
+            collection_duration.as_secs_f64(),
+        );
+        timings.insert(
+            "bytecode_compilation".to_string(),
+            embedded.bytecode_compile_duration.as_secs_f64(),
+        );
+
+        let resources_digest = hex::encode(Sha256::digest(&embedded.resources));
+
+        if let Some(previous) = previous {
+            warn!(
+                &context.logger,
+                "{} is up to date; reusing existing executable",
+                dest_path.display()
+            );
+
+            write_checksums(
+                &context.logger,
+                &dest_path,
+                &previous.exe_digest,
+                &resources_digest,
+            )?;
+
+            return Ok((dest_path, embedded.extra_files));
+        }
+
+        if self.overwrite_protection && !self.force_overwrite && dest_path.exists() {
+            return Err(anyhow!(
+                "refusing to overwrite existing {}; remove it or enable force in set_overwrite_protection()",
+                dest_path.display()
+            ));
+        }
+
         // Build an executable by writing out a temporary Rust project
         // and building it.
+        let start = Instant::now();
         let build = build_python_executable(
             &context.logger,
             &self.exe.name(),
@@ -94,13 +371,23 @@ impl BuildTarget for PythonExecutable {
             &context.opt_level,
             context.release,
         )?;
+        timings.insert(
+            "rust_compile".to_string(),
+            log_phase_duration(&context.logger, "rust_compile", start).as_secs_f64(),
+        );
 
-        let dest_path = context.output_path.join(build.exe_name);
         warn!(
             &context.logger,
             "writing executable to {}",
             dest_path.display()
         );
+        // Remove the old fingerprint first so a partially written executable
+        // is never considered current.
+        if fingerprint_path.exists() {
+            std::fs::remove_file(&fingerprint_path)
+                .context(format!("removing {}", fingerprint_path.display()))?;
+        }
+
         let mut fh = std::fs::File::create(&dest_path)
             .context(format!("creating {}", dest_path.display()))?;
         fh.write_all(&build.exe_data)
@@ -108,17 +395,143 @@ impl BuildTarget for PythonExecutable {
 
         crate::app_packaging::resource::set_executable(&mut fh)
             .context("making binary executable")?;
+        drop(fh);
+
+        if let Some(signing) = signing {
+            signing
+                .sign(&context.logger, &dest_path)
+                .context(format!("signing {}", dest_path.display()))?;
+        }
+
+        let build = PreviousBuild {
+            exe_digest: file_sha256(&dest_path)?,
+            resources_digest,
+            has_extra_files: embedded.extra_files.entries().next().is_some(),
+        };
+
+        std::fs::write(&fingerprint_path, build.to_fingerprint_file(&fingerprint))
+            .context(format!("writing {}", fingerprint_path.display()))?;
+
+        write_checksums(
+            &context.logger,
+            &dest_path,
+            &build.exe_digest,
+            &build.resources_digest,
+        )?;
+
+        Ok((dest_path, embedded.extra_files))
+    }
+}
+
+/// Outputs of a previous executable build, as recorded in its fingerprint file.
+#[derive(Clone, Debug, PartialEq)]
+struct PreviousBuild {
+    /// SHA-256 of the written (and possibly signed) executable.
+    exe_digest: String,
+    /// SHA-256 of the packed resources data.
+    resources_digest: String,
+    /// Whether the build required files to be installed next to the executable.
+    has_extra_files: bool,
+}
+
+impl PreviousBuild {
+    /// Obtain the content of a fingerprint file recording this build.
+    fn to_fingerprint_file(&self, fingerprint: &str) -> String {
+        format!(
+            "{}output {}\nresources {}\nextra-files {}\n",
+            fingerprint, self.exe_digest, self.resources_digest, self.has_extra_files
+        )
+    }
+
+    /// Parse the content of a fingerprint file.
+    ///
+    /// Returns `None` unless it was written for a build with `fingerprint`.
+    fn parse(data: &str, fingerprint: &str) -> Option<Self> {
+        if !data.starts_with(fingerprint) {
+            return None;
+        }
+
+        let mut lines = data[fingerprint.len()..].lines();
+        let mut field = |name: &str| -> Option<String> {
+            let line = lines.next()?;
+            if line.starts_with(name) && line[name.len()..].starts_with(' ') {
+                Some(line[name.len() + 1..].to_string())
+            } else {
+                None
+            }
+        };
+
+        let exe_digest = field("output")?;
+        let resources_digest = field("resources")?;
+        let has_extra_files = field("extra-files")?.parse().ok()?;
+
+        if lines.next().is_some() {
+            return None;
+        }
 
-        Ok(ResolvedTarget {
-            run_mode: RunMode::Path { path: dest_path },
-            output_path: context.output_path.clone(),
+        Some(Self {
+            exe_digest,
+            resources_digest,
+            has_extra_files,
         })
     }
+
+    /// Read the fingerprint file at `path`.
+    ///
+    /// Returns `None` if it doesn't exist or wasn't written for a build
+    /// with `fingerprint`.
+    fn read(path: &Path, fingerprint: &str) -> Option<Self> {
+        Self::parse(&std::fs::read_to_string(path).ok()?, fingerprint)
+    }
+}
+
+/// Compute the hex encoded SHA-256 of a file's content.
+fn file_sha256(path: &Path) -> Result<String> {
+    let data = std::fs::read(path).context(format!("reading {}", path.display()))?;
+
+    Ok(hex::encode(Sha256::digest(&data)))
+}
+
+/// Log SHA-256 digests of a built executable and its packed resources.
+///
+/// The executable's digest is also written to `<executable>.sha256` in the
+/// format of `sha256sum`, so it can be verified with `sha256sum -c`. Digests
+/// are computed after signing, so they describe the distributed file.
+fn write_checksums(
+    logger: &slog::Logger,
+    exe_path: &Path,
+    exe_digest: &str,
+    resources_digest: &str,
+) -> Result<()> {
+    warn!(logger, "sha256 of {}: {}", exe_path.display(), exe_digest);
+    warn!(logger, "sha256 of packed resources: {}", resources_digest);
+
+    let exe_name = exe_path
+        .file_name()
+        .ok_or_else(|| anyhow!("{} has no file name", exe_path.display()))?
+        .to_string_lossy();
+    let checksum_path = exe_path.with_file_name(format!("{}.sha256", exe_name));
+
+    std::fs::write(&checksum_path, format!("{}  {}\n", exe_digest, exe_name))
+        .context(format!("writing {}", checksum_path.display()))?;
+
+    Ok(())
+}
+
+/// Whether a string is a valid, dot delimited Python module name.
+fn is_valid_module_name(name: &str) -> bool {
+    name.split('.').all(|part| match part.chars().next() {
+        Some(c) => {
+            (c.is_ascii_alphabetic() || c == '_')
+                && part.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+        }
+        None => false,
+    })
 }
 
 // Starlark functions.
 impl PythonExecutable {
-    /// PythonExecutable.make_python_module_source(name, source, is_package=false)
+    /// PythonExecutable.make_python_module_source(name, source, is_package=false, cache_tag=None)
     pub fn starlark_make_python_module_source(
         &self,
         type_values: &TypeValues,
@@ -126,35 +539,81 @@ impl PythonExecutable {
         name: &Value,
         source: &Value,
         is_package: &Value,
+        cache_tag: &Value,
     ) -> ValueResult {
         let name = required_str_arg("name", &name)?;
         let source = required_str_arg("source", &source)?;
         let is_package = required_bool_arg("is_package", &is_package)?;
+        let cache_tag = optional_str_arg("cache_tag", &cache_tag)?;
+
+        if let Some(cache_tag) = &cache_tag {
+            let valid = match cache_tag.strip_prefix("cpython-") {
+                Some(version) => version.len() >= 2 && version.chars().all(|c| c.is_ascii_digit()),
+                None => false,
+            };
+
+            if !valid {
+                return Err(ValueError::from(RuntimeError {
+                    code: INCORRECT_PARAMETER_TYPE_ERROR_CODE,
+                    message: format!(
+                        "cache_tag must be of the form cpython-XY; got {}",
+                        cache_tag
+                    ),
+                    label: "make_python_module_source()".to_string(),
+                }));
+            }
+        }
 
         let module = PythonModuleSource {
             name,
             source: DataLocation::Memory(source.into_bytes()),
             is_package,
-            cache_tag: self.exe.cache_tag().to_string(),
+            cache_tag: cache_tag.unwrap_or_else(|| self.exe.cache_tag().to_string()),
             is_stdlib: false,
             is_test: false,
         };
 
+        let raw_context = get_context(type_values)?;
+        let context = raw_context
+            .downcast_ref::<EnvironmentContext>()
+            .ok_or(ValueError::IncorrectParameterType)?;
+
         let mut value = PythonModuleSourceValue::new(module);
+        value.origin = Some(context.config_path.display().to_string());
         self.python_packaging_policy()
             .apply_to_resource(type_values, call_stack, &mut value)?;
 
         Ok(Value::new(value))
     }
 
-    /// PythonExecutable.pip_download(args)
+    /// PythonExecutable.pip_download(
+    ///     args,
+    ///     platform=None,
+    ///     abi=None,
+    ///     python_version=None,
+    ///     implementation=None,
+    ///     only_binary=True,
+    /// )
+    #[allow(clippy::too_many_arguments)]
     pub fn starlark_pip_download(
         &self,
         type_values: &TypeValues,
         call_stack: &mut CallStack,
         args: &Value,
+        platform: &Value,
+        abi: &Value,
+        python_version: &Value,
+        implementation: &Value,
+        only_binary: &Value,
     ) -> ValueResult {
         required_list_arg("args", "string", &args)?;
+        let options = PipDownloadOptions {
+            platform: optional_str_arg("platform", &platform)?,
+            abi: optional_str_arg("abi", &abi)?,
+            python_version: optional_str_arg("python_version", &python_version)?,
+            implementation: optional_str_arg("implementation", &implementation)?,
+            only_binary: required_bool_arg("only_binary", &only_binary)?,
+        };
 
         let args: Vec<String> = args.iter()?.iter().map(|x| x.to_string()).collect();
 
@@ -163,16 +622,43 @@ impl PythonExecutable {
             .downcast_ref::<EnvironmentContext>()
             .ok_or(ValueError::IncorrectParameterType)?;
 
+        // Packages aren't installed when only validating configuration.
+        if context.validate_only {
+            return Ok(Value::from(Vec::<Value>::new()));
+        }
+
+        if context.offline {
+            return Err(ValueError::from(RuntimeError {
+                code: "OFFLINE_ERROR",
+                message: format!(
+                    "offline mode forbids downloading Python packages: {}",
+                    args.join(" ")
+                ),
+                label: "pip_download()".to_string(),
+            }));
+        }
+
         let resources = self
             .exe
-            .pip_download(&context.logger, context.verbose, &args)
+            .pip_download(
+                &context.logger,
+                context.verbose,
+                &options,
+                &args,
+                &context.http_proxy,
+            )
             .map_err(|e| {
                 ValueError::from(RuntimeError {
                     code: "PIP_INSTALL_ERROR",
                     message: format!("error running pip install: {}", e),
                     label: "pip_install()".to_string(),
                 })
-            })?
+            })?;
+
+        let resources = self.add_path_extensions(context.deref(), "pip_download()", resources)?;
+        let origin = format!("pip download {}", redact_credentials(&args.join(" ")));
+
+        let resources = resources
             .iter()
             .filter(|r| is_resource_starlark_compatible(r))
             .map(|r| {
@@ -181,6 +667,7 @@ impl PythonExecutable {
                     call_stack,
                     r,
                     &self.python_packaging_policy(),
+                    &origin,
                 )
             })
             .collect::<Result<Vec<Value>, ValueError>>()?;
@@ -188,18 +675,20 @@ impl PythonExecutable {
         Ok(Value::from(resources))
     }
 
-    /// PythonExecutable.pip_install(args, extra_envs=None)
+    /// PythonExecutable.pip_install(args, extra_envs=None, find_links=None)
     pub fn starlark_pip_install(
         &self,
         type_values: &TypeValues,
         call_stack: &mut CallStack,
         args: &Value,
         extra_envs: &Value,
+        find_links: &Value,
     ) -> ValueResult {
         required_list_arg("args", "string", &args)?;
         optional_dict_arg("extra_envs", "string", "string", &extra_envs)?;
+        let find_links = optional_str_arg("find_links", &find_links)?;
 
-        let args: Vec<String> = args.iter()?.iter().map(|x| x.to_string()).collect();
+        let mut args: Vec<String> = args.iter()?.iter().map(|x| x.to_string()).collect();
 
         let extra_envs = match extra_envs.get_type() {
             "dict" => extra_envs
@@ -220,16 +709,88 @@ impl PythonExecutable {
             .downcast_ref::<EnvironmentContext>()
             .ok_or(ValueError::IncorrectParameterType)?;
 
+        // Packages aren't installed when only validating configuration.
+        if context.validate_only {
+            return Ok(Value::from(Vec::<Value>::new()));
+        }
+
+        if let Some(find_links) = &find_links {
+            let path = context.cwd.join(find_links);
+
+            let has_wheels = std::fs::read_dir(&path)
+                .map(|entries| {
+                    entries.filter_map(|entry| entry.ok()).any(|entry| {
+                        entry.path().extension().and_then(|x| x.to_str()) == Some("whl")
+                    })
+                })
+                .map_err(|e| {
+                    ValueError::from(RuntimeError {
+                        code: INCORRECT_PARAMETER_TYPE_ERROR_CODE,
+                        message: format!(
+                            "unable to read find_links directory {}: {}",
+                            path.display(),
+                            e
+                        ),
+                        label: "pip_install()".to_string(),
+                    })
+                })?;
+
+            if !has_wheels {
+                return Err(ValueError::from(RuntimeError {
+                    code: INCORRECT_PARAMETER_TYPE_ERROR_CODE,
+                    message: format!(
+                        "find_links directory {} does not contain any wheels",
+                        path.display()
+                    ),
+                    label: "pip_install()".to_string(),
+                }));
+            }
+
+            args.push("--find-links".to_string());
+            args.push(path.display().to_string());
+        }
+
+        // Prevent pip from consulting a package index so only local
+        // sources can be installed.
+        if context.offline || find_links.is_some() {
+            args.push("--no-index".to_string());
+        }
+
         let resources = self
             .exe
-            .pip_install(&context.logger, context.verbose, &args, &extra_envs)
+            .pip_install(
+                &context.logger,
+                context.verbose,
+                &args,
+                &extra_envs,
+                &context.cwd,
+                &context.pip_install_cache_path,
+                &context.http_proxy,
+            )
             .map_err(|e| {
-                ValueError::from(RuntimeError {
-                    code: "PIP_INSTALL_ERROR",
-                    message: format!("error running pip install: {}", e),
-                    label: "pip_install()".to_string(),
-                })
-            })?
+                if context.offline {
+                    ValueError::from(RuntimeError {
+                        code: "OFFLINE_ERROR",
+                        message: format!(
+                            "error running pip install {} in offline mode: {}",
+                            args.join(" "),
+                            e
+                        ),
+                        label: "pip_install()".to_string(),
+                    })
+                } else {
+                    ValueError::from(RuntimeError {
+                        code: "PIP_INSTALL_ERROR",
+                        message: format!("error running pip install: {}", e),
+                        label: "pip_install()".to_string(),
+                    })
+                }
+            })?;
+
+        let resources = self.add_path_extensions(context.deref(), "pip_install()", resources)?;
+        let origin = format!("pip install {}", redact_credentials(&args.join(" ")));
+
+        let resources = resources
             .iter()
             .filter(|r| is_resource_starlark_compatible(r))
             .map(|r| {
@@ -238,6 +799,7 @@ impl PythonExecutable {
                     call_stack,
                     r,
                     &self.python_packaging_policy(),
+                    &origin,
                 )
             })
             .collect::<Result<Vec<Value>, ValueError>>()?;
@@ -245,39 +807,84 @@ impl PythonExecutable {
         Ok(Value::from(resources))
     }
 
-    /// PythonExecutable.read_package_root(path, packages)
-    pub fn starlark_read_package_root(
-        &self,
+    /// PythonExecutable.pip_install_editable(package_path, extra_envs=None)
+    pub fn starlark_pip_install_editable(
+        &mut self,
         type_values: &TypeValues,
         call_stack: &mut CallStack,
-        path: &Value,
-        packages: &Value,
+        package_path: &Value,
+        extra_envs: &Value,
     ) -> ValueResult {
-        let path = required_str_arg("path", &path)?;
-        required_list_arg("packages", "string", &packages)?;
+        let package_path = required_str_arg("package_path", &package_path)?;
+        optional_dict_arg("extra_envs", "string", "string", &extra_envs)?;
 
-        let packages = packages
-            .iter()?
-            .iter()
-            .map(|x| x.to_string())
-            .collect::<Vec<String>>();
+        let extra_envs = match extra_envs.get_type() {
+            "dict" => extra_envs
+                .iter()?
+                .iter()
+                .map(|key| {
+                    let k = key.to_string();
+                    let v = extra_envs.at(key).unwrap().to_string();
+                    (k, v)
+                })
+                .collect(),
+            "NoneType" => HashMap::new(),
+            _ => panic!("should have validated type above"),
+        };
+
+        let package_path = PathBuf::from(package_path);
 
         let raw_context = get_context(type_values)?;
         let context = raw_context
             .downcast_ref::<EnvironmentContext>()
             .ok_or(ValueError::IncorrectParameterType)?;
 
+        // Packages aren't installed when only validating configuration.
+        if context.validate_only {
+            return Ok(Value::from(Vec::<Value>::new()));
+        }
+
+        let package_path = if package_path.is_absolute() {
+            package_path
+        } else {
+            PathBuf::from(&context.cwd).join(package_path)
+        };
+
+        let mut args = vec!["-e".to_string(), package_path.display().to_string()];
+
+        if context.offline {
+            args.push("--no-index".to_string());
+        }
+
+        warn!(
+            &context.logger,
+            "{} is installed in editable mode; its resources will be loaded from the filesystem and won't be embedded in the binary",
+            package_path.display()
+        );
+
+        let origin = package_path.display().to_string();
         let resources = self
             .exe
-            .read_package_root(&context.logger, Path::new(&path), &packages)
+            .pip_install(
+                &context.logger,
+                context.verbose,
+                &args,
+                &extra_envs,
+                &context.cwd,
+                &context.pip_install_cache_path,
+                &context.http_proxy,
+            )
             .map_err(|e| {
                 ValueError::from(RuntimeError {
-                    code: "PACKAGE_ROOT_ERROR",
-                    message: format!("could not find resources: {}", e),
-                    label: "read_package_root()".to_string(),
+                    code: "PIP_INSTALL_ERROR",
+                    message: format!("error running pip install -e: {}", e),
+                    label: "pip_install_editable()".to_string(),
                 })
             })?
             .iter()
+            // Import hooks installed by editable installs reference the
+            // source tree, which we add to the search path instead.
+            .filter(|r| !r.full_name().starts_with("__editable__"))
             .filter(|r| is_resource_starlark_compatible(r))
             .map(|r| {
                 python_resource_to_value(
@@ -285,21 +892,220 @@ impl PythonExecutable {
                     call_stack,
                     r,
                     &self.python_packaging_policy(),
+                    &origin,
                 )
             })
             .collect::<Result<Vec<Value>, ValueError>>()?;
 
+        self.exe.add_module_search_path(&package_path);
+
         Ok(Value::from(resources))
     }
 
-    /// PythonExecutable.read_virtualenv(path)
-    pub fn starlark_read_virtualenv(
+    /// PythonExecutable.pipenv_install(project_path, dev=False, extra_envs=None)
+    pub fn starlark_pipenv_install(
         &self,
         type_values: &TypeValues,
         call_stack: &mut CallStack,
-        path: &Value,
+        project_path: &Value,
+        dev: &Value,
+        extra_envs: &Value,
     ) -> ValueResult {
-        let path = required_str_arg("path", &path)?;
+        let project_path = required_str_arg("project_path", &project_path)?;
+        let dev = optional_bool_arg("dev", &dev)?.unwrap_or(false);
+        optional_dict_arg("extra_envs", "string", "string", &extra_envs)?;
+
+        let extra_envs = match extra_envs.get_type() {
+            "dict" => extra_envs
+                .iter()?
+                .iter()
+                .map(|key| {
+                    let k = key.to_string();
+                    let v = extra_envs.at(key).unwrap().to_string();
+                    (k, v)
+                })
+                .collect(),
+            "NoneType" => HashMap::new(),
+            _ => panic!("should have validated type above"),
+        };
+
+        let raw_context = get_context(type_values)?;
+        let context = raw_context
+            .downcast_ref::<EnvironmentContext>()
+            .ok_or(ValueError::IncorrectParameterType)?;
+
+        // Packages aren't installed when only validating configuration.
+        if context.validate_only {
+            return Ok(Value::from(Vec::<Value>::new()));
+        }
+
+        let lock_path = context.cwd.join(project_path).join("Pipfile.lock");
+
+        if !lock_path.exists() {
+            return Err(ValueError::from(RuntimeError {
+                code: "PIPENV_ERROR",
+                message: format!("{} does not exist", lock_path.display()),
+                label: "pipenv_install()".to_string(),
+            }));
+        }
+
+        let requirements = pipfile_lock_requirements(&lock_path, dev).map_err(|e| {
+            ValueError::from(RuntimeError {
+                code: "PIPENV_ERROR",
+                message: format!("{:#}", e),
+                label: "pipenv_install()".to_string(),
+            })
+        })?;
+
+        if requirements.is_empty() {
+            return Ok(Value::from(Vec::<Value>::new()));
+        }
+
+        let temp_dir = tempdir::TempDir::new("pyoxidizer-pipenv").map_err(|e| {
+            ValueError::from(RuntimeError {
+                code: "PIPENV_ERROR",
+                message: e.to_string(),
+                label: "pipenv_install()".to_string(),
+            })
+        })?;
+        let requirements_path = temp_dir.path().join("requirements.txt");
+        std::fs::write(&requirements_path, requirements.join("\n") + "\n").map_err(|e| {
+            ValueError::from(RuntimeError {
+                code: "PIPENV_ERROR",
+                message: e.to_string(),
+                label: "pipenv_install()".to_string(),
+            })
+        })?;
+
+        // The lock file defines the full dependency closure.
+        let mut args = vec![
+            "--no-deps".to_string(),
+            "-r".to_string(),
+            requirements_path.display().to_string(),
+        ];
+
+        if context.offline {
+            args.push("--no-index".to_string());
+        }
+
+        let resources = self
+            .exe
+            .pip_install(
+                &context.logger,
+                context.verbose,
+                &args,
+                &extra_envs,
+                &context.cwd,
+                &context.pip_install_cache_path,
+                &context.http_proxy,
+            )
+            .map_err(|e| {
+                ValueError::from(RuntimeError {
+                    code: "PIPENV_ERROR",
+                    message: format!("error installing {}: {}", lock_path.display(), e),
+                    label: "pipenv_install()".to_string(),
+                })
+            })?;
+
+        let resources = self.add_path_extensions(context.deref(), "pipenv_install()", resources)?;
+        let origin = lock_path.display().to_string();
+
+        let resources = resources
+            .iter()
+            .filter(|r| is_resource_starlark_compatible(r))
+            .map(|r| {
+                python_resource_to_value(
+                    type_values,
+                    call_stack,
+                    r,
+                    &self.python_packaging_policy(),
+                    &origin,
+                )
+            })
+            .collect::<Result<Vec<Value>, ValueError>>()?;
+
+        Ok(Value::from(resources))
+    }
+
+    /// PythonExecutable.read_package_root(path, packages, namespace_packages=None)
+    pub fn starlark_read_package_root(
+        &self,
+        type_values: &TypeValues,
+        call_stack: &mut CallStack,
+        path: &Value,
+        packages: &Value,
+        namespace_packages: &Value,
+    ) -> ValueResult {
+        let path = required_str_arg("path", &path)?;
+        required_list_arg("packages", "string", &packages)?;
+        optional_list_arg("namespace_packages", "string", &namespace_packages)?;
+
+        let packages = packages
+            .iter()?
+            .iter()
+            .map(|x| x.to_string())
+            .collect::<Vec<String>>();
+
+        let namespace_packages = match namespace_packages.get_type() {
+            "list" => namespace_packages
+                .iter()?
+                .iter()
+                .map(|x| x.to_string())
+                .collect::<Vec<String>>(),
+            "NoneType" => Vec::new(),
+            _ => panic!("should have validated type above"),
+        };
+
+        let raw_context = get_context(type_values)?;
+        let context = raw_context
+            .downcast_ref::<EnvironmentContext>()
+            .ok_or(ValueError::IncorrectParameterType)?;
+
+        let resources = self
+            .exe
+            .read_package_root(
+                &context.logger,
+                Path::new(&path),
+                &packages,
+                &namespace_packages,
+            )
+            .map_err(|e| {
+                ValueError::from(RuntimeError {
+                    code: "PACKAGE_ROOT_ERROR",
+                    message: format!("could not find resources: {}", e),
+                    label: "read_package_root()".to_string(),
+                })
+            })?;
+
+        let resources =
+            self.add_path_extensions(context.deref(), "read_package_root()", resources)?;
+        let origin = path.clone();
+
+        let resources = resources
+            .iter()
+            .filter(|r| is_resource_starlark_compatible(r))
+            .map(|r| {
+                python_resource_to_value(
+                    type_values,
+                    call_stack,
+                    r,
+                    &self.python_packaging_policy(),
+                    &origin,
+                )
+            })
+            .collect::<Result<Vec<Value>, ValueError>>()?;
+
+        Ok(Value::from(resources))
+    }
+
+    /// PythonExecutable.read_virtualenv(path)
+    pub fn starlark_read_virtualenv(
+        &self,
+        type_values: &TypeValues,
+        call_stack: &mut CallStack,
+        path: &Value,
+    ) -> ValueResult {
+        let path = required_str_arg("path", &path)?;
 
         let raw_context = get_context(type_values)?;
         let context = raw_context
@@ -315,7 +1121,112 @@ impl PythonExecutable {
                     message: format!("could not find resources: {}", e),
                     label: "read_virtualenv()".to_string(),
                 })
-            })?
+            })?;
+
+        let resources =
+            self.add_path_extensions(context.deref(), "read_virtualenv()", resources)?;
+        let origin = path.clone();
+
+        let resources = resources
+            .iter()
+            .filter(|r| is_resource_starlark_compatible(r))
+            .map(|r| {
+                python_resource_to_value(
+                    type_values,
+                    call_stack,
+                    r,
+                    &self.python_packaging_policy(),
+                    &origin,
+                )
+            })
+            .collect::<Result<Vec<Value>, ValueError>>()?;
+
+        Ok(Value::from(resources))
+    }
+
+    /// PythonExecutable.read_site_packages(path)
+    pub fn starlark_read_site_packages(
+        &self,
+        type_values: &TypeValues,
+        call_stack: &mut CallStack,
+        path: &Value,
+    ) -> ValueResult {
+        let path = required_str_arg("path", &path)?;
+
+        let raw_context = get_context(type_values)?;
+        let context = raw_context
+            .downcast_ref::<EnvironmentContext>()
+            .ok_or(ValueError::IncorrectParameterType)?;
+
+        let resources = self
+            .exe
+            .read_site_packages(&context.logger, &Path::new(&path))
+            .map_err(|e| {
+                ValueError::from(RuntimeError {
+                    code: "SITE_PACKAGES_ERROR",
+                    message: format!("could not find resources: {}", e),
+                    label: "read_site_packages()".to_string(),
+                })
+            })?;
+
+        let resources =
+            self.add_path_extensions(context.deref(), "read_site_packages()", resources)?;
+        let origin = path.clone();
+
+        let resources = resources
+            .iter()
+            .filter(|r| is_resource_starlark_compatible(r))
+            .map(|r| {
+                python_resource_to_value(
+                    type_values,
+                    call_stack,
+                    r,
+                    &self.python_packaging_policy(),
+                    &origin,
+                )
+            })
+            .collect::<Result<Vec<Value>, ValueError>>()?;
+
+        Ok(Value::from(resources))
+    }
+
+    /// PythonExecutable.read_zip_file(path, strip_prefix=None)
+    pub fn starlark_read_zip_file(
+        &self,
+        type_values: &TypeValues,
+        call_stack: &mut CallStack,
+        path: &Value,
+        strip_prefix: &Value,
+    ) -> ValueResult {
+        let path = required_str_arg("path", &path)?;
+        let strip_prefix = optional_str_arg("strip_prefix", &strip_prefix)?;
+
+        let raw_context = get_context(type_values)?;
+        let context = raw_context
+            .downcast_ref::<EnvironmentContext>()
+            .ok_or(ValueError::IncorrectParameterType)?;
+
+        let path = context.cwd.join(path);
+
+        let resources = self
+            .exe
+            .read_zip_file(
+                &context.logger,
+                &path,
+                strip_prefix.as_ref().map(|x| x.as_str()),
+            )
+            .map_err(|e| {
+                ValueError::from(RuntimeError {
+                    code: "ZIP_FILE_ERROR",
+                    message: format!("could not find resources: {:?}", e),
+                    label: "read_zip_file()".to_string(),
+                })
+            })?;
+
+        let resources = self.add_path_extensions(context.deref(), "read_zip_file()", resources)?;
+        let origin = path.display().to_string();
+
+        let resources = resources
             .iter()
             .filter(|r| is_resource_starlark_compatible(r))
             .map(|r| {
@@ -324,6 +1235,7 @@ impl PythonExecutable {
                     call_stack,
                     r,
                     &self.python_packaging_policy(),
+                    &origin,
                 )
             })
             .collect::<Result<Vec<Value>, ValueError>>()?;
@@ -344,7 +1256,7 @@ impl PythonExecutable {
         optional_dict_arg("extra_envs", "string", "string", &extra_envs)?;
         optional_list_arg("extra_global_arguments", "string", &extra_global_arguments)?;
 
-        let extra_envs = match extra_envs.get_type() {
+        let mut extra_envs: HashMap<String, String> = match extra_envs.get_type() {
             "dict" => extra_envs
                 .iter()?
                 .iter()
@@ -374,12 +1286,25 @@ impl PythonExecutable {
             .downcast_ref::<EnvironmentContext>()
             .ok_or(ValueError::IncorrectParameterType)?;
 
+        // Packages aren't installed when only validating configuration.
+        if context.validate_only {
+            return Ok(Value::from(Vec::<Value>::new()));
+        }
+
         let package_path = if package_path.is_absolute() {
             package_path
         } else {
             PathBuf::from(&context.cwd).join(package_path)
         };
 
+        // setuptools installs setup_requires dependencies via pip, which
+        // would otherwise download them.
+        if context.offline {
+            extra_envs
+                .entry("PIP_NO_INDEX".to_string())
+                .or_insert_with(|| "1".to_string());
+        }
+
         let resources = self
             .exe
             .setup_py_install(
@@ -388,6 +1313,8 @@ impl PythonExecutable {
                 context.verbose,
                 &extra_envs,
                 &extra_global_arguments,
+                &context.build_path.join("setup_py_install"),
+                &context.http_proxy,
             )
             .map_err(|e| {
                 ValueError::from(RuntimeError {
@@ -395,7 +1322,13 @@ impl PythonExecutable {
                     message: e.to_string(),
                     label: "setup_py_install()".to_string(),
                 })
-            })?
+            })?;
+
+        let resources =
+            self.add_path_extensions(context.deref(), "setup_py_install()", resources)?;
+        let origin = package_path.display().to_string();
+
+        let resources = resources
             .iter()
             .filter(|r| is_resource_starlark_compatible(r))
             .map(|r| {
@@ -404,6 +1337,7 @@ impl PythonExecutable {
                     call_stack,
                     r,
                     &self.python_packaging_policy(),
+                    &origin,
                 )
             })
             .collect::<Result<Vec<Value>, ValueError>>()?;
@@ -417,6 +1351,98 @@ impl PythonExecutable {
         Ok(Value::from(resources))
     }
 
+    /// Handle .pth files among collected resources.
+    ///
+    /// If the packaging policy applies .pth files, the resources in the
+    /// directories they reference are appended to `resources`. A warning is
+    /// emitted for content that is dropped.
+    fn add_path_extensions<'a>(
+        &self,
+        context: &EnvironmentContext,
+        label: &str,
+        mut resources: Vec<PythonResource<'a>>,
+    ) -> Result<Vec<PythonResource<'a>>, ValueError> {
+        let (path_resources, warnings) = path_extension_resources(
+            &resources,
+            self.exe.python_packaging_policy().apply_pth_files(),
+        )
+        .map_err(|e| {
+            ValueError::from(RuntimeError {
+                code: "PYOXIDIZER_BUILD",
+                message: format!("{:?}", e),
+                label: label.to_string(),
+            })
+        })?;
+
+        for warning in warnings {
+            warn!(&context.logger, "{}", warning);
+        }
+
+        if !path_resources.is_empty() {
+            info!(
+                &context.logger,
+                "collected {} resources referenced by .pth files",
+                path_resources.len()
+            );
+        }
+
+        resources.extend(path_resources);
+
+        Ok(resources)
+    }
+
+    /// Check whether a resource being added conflicts with an added resource.
+    ///
+    /// A resource conflicts if a resource with the same name but different
+    /// content was previously added. A warning is emitted for conflicts, or
+    /// an error is returned if the packaging policy forbids duplicates.
+    ///
+    /// `origin` describes where the resource being added was obtained from.
+    fn check_duplicate_resource(
+        &self,
+        context: &EnvironmentContext,
+        label: &str,
+        origin: &str,
+        resource: &PythonResource,
+    ) -> Result<(), ValueError> {
+        let conflicting = self.exe.is_conflicting_resource(resource).map_err(|e| {
+            ValueError::from(RuntimeError {
+                code: "PYOXIDIZER_BUILD",
+                message: e.to_string(),
+                label: label.to_string(),
+            })
+        })?;
+
+        if !conflicting {
+            return Ok(());
+        }
+
+        let name = resource.full_name();
+        let message = format!(
+            "resource {} from {} replaces different content from {}",
+            name,
+            origin,
+            self.exe
+                .resource_origin(&name)
+                .unwrap_or_else(|| "unknown origin".to_string())
+        );
+
+        if self
+            .exe
+            .python_packaging_policy()
+            .error_on_duplicate_resources()
+        {
+            Err(ValueError::from(RuntimeError {
+                code: "PYOXIDIZER_BUILD",
+                message,
+                label: label.to_string(),
+            }))
+        } else {
+            warn!(&context.logger, "{}", message);
+            Ok(())
+        }
+    }
+
     pub fn add_python_module_source(
         &mut self,
         context: &EnvironmentContext,
@@ -427,6 +1453,10 @@ impl PythonExecutable {
             &context.logger,
             "adding Python source module {}", module.inner.name;
         );
+        let python_resource: PythonResource = (&module.inner).into();
+        let origin = module.origin.as_deref().unwrap_or(label);
+        self.check_duplicate_resource(context, label, origin, &python_resource)?;
+
         self.exe
             .add_python_module_source(&module.inner, module.add_collection_context().clone())
             .map_err(|e| {
@@ -437,6 +1467,9 @@ impl PythonExecutable {
                 })
             })?;
 
+        self.exe
+            .set_resource_origin(&python_resource.full_name(), origin);
+
         Ok(Value::new(NoneType::None))
     }
 
@@ -451,6 +1484,10 @@ impl PythonExecutable {
             "adding Python package resource {}",
             resource.inner.symbolic_name()
         );
+        let python_resource: PythonResource = (&resource.inner).into();
+        let origin = resource.origin.as_deref().unwrap_or(label);
+        self.check_duplicate_resource(context, label, origin, &python_resource)?;
+
         self.exe
             .add_python_package_resource(&resource.inner, resource.add_collection_context().clone())
             .map_err(|e| {
@@ -461,6 +1498,9 @@ impl PythonExecutable {
                 })
             })?;
 
+        self.exe
+            .set_resource_origin(&python_resource.full_name(), origin);
+
         Ok(Value::new(NoneType::None))
     }
 
@@ -476,6 +1516,10 @@ impl PythonExecutable {
             resource.inner.package,
             resource.inner.name
         );
+        let python_resource: PythonResource = (&resource.inner).into();
+        let origin = resource.origin.as_deref().unwrap_or(label);
+        self.check_duplicate_resource(context, label, origin, &python_resource)?;
+
         self.exe
             .add_python_package_distribution_resource(
                 &resource.inner,
@@ -489,6 +1533,9 @@ impl PythonExecutable {
                 })
             })?;
 
+        self.exe
+            .set_resource_origin(&python_resource.full_name(), origin);
+
         Ok(Value::new(NoneType::None))
     }
 
@@ -498,10 +1545,26 @@ impl PythonExecutable {
         label: &str,
         module: &PythonExtensionModuleValue,
     ) -> ValueResult {
+        if self
+            .exe
+            .python_packaging_policy()
+            .is_extension_module_excluded(&module.inner.name)
+        {
+            info!(
+                &context.logger,
+                "excluding extension module {} per packaging policy", module.inner.name
+            );
+            return Ok(Value::new(NoneType::None));
+        }
+
         info!(
             &context.logger,
             "adding extension module {}", module.inner.name
         );
+        let python_resource: PythonResource = (&module.inner).into();
+        let origin = module.origin.as_deref().unwrap_or(label);
+        self.check_duplicate_resource(context, label, origin, &python_resource)?;
+
         self.exe
             .add_python_extension_module(&module.inner, module.add_collection_context().clone())
             .map_err(|e| {
@@ -512,6 +1575,9 @@ impl PythonExecutable {
                 })
             })?;
 
+        self.exe
+            .set_resource_origin(&python_resource.full_name(), origin);
+
         Ok(Value::new(NoneType::None))
     }
 
@@ -571,383 +1637,3719 @@ impl PythonExecutable {
         Ok(Value::new(NoneType::None))
     }
 
-    /// PythonExecutable.to_embedded_resources()
-    pub fn starlark_to_embedded_resources(&self) -> ValueResult {
-        Ok(Value::new(PythonEmbeddedResources {
-            exe: self.exe.clone_box(),
-        }))
-    }
-
-    /// PythonExecutable.filter_resources_from_files(files=None, glob_files=None)
-    pub fn starlark_filter_resources_from_files(
+    /// PythonExecutable.add_extension_module_from_file(name, path)
+    pub fn starlark_add_extension_module_from_file(
         &mut self,
         type_values: &TypeValues,
-        files: &Value,
-        glob_files: &Value,
+        call_stack: &mut CallStack,
+        name: &Value,
+        path: &Value,
     ) -> ValueResult {
-        optional_list_arg("files", "string", &files)?;
-        optional_list_arg("glob_files", "string", &glob_files)?;
+        let name = required_str_arg("name", &name)?;
+        let path = required_str_arg("path", &path)?;
 
-        let files = match files.get_type() {
-            "list" => files
-                .iter()?
-                .iter()
-                .map(|x| PathBuf::from(x.to_string()))
-                .collect(),
-            "NoneType" => Vec::new(),
-            _ => panic!("type should have been validated above"),
-        };
+        let raw_context = get_context(type_values)?;
+        let context = raw_context
+            .downcast_ref::<EnvironmentContext>()
+            .ok_or(ValueError::IncorrectParameterType)?;
 
-        let glob_files = match glob_files.get_type() {
-            "list" => glob_files.iter()?.iter().map(|x| x.to_string()).collect(),
-            "NoneType" => Vec::new(),
-            _ => panic!("type should have been validated above"),
+        let path = context.cwd.join(path);
+
+        let module = self
+            .exe
+            .read_extension_module_file(&context.logger, &name, &path)
+            .map_err(|e| {
+                ValueError::from(RuntimeError {
+                    code: "PYOXIDIZER_BUILD",
+                    message: format!("{:?}", e),
+                    label: "add_extension_module_from_file()".to_string(),
+                })
+            })?;
+
+        let mut module = PythonExtensionModuleValue::new(module);
+        module.origin = Some(path.display().to_string());
+        self.python_packaging_policy()
+            .apply_to_resource(type_values, call_stack, &mut module)?;
+
+        self.add_python_extension_module(
+            context.deref(),
+            "add_extension_module_from_file()",
+            &module,
+        )
+    }
+
+    /// PythonExecutable.add_file_data(path, data, executable=False)
+    pub fn starlark_add_file_data(
+        &mut self,
+        type_values: &TypeValues,
+        path: &Value,
+        data: &Value,
+        executable: &Value,
+    ) -> ValueResult {
+        let path = required_str_arg("path", &path)?;
+        let executable = required_bool_arg("executable", &executable)?;
+
+        // The Starlark dialect we use doesn't have a bytes type. So data is
+        // expressed as a string.
+        let data = match data.get_type() {
+            "string" => data.to_string().into_bytes(),
+            t => {
+                return Err(ValueError::from(RuntimeError {
+                    code: INCORRECT_PARAMETER_TYPE_ERROR_CODE,
+                    message: format!("data must be a string; got {}", t),
+                    label: "add_file_data()".to_string(),
+                }))
+            }
         };
 
-        let files_refs = files.iter().map(|x| x.as_ref()).collect::<Vec<&Path>>();
-        let glob_files_refs = glob_files.iter().map(|x| x.as_ref()).collect::<Vec<&str>>();
+        let raw_context = get_context(type_values)?;
+        let context = raw_context
+            .downcast_ref::<EnvironmentContext>()
+            .ok_or(ValueError::IncorrectParameterType)?;
+
+        let file = FileData {
+            path: PathBuf::from(path),
+            data: DataLocation::Memory(data),
+            executable,
+        };
+
+        info!(&context.logger, "adding file data {}", file.path_string());
+        let origin = context.config_path.display().to_string();
+        self.check_duplicate_resource(&context, "add_file_data()", &origin, &(&file).into())?;
+
+        self.exe.add_file_data(&file, None).map_err(|e| {
+            ValueError::from(RuntimeError {
+                code: "PYOXIDIZER_BUILD",
+                message: e.to_string(),
+                label: "add_file_data()".to_string(),
+            })
+        })?;
+
+        self.exe.set_resource_origin(&file.path_string(), &origin);
+
+        Ok(Value::new(NoneType::None))
+    }
+
+    /// PythonExecutable.add_metadata_resource(name, data)
+    pub fn starlark_add_metadata_resource(
+        &mut self,
+        type_values: &TypeValues,
+        name: &Value,
+        data: &Value,
+    ) -> ValueResult {
+        let name = required_str_arg("name", &name)?;
+        // There is no bytes type in our Starlark dialect.
+        let data = required_str_arg("data", &data)?;
+
+        let mut components = Path::new(&name).components();
+
+        if !matches!(
+            (components.next(), components.next()),
+            (Some(Component::Normal(_)), None)
+        ) {
+            return Err(ValueError::from(RuntimeError {
+                code: INCORRECT_PARAMETER_TYPE_ERROR_CODE,
+                message: format!(
+                    "metadata resource name must be a plain file name; got {}",
+                    name
+                ),
+                label: "add_metadata_resource()".to_string(),
+            }));
+        }
 
         let raw_context = get_context(type_values)?;
         let context = raw_context
             .downcast_ref::<EnvironmentContext>()
             .ok_or(ValueError::IncorrectParameterType)?;
 
+        // Resources are only reachable through importlib if their package is
+        // importable.
+        let package = PythonModuleSource {
+            name: METADATA_RESOURCES_PACKAGE.to_string(),
+            source: DataLocation::Memory(vec![]),
+            is_package: true,
+            cache_tag: self.exe.cache_tag().to_string(),
+            is_stdlib: false,
+            is_test: false,
+        };
+
+        let resource = PythonPackageResource {
+            leaf_package: METADATA_RESOURCES_PACKAGE.to_string(),
+            relative_name: name,
+            data: DataLocation::Memory(data.into_bytes()),
+            is_stdlib: false,
+            is_test: false,
+        };
+
+        info!(
+            &context.logger,
+            "adding metadata resource {}",
+            resource.symbolic_name()
+        );
+        let python_resource: PythonResource = (&resource).into();
+        let origin = context.config_path.display().to_string();
+        self.check_duplicate_resource(
+            &context,
+            "add_metadata_resource()",
+            &origin,
+            &python_resource,
+        )?;
+
+        let policy = self.exe.python_packaging_policy();
+        let mut package_context = policy.derive_add_collection_context(&(&package).into());
+        package_context.include = true;
+        let mut resource_context = policy.derive_add_collection_context(&(&resource).into());
+        resource_context.include = true;
+
         self.exe
-            .filter_resources_from_files(&context.logger, &files_refs, &glob_files_refs)
+            .add_python_module_source(&package, Some(package_context))
+            .and_then(|_| {
+                self.exe
+                    .add_python_package_resource(&resource, Some(resource_context))
+            })
             .map_err(|e| {
                 ValueError::from(RuntimeError {
-                    code: "RUNTIME_ERROR",
+                    code: "PYOXIDIZER_BUILD",
                     message: e.to_string(),
-                    label: "filter_from_files()".to_string(),
+                    label: "add_metadata_resource()".to_string(),
                 })
             })?;
 
+        self.exe
+            .set_resource_origin(METADATA_RESOURCES_PACKAGE, &origin);
+        self.exe
+            .set_resource_origin(&python_resource.full_name(), &origin);
+
         Ok(Value::new(NoneType::None))
     }
-}
 
-starlark_module! { python_executable_env =>
-    #[allow(non_snake_case, clippy::ptr_arg)]
-    PythonExecutable.make_python_module_source(
-        env env,
-        call_stack cs,
-        this,
-        name,
-        source,
-        is_package=false
-    ) {
-        match this.clone().downcast_ref::<PythonExecutable>() {
-            Some(exe) => exe.starlark_make_python_module_source(&env, cs, &name, &source, &is_package),
-            None => Err(ValueError::IncorrectParameterType),
+    /// PythonExecutable.dry_run()
+    pub fn starlark_dry_run(&self, type_values: &TypeValues) -> ValueResult {
+        let raw_context = get_context(type_values)?;
+        let context = raw_context
+            .downcast_ref::<EnvironmentContext>()
+            .ok_or(ValueError::IncorrectParameterType)?;
+
+        let estimate = self
+            .exe
+            .to_embedded_python_context(&context.logger, &context.build_opt_level)
+            .map_err(|e| {
+                ValueError::from(RuntimeError {
+                    code: "PYOXIDIZER_BUILD",
+                    message: format!("{:?}", e),
+                    label: "dry_run()".to_string(),
+                })
+            })?
+            .size_estimate();
+
+        info!(
+            &context.logger,
+            "dry run of {}: {} resources; estimated executable size {} bytes; {} extra files totaling {} bytes",
+            self.exe.name(),
+            estimate.resource_count,
+            estimate.executable_size(),
+            estimate.extra_files_count,
+            estimate.extra_files_size
+        );
+
+        let mut summary = LinkedHashMap::new();
+        for (key, value) in &[
+            ("resource_count", estimate.resource_count as i64),
+            ("resources_size", estimate.resources_size as i64),
+            ("libpython_size", estimate.libpython_size as i64),
+            ("extra_files_count", estimate.extra_files_count as i64),
+            ("extra_files_size", estimate.extra_files_size as i64),
+            ("executable_size", estimate.executable_size() as i64),
+            ("total_size", estimate.total_size() as i64),
+        ] {
+            summary.insert(key.to_string(), Value::from(*value));
         }
+
+        Value::try_from(summary)
     }
 
-    #[allow(non_snake_case, clippy::ptr_arg)]
-    PythonExecutable.pip_download(
-        env env,
-        call_stack cs,
-        this,
-        args
-    ) {
-        match this.clone().downcast_ref::<PythonExecutable>() {
-            Some(exe) => exe.starlark_pip_download(&env, cs, &args),
-            None => Err(ValueError::IncorrectParameterType),
+    /// PythonExecutable.resource_stats()
+    pub fn starlark_resource_stats(&self) -> ValueResult {
+        let stats = binary_resource_stats(self.exe.deref()).map_err(|e| {
+            ValueError::from(RuntimeError {
+                code: "PYOXIDIZER_BUILD",
+                message: format!("{:?}", e),
+                label: "resource_stats()".to_string(),
+            })
+        })?;
+
+        let mut summary = LinkedHashMap::new();
+        for (key, value) in &[
+            ("module_sources", stats.module_sources as i64),
+            ("module_bytecodes", stats.module_bytecodes as i64),
+            ("extension_modules", stats.extension_modules as i64),
+            ("frozen_modules", stats.frozen_modules as i64),
+            ("package_resources", stats.package_resources as i64),
+            (
+                "distribution_resources",
+                stats.distribution_resources as i64,
+            ),
+            ("shared_libraries", stats.shared_libraries as i64),
+            ("file_data", stats.file_data as i64),
+            ("total_bytes", stats.total_bytes as i64),
+        ] {
+            summary.insert(key.to_string(), Value::from(*value));
         }
+
+        Value::try_from(summary)
     }
 
-    #[allow(non_snake_case, clippy::ptr_arg)]
-    PythonExecutable.pip_install(
-        env env,
-        call_stack cs,
-        this,
-        args,
-        extra_envs=NoneType::None
-    ) {
-        match this.clone().downcast_ref::<PythonExecutable>() {
-            Some(exe) => exe.starlark_pip_install(&env, cs, &args, &extra_envs),
-            None => Err(ValueError::IncorrectParameterType),
+    /// PythonExecutable.lint()
+    pub fn starlark_lint(&self, type_values: &TypeValues) -> ValueResult {
+        let raw_context = get_context(type_values)?;
+        let context = raw_context
+            .downcast_ref::<EnvironmentContext>()
+            .ok_or(ValueError::IncorrectParameterType)?;
+
+        let findings = lint_resources(self.exe.deref()).map_err(|e| {
+            ValueError::from(RuntimeError {
+                code: "PYOXIDIZER_BUILD",
+                message: format!("{:?}", e),
+                label: "lint()".to_string(),
+            })
+        })?;
+
+        let mut values = vec![];
+
+        for finding in findings {
+            warn!(
+                &context.logger,
+                "{}: {}: {}",
+                finding.severity.to_string(),
+                finding.resource,
+                finding.message
+            );
+
+            let mut value = LinkedHashMap::new();
+            value.insert(
+                "severity".to_string(),
+                Value::from(finding.severity.to_string()),
+            );
+            value.insert("resource".to_string(), Value::from(finding.resource));
+            value.insert("message".to_string(), Value::from(finding.message));
+
+            values.push(Value::try_from(value)?);
         }
+
+        Ok(Value::from(values))
     }
 
-    #[allow(non_snake_case, clippy::ptr_arg)]
-    PythonExecutable.read_package_root(
-        env env,
-        call_stack cs,
-        this,
-        path,
-        packages
-    ) {
-        match this.clone().downcast_ref::<PythonExecutable>() {
-            Some(exe) => exe.starlark_read_package_root(&env, cs, &path, &packages),
-            None => Err(ValueError::IncorrectParameterType),
-        }
+    /// PythonExecutable.to_embedded_resources()
+    pub fn starlark_to_embedded_resources(&self) -> ValueResult {
+        Ok(Value::new(PythonEmbeddedResources {
+            exe: self.exe.clone_box(),
+        }))
     }
 
-    #[allow(non_snake_case, clippy::ptr_arg)]
-    PythonExecutabvle.read_virtualenv(
-        env env,
-        call_stack cs,
-        this,
-        path
-    ) {
-        match this.clone().downcast_ref::<PythonExecutable>() {
-            Some(exe) => exe.starlark_read_virtualenv(&env, cs, &path),
-            None => Err(ValueError::IncorrectParameterType),
-        }
+    /// PythonExecutable.extension_module_link_report()
+    pub fn starlark_extension_module_link_report(&self) -> ValueResult {
+        Ok(Value::from(
+            self.exe
+                .extension_module_link_modes()
+                .into_iter()
+                .map(|(name, mode)| Value::from((name, mode.to_string())))
+                .collect::<Vec<_>>(),
+        ))
     }
 
-    #[allow(non_snake_case, clippy::ptr_arg)]
-    PythonExecutable.setup_py_install(
-        env env,
-        call_stack cs,
-        this,
-        package_path,
-        extra_envs=NoneType::None,
-        extra_global_arguments=NoneType::None
-    ) {
-        match this.clone().downcast_ref::<PythonExecutable>() {
-            Some(exe) => exe.starlark_setup_py_install(&env, cs, &package_path, &extra_envs, &extra_global_arguments),
-            None => Err(ValueError::IncorrectParameterType),
+    /// PythonExecutable.to_tarball(path_prefix=None)
+    pub fn starlark_to_tarball(&self, path_prefix: &Value) -> ValueResult {
+        let path_prefix = optional_str_arg("path_prefix", &path_prefix)?;
+
+        if let Some(prefix) = &path_prefix {
+            if Path::new(prefix).is_absolute() {
+                return Err(ValueError::from(RuntimeError {
+                    code: INCORRECT_PARAMETER_TYPE_ERROR_CODE,
+                    message: format!("path_prefix must be a relative path; got {}", prefix),
+                    label: "to_tarball()".to_string(),
+                }));
+            }
         }
+
+        Ok(Value::new(PythonExecutableTarball {
+            exe: self.clone(),
+            path_prefix,
+        }))
     }
 
-    #[allow(non_snake_case, clippy::ptr_arg)]
-    PythonExecutable.add_python_resource(
-        env env,
-        this,
-        resource
-    ) {
-        match this.clone().downcast_mut::<PythonExecutable>()? {
-            Some(mut exe) => exe.starlark_add_python_resource(
-                &env,
-                &resource,
-                "add_python_resource",
-            ),
-            None => Err(ValueError::IncorrectParameterType),
+    /// PythonExecutable.to_resources_bundle(filename=None)
+    pub fn starlark_to_resources_bundle(&self, filename: &Value) -> ValueResult {
+        let filename = optional_str_arg("filename", &filename)?
+            .unwrap_or_else(|| format!("{}.pyoxidizer", self.exe.name()));
+
+        let mut components = Path::new(&filename).components();
+
+        if !matches!(
+            (components.next(), components.next()),
+            (Some(Component::Normal(_)), None)
+        ) {
+            return Err(ValueError::from(RuntimeError {
+                code: INCORRECT_PARAMETER_TYPE_ERROR_CODE,
+                message: format!("filename must be a plain file name; got {}", filename),
+                label: "to_resources_bundle()".to_string(),
+            }));
         }
+
+        let mut exe = self.clone();
+        exe.exe
+            .set_resources_bundle_filename(Some(filename.clone()));
+
+        Ok(Value::new(PythonResourcesBundle { exe, filename }))
     }
 
-    #[allow(non_snake_case, clippy::ptr_arg)]
-    PythonExecutable.add_python_resources(
-        env env,
-        this,
-        resources
-    ) {
-        match this.clone().downcast_mut::<PythonExecutable>()? {
-            Some(mut exe) => exe.starlark_add_python_resources(
-                &env,
-                &resources,
-            ),
-            None => Err(ValueError::IncorrectParameterType),
-        }
+    /// PythonExecutable.write_resource_manifest(path)
+    pub fn starlark_write_resource_manifest(
+        &self,
+        type_values: &TypeValues,
+        path: &Value,
+    ) -> ValueResult {
+        let path = required_str_arg("path", &path)?;
+
+        let raw_context = get_context(type_values)?;
+        let context = raw_context
+            .downcast_ref::<EnvironmentContext>()
+            .ok_or(ValueError::IncorrectParameterType)?;
+
+        let path = context.cwd.join(path);
+
+        let write = || -> Result<usize> {
+            let manifest = resource_manifest(self.exe.deref())?;
+
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent)
+                    .with_context(|| format!("creating directory {}", parent.display()))?;
+            }
+
+            let fh = std::fs::File::create(&path)
+                .with_context(|| format!("creating {}", path.display()))?;
+            serde_json::to_writer_pretty(fh, &manifest)?;
+
+            Ok(manifest.len())
+        };
+
+        let count = write().map_err(|e| {
+            ValueError::from(RuntimeError {
+                code: "PYOXIDIZER_BUILD",
+                message: format!("{:?}", e),
+                label: "write_resource_manifest()".to_string(),
+            })
+        })?;
+
+        info!(
+            &context.logger,
+            "wrote manifest of {} resources to {}",
+            count,
+            path.display()
+        );
+
+        Ok(Value::new(NoneType::None))
+    }
+
+    /// PythonExecutable.write_installed_requirements(path)
+    pub fn starlark_write_installed_requirements(
+        &self,
+        type_values: &TypeValues,
+        path: &Value,
+    ) -> ValueResult {
+        let path = required_str_arg("path", &path)?;
+
+        let raw_context = get_context(type_values)?;
+        let context = raw_context
+            .downcast_ref::<EnvironmentContext>()
+            .ok_or(ValueError::IncorrectParameterType)?;
+
+        let path = context.cwd.join(path);
+
+        let write = || -> Result<usize> {
+            let requirements = installed_requirements(self.exe.deref())?;
+
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent)
+                    .with_context(|| format!("creating directory {}", parent.display()))?;
+            }
+
+            let content = requirements
+                .iter()
+                .map(|(name, version)| format!("{}=={}\n", name, version))
+                .collect::<String>();
+
+            std::fs::write(&path, content)
+                .with_context(|| format!("writing {}", path.display()))?;
+
+            Ok(requirements.len())
+        };
+
+        let count = write().map_err(|e| {
+            ValueError::from(RuntimeError {
+                code: "PYOXIDIZER_BUILD",
+                message: format!("{:?}", e),
+                label: "write_installed_requirements()".to_string(),
+            })
+        })?;
+
+        info!(
+            &context.logger,
+            "wrote {} installed requirements to {}",
+            count,
+            path.display()
+        );
+
+        Ok(Value::new(NoneType::None))
+    }
+
+    /// PythonExecutable.register_post_build_callback(func)
+    pub fn starlark_register_post_build_callback(&mut self, func: &Value) -> ValueResult {
+        required_type_arg("func", "function", func)?;
+
+        self.post_build_callbacks.push(func.clone());
+
+        Ok(Value::new(NoneType::None))
+    }
+
+    /// PythonExecutable.set_overwrite_protection(enabled=True, force=False)
+    pub fn starlark_set_overwrite_protection(
+        &mut self,
+        enabled: &Value,
+        force: &Value,
+    ) -> ValueResult {
+        self.overwrite_protection = required_bool_arg("enabled", enabled)?;
+        self.force_overwrite = required_bool_arg("force", force)?;
+
+        Ok(Value::new(NoneType::None))
+    }
+
+    /// PythonExecutable.set_macos_signing(identity, entitlements_path=None, hardened_runtime=True)
+    pub fn starlark_set_macos_signing(
+        &mut self,
+        type_values: &TypeValues,
+        identity: &Value,
+        entitlements_path: &Value,
+        hardened_runtime: &Value,
+    ) -> ValueResult {
+        let identity = required_str_arg("identity", &identity)?;
+        let entitlements_path = optional_str_arg("entitlements_path", &entitlements_path)?;
+        let hardened_runtime = required_bool_arg("hardened_runtime", &hardened_runtime)?;
+
+        if identity.is_empty() {
+            return Err(ValueError::from(RuntimeError {
+                code: INCORRECT_PARAMETER_TYPE_ERROR_CODE,
+                message: "identity must not be empty".to_string(),
+                label: "set_macos_signing()".to_string(),
+            }));
+        }
+
+        let raw_context = get_context(type_values)?;
+        let context = raw_context
+            .downcast_ref::<EnvironmentContext>()
+            .ok_or(ValueError::IncorrectParameterType)?;
+
+        self.macos_signing = Some(MacOsSigningConfig {
+            identity,
+            entitlements_path: entitlements_path.map(|path| context.cwd.join(path)),
+            hardened_runtime,
+        });
+
+        Ok(Value::new(NoneType::None))
+    }
+
+    /// PythonExecutable.set_windows_metadata(file_version=None, product_name=None, icon_path=None)
+    pub fn starlark_set_windows_metadata(
+        &mut self,
+        type_values: &TypeValues,
+        file_version: &Value,
+        product_name: &Value,
+        icon_path: &Value,
+    ) -> ValueResult {
+        let file_version = optional_str_arg("file_version", &file_version)?;
+        let product_name = optional_str_arg("product_name", &product_name)?;
+        let icon_path = optional_str_arg("icon_path", &icon_path)?;
+
+        if let Some(version) = &file_version {
+            parse_windows_version(version).map_err(|e| {
+                ValueError::from(RuntimeError {
+                    code: INCORRECT_PARAMETER_TYPE_ERROR_CODE,
+                    message: format!("{}", e),
+                    label: "set_windows_metadata()".to_string(),
+                })
+            })?;
+        }
+
+        let raw_context = get_context(type_values)?;
+        let context = raw_context
+            .downcast_ref::<EnvironmentContext>()
+            .ok_or(ValueError::IncorrectParameterType)?;
+
+        let icon_path = icon_path.map(|path| context.cwd.join(path));
+
+        if let Some(path) = &icon_path {
+            if !path.exists() {
+                return Err(ValueError::from(RuntimeError {
+                    code: INCORRECT_PARAMETER_TYPE_ERROR_CODE,
+                    message: format!("icon file {} does not exist", path.display()),
+                    label: "set_windows_metadata()".to_string(),
+                }));
+            }
+        }
+
+        self.exe
+            .set_windows_resource_metadata(WindowsResourceMetadata {
+                file_version,
+                product_name,
+                icon_path,
+            });
+
+        Ok(Value::new(NoneType::None))
+    }
+
+    /// PythonExecutable.set_build_environment_variable(name, value)
+    pub fn starlark_set_build_environment_variable(
+        &mut self,
+        name: &Value,
+        value: &Value,
+    ) -> ValueResult {
+        let name = required_env_name_arg("name", name)?;
+        let value = required_str_arg("value", value)?;
+
+        self.exe.set_build_environment_variable(&name, &value);
+
+        Ok(Value::new(NoneType::None))
+    }
+
+    /// PythonExecutable.set_packed_resources_compression(level)
+    pub fn starlark_set_packed_resources_compression(&mut self, level: &Value) -> ValueResult {
+        let level = match level.get_type() {
+            "NoneType" => None,
+            "int" => {
+                let level = level.to_int()?;
+
+                let levels = PACKED_RESOURCES_COMPRESSION_LEVELS;
+
+                if level < i64::from(*levels.start()) || level > i64::from(*levels.end()) {
+                    return Err(ValueError::from(RuntimeError {
+                        code: INCORRECT_PARAMETER_TYPE_ERROR_CODE,
+                        message: format!(
+                            "compression level must be between {} and {}; got {}",
+                            levels.start(),
+                            levels.end(),
+                            level
+                        ),
+                        label: "set_packed_resources_compression()".to_string(),
+                    }));
+                }
+
+                Some(level as i32)
+            }
+            t => {
+                return Err(ValueError::from(RuntimeError {
+                    code: INCORRECT_PARAMETER_TYPE_ERROR_CODE,
+                    message: format!("level must be an int or None; got {}", t),
+                    label: "set_packed_resources_compression()".to_string(),
+                }))
+            }
+        };
+
+        self.exe.set_packed_resources_compression_level(level);
+
+        Ok(Value::new(NoneType::None))
+    }
+
+    /// PythonExecutable.add_frozen_module(name, source, is_package=False)
+    pub fn starlark_add_frozen_module(
+        &mut self,
+        name: &Value,
+        source: &Value,
+        is_package: &Value,
+    ) -> ValueResult {
+        let name = required_str_arg("name", name)?;
+        let source = required_str_arg("source", source)?;
+        let is_package = optional_bool_arg("is_package", is_package)?.unwrap_or(false);
+
+        if !is_valid_module_name(&name) {
+            return Err(ValueError::from(RuntimeError {
+                code: INCORRECT_PARAMETER_TYPE_ERROR_CODE,
+                message: format!(
+                    "invalid module name {}; must be dot delimited Python identifiers",
+                    name
+                ),
+                label: "add_frozen_module()".to_string(),
+            }));
+        }
+
+        self.exe
+            .add_frozen_module(&name, source.as_bytes(), is_package)
+            .map_err(|e| {
+                ValueError::from(RuntimeError {
+                    code: "PYOXIDIZER_BUILD",
+                    message: format!("{:#}", e),
+                    label: "add_frozen_module()".to_string(),
+                })
+            })?;
+
+        Ok(Value::new(NoneType::None))
+    }
+
+    /// PythonExecutable.replace_module_source(name, new_source)
+    pub fn starlark_replace_module_source(
+        &mut self,
+        name: &Value,
+        new_source: &Value,
+    ) -> ValueResult {
+        let name = required_str_arg("name", name)?;
+        let new_source = required_str_arg("new_source", new_source)?;
+
+        self.exe
+            .replace_module_source(&name, new_source.as_bytes())
+            .map_err(|e| {
+                ValueError::from(RuntimeError {
+                    code: "PYOXIDIZER_BUILD",
+                    message: format!("{:#}", e),
+                    label: "replace_module_source()".to_string(),
+                })
+            })?;
+
+        Ok(Value::new(NoneType::None))
+    }
+
+    /// PythonExecutable.set_run_command_from_entry_point(distribution, name)
+    pub fn starlark_set_run_command_from_entry_point(
+        &mut self,
+        distribution: &Value,
+        name: &Value,
+    ) -> ValueResult {
+        let distribution = required_str_arg("distribution", distribution)?;
+        let name = required_str_arg("name", name)?;
+
+        let error = |message: String| {
+            ValueError::from(RuntimeError {
+                code: "PYOXIDIZER_BUILD",
+                message,
+                label: "set_run_command_from_entry_point()".to_string(),
+            })
+        };
+
+        let entry_point = console_script_entry_point(self.exe.deref(), &distribution, &name)
+            .map_err(|e| error(format!("{:#}", e)))?;
+
+        let attr = match &entry_point.attr {
+            Some(attr)
+                if is_valid_module_name(&entry_point.module) && is_valid_module_name(attr) =>
+            {
+                attr
+            }
+            _ => {
+                return Err(error(format!(
+                    "entry point {} of {} does not reference a callable as module:attr",
+                    name, distribution
+                )))
+            }
+        };
+
+        // Console scripts call the referenced callable and exit with its return value.
+        let code = format!(
+            "import sys; from {} import {}; sys.exit({}())",
+            entry_point.module,
+            attr.split('.').next().unwrap(),
+            attr
+        );
+
+        self.exe.set_run_mode(PythonRunMode::Eval { code });
+
+        Ok(Value::new(NoneType::None))
+    }
+
+    /// PythonExecutable.filter_resources_from_files(files=None, glob_files=None, regexes=None)
+    pub fn starlark_filter_resources_from_files(
+        &mut self,
+        type_values: &TypeValues,
+        files: &Value,
+        glob_files: &Value,
+        regexes: &Value,
+    ) -> ValueResult {
+        optional_list_arg("files", "string", &files)?;
+        optional_list_arg("glob_files", "string", &glob_files)?;
+        optional_list_arg("regexes", "string", &regexes)?;
+
+        let files = match files.get_type() {
+            "list" => files
+                .iter()?
+                .iter()
+                .map(|x| PathBuf::from(x.to_string()))
+                .collect(),
+            "NoneType" => Vec::new(),
+            _ => panic!("type should have been validated above"),
+        };
+
+        let glob_files = match glob_files.get_type() {
+            "list" => glob_files.iter()?.iter().map(|x| x.to_string()).collect(),
+            "NoneType" => Vec::new(),
+            _ => panic!("type should have been validated above"),
+        };
+
+        let regexes = match regexes.get_type() {
+            "list" => regexes
+                .iter()?
+                .iter()
+                .map(|x| {
+                    let pattern = x.to_string();
+
+                    Regex::new(&pattern).map_err(|e| {
+                        ValueError::from(RuntimeError {
+                            code: INCORRECT_PARAMETER_TYPE_ERROR_CODE,
+                            message: format!("invalid regular expression {}: {}", pattern, e),
+                            label: "filter_resources_from_files()".to_string(),
+                        })
+                    })
+                })
+                .collect::<Result<Vec<_>, _>>()?,
+            "NoneType" => Vec::new(),
+            _ => panic!("type should have been validated above"),
+        };
+
+        let files_refs = files.iter().map(|x| x.as_ref()).collect::<Vec<&Path>>();
+        let glob_files_refs = glob_files.iter().map(|x| x.as_ref()).collect::<Vec<&str>>();
+
+        let raw_context = get_context(type_values)?;
+        let context = raw_context
+            .downcast_ref::<EnvironmentContext>()
+            .ok_or(ValueError::IncorrectParameterType)?;
+
+        self.exe
+            .filter_resources_from_files(&context.logger, &files_refs, &glob_files_refs, &regexes)
+            .map_err(|e| {
+                ValueError::from(RuntimeError {
+                    code: "RUNTIME_ERROR",
+                    message: e.to_string(),
+                    label: "filter_from_files()".to_string(),
+                })
+            })?;
+
+        Ok(Value::new(NoneType::None))
+    }
+
+    /// PythonExecutable.drop_test_packages(package_names=None)
+    pub fn starlark_drop_test_packages(
+        &mut self,
+        type_values: &TypeValues,
+        package_names: &Value,
+    ) -> ValueResult {
+        optional_list_arg("package_names", "string", &package_names)?;
+
+        let package_names = match package_names.get_type() {
+            "list" => package_names
+                .iter()?
+                .iter()
+                .map(|x| x.to_string())
+                .collect(),
+            "NoneType" => vec!["test".to_string(), "tests".to_string()],
+            _ => panic!("type should have been validated above"),
+        };
+
+        let raw_context = get_context(type_values)?;
+        let context = raw_context
+            .downcast_ref::<EnvironmentContext>()
+            .ok_or(ValueError::IncorrectParameterType)?;
+
+        let names = test_package_resource_names(self.exe.deref(), &package_names);
+
+        self.exe
+            .remove_resources(&context.logger, &names)
+            .map_err(|e| {
+                ValueError::from(RuntimeError {
+                    code: "PYOXIDIZER_BUILD",
+                    message: e.to_string(),
+                    label: "drop_test_packages()".to_string(),
+                })
+            })?;
+
+        warn!(
+            &context.logger,
+            "removed {} resources belonging to test packages",
+            names.len()
+        );
+
+        Ok(Value::from(names.len() as i64))
+    }
+
+    /// PythonExecutable.prune_unused_stdlib(entry_modules)
+    pub fn starlark_prune_unused_stdlib(
+        &mut self,
+        type_values: &TypeValues,
+        entry_modules: &Value,
+    ) -> ValueResult {
+        required_list_arg("entry_modules", "string", &entry_modules)?;
+
+        let entry_modules = entry_modules
+            .iter()?
+            .iter()
+            .map(|x| x.to_string())
+            .collect::<Vec<_>>();
+
+        let raw_context = get_context(type_values)?;
+        let context = raw_context
+            .downcast_ref::<EnvironmentContext>()
+            .ok_or(ValueError::IncorrectParameterType)?;
+
+        let prune = |exe: &mut Box<dyn PythonBinaryBuilder>| -> Result<(BTreeSet<String>, u64)> {
+            let names = unreachable_stdlib_modules(exe.as_ref(), &entry_modules)?;
+
+            let bytes_saved = resource_manifest(exe.as_ref())?
+                .iter()
+                .filter(|entry| names.contains(&entry.name))
+                .map(|entry| entry.size)
+                .sum();
+
+            exe.remove_resources(&context.logger, &names)?;
+
+            Ok((names, bytes_saved))
+        };
+
+        let (names, bytes_saved) = prune(&mut self.exe).map_err(|e| {
+            ValueError::from(RuntimeError {
+                code: "PYOXIDIZER_BUILD",
+                message: format!("{:?}", e),
+                label: "prune_unused_stdlib()".to_string(),
+            })
+        })?;
+
+        warn!(
+            &context.logger,
+            "removed {} unused standard library modules saving {} bytes",
+            names.len(),
+            bytes_saved
+        );
+
+        let mut value = LinkedHashMap::new();
+        value.insert(
+            "removed".to_string(),
+            Value::from(names.into_iter().map(Value::from).collect::<Vec<_>>()),
+        );
+        value.insert("bytes_saved".to_string(), Value::from(bytes_saved as i64));
+
+        Value::try_from(value)
+    }
+}
+
+starlark_module! { python_executable_env =>
+    #[allow(non_snake_case, clippy::ptr_arg)]
+    PythonExecutable.make_python_module_source(
+        env env,
+        call_stack cs,
+        this,
+        name,
+        source,
+        is_package=false,
+        cache_tag=NoneType::None
+    ) {
+        match this.clone().downcast_ref::<PythonExecutable>() {
+            Some(exe) => exe.starlark_make_python_module_source(&env, cs, &name, &source, &is_package, &cache_tag),
+            None => Err(ValueError::IncorrectParameterType),
+        }
+    }
+
+    #[allow(non_snake_case, clippy::ptr_arg)]
+    PythonExecutable.pip_download(
+        env env,
+        call_stack cs,
+        this,
+        args,
+        platform=NoneType::None,
+        abi=NoneType::None,
+        python_version=NoneType::None,
+        implementation=NoneType::None,
+        only_binary=true
+    ) {
+        match this.clone().downcast_ref::<PythonExecutable>() {
+            Some(exe) => exe.starlark_pip_download(
+                &env,
+                cs,
+                &args,
+                &platform,
+                &abi,
+                &python_version,
+                &implementation,
+                &only_binary,
+            ),
+            None => Err(ValueError::IncorrectParameterType),
+        }
+    }
+
+    #[allow(non_snake_case, clippy::ptr_arg)]
+    PythonExecutable.pip_install(
+        env env,
+        call_stack cs,
+        this,
+        args,
+        extra_envs=NoneType::None,
+        find_links=NoneType::None
+    ) {
+        match this.clone().downcast_ref::<PythonExecutable>() {
+            Some(exe) => exe.starlark_pip_install(&env, cs, &args, &extra_envs, &find_links),
+            None => Err(ValueError::IncorrectParameterType),
+        }
+    }
+
+    #[allow(non_snake_case, clippy::ptr_arg)]
+    PythonExecutable.pip_install_editable(
+        env env,
+        call_stack cs,
+        this,
+        package_path,
+        extra_envs=NoneType::None
+    ) {
+        match this.clone().downcast_mut::<PythonExecutable>()? {
+            Some(mut exe) => exe.starlark_pip_install_editable(&env, cs, &package_path, &extra_envs),
+            None => Err(ValueError::IncorrectParameterType),
+        }
+    }
+
+    PythonExecutable.pipenv_install(
+        env env,
+        call_stack cs,
+        this,
+        project_path,
+        dev=NoneType::None,
+        extra_envs=NoneType::None
+    ) {
+        match this.clone().downcast_ref::<PythonExecutable>() {
+            Some(exe) => exe.starlark_pipenv_install(&env, cs, &project_path, &dev, &extra_envs),
+            None => Err(ValueError::IncorrectParameterType),
+        }
+    }
+
+    #[allow(non_snake_case, clippy::ptr_arg)]
+    PythonExecutable.read_package_root(
+        env env,
+        call_stack cs,
+        this,
+        path,
+        packages,
+        namespace_packages=NoneType::None
+    ) {
+        match this.clone().downcast_ref::<PythonExecutable>() {
+            Some(exe) => exe.starlark_read_package_root(&env, cs, &path, &packages, &namespace_packages),
+            None => Err(ValueError::IncorrectParameterType),
+        }
+    }
+
+    #[allow(non_snake_case, clippy::ptr_arg)]
+    PythonExecutabvle.read_virtualenv(
+        env env,
+        call_stack cs,
+        this,
+        path
+    ) {
+        match this.clone().downcast_ref::<PythonExecutable>() {
+            Some(exe) => exe.starlark_read_virtualenv(&env, cs, &path),
+            None => Err(ValueError::IncorrectParameterType),
+        }
+    }
+
+    #[allow(non_snake_case, clippy::ptr_arg)]
+    PythonExecutable.read_site_packages(
+        env env,
+        call_stack cs,
+        this,
+        path
+    ) {
+        match this.clone().downcast_ref::<PythonExecutable>() {
+            Some(exe) => exe.starlark_read_site_packages(&env, cs, &path),
+            None => Err(ValueError::IncorrectParameterType),
+        }
+    }
+
+    #[allow(non_snake_case, clippy::ptr_arg)]
+    PythonExecutable.read_zip_file(
+        env env,
+        call_stack cs,
+        this,
+        path,
+        strip_prefix=NoneType::None
+    ) {
+        match this.clone().downcast_ref::<PythonExecutable>() {
+            Some(exe) => exe.starlark_read_zip_file(&env, cs, &path, &strip_prefix),
+            None => Err(ValueError::IncorrectParameterType),
+        }
+    }
+
+    #[allow(non_snake_case, clippy::ptr_arg)]
+    PythonExecutable.setup_py_install(
+        env env,
+        call_stack cs,
+        this,
+        package_path,
+        extra_envs=NoneType::None,
+        extra_global_arguments=NoneType::None
+    ) {
+        match this.clone().downcast_ref::<PythonExecutable>() {
+            Some(exe) => exe.starlark_setup_py_install(&env, cs, &package_path, &extra_envs, &extra_global_arguments),
+            None => Err(ValueError::IncorrectParameterType),
+        }
+    }
+
+    #[allow(non_snake_case, clippy::ptr_arg)]
+    PythonExecutable.add_python_resource(
+        env env,
+        this,
+        resource
+    ) {
+        match this.clone().downcast_mut::<PythonExecutable>()? {
+            Some(mut exe) => exe.starlark_add_python_resource(
+                &env,
+                &resource,
+                "add_python_resource",
+            ),
+            None => Err(ValueError::IncorrectParameterType),
+        }
+    }
+
+    #[allow(non_snake_case, clippy::ptr_arg)]
+    PythonExecutable.add_python_resources(
+        env env,
+        this,
+        resources
+    ) {
+        match this.clone().downcast_mut::<PythonExecutable>()? {
+            Some(mut exe) => exe.starlark_add_python_resources(
+                &env,
+                &resources,
+            ),
+            None => Err(ValueError::IncorrectParameterType),
+        }
+    }
+
+    #[allow(non_snake_case, clippy::ptr_arg)]
+    PythonExecutable.add_extension_module_from_file(
+        env env,
+        call_stack cs,
+        this,
+        name,
+        path
+    ) {
+        match this.clone().downcast_mut::<PythonExecutable>()? {
+            Some(mut exe) => exe.starlark_add_extension_module_from_file(&env, cs, &name, &path),
+            None => Err(ValueError::IncorrectParameterType),
+        }
+    }
+
+    #[allow(non_snake_case, clippy::ptr_arg)]
+    PythonExecutable.add_metadata_resource(env env, this, name, data) {
+        match this.clone().downcast_mut::<PythonExecutable>()? {
+            Some(mut exe) => exe.starlark_add_metadata_resource(&env, &name, &data),
+            None => Err(ValueError::IncorrectParameterType),
+        }
+    }
+
+    #[allow(clippy::ptr_arg)]
+    PythonExecutable.add_file_data(
+        env env,
+        this,
+        path,
+        data,
+        executable=false
+    ) {
+        match this.clone().downcast_mut::<PythonExecutable>()? {
+            Some(mut exe) => exe.starlark_add_file_data(&env, &path, &data, &executable),
+            None => Err(ValueError::IncorrectParameterType),
+        }
+    }
+
+    #[allow(clippy::ptr_arg)]
+    PythonExecutable.filter_resources_from_files(
+        env env,
+        this,
+        files=NoneType::None,
+        glob_files=NoneType::None,
+        regexes=NoneType::None)
+    {
+        match this.clone().downcast_mut::<PythonExecutable>()? {
+            Some(mut exe) => exe.starlark_filter_resources_from_files(&env, &files, &glob_files, &regexes),
+            None => Err(ValueError::IncorrectParameterType),
+        }
+    }
+
+    PythonExecutable.drop_test_packages(env env, this, package_names=NoneType::None) {
+        match this.clone().downcast_mut::<PythonExecutable>()? {
+            Some(mut exe) => exe.starlark_drop_test_packages(&env, &package_names),
+            None => Err(ValueError::IncorrectParameterType),
+        }
+    }
+
+    PythonExecutable.prune_unused_stdlib(env env, this, entry_modules) {
+        match this.clone().downcast_mut::<PythonExecutable>()? {
+            Some(mut exe) => exe.starlark_prune_unused_stdlib(&env, &entry_modules),
+            None => Err(ValueError::IncorrectParameterType),
+        }
+    }
+
+    PythonExecutable.register_post_build_callback(this, func) {
+        match this.clone().downcast_mut::<PythonExecutable>()? {
+            Some(mut exe) => exe.starlark_register_post_build_callback(&func),
+            None => Err(ValueError::IncorrectParameterType),
+        }
+    }
+
+    PythonExecutable.set_overwrite_protection(this, enabled=true, force=false) {
+        match this.clone().downcast_mut::<PythonExecutable>()? {
+            Some(mut exe) => exe.starlark_set_overwrite_protection(&enabled, &force),
+            None => Err(ValueError::IncorrectParameterType),
+        }
+    }
+
+    PythonExecutable.set_macos_signing(
+        env env,
+        this,
+        identity,
+        entitlements_path=NoneType::None,
+        hardened_runtime=true
+    ) {
+        match this.clone().downcast_mut::<PythonExecutable>()? {
+            Some(mut exe) => exe.starlark_set_macos_signing(
+                &env,
+                &identity,
+                &entitlements_path,
+                &hardened_runtime,
+            ),
+            None => Err(ValueError::IncorrectParameterType),
+        }
+    }
+
+    PythonExecutable.set_windows_metadata(
+        env env,
+        this,
+        file_version=NoneType::None,
+        product_name=NoneType::None,
+        icon_path=NoneType::None
+    ) {
+        match this.clone().downcast_mut::<PythonExecutable>()? {
+            Some(mut exe) => exe.starlark_set_windows_metadata(&env, &file_version, &product_name, &icon_path),
+            None => Err(ValueError::IncorrectParameterType),
+        }
+    }
+
+    PythonExecutable.set_build_environment_variable(this, name, value) {
+        match this.clone().downcast_mut::<PythonExecutable>()? {
+            Some(mut exe) => exe.starlark_set_build_environment_variable(&name, &value),
+            None => Err(ValueError::IncorrectParameterType),
+        }
+    }
+
+    PythonExecutable.add_frozen_module(this, name, source, is_package=NoneType::None) {
+        match this.clone().downcast_mut::<PythonExecutable>()? {
+            Some(mut exe) => exe.starlark_add_frozen_module(&name, &source, &is_package),
+            None => Err(ValueError::IncorrectParameterType),
+        }
+    }
+
+    PythonExecutable.replace_module_source(this, name, new_source) {
+        match this.clone().downcast_mut::<PythonExecutable>()? {
+            Some(mut exe) => exe.starlark_replace_module_source(&name, &new_source),
+            None => Err(ValueError::IncorrectParameterType),
+        }
+    }
+
+    PythonExecutable.set_run_command_from_entry_point(this, distribution, name) {
+        match this.clone().downcast_mut::<PythonExecutable>()? {
+            Some(mut exe) => exe.starlark_set_run_command_from_entry_point(&distribution, &name),
+            None => Err(ValueError::IncorrectParameterType),
+        }
+    }
+
+    PythonExecutable.set_packed_resources_compression(this, level) {
+        match this.clone().downcast_mut::<PythonExecutable>()? {
+            Some(mut exe) => exe.starlark_set_packed_resources_compression(&level),
+            None => Err(ValueError::IncorrectParameterType),
+        }
+    }
+
+    PythonExecutable.dry_run(env env, this) {
+        match this.clone().downcast_ref::<PythonExecutable>() {
+            Some(exe) => exe.starlark_dry_run(&env),
+            None => Err(ValueError::IncorrectParameterType),
+        }
+    }
+
+    PythonExecutable.resource_stats(this) {
+        match this.clone().downcast_ref::<PythonExecutable>() {
+            Some(exe) => exe.starlark_resource_stats(),
+            None => Err(ValueError::IncorrectParameterType),
+        }
+    }
+
+    PythonExecutable.lint(env env, this) {
+        match this.clone().downcast_ref::<PythonExecutable>() {
+            Some(exe) => exe.starlark_lint(&env),
+            None => Err(ValueError::IncorrectParameterType),
+        }
+    }
+
+    #[allow(clippy::ptr_arg)]
+    PythonExecutable.write_resource_manifest(env env, this, path) {
+        match this.clone().downcast_ref::<PythonExecutable>() {
+            Some(exe) => exe.starlark_write_resource_manifest(&env, &path),
+            None => Err(ValueError::IncorrectParameterType),
+        }
+    }
+
+    #[allow(clippy::ptr_arg)]
+    PythonExecutable.write_installed_requirements(env env, this, path) {
+        match this.clone().downcast_ref::<PythonExecutable>() {
+            Some(exe) => exe.starlark_write_installed_requirements(&env, &path),
+            None => Err(ValueError::IncorrectParameterType),
+        }
+    }
+
+    #[allow(clippy::ptr_arg)]
+    PythonExecutable.to_embedded_resources(this) {
+        match this.clone().downcast_ref::<PythonExecutable>() {
+            Some(exe) => exe.starlark_to_embedded_resources(),
+            None => Err(ValueError::IncorrectParameterType),
+        }
+    }
+
+    PythonExecutable.extension_module_link_report(this) {
+        match this.clone().downcast_ref::<PythonExecutable>() {
+            Some(exe) => exe.starlark_extension_module_link_report(),
+            None => Err(ValueError::IncorrectParameterType),
+        }
+    }
+
+    #[allow(clippy::ptr_arg)]
+    PythonExecutable.to_tarball(this, path_prefix=NoneType::None) {
+        match this.clone().downcast_ref::<PythonExecutable>() {
+            Some(exe) => exe.starlark_to_tarball(&path_prefix),
+            None => Err(ValueError::IncorrectParameterType),
+        }
+    }
+
+    #[allow(clippy::ptr_arg)]
+    PythonExecutable.to_resources_bundle(this, filename=NoneType::None) {
+        match this.clone().downcast_ref::<PythonExecutable>() {
+            Some(exe) => exe.starlark_to_resources_bundle(&filename),
+            None => Err(ValueError::IncorrectParameterType),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::super::testutil::*,
+        super::*,
+        crate::{
+            py_packaging::distribution::PythonDistribution,
+            python_distributions::PYTHON_DISTRIBUTIONS,
+        },
+        python_packaging::resource::{BytecodeOptimizationLevel, PythonPathExtension},
+    };
+
+    #[test]
+    fn test_default_values() -> Result<()> {
+        let mut env = StarlarkEnvironment::new_with_exe()?;
+        let exe = env.eval("exe")?;
+
+        assert_eq!(exe.get_type(), "PythonExecutable");
+
+        let exe = exe.downcast_ref::<PythonExecutable>().unwrap();
+        assert!(exe
+            .exe
+            .iter_resources()
+            .any(|(_, r)| r.in_memory_source.is_some()));
+        assert!(exe
+            .exe
+            .iter_resources()
+            .all(|(_, r)| r.in_memory_resources.is_none()));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_resource_filter() -> Result<()> {
+        let mut env = StarlarkEnvironment::new()?;
+
+        env.eval("dist = default_python_distribution()")?;
+        env.eval(&format!(
+            "def my_filter(resource, target_triple):\n    return not (resource.name == 'json' and target_triple == '{}')",
+            crate::project_building::HOST
+        ))?;
+        env.eval("def other_filter(resource, target_triple):\n    return not (resource.name == 'json' and target_triple == 'not-a-triple')")?;
+        env.eval("def bad_filter(resource, target_triple):\n    return None")?;
+
+        let exe = env.eval("dist.to_python_executable('testapp', resource_filter=my_filter)")?;
+        let exe = exe.downcast_ref::<PythonExecutable>().unwrap();
+        assert!(!exe.exe.iter_resources().any(|(name, _)| name == "json"));
+        assert!(exe
+            .exe
+            .iter_resources()
+            .any(|(name, _)| name == "json.decoder"));
+
+        let exe = env.eval("dist.to_python_executable('testapp', resource_filter=other_filter)")?;
+        let exe = exe.downcast_ref::<PythonExecutable>().unwrap();
+        assert!(exe.exe.iter_resources().any(|(name, _)| name == "json"));
+
+        assert!(env
+            .eval("dist.to_python_executable('testapp', resource_filter=bad_filter)")
+            .is_err());
+        assert!(env
+            .eval("dist.to_python_executable('testapp', resource_filter=42)")
+            .is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_resource_callback_error() -> Result<()> {
+        let mut env = StarlarkEnvironment::new()?;
+
+        env.eval("dist = default_python_distribution()")?;
+        env.eval("def failing_filter(resource, target_triple):\n    if resource.name == 'json':\n        fail('filter failed')\n    return True")?;
+
+        let err = env
+            .eval("dist.to_python_executable('testapp', resource_filter=failing_filter)")
+            .unwrap_err()
+            .to_string();
+        assert!(err
+            .as_str()
+            .contains("resource callback failed for PythonModuleSource json"));
+        assert!(err.as_str().contains("filter failed"));
+
+        env.eval("policy = dist.make_python_packaging_policy()")?;
+        env.eval("def failing_callback(policy, resource):\n    if resource.name == 'json.decoder':\n        fail('policy callback failed')")?;
+        env.eval("policy.register_resource_callback(failing_callback)")?;
+
+        let err = env
+            .eval("dist.to_python_executable('testapp', packaging_policy=policy)")
+            .unwrap_err()
+            .to_string();
+        assert!(err
+            .as_str()
+            .contains("resource callback failed for PythonModuleSource json.decoder"));
+        assert!(err.as_str().contains("policy callback failed"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_no_sources() -> Result<()> {
+        let mut env = StarlarkEnvironment::new()?;
+
+        env.eval("dist = default_python_distribution()")?;
+        env.eval("policy = dist.make_python_packaging_policy()")?;
+        env.eval("policy.include_distribution_sources = False")?;
+
+        let exe = env.eval("dist.to_python_executable('testapp', packaging_policy=policy)")?;
+
+        assert_eq!(exe.get_type(), "PythonExecutable");
+
+        let exe = exe.downcast_ref::<PythonExecutable>().unwrap();
+        assert!(exe
+            .exe
+            .iter_resources()
+            .all(|(_, r)| r.in_memory_source.is_none()));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_bytecode_only() -> Result<()> {
+        let mut env = StarlarkEnvironment::new()?;
+
+        env.eval("dist = default_python_distribution()")?;
+        env.eval("policy = dist.make_python_packaging_policy()")?;
+        env.eval("policy.bytecode_only = True")?;
+        env.eval("policy.bytecode_optimize_level_zero = False")?;
+        // Per-resource settings should not be able to add source.
+        env.eval("def my_func(policy, resource):\n    resource.add_source = True\n")?;
+        env.eval("policy.register_resource_callback(my_func)")?;
+        env.eval("exe = dist.to_python_executable('testapp', packaging_policy = policy)")?;
+        env.eval("exe.add_python_resource(exe.make_python_module_source('foo', 'import bar'))")?;
+
+        let exe = env.eval("exe")?;
+        let exe = exe.downcast_ref::<PythonExecutable>().unwrap();
+
+        assert!(exe.exe.iter_resources().all(|(_, r)| {
+            r.in_memory_source.is_none() && r.relative_path_module_source.is_none()
+        }));
+        assert!(exe
+            .exe
+            .iter_resources()
+            .any(|(_, r)| r.in_memory_bytecode.is_some()));
+
+        let (_, foo) = exe
+            .exe
+            .iter_resources()
+            .find(|(name, _)| *name == "foo")
+            .unwrap();
+        assert!(foo.in_memory_source.is_none());
+        assert!(foo.in_memory_bytecode.is_some());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_strip_docstrings() -> Result<()> {
+        let temp_dir = tempdir::TempDir::new("pyoxidizer-test")?;
+        let drain = CapturingDrain::default();
+        let logger = slog::Logger::root(drain.clone(), slog::o!());
+
+        let mut env = StarlarkEnvironment::new()?;
+
+        env.eval("dist = default_python_distribution()")?;
+        env.eval("policy = dist.make_python_packaging_policy()")?;
+        env.eval("policy.strip_docstrings = True")?;
+        // Per-resource settings should not be able to add source or other bytecode.
+        env.eval("def my_func(policy, resource):\n    resource.add_source = True\n    resource.add_bytecode_optimization_level_zero = True\n")?;
+        env.eval("policy.register_resource_callback(my_func)")?;
+        env.eval("exe = dist.to_python_executable('testapp', packaging_policy = policy)")?;
+        env.eval("exe.add_python_resource(exe.make_python_module_source('foo', '\"\"\"Module docstring.\"\"\"\\ndef bar():\\n    \"\"\"Function docstring.\"\"\"\\n    return 42\\n'))")?;
+
+        let exe = env.eval("exe")?;
+        let exe = exe.downcast_ref::<PythonExecutable>().unwrap();
+
+        let (_, foo) = exe
+            .exe
+            .iter_resources()
+            .find(|(name, _)| *name == "foo")
+            .unwrap();
+        assert!(foo.in_memory_source.is_none());
+        assert!(foo.in_memory_bytecode.is_none());
+        assert!(foo.in_memory_bytecode_opt1.is_none());
+        assert!(foo.in_memory_bytecode_opt2.is_some());
+
+        let embedded = exe.exe.to_embedded_python_context(&logger, "0")?;
+        assert_eq!(
+            embedded.config.config.optimization_level,
+            Some(BytecodeOptimizationLevel::Two)
+        );
+        assert!(drain
+            .messages
+            .lock()
+            .unwrap()
+            .iter()
+            .any(|m| m == "strip_docstrings is set; setting interpreter optimization_level to 2"));
+        assert!(drain.messages.lock().unwrap().iter().any(|m| m
+            .starts_with("stripping docstrings removed ")
+            && m.ends_with(" bytes saved)")));
+
+        let bytecode = python_packed_resources::parser::load_resources(&embedded.resources)
+            .map_err(|e| anyhow!(e))?
+            .filter_map(|resource| resource.ok())
+            .find(|resource| resource.name == "foo")
+            .unwrap()
+            .in_memory_bytecode_opt2
+            .unwrap()
+            .to_vec();
+
+        let bytecode_path = temp_dir.path().join("foo.bytecode");
+        std::fs::write(&bytecode_path, &bytecode)?;
+
+        let output = std::process::Command::new(
+            crate::testutil::get_default_distribution()?.python_exe_path(),
+        )
+        .arg("-c")
+        .arg(
+            "import dis, marshal, sys\n\
+             code = marshal.loads(open(sys.argv[1], 'rb').read())\n\
+             dis.dis(code)\n\
+             [print(c.co_consts) for c in code.co_consts if hasattr(c, 'co_consts')]\n",
+        )
+        .arg(&bytecode_path)
+        .output()?;
+        assert!(output.status.success());
+
+        let disassembly = String::from_utf8_lossy(&output.stdout);
+        assert!(disassembly.contains("LOAD_CONST"));
+        assert!(disassembly.contains("42"));
+        assert!(!disassembly.contains("docstring"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_strip_docstrings_optimization_level_conflict() -> Result<()> {
+        let logger = crate::testutil::get_logger()?;
+
+        let mut env = StarlarkEnvironment::new()?;
+        env.eval("dist = default_python_distribution()")?;
+        env.eval("policy = dist.make_python_packaging_policy()")?;
+        env.eval("policy.strip_docstrings = True")?;
+        env.eval("config = dist.make_python_interpreter_config()")?;
+        env.eval("config.optimization_level = 1")?;
+        env.eval(
+            "exe = dist.to_python_executable('testapp', packaging_policy = policy, config = config)",
+        )?;
+
+        let exe = env.eval("exe")?;
+        let exe = exe.downcast_ref::<PythonExecutable>().unwrap();
+
+        let err = exe
+            .exe
+            .to_embedded_python_context(&logger, "0")
+            .err()
+            .unwrap();
+        assert_eq!(
+            err.to_string(),
+            "strip_docstrings packages optimization level 2 bytecode but the interpreter optimization_level is One"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_optimization_level_missing_bytecode() -> Result<()> {
+        let drain = CapturingDrain::default();
+        let logger = slog::Logger::root(drain.clone(), slog::o!());
+
+        let mut env = StarlarkEnvironment::new()?;
+        env.eval("dist = default_python_distribution()")?;
+        env.eval("policy = dist.make_python_packaging_policy()")?;
+        env.eval("policy.include_distribution_sources = False")?;
+        env.eval("config = dist.make_python_interpreter_config()")?;
+        env.eval("config.optimization_level = 1")?;
+        env.eval(
+            "exe = dist.to_python_executable('testapp', packaging_policy = policy, config = config)",
+        )?;
+        env.eval("exe.add_python_resource(exe.make_python_module_source('foo', 'x = 1'))")?;
+
+        let exe = env.eval("exe")?;
+        let exe = exe.downcast_ref::<PythonExecutable>().unwrap();
+        let embedded = exe.exe.to_embedded_python_context(&logger, "0")?;
+        assert_eq!(
+            embedded.config.config.optimization_level,
+            Some(BytecodeOptimizationLevel::One)
+        );
+        assert!(drain.messages.lock().unwrap().iter().any(|m| m
+            .starts_with("interpreter optimization_level is One but ")
+            && m.as_str()
+                .contains(" modules do not have bytecode for that level")));
+
+        // No warning when the requested level is packaged.
+        drain.messages.lock().unwrap().clear();
+        env.eval("config.optimization_level = 0")?;
+        env.eval(
+            "exe = dist.to_python_executable('testapp', packaging_policy = policy, config = config)",
+        )?;
+        env.eval("exe.add_python_resource(exe.make_python_module_source('foo', 'x = 1'))")?;
+
+        let exe = env.eval("exe")?;
+        let exe = exe.downcast_ref::<PythonExecutable>().unwrap();
+        exe.exe.to_embedded_python_context(&logger, "0")?;
+        assert!(!drain
+            .messages
+            .lock()
+            .unwrap()
+            .iter()
+            .any(|m| m.starts_with("interpreter optimization_level is ")));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_make_python_module_source() -> Result<()> {
+        let mut env = StarlarkEnvironment::new_with_exe()?;
+        let m = env.eval("exe.make_python_module_source('foo', 'import bar')")?;
+
+        assert_eq!(m.get_type(), PythonModuleSourceValue::TYPE);
+        assert_eq!(m.get_attr("name").unwrap().to_str(), "foo");
+        assert_eq!(m.get_attr("source").unwrap().to_str(), "import bar");
+        assert_eq!(m.get_attr("is_package").unwrap().to_bool(), false);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_make_python_module_source_cache_tag() -> Result<()> {
+        let mut env = StarlarkEnvironment::new_with_exe()?;
+
+        let m = env.eval("exe.make_python_module_source('foo', 'import bar')")?;
+        let exe = env.eval("exe")?;
+        assert_eq!(
+            m.downcast_ref::<PythonModuleSourceValue>()
+                .unwrap()
+                .inner
+                .cache_tag,
+            exe.downcast_ref::<PythonExecutable>()
+                .unwrap()
+                .exe
+                .cache_tag()
+        );
+
+        let m = env.eval(
+            "exe.make_python_module_source('foo', 'import bar', cache_tag = 'cpython-310')",
+        )?;
+        assert_eq!(
+            m.downcast_ref::<PythonModuleSourceValue>()
+                .unwrap()
+                .inner
+                .cache_tag,
+            "cpython-310"
+        );
+
+        assert!(env
+            .eval("exe.make_python_module_source('foo', 'import bar', cache_tag = 'pypy-37')")
+            .is_err());
+        assert!(env
+            .eval("exe.make_python_module_source('foo', 'import bar', cache_tag = 'cpython-3')")
+            .is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_make_python_module_source_callback() -> Result<()> {
+        let mut env = StarlarkEnvironment::new()?;
+        env.eval("dist = default_python_distribution()")?;
+        env.eval("policy = dist.make_python_packaging_policy()")?;
+        env.eval(
+            "def my_func(policy, resource):\n    resource.add_source = True\n    resource.add_bytecode_optimization_level_two = True\n",
+        )?;
+        env.eval("policy.register_resource_callback(my_func)")?;
+        env.eval("exe = dist.to_python_executable('testapp', packaging_policy = policy)")?;
+
+        let m = env.eval("exe.make_python_module_source('foo', 'import bar')")?;
+
+        assert_eq!(m.get_type(), PythonModuleSourceValue::TYPE);
+        assert_eq!(m.get_attr("name").unwrap().to_str(), "foo");
+        assert_eq!(m.get_attr("source").unwrap().to_str(), "import bar");
+        assert_eq!(m.get_attr("is_package").unwrap().to_bool(), false);
+        assert_eq!(m.get_attr("add_source").unwrap().to_bool(), true);
+        assert_eq!(
+            m.get_attr("add_bytecode_optimization_level_two")
+                .unwrap()
+                .to_bool(),
+            true
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_pip_download_pyflakes() -> Result<()> {
+        for target_triple in PYTHON_DISTRIBUTIONS.all_target_triples() {
+            let mut env = StarlarkEnvironment::new()?;
+            env.set_target_triple(target_triple)?;
+
+            env.eval("dist = default_python_distribution()")?;
+            env.eval("exe = dist.to_python_executable('testapp')")?;
+
+            let resources = env.eval("exe.pip_download(['pyflakes==2.2.0'])")?;
+
+            assert_eq!(resources.get_type(), "list");
+
+            let raw_it = resources.iter().unwrap();
+            let mut it = raw_it.iter();
+
+            let v = it.next().unwrap();
+            assert_eq!(v.get_type(), PythonModuleSourceValue::TYPE);
+            let x = v.downcast_ref::<PythonModuleSourceValue>().unwrap();
+            assert!(x.inner.package().starts_with("pyflakes"));
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_pip_download_bad_tag_args() -> Result<()> {
+        let mut env = StarlarkEnvironment::new_with_exe()?;
+
+        for arg in &["platform", "abi", "python_version", "implementation"] {
+            assert!(env
+                .eval(&format!("exe.pip_download(['pyflakes'], {}=42)", arg))
+                .is_err());
+        }
+
+        assert!(env
+            .eval("exe.pip_download(['pyflakes'], only_binary=None)")
+            .is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_pip_install_simple() -> Result<()> {
+        let mut env = StarlarkEnvironment::new()?;
+
+        env.eval("dist = default_python_distribution()")?;
+        env.eval("policy = dist.make_python_packaging_policy()")?;
+        env.eval("policy.include_distribution_sources = False")?;
+        env.eval("exe = dist.to_python_executable('testapp', packaging_policy = policy)")?;
+
+        let resources = env.eval("exe.pip_install(['pyflakes==2.1.1'])")?;
+        assert_eq!(resources.get_type(), "list");
+
+        let raw_it = resources.iter().unwrap();
+        let mut it = raw_it.iter();
+
+        let v = it.next().unwrap();
+        assert_eq!(v.get_type(), PythonModuleSourceValue::TYPE);
+        let x = v.downcast_ref::<PythonModuleSourceValue>().unwrap();
+        assert_eq!(x.inner.name, "pyflakes");
+        assert!(x.inner.is_package);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_pip_install_find_links() -> Result<()> {
+        let temp_dir = tempdir::TempDir::new("pyoxidizer-test")?;
+        let wheelhouse = temp_dir.path().join("wheelhouse");
+        std::fs::create_dir(&wheelhouse)?;
+
+        let mut env = StarlarkEnvironment::new()?;
+        env.eval("dist = default_python_distribution()")?;
+        env.eval("policy = dist.make_python_packaging_policy()")?;
+        env.eval("policy.include_distribution_sources = False")?;
+        env.eval("exe = dist.to_python_executable('testapp', packaging_policy = policy)")?;
+
+        let pip_install = format!(
+            "exe.pip_install(['pyoxidizer_wheelhouse_test'], find_links = {:?})",
+            wheelhouse.display().to_string()
+        );
+
+        // The directory must exist and contain wheels.
+        assert!(env
+            .eval(&format!(
+                "exe.pip_install(['pyoxidizer_wheelhouse_test'], find_links = {:?})",
+                temp_dir.path().join("missing").display().to_string()
+            ))
+            .is_err());
+        assert!(env.eval(&pip_install).is_err());
+
+        let wheel_path = wheelhouse.join("pyoxidizer_wheelhouse_test-1.0-py3-none-any.whl");
+        let mut writer = zip::ZipWriter::new(std::fs::File::create(&wheel_path)?);
+        for (name, data) in &[
+            ("pyoxidizer_wheelhouse_test/__init__.py", "# test"),
+            (
+                "pyoxidizer_wheelhouse_test-1.0.dist-info/METADATA",
+                "Metadata-Version: 2.1\nName: pyoxidizer_wheelhouse_test\nVersion: 1.0\n",
+            ),
+            (
+                "pyoxidizer_wheelhouse_test-1.0.dist-info/WHEEL",
+                "Wheel-Version: 1.0\nGenerator: pyoxidizer\nRoot-Is-Purelib: true\nTag: py3-none-any\n",
+            ),
+            (
+                "pyoxidizer_wheelhouse_test-1.0.dist-info/RECORD",
+                "pyoxidizer_wheelhouse_test/__init__.py,,\n\
+                 pyoxidizer_wheelhouse_test-1.0.dist-info/METADATA,,\n\
+                 pyoxidizer_wheelhouse_test-1.0.dist-info/WHEEL,,\n\
+                 pyoxidizer_wheelhouse_test-1.0.dist-info/RECORD,,\n",
+            ),
+        ] {
+            writer.start_file(*name, zip::write::FileOptions::default())?;
+            writer.write_all(data.as_bytes())?;
+        }
+        writer.finish()?;
+
+        // find_links disables the package index, so the package can only be
+        // installed from the wheelhouse.
+        let resources = env.eval(&pip_install)?;
+        assert_eq!(resources.get_type(), "list");
+
+        let found = resources.iter().unwrap().iter().any(|v| {
+            v.get_type() == PythonModuleSourceValue::TYPE
+                && v.downcast_ref::<PythonModuleSourceValue>()
+                    .unwrap()
+                    .inner
+                    .name
+                    == "pyoxidizer_wheelhouse_test"
+        });
+        assert!(found);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_pipenv_install() -> Result<()> {
+        let temp_dir = tempdir::TempDir::new("pyoxidizer-test")?;
+        let project = temp_dir.path().join("project");
+        std::fs::create_dir(&project)?;
+
+        let mut env = StarlarkEnvironment::new()?;
+        env.eval("dist = default_python_distribution()")?;
+        env.eval("policy = dist.make_python_packaging_policy()")?;
+        env.eval("policy.include_distribution_sources = False")?;
+        env.eval("exe = dist.to_python_executable('testapp', packaging_policy = policy)")?;
+
+        let pipenv_install = format!("exe.pipenv_install({:?})", project.display().to_string());
+
+        // A lock file is required.
+        assert!(env.eval(&pipenv_install).is_err());
+
+        std::fs::write(
+            project.join("Pipfile.lock"),
+            r#"{
+                "_meta": {"pipfile-spec": 6},
+                "default": {
+                    "pyflakes": {"index": "pypi", "version": "==2.1.1"}
+                },
+                "develop": {
+                    "mccabe": {"index": "pypi", "version": "==0.6.1"}
+                }
+            }"#,
+        )?;
+
+        let resources = env.eval(&pipenv_install)?;
+        assert_eq!(resources.get_type(), "list");
+
+        let names = resources
+            .iter()
+            .unwrap()
+            .iter()
+            .filter_map(|v| {
+                v.downcast_ref::<PythonModuleSourceValue>()
+                    .map(|x| x.inner.name.clone())
+            })
+            .collect::<Vec<_>>();
+        assert!(names.contains(&"pyflakes".to_string()));
+        assert!(!names.contains(&"mccabe".to_string()));
+
+        let resources = env.eval(&format!(
+            "exe.pipenv_install({:?}, dev = True)",
+            project.display().to_string()
+        ))?;
+        let names = resources
+            .iter()
+            .unwrap()
+            .iter()
+            .filter_map(|v| {
+                v.downcast_ref::<PythonModuleSourceValue>()
+                    .map(|x| x.inner.name.clone())
+            })
+            .collect::<Vec<_>>();
+        assert!(names.contains(&"mccabe".to_string()));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_pip_install_editable() -> Result<()> {
+        let temp_dir = tempdir::TempDir::new("pyoxidizer-test")?;
+
+        let root = temp_dir.path().join("editable_app");
+        std::fs::create_dir_all(root.join("editable_app"))?;
+        std::fs::write(
+            root.join("setup.py"),
+            "from setuptools import setup\nsetup(name='editable_app', version='0.1', packages=['editable_app'])\n",
+        )?;
+        std::fs::write(root.join("editable_app").join("__init__.py"), "# app")?;
+
+        let mut env = StarlarkEnvironment::new()?;
+        env.set_offline(true)?;
+        env.eval("dist = default_python_distribution()")?;
+        env.eval("policy = dist.make_python_packaging_policy()")?;
+        env.eval("policy.include_distribution_sources = False")?;
+        env.eval("exe = dist.to_python_executable('testapp', packaging_policy = policy)")?;
+
+        let resources = env.eval(&format!(
+            "exe.pip_install_editable({:?})",
+            root.display().to_string()
+        ))?;
+        assert_eq!(resources.get_type(), "list");
+
+        let raw_it = resources.iter().unwrap();
+        assert!(!raw_it.iter().any(|v| {
+            v.get_type() == PythonModuleSourceValue::TYPE
+                && v.downcast_ref::<PythonModuleSourceValue>()
+                    .unwrap()
+                    .inner
+                    .name
+                    .starts_with("editable_app")
+        }));
+
+        let exe = env.eval("exe")?;
+        let exe = exe.downcast_ref::<PythonExecutable>().unwrap();
+        let embedded = exe
+            .exe
+            .to_embedded_python_context(&crate::testutil::get_logger()?, "0")?;
+        assert!(embedded.config.filesystem_importer);
+        assert_eq!(embedded.config.config.module_search_paths, None);
+
+        // The source tree is referenced relative to the executable.
+        let output_path = temp_dir.path().join("build").join("out");
+        std::fs::create_dir_all(&output_path)?;
+        assert_eq!(
+            exe.exe.origin_module_search_paths(&output_path)?,
+            vec![PathBuf::from("$ORIGIN")
+                .join("..")
+                .join("..")
+                .join("editable_app")]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_read_package_root_simple() -> Result<()> {
+        let temp_dir = tempdir::TempDir::new("pyoxidizer-test")?;
+
+        let root = temp_dir.path();
+        std::fs::create_dir(root.join("bar"))?;
+        let bar_init = root.join("bar").join("__init__.py");
+        std::fs::write(&bar_init, "# bar")?;
+
+        let foo_path = root.join("foo.py");
+        std::fs::write(&foo_path, "# foo")?;
+
+        let baz_path = root.join("baz.py");
+        std::fs::write(&baz_path, "# baz")?;
+
+        std::fs::create_dir(root.join("extra"))?;
+        let extra_path = root.join("extra").join("__init__.py");
+        std::fs::write(&extra_path, "# extra")?;
+
+        let mut env = StarlarkEnvironment::new()?;
+        env.eval("dist = default_python_distribution()")?;
+        env.eval("policy = dist.make_python_packaging_policy()")?;
+        env.eval("policy.include_distribution_sources = False")?;
+        env.eval("exe = dist.to_python_executable('testapp', packaging_policy = policy)")?;
+
+        let resources = env.eval(&format!(
+            "exe.read_package_root(\"{}\", packages=['foo', 'bar'])",
+            root.display()
+        ))?;
+
+        assert_eq!(resources.get_type(), "list");
+        assert_eq!(resources.length().unwrap(), 2);
+
+        let raw_it = resources.iter().unwrap();
+        let mut it = raw_it.iter();
+
+        let v = it.next().unwrap();
+        assert_eq!(v.get_type(), PythonModuleSourceValue::TYPE);
+        let x = v.downcast_ref::<PythonModuleSourceValue>().unwrap();
+        assert_eq!(x.inner.name, "bar");
+        assert!(x.inner.is_package);
+        assert_eq!(x.inner.source.resolve().unwrap(), b"# bar");
+
+        let v = it.next().unwrap();
+        assert_eq!(v.get_type(), PythonModuleSourceValue::TYPE);
+        let x = v.downcast_ref::<PythonModuleSourceValue>().unwrap();
+        assert_eq!(x.inner.name, "foo");
+        assert!(!x.inner.is_package);
+        assert_eq!(x.inner.source.resolve().unwrap(), b"# foo");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_read_package_root_namespace_packages() -> Result<()> {
+        let temp_dir = tempdir::TempDir::new("pyoxidizer-test")?;
+
+        let root = temp_dir.path();
+        let ns_path = root.join("ns");
+        std::fs::create_dir_all(ns_path.join("sub"))?;
+        std::fs::write(ns_path.join("mod.py"), "# mod")?;
+        std::fs::write(ns_path.join("data.txt"), "data")?;
+        std::fs::write(ns_path.join("sub").join("__init__.py"), "# sub")?;
+        std::fs::write(ns_path.join("sub").join("x.py"), "# x")?;
+        std::fs::write(root.join("other.py"), "# other")?;
+
+        let mut env = StarlarkEnvironment::new()?;
+        env.eval("dist = default_python_distribution()")?;
+        env.eval("policy = dist.make_python_packaging_policy()")?;
+        env.eval("policy.include_distribution_sources = False")?;
+        env.eval("exe = dist.to_python_executable('testapp', packaging_policy = policy)")?;
+
+        assert!(env
+            .eval(&format!(
+                "exe.read_package_root(\"{}\", packages=[], namespace_packages=['missing'])",
+                root.display()
+            ))
+            .is_err());
+
+        let resources = env.eval(&format!(
+            "exe.read_package_root(\"{}\", packages=[], namespace_packages=['ns'])",
+            root.display()
+        ))?;
+
+        assert_eq!(resources.get_type(), "list");
+        assert_eq!(resources.length().unwrap(), 4);
+
+        let raw_it = resources.iter().unwrap();
+        let mut it = raw_it.iter();
+
+        for (name, is_package) in &[("ns.mod", false), ("ns.sub", true), ("ns.sub.x", false)] {
+            let v = it.next().unwrap();
+            assert_eq!(v.get_type(), PythonModuleSourceValue::TYPE);
+            let x = v.downcast_ref::<PythonModuleSourceValue>().unwrap();
+            assert_eq!(&x.inner.name, name);
+            assert_eq!(x.inner.is_package, *is_package);
+        }
+
+        let v = it.next().unwrap();
+        assert_eq!(v.get_type(), PythonPackageResourceValue::TYPE);
+        let x = v.downcast_ref::<PythonPackageResourceValue>().unwrap();
+        assert_eq!(x.inner.leaf_package, "ns");
+        assert_eq!(x.inner.relative_name, "data.txt");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_setup_py_install_relative_cwd() -> Result<()> {
+        let temp_dir = tempdir::TempDir::new("pyoxidizer-test")?;
+        let package_path = temp_dir.path().join("foo");
+        std::fs::create_dir(&package_path)?;
+        std::fs::write(
+            package_path.join("setup.py"),
+            "from distutils.core import setup\nsetup(name='foo', version='1.0', py_modules=['foo'])\n",
+        )?;
+        std::fs::write(package_path.join("foo.py"), "# foo")?;
+
+        let mut env = StarlarkEnvironment::new_with_exe()?;
+        env.eval(&format!(
+            "set_cwd({:?})",
+            temp_dir.path().display().to_string()
+        ))?;
+
+        let resources = env.eval("exe.setup_py_install('foo')")?;
+        assert_eq!(resources.get_type(), "list");
+
+        let raw_it = resources.iter().unwrap();
+        assert!(raw_it.iter().any(|v| {
+            v.get_type() == PythonModuleSourceValue::TYPE
+                && v.downcast_ref::<PythonModuleSourceValue>()
+                    .unwrap()
+                    .inner
+                    .name
+                    == "foo"
+        }));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_setup_py_install_offline() -> Result<()> {
+        let temp_dir = tempdir::TempDir::new("pyoxidizer-test")?;
+        let package_path = temp_dir.path().join("foo");
+        std::fs::create_dir(&package_path)?;
+        std::fs::write(
+            package_path.join("setup.py"),
+            "import os\n\
+             from distutils.core import setup\n\
+             with open('foo.py', 'w') as fh:\n    \
+                 fh.write('NO_INDEX = %r\\n' % os.environ.get('PIP_NO_INDEX'))\n\
+             setup(name='foo', version='1.0', py_modules=['foo'])\n",
+        )?;
+
+        let mut env = StarlarkEnvironment::new_with_exe()?;
+        env.set_offline(true)?;
+
+        let resources = env.eval(&format!(
+            "exe.setup_py_install({:?})",
+            package_path.display().to_string()
+        ))?;
+
+        let raw_it = resources.iter().unwrap();
+        assert!(raw_it.iter().any(|v| {
+            v.get_type() == PythonModuleSourceValue::TYPE && {
+                let x = v.downcast_ref::<PythonModuleSourceValue>().unwrap();
+                x.inner.name == "foo" && x.inner.source.resolve().unwrap() == b"NO_INDEX = '1'\n"
+            }
+        }));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_read_zip_file() -> Result<()> {
+        let temp_dir = tempdir::TempDir::new("pyoxidizer-test")?;
+        let zip_path = temp_dir.path().join("foo-1.0.zip");
+
+        let mut writer = zip::ZipWriter::new(std::fs::File::create(&zip_path)?);
+        for (name, data) in &[
+            ("foo-1.0/setup.py", "# setup"),
+            ("foo-1.0/foo/__init__.py", "# foo"),
+            ("foo-1.0/foo/__pycache__/__init__.cpython-37.pyc", "pyc"),
+            ("foo-1.0/foo/sub/__init__.py", "# sub"),
+            ("foo-1.0/foo/sub/data.txt", "data"),
+        ] {
+            writer.start_file(*name, zip::write::FileOptions::default())?;
+            writer.write_all(data.as_bytes())?;
+        }
+        writer.finish()?;
+
+        let mut env = StarlarkEnvironment::new_with_exe()?;
+        env.eval(&format!(
+            "set_cwd({:?})",
+            temp_dir.path().display().to_string()
+        ))?;
+
+        assert!(env.eval("exe.read_zip_file('missing.zip')").is_err());
+
+        // Relative paths are resolved against the current directory.
+        let resources = env.eval("exe.read_zip_file('foo-1.0.zip', strip_prefix = 'foo-1.0')")?;
+
+        assert_eq!(resources.get_type(), "list");
+        assert_eq!(resources.length().unwrap(), 4);
+
+        let raw_it = resources.iter().unwrap();
+        let mut it = raw_it.iter();
+
+        for (name, is_package, source) in &[
+            ("foo", true, "# foo"),
+            ("foo.sub", true, "# sub"),
+            ("setup", false, "# setup"),
+        ] {
+            let v = it.next().unwrap();
+            assert_eq!(v.get_type(), PythonModuleSourceValue::TYPE);
+            let x = v.downcast_ref::<PythonModuleSourceValue>().unwrap();
+            assert_eq!(&x.inner.name, name);
+            assert_eq!(x.inner.is_package, *is_package);
+            assert_eq!(x.inner.source.resolve()?, source.as_bytes());
+        }
+
+        let v = it.next().unwrap();
+        assert_eq!(v.get_type(), PythonPackageResourceValue::TYPE);
+        let x = v.downcast_ref::<PythonPackageResourceValue>().unwrap();
+        assert_eq!(x.inner.leaf_package, "foo.sub");
+        assert_eq!(x.inner.relative_name, "data.txt");
+
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn test_add_extension_module_from_file() -> Result<()> {
+        let temp_dir = tempdir::TempDir::new("pyoxidizer-test")?;
+        std::fs::write(temp_dir.path().join("foo.so"), "fake shared library")?;
+        std::fs::write(
+            temp_dir.path().join("bar.cpython-37m-darwin.so"),
+            "fake shared library",
+        )?;
+        std::fs::write(temp_dir.path().join("baz.pyd"), "fake shared library")?;
+
+        let mut env = StarlarkEnvironment::new_with_exe()?;
+        env.eval(&format!(
+            "set_cwd({:?})",
+            temp_dir.path().display().to_string()
+        ))?;
+
+        env.eval("exe.add_extension_module_from_file('pkg.foo', 'foo.so')")?;
+
+        let err = env
+            .eval("exe.add_extension_module_from_file('bar', 'bar.cpython-37m-darwin.so')")
+            .unwrap_err()
+            .to_string();
+        assert!(err.as_str().contains("platform tag cpython-37m-darwin"));
+
+        let err = env
+            .eval("exe.add_extension_module_from_file('baz', 'baz.pyd')")
+            .unwrap_err()
+            .to_string();
+        assert!(err
+            .as_str()
+            .contains("does not have an extension module suffix"));
+
+        let exe = env.eval("exe")?;
+        let exe = exe.downcast_ref::<PythonExecutable>().unwrap();
+
+        let (_, resource) = exe
+            .exe
+            .iter_resources()
+            .find(|(name, _)| name.as_str() == "pkg.foo")
+            .unwrap();
+        assert!(resource.is_extension_module);
+        assert!(!exe
+            .exe
+            .iter_resources()
+            .any(|(name, _)| name.as_str() == "bar" || name.as_str() == "baz"));
+        assert_eq!(
+            exe.exe.resource_origin("pkg.foo"),
+            Some(temp_dir.path().join("foo.so").display().to_string())
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_read_site_packages_pth() -> Result<()> {
+        let temp_dir = tempdir::TempDir::new("pyoxidizer-test")?;
+        let site_packages = temp_dir.path().join("site-packages");
+        let extra = temp_dir.path().join("extra");
+        let vendor = temp_dir.path().join("vendor");
+        std::fs::create_dir_all(&site_packages)?;
+        std::fs::create_dir_all(&extra)?;
+        std::fs::create_dir_all(&vendor)?;
+
+        let pth_path = site_packages.join("foo.pth");
+        std::fs::write(
+            &pth_path,
+            format!("{}\n../vendor\nimport foo\n", extra.display()),
+        )?;
+        std::fs::write(site_packages.join("foo.py"), "# foo")?;
+        std::fs::write(extra.join("extra_mod.py"), "# extra")?;
+        std::fs::write(vendor.join("vendored.py"), "# vendored")?;
+
+        let resources: Vec<PythonResource> = vec![PythonPathExtension {
+            data: DataLocation::Path(pth_path.clone()),
+            path_resources: vec![],
+        }
+        .into()];
+
+        let (path_resources, warnings) = path_extension_resources(&resources, false)?;
+        assert!(path_resources.is_empty());
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].starts_with(&format!("dropping {}: ", pth_path.display())));
+
+        // Nothing was collected from the referenced directories.
+        let (path_resources, warnings) = path_extension_resources(&resources, true)?;
+        assert!(path_resources.is_empty());
+        assert_eq!(warnings.len(), 2);
+        assert!(warnings[0].starts_with("dropping `import foo` from "));
+        assert!(warnings[1].starts_with("no resources found in directories referenced by "));
+
+        // .pth files are dropped by default.
+        let mut env = StarlarkEnvironment::new_with_exe()?;
+        let resources = env.eval(&format!(
+            "exe.read_site_packages({:?})",
+            site_packages.display().to_string()
+        ))?;
+        assert_eq!(resources.length().unwrap(), 1);
+
+        // Otherwise the modules they reference are packaged, including
+        // those referenced by relative paths.
+        let mut env = StarlarkEnvironment::new()?;
+        env.eval("dist = default_python_distribution()")?;
+        env.eval("policy = dist.make_python_packaging_policy()")?;
+        env.eval("policy.apply_pth_files = True")?;
+        env.eval("exe = dist.to_python_executable('testapp', packaging_policy = policy)")?;
+        let resources = env.eval(&format!(
+            "exe.read_site_packages({:?})",
+            site_packages.display().to_string()
+        ))?;
+        let mut names = resources
+            .iter()
+            .unwrap()
+            .iter()
+            .map(|v| {
+                v.downcast_ref::<PythonModuleSourceValue>()
+                    .unwrap()
+                    .inner
+                    .name
+                    .clone()
+            })
+            .collect::<Vec<_>>();
+        names.sort();
+        assert_eq!(names, vec!["extra_mod", "foo", "vendored"]);
+
+        // No build host paths are referenced at run-time.
+        let exe = env.eval("exe")?;
+        let exe = exe.downcast_ref::<PythonExecutable>().unwrap();
+        let embedded = exe
+            .exe
+            .to_embedded_python_context(&crate::testutil::get_logger()?, "0")?;
+        assert_eq!(embedded.config.config.module_search_paths, None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_read_site_packages() -> Result<()> {
+        let temp_dir = tempdir::TempDir::new("pyoxidizer-test")?;
+        let site_packages = temp_dir.path().join("site-packages");
+
+        for (path, data) in &[
+            ("foo/__init__.py", "# foo"),
+            ("foo/__pycache__/__init__.cpython-37.pyc", "pyc"),
+            ("foo/data.txt", "data"),
+            ("bar.py", "# bar"),
+            ("foo-1.0.dist-info/METADATA", "Name: foo\nVersion: 1.0\n"),
+        ] {
+            let path = site_packages.join(path);
+            std::fs::create_dir_all(path.parent().unwrap())?;
+            std::fs::write(&path, data)?;
+        }
+
+        let mut env = StarlarkEnvironment::new_with_exe()?;
+
+        assert!(env
+            .eval(&format!(
+                "exe.read_site_packages({:?})",
+                temp_dir.path().join("missing").display().to_string()
+            ))
+            .is_err());
+
+        let resources = env.eval(&format!(
+            "exe.read_site_packages({:?})",
+            site_packages.display().to_string()
+        ))?;
+
+        assert_eq!(resources.get_type(), "list");
+        assert_eq!(resources.length().unwrap(), 4);
+
+        let raw_it = resources.iter().unwrap();
+        let values = raw_it.iter().collect::<Vec<_>>();
+
+        let mut modules = values
+            .iter()
+            .filter_map(|v| v.downcast_ref::<PythonModuleSourceValue>())
+            .map(|x| (x.inner.name.clone(), x.inner.is_package))
+            .collect::<Vec<_>>();
+        modules.sort();
+        assert_eq!(
+            modules,
+            vec![("bar".to_string(), false), ("foo".to_string(), true)]
+        );
+
+        let resource = values
+            .iter()
+            .find_map(|v| v.downcast_ref::<PythonPackageResourceValue>())
+            .unwrap();
+        assert_eq!(resource.inner.leaf_package, "foo");
+        assert_eq!(resource.inner.relative_name, "data.txt");
+
+        let distribution = values
+            .iter()
+            .find_map(|v| v.downcast_ref::<PythonPackageDistributionResourceValue>())
+            .unwrap();
+        assert_eq!(distribution.inner.package, "foo");
+        assert_eq!(distribution.inner.version, "1.0");
+        assert_eq!(distribution.inner.name, "METADATA");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_add_file_data() -> Result<()> {
+        let mut env = StarlarkEnvironment::new_with_exe()?;
+        env.eval("exe.add_file_data('config/template.ini', '[main]\\n')")?;
+        env.eval("exe.add_file_data('bin/helper.sh', '#!/bin/sh\\n', executable = True)")?;
+        assert!(env.eval("exe.add_file_data('foo', None)").is_err());
+
+        let exe = env.eval("exe")?;
+        let exe = exe.downcast_ref::<PythonExecutable>().unwrap();
+
+        let (_, r) = exe
+            .exe
+            .iter_resources()
+            .find(|(name, _)| *name == "config/template.ini")
+            .unwrap();
+        assert!(r.is_file_data);
+        assert!(!r.file_executable);
+        assert_eq!(
+            r.in_memory_file_data,
+            Some(DataLocation::Memory(b"[main]\n".to_vec()))
+        );
+
+        let (_, r) = exe
+            .exe
+            .iter_resources()
+            .find(|(name, _)| *name == "bin/helper.sh")
+            .unwrap();
+        assert!(r.is_file_data);
+        assert!(r.file_executable);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_add_metadata_resource() -> Result<()> {
+        let mut env = StarlarkEnvironment::new_with_exe()?;
+        env.eval("exe.add_metadata_resource('git_sha', 'abc123')")?;
+        env.eval("exe.add_metadata_resource('build_date', '2020-10-01')")?;
+        assert!(env.eval("exe.add_metadata_resource('foo', None)").is_err());
+        assert!(env
+            .eval("exe.add_metadata_resource('../foo', 'x')")
+            .is_err());
+        assert!(env.eval("exe.add_metadata_resource('', 'x')").is_err());
+
+        let exe = env.eval("exe")?;
+        let exe = exe.downcast_ref::<PythonExecutable>().unwrap();
+
+        let manifest = resource_manifest(exe.exe.deref())?;
+        let entry = manifest
+            .iter()
+            .find(|entry| entry.name == METADATA_RESOURCES_PACKAGE)
+            .unwrap();
+        assert!(entry.types.contains(&"module"));
+        assert!(entry.types.contains(&"package_resources"));
+        assert_eq!(entry.size, ("abc123".len() + "2020-10-01".len()) as u64);
+
+        let (_, r) = exe
+            .exe
+            .iter_resources()
+            .find(|(name, _)| *name == METADATA_RESOURCES_PACKAGE)
+            .unwrap();
+        assert!(r.is_package);
+        let resources = r.in_memory_resources.as_ref().unwrap();
+        assert_eq!(
+            resources.get("git_sha"),
+            Some(&DataLocation::Memory(b"abc123".to_vec()))
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_package_resource_install_relative_path() -> Result<()> {
+        let logger = crate::testutil::get_logger()?;
+        let temp_dir = tempdir::TempDir::new("pyoxidizer-test")?;
+
+        let root = temp_dir.path();
+        std::fs::create_dir(root.join("foo"))?;
+        std::fs::write(root.join("foo").join("__init__.py"), "# foo")?;
+        std::fs::write(root.join("foo").join("data.txt"), "data")?;
+
+        let mut env = StarlarkEnvironment::new()?;
+        env.eval("dist = default_python_distribution()")?;
+        env.eval("policy = dist.make_python_packaging_policy()")?;
+        env.eval("policy.resources_location_fallback = 'filesystem-relative:lib'")?;
+        env.eval("exe = dist.to_python_executable('testapp', packaging_policy = policy)")?;
+        env.eval(&format!(
+            "resources = [r for r in exe.read_package_root(\"{}\", packages=['foo']) if type(r) == 'PythonPackageResource']",
+            root.display()
+        ))?;
+        env.eval("r = resources[0]")?;
+        env.eval_assert("r.add_install_relative_path == None")?;
+
+        assert!(env.eval("r.add_install_relative_path = ''").is_err());
+        assert!(env
+            .eval("r.add_install_relative_path = '/etc/data.txt'")
+            .is_err());
+        assert!(env
+            .eval("r.add_install_relative_path = '../data.txt'")
+            .is_err());
+        assert!(env
+            .eval("r.add_install_relative_path = 'share/../data.txt'")
+            .is_err());
+        assert!(env.eval("r.add_install_relative_path = True").is_err());
+
+        env.eval("r.add_location = 'filesystem-relative:lib'")?;
+        env.eval("r.add_install_relative_path = 'share/foo/data.txt'")?;
+        env.eval_assert("r.add_install_relative_path == 'share/foo/data.txt'")?;
+        env.eval("exe.add_python_resource(r)")?;
+
+        let exe = env.eval("exe")?;
+        let exe = exe.downcast_ref::<PythonExecutable>().unwrap();
+
+        let (_, foo) = exe
+            .exe
+            .iter_resources()
+            .find(|(name, _)| *name == "foo")
+            .unwrap();
+        assert_eq!(
+            foo.relative_path_package_resources
+                .as_ref()
+                .unwrap()
+                .get("data.txt")
+                .map(|(path, _)| path.clone()),
+            Some(PathBuf::from("share/foo/data.txt"))
+        );
+
+        let embedded = exe.exe.to_embedded_python_context(&logger, "0")?;
+        assert!(embedded
+            .extra_files
+            .has_path(&PathBuf::from("share/foo/data.txt")));
+        assert!(!embedded
+            .extra_files
+            .has_path(&PathBuf::from("lib/foo/data.txt")));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_run_mode_resource() -> Result<()> {
+        let logger = crate::testutil::get_logger()?;
+        let mut env = StarlarkEnvironment::new()?;
+
+        env.eval("dist = default_python_distribution()")?;
+        env.eval("config = dist.make_python_interpreter_config()")?;
+        env.eval("config.run_mode = 'resource:myapp'")?;
+        env.eval("exe = dist.to_python_executable('testapp', config=config)")?;
+        env.eval("def make_exe(): return exe")?;
+        env.eval("register_target('exe', make_exe)")?;
+
+        // The missing module is reported when the target is evaluated.
+        let err = env.eval("resolve_target('exe')").unwrap_err();
+        assert!(err.to_string().as_str().contains(
+            "target exe: run_mode references Python module myapp but no such module is packaged in the executable"
+        ));
+
+        env.eval(
+            "exe.add_python_resource(exe.make_python_module_source('myapp', 'print(\"hello\")'))",
+        )?;
+        env.eval("resolve_target('exe')")?;
+
+        let exe = env.eval("exe")?;
+        let embedded = exe
+            .downcast_ref::<PythonExecutable>()
+            .unwrap()
+            .exe
+            .to_embedded_python_context(&logger, "0")?;
+        let code = embedded
+            .config
+            .to_oxidized_python_interpreter_config_rs(None)?;
+        assert!(code
+            .as_str()
+            .contains("run: pyembed::PythonRunMode::Resource { name: \"myapp\".to_string() },"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_run_module_missing() -> Result<()> {
+        let logger = crate::testutil::get_logger()?;
+        let mut env = StarlarkEnvironment::new()?;
+
+        env.eval("dist = default_python_distribution()")?;
+        env.eval("config = dist.make_python_interpreter_config()")?;
+        env.eval("config.run_module = 'bogus_module'")?;
+        env.eval("exe = dist.to_python_executable('testapp', config=config)")?;
+
+        let exe = env.eval("exe")?;
+        let err = exe
+            .downcast_ref::<PythonExecutable>()
+            .unwrap()
+            .exe
+            .to_embedded_python_context(&logger, "0")
+            .err()
+            .unwrap();
+        assert_eq!(
+            err.to_string(),
+            "run_module references Python module bogus_module but no such module is packaged in the executable"
+        );
+
+        env.eval("config.run_module = None")?;
+        env.eval("config.run_mode = 'module:bogus_module'")?;
+        env.eval("exe = dist.to_python_executable('testapp', config=config)")?;
+
+        let exe = env.eval("exe")?;
+        let err = exe
+            .downcast_ref::<PythonExecutable>()
+            .unwrap()
+            .exe
+            .to_embedded_python_context(&logger, "0")
+            .err()
+            .unwrap();
+        assert!(err.to_string().as_str().contains("bogus_module"));
+
+        // Modules may be importable from the filesystem.
+        env.eval("config.filesystem_importer = True")?;
+        env.eval("exe = dist.to_python_executable('testapp', config=config)")?;
+
+        let exe = env.eval("exe")?;
+        let exe = exe.downcast_ref::<PythonExecutable>().unwrap();
+        assert_eq!(
+            exe.exe.validate_run_modules()?,
+            vec!["run_mode references Python module bogus_module but no such module is packaged in the executable; it must be importable from the filesystem at run-time".to_string()]
+        );
+        exe.exe.to_embedded_python_context(&logger, "0")?;
+
+        // Packaged stdlib modules are found.
+        env.eval("config.filesystem_importer = False")?;
+        env.eval("config.run_mode = 'module:json.tool'")?;
+        env.eval("exe = dist.to_python_executable('testapp', config=config)")?;
+
+        let exe = env.eval("exe")?;
+        exe.downcast_ref::<PythonExecutable>()
+            .unwrap()
+            .exe
+            .to_embedded_python_context(&logger, "0")?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_dry_run() -> Result<()> {
+        let temp_dir = tempdir::TempDir::new("pyoxidizer-test")?;
+        let build_path = temp_dir.path().join("build");
+
+        let mut env = StarlarkEnvironment::new()?;
+        env.eval(&format!(
+            "set_build_path({:?})",
+            build_path.display().to_string()
+        ))?;
+        env.eval("dist = default_python_distribution()")?;
+        env.eval("exe = dist.to_python_executable('testapp')")?;
+        env.eval("exe.add_python_resource(exe.make_python_module_source('foo', 'import bar'))")?;
+
+        env.eval("summary = exe.dry_run()")?;
+        env.eval_assert("summary['resource_count'] > 0")?;
+        env.eval_assert("summary['resources_size'] > 0")?;
+        env.eval_assert("summary['libpython_size'] > 0")?;
+        env.eval_assert(
+            "summary['executable_size'] == summary['resources_size'] + summary['libpython_size']",
+        )?;
+        env.eval_assert(
+            "summary['total_size'] == summary['executable_size'] + summary['extra_files_size']",
+        )?;
+
+        assert!(!walkdir::WalkDir::new(temp_dir.path())
+            .into_iter()
+            .filter_map(|entry| entry.ok())
+            .any(|entry| entry.file_name().to_string_lossy().starts_with("testapp")));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_resource_stats() -> Result<()> {
+        let mut env = StarlarkEnvironment::new_with_exe()?;
+
+        env.eval("stats = exe.resource_stats()")?;
+        env.eval_assert("stats['module_sources'] > 0")?;
+        env.eval_assert("stats['extension_modules'] > 0")?;
+        env.eval_assert("stats['total_bytes'] > 0")?;
+        env.eval_assert("stats['file_data'] == 0")?;
+
+        env.eval("exe.add_python_resource(exe.make_python_module_source('foo', 'import bar'))")?;
+        env.eval("new_stats = exe.resource_stats()")?;
+        env.eval_assert("new_stats['module_sources'] == stats['module_sources'] + 1")?;
+        env.eval_assert("new_stats['total_bytes'] > stats['total_bytes']")?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_extension_module_link_report() -> Result<()> {
+        let mut env = StarlarkEnvironment::new_with_exe()?;
+
+        env.eval("report = exe.extension_module_link_report()")?;
+        env.eval_assert("type(report) == 'list'")?;
+        env.eval_assert("len(report) > 0")?;
+        env.eval_assert("('_io', 'builtin') in report")?;
+        env.eval_assert(
+            "all([mode in ('builtin', 'static', 'shared') for (name, mode) in report])",
+        )?;
+        env.eval_assert(
+            "[name for (name, mode) in report] == sorted([name for (name, mode) in report])",
+        )?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_lint() -> Result<()> {
+        let mut env = StarlarkEnvironment::new_with_exe()?;
+
+        env.eval("findings = exe.lint()")?;
+        env.eval_assert("findings == []")?;
+
+        env.eval(
+            "exe.add_python_resource(exe.make_python_module_source('foo', 'print(__file__)'))",
+        )?;
+        env.eval("exe.add_python_resource(exe.make_python_module_source('bar', 'import os'))")?;
+
+        env.eval("findings = exe.lint()")?;
+        env.eval_assert("len(findings) == 1")?;
+        env.eval_assert("findings[0]['severity'] == 'warning'")?;
+        env.eval_assert("findings[0]['resource'] == 'foo'")?;
+        env.eval_assert("'__file__' in findings[0]['message']")?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_write_resource_manifest() -> Result<()> {
+        let temp_dir = tempdir::TempDir::new("pyoxidizer-test")?;
+        let manifest_path = temp_dir.path().join("manifest.json");
+
+        let mut env = StarlarkEnvironment::new_with_exe()?;
+        env.eval("exe.add_python_resource(exe.make_python_module_source('foo', 'import bar'))")?;
+        env.eval(&format!(
+            "exe.write_resource_manifest({:?})",
+            manifest_path.display().to_string()
+        ))?;
+
+        let manifest: serde_json::Value = serde_json::from_slice(&std::fs::read(&manifest_path)?)?;
+        let entries = manifest.as_array().unwrap();
+
+        let foo = entries.iter().find(|entry| entry["name"] == "foo").unwrap();
+        assert_eq!(foo["types"], serde_json::json!(["module"]));
+        assert_eq!(foo["size"], "import bar".len());
+        assert_eq!(foo["origin"], "add_python_resource");
+        assert_eq!(foo["source"], true);
+        assert_eq!(foo["bytecode"], true);
+
+        assert!(entries.iter().any(|entry| entry["name"] == "json"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_write_installed_requirements() -> Result<()> {
+        let temp_dir = tempdir::TempDir::new("pyoxidizer-test")?;
+        let site_packages = temp_dir.path().join("site-packages");
+
+        for (path, data) in &[
+            ("foo/__init__.py", "# foo"),
+            ("foo-1.0.dist-info/METADATA", "Name: foo\nVersion: 1.0\n"),
+            ("bar.py", "# bar"),
+            ("bar-2.1.dist-info/METADATA", "Name: bar\nVersion: 2.1\n"),
+        ] {
+            let path = site_packages.join(path);
+            std::fs::create_dir_all(path.parent().unwrap())?;
+            std::fs::write(&path, data)?;
+        }
+
+        let mut env = StarlarkEnvironment::new_with_exe()?;
+        env.eval(&format!(
+            "set_cwd({:?})",
+            temp_dir.path().display().to_string()
+        ))?;
+        env.eval(&format!(
+            "exe.add_python_resources(exe.read_site_packages({:?}))",
+            site_packages.display().to_string()
+        ))?;
+        env.eval("exe.write_installed_requirements('requirements.txt')")?;
+
+        assert_eq!(
+            std::fs::read_to_string(temp_dir.path().join("requirements.txt"))?,
+            "bar==2.1\nfoo==1.0\n"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_set_run_command_from_entry_point() -> Result<()> {
+        let temp_dir = tempdir::TempDir::new("pyoxidizer-test")?;
+        let site_packages = temp_dir.path().join("site-packages");
+
+        for (path, data) in &[
+            ("my_app/__init__.py", "# my_app"),
+            ("my_app/cli.py", "def main():\n    return 0\n"),
+            ("my_app-1.0.dist-info/METADATA", "Name: My-App\nVersion: 1.0\n"),
+            (
+                "my_app-1.0.dist-info/entry_points.txt",
+                "[console_scripts]\nmy-app = my_app.cli:main\n\n[my_app.plugins]\nplugin = my_app.cli\n",
+            ),
+        ] {
+            let path = site_packages.join(path);
+            std::fs::create_dir_all(path.parent().unwrap())?;
+            std::fs::write(&path, data)?;
+        }
+
+        let mut env = StarlarkEnvironment::new_with_exe()?;
+        env.eval(&format!(
+            "exe.add_python_resources(exe.read_site_packages({:?}))",
+            site_packages.display().to_string()
+        ))?;
+
+        assert!(env
+            .eval("exe.set_run_command_from_entry_point('missing', 'my-app')")
+            .is_err());
+        assert!(env
+            .eval("exe.set_run_command_from_entry_point('my_app', 'missing')")
+            .is_err());
+        assert!(env
+            .eval("exe.set_run_command_from_entry_point('my_app', 'plugin')")
+            .is_err());
+
+        env.eval("exe.set_run_command_from_entry_point('my_app', 'my-app')")?;
+
+        let exe = env.eval("exe")?;
+        let exe = exe.downcast_ref::<PythonExecutable>().unwrap();
+        assert_eq!(
+            exe.exe.run_mode(),
+            &PythonRunMode::Eval {
+                code: "import sys; from my_app.cli import main; sys.exit(main())".to_string()
+            }
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_overwrite_protection() -> Result<()> {
+        let temp_dir = tempdir::TempDir::new("pyoxidizer-test")?;
+        let logger = crate::testutil::get_logger()?;
+
+        let mut env = StarlarkEnvironment::new_with_exe()?;
+        assert!(env
+            .eval("exe.set_overwrite_protection(enabled = 1)")
+            .is_err());
+        env.eval("exe.set_overwrite_protection()")?;
+        env.eval("forced = dist.to_python_executable('testapp')")?;
+        env.eval("forced.set_overwrite_protection(force = True)")?;
+
+        let exe_path = temp_dir.path().join(executable_filename(
+            "testapp",
+            crate::project_building::HOST,
+        ));
+        std::fs::write(&exe_path, b"precious")?;
+
+        let exe = env.eval("exe")?;
+        let forced = env.eval("forced")?;
+
+        let context = BuildContext {
+            logger,
+            host_triple: crate::project_building::HOST.to_string(),
+            target_triple: crate::project_building::HOST.to_string(),
+            release: false,
+            opt_level: "0".to_string(),
+            output_path: temp_dir.path().to_path_buf(),
+            type_values: &env.type_values,
+        };
+
+        let err = exe
+            .downcast_mut::<PythonExecutable>()
+            .unwrap()
+            .unwrap()
+            .build(&context)
+            .unwrap_err();
+        assert!(err.to_string().as_str().contains(&format!(
+            "refusing to overwrite existing {}",
+            exe_path.display()
+        )));
+        assert_eq!(std::fs::read(&exe_path)?, b"precious");
+
+        forced
+            .downcast_mut::<PythonExecutable>()
+            .unwrap()
+            .unwrap()
+            .build(&context)?;
+        assert_ne!(std::fs::read(&exe_path)?, b"precious");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_build_reuses_unchanged_executable() -> Result<()> {
+        let temp_dir = tempdir::TempDir::new("pyoxidizer-test")?;
+        let drain = CapturingDrain::default();
+        let logger = slog::Logger::root(drain.clone(), slog::o!());
+
+        let mut env = StarlarkEnvironment::new_with_exe()?;
+        let exe = env.eval("exe")?;
+        let mut exe = exe.downcast_mut::<PythonExecutable>().unwrap().unwrap();
+
+        let context = BuildContext {
+            logger: logger.clone(),
+            host_triple: crate::project_building::HOST.to_string(),
+            target_triple: crate::project_building::HOST.to_string(),
+            release: false,
+            opt_level: "0".to_string(),
+            output_path: temp_dir.path().to_path_buf(),
+            type_values: &env.type_values,
+        };
+
+        let exe_path = match exe.build(&context)?.run_mode {
+            RunMode::Path { path } => path,
+            RunMode::None => panic!("executable should be runnable"),
+        };
+        let modified = std::fs::metadata(&exe_path)?.modified()?;
+        assert!(!drain
+            .messages
+            .lock()
+            .unwrap()
+            .iter()
+            .any(|m| m.as_str().contains("reusing existing executable")));
+
+        match exe.build(&context)?.run_mode {
+            RunMode::Path { path } => assert_eq!(path, exe_path),
+            RunMode::None => panic!("executable should be runnable"),
+        }
+        assert_eq!(std::fs::metadata(&exe_path)?.modified()?, modified);
+        assert!(drain
+            .messages
+            .lock()
+            .unwrap()
+            .iter()
+            .any(|m| m.as_str().contains("reusing existing executable")));
+
+        // A modified executable is rebuilt.
+        drain.messages.lock().unwrap().clear();
+        let mut data = std::fs::read(&exe_path)?;
+        data.push(0);
+        std::fs::write(&exe_path, &data)?;
+        exe.build(&context)?;
+        assert_ne!(std::fs::read(&exe_path)?, data);
+        assert!(!drain
+            .messages
+            .lock()
+            .unwrap()
+            .iter()
+            .any(|m| m.as_str().contains("reusing existing executable")));
+
+        // Changes to the content of files backing resources change the
+        // input fingerprint.
+        let source_path = temp_dir.path().join("foo.py");
+        std::fs::write(&source_path, b"import bar")?;
+        let cache_tag = exe.exe.cache_tag().to_string();
+        let mut file_backed = exe.exe.clone_box();
+        file_backed.add_python_module_source(
+            &PythonModuleSource {
+                name: "foo".to_string(),
+                source: DataLocation::Path(source_path.clone()),
+                is_package: false,
+                cache_tag,
+                is_stdlib: false,
+                is_test: false,
+            },
+            None,
+        )?;
+        let original = file_backed.input_fingerprint()?;
+        assert_eq!(file_backed.input_fingerprint()?, original);
+        std::fs::write(&source_path, b"import baz")?;
+        assert_ne!(file_backed.input_fingerprint()?, original);
+
+        // Changes to resources or config change the fingerprint.
+        let mut embedded = exe.exe.to_embedded_python_context(&logger, "0")?;
+        let original = embedded.fingerprint()?;
+        assert_eq!(embedded.fingerprint()?, original);
+
+        embedded.config.sys_frozen = !embedded.config.sys_frozen;
+        assert_ne!(embedded.fingerprint()?, original);
+
+        let cache_tag = exe.exe.cache_tag().to_string();
+        exe.exe.add_python_module_source(
+            &PythonModuleSource {
+                name: "foo".to_string(),
+                source: DataLocation::Memory(b"import bar".to_vec()),
+                is_package: false,
+                cache_tag,
+                is_stdlib: false,
+                is_test: false,
+            },
+            None,
+        )?;
+        assert_ne!(
+            exe.exe
+                .to_embedded_python_context(&logger, "0")?
+                .fingerprint()?,
+            original
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_previous_build_fingerprint_file() {
+        let build = PreviousBuild {
+            exe_digest: "aa".to_string(),
+            resources_digest: "bb".to_string(),
+            has_extra_files: true,
+        };
+        let data = build.to_fingerprint_file("inputs 42\n");
+
+        assert_eq!(
+            data,
+            "inputs 42\noutput aa\nresources bb\nextra-files true\n"
+        );
+        assert_eq!(PreviousBuild::parse(&data, "inputs 42\n"), Some(build));
+        assert_eq!(PreviousBuild::parse(&data, "inputs 43\n"), None);
+        assert_eq!(PreviousBuild::parse("inputs 42\n", "inputs 42\n"), None);
+    }
+
+    #[test]
+    fn test_build_timings() -> Result<()> {
+        let temp_dir = tempdir::TempDir::new("pyoxidizer-test")?;
+        let drain = CapturingDrain::default();
+        let logger = slog::Logger::root(drain.clone(), slog::o!());
+
+        let mut env = StarlarkEnvironment::new_with_exe()?;
+        let exe = env.eval("exe")?;
+        let mut exe = exe.downcast_mut::<PythonExecutable>().unwrap().unwrap();
+
+        let context = BuildContext {
+            logger,
+            host_triple: crate::project_building::HOST.to_string(),
+            target_triple: crate::project_building::HOST.to_string(),
+            release: false,
+            opt_level: "0".to_string(),
+            output_path: temp_dir.path().to_path_buf(),
+            type_values: &env.type_values,
+        };
+
+        let resolved = exe.build(&context)?;
+        assert_eq!(
+            resolved.timings.keys().collect::<Vec<_>>(),
+            vec![
+                "bytecode_compilation",
+                "distribution_resolution",
+                "resource_collection",
+                "rust_compile"
+            ]
+        );
+        assert!(drain
+            .messages
+            .lock()
+            .unwrap()
+            .iter()
+            .any(|m| m.starts_with("rust_compile took ")));
+
+        // Reusing an existing executable doesn't compile Rust.
+        let resolved = exe.build(&context)?;
+        assert!(!resolved.timings.contains_key("rust_compile"));
+        assert!(resolved.timings.contains_key("resource_collection"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_to_tarball() -> Result<()> {
+        let temp_dir = tempdir::TempDir::new("pyoxidizer-test")?;
+        let logger = crate::testutil::get_logger()?;
+
+        let mut env = StarlarkEnvironment::new()?;
+        env.eval("dist = default_python_distribution()")?;
+        env.eval("policy = dist.make_python_packaging_policy()")?;
+        env.eval("policy.resources_location_fallback = 'filesystem-relative:lib'")?;
+        env.eval("exe = dist.to_python_executable('testapp', packaging_policy = policy)")?;
+        env.eval("m = exe.make_python_module_source('foo', 'x = 1')")?;
+        env.eval("m.add_location = 'filesystem-relative:lib'")?;
+        env.eval("exe.add_python_resource(m)")?;
+
+        assert!(env.eval("exe.to_tarball(path_prefix = '/abs')").is_err());
+
+        let tarball = env.eval("exe.to_tarball(path_prefix = 'testapp-1.0')")?;
+        assert_eq!(tarball.get_type(), "PythonExecutableTarball");
+        let mut tarball = tarball
+            .downcast_mut::<PythonExecutableTarball>()
+            .unwrap()
+            .unwrap();
+
+        let context = BuildContext {
+            logger,
+            host_triple: crate::project_building::HOST.to_string(),
+            target_triple: crate::project_building::HOST.to_string(),
+            release: false,
+            opt_level: "0".to_string(),
+            output_path: temp_dir.path().to_path_buf(),
+            type_values: &env.type_values,
+        };
+
+        tarball.build(&context)?;
+
+        let fh = std::fs::File::open(temp_dir.path().join("testapp.tar.gz"))?;
+        let mut archive = tar::Archive::new(flate2::read::GzDecoder::new(fh));
+
+        let entries = archive
+            .entries()?
+            .map(|entry| {
+                let entry = entry?;
+                Ok((entry.path()?.to_path_buf(), entry.header().mode()?))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let exe_path = Path::new("testapp-1.0").join(executable_filename(
+            "testapp",
+            crate::project_building::HOST,
+        ));
+        let (_, exe_mode) = entries.iter().find(|(path, _)| path == &exe_path).unwrap();
+        assert_eq!(exe_mode & 0o777, 0o755);
+
+        assert!(entries
+            .iter()
+            .any(|(path, _)| path.starts_with("testapp-1.0/lib") && path.ends_with("foo.py")));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_to_resources_bundle() -> Result<()> {
+        let mut env = StarlarkEnvironment::new_with_exe()?;
+
+        assert!(env
+            .eval("exe.to_resources_bundle('../foo.pyoxidizer')")
+            .is_err());
+        assert!(env
+            .eval("exe.to_resources_bundle('/foo.pyoxidizer')")
+            .is_err());
+
+        let bundle = env.eval("exe.to_resources_bundle()")?;
+        assert_eq!(bundle.get_type(), "PythonResourcesBundle");
+        let bundle = bundle.downcast_ref::<PythonResourcesBundle>().unwrap();
+        assert_eq!(bundle.filename, "testapp.pyoxidizer");
+        assert_eq!(
+            bundle.exe.exe.resources_bundle_filename(),
+            Some("testapp.pyoxidizer".to_string())
+        );
+
+        let bundle = env.eval("exe.to_resources_bundle('resources.bin')")?;
+        let bundle = bundle.downcast_ref::<PythonResourcesBundle>().unwrap();
+        assert_eq!(bundle.filename, "resources.bin");
+
+        let exe = env.eval("exe")?;
+        let exe = exe.downcast_ref::<PythonExecutable>().unwrap();
+        assert!(exe.exe.resources_bundle_filename().is_none());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_build_checksums() -> Result<()> {
+        let temp_dir = tempdir::TempDir::new("pyoxidizer-test")?;
+        let drain = CapturingDrain::default();
+        let logger = slog::Logger::root(drain.clone(), slog::o!());
+
+        let mut env = StarlarkEnvironment::new_with_exe()?;
+        let exe = env.eval("exe")?;
+        let mut exe = exe.downcast_mut::<PythonExecutable>().unwrap().unwrap();
+
+        let context = BuildContext {
+            logger,
+            host_triple: crate::project_building::HOST.to_string(),
+            target_triple: crate::project_building::HOST.to_string(),
+            release: false,
+            opt_level: "0".to_string(),
+            output_path: temp_dir.path().to_path_buf(),
+            type_values: &env.type_values,
+        };
+
+        exe.build(&context)?;
+
+        let exe_name = executable_filename(&exe.exe.name(), &context.target_triple);
+        let exe_path = temp_dir.path().join(&exe_name);
+        let checksum_path = temp_dir.path().join(format!("{}.sha256", exe_name));
+
+        let digest = hex::encode(Sha256::digest(&std::fs::read(&exe_path)?));
+        assert_eq!(
+            std::fs::read_to_string(&checksum_path)?,
+            format!("{}  {}\n", digest, exe_name)
+        );
+
+        let messages = drain.messages.lock().unwrap();
+        assert!(messages.iter().any(|m| m.ends_with(&digest)));
+        assert!(messages
+            .iter()
+            .any(|m| m.starts_with("sha256 of packed resources: ")));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_set_macos_signing() -> Result<()> {
+        let mut env = StarlarkEnvironment::new_with_exe()?;
+
+        assert!(env.eval("exe.set_macos_signing('')").is_err());
+        assert!(env.eval("exe.set_macos_signing(42)").is_err());
+
+        env.eval("exe.set_macos_signing('Developer ID Application: Example', entitlements_path='entitlements.plist')")?;
+
+        let exe = env.eval("exe")?;
+        let exe = exe.downcast_ref::<PythonExecutable>().unwrap();
+        assert_eq!(
+            exe.macos_signing,
+            Some(MacOsSigningConfig {
+                identity: "Developer ID Application: Example".to_string(),
+                entitlements_path: Some(std::env::current_dir()?.join("entitlements.plist")),
+                hardened_runtime: true,
+            })
+        );
+
+        assert!(env
+            .eval("exe.set_macos_signing('-', hardened_runtime=None)")
+            .is_err());
+        env.eval("exe.set_macos_signing('-', hardened_runtime=False)")?;
+
+        let exe = env.eval("exe")?;
+        let exe = exe.downcast_ref::<PythonExecutable>().unwrap();
+        assert_eq!(
+            exe.macos_signing,
+            Some(MacOsSigningConfig {
+                identity: "-".to_string(),
+                entitlements_path: None,
+                hardened_runtime: false,
+            })
+        );
+
+        Ok(())
     }
 
-    #[allow(clippy::ptr_arg)]
-    PythonExecutable.filter_resources_from_files(
-        env env,
-        this,
-        files=NoneType::None,
-        glob_files=NoneType::None)
-    {
-        match this.clone().downcast_mut::<PythonExecutable>()? {
-            Some(mut exe) => exe.starlark_filter_resources_from_files(&env, &files, &glob_files),
-            None => Err(ValueError::IncorrectParameterType),
-        }
-    }
+    #[test]
+    #[cfg(target_os = "macos")]
+    fn test_build_macos_signing() -> Result<()> {
+        let temp_dir = tempdir::TempDir::new("pyoxidizer-test")?;
+        let drain = CapturingDrain::default();
+        let logger = slog::Logger::root(drain.clone(), slog::o!());
 
-    #[allow(clippy::ptr_arg)]
-    PythonExecutable.to_embedded_resources(this) {
-        match this.clone().downcast_ref::<PythonExecutable>() {
-            Some(exe) => exe.starlark_to_embedded_resources(),
-            None => Err(ValueError::IncorrectParameterType),
-        }
-    }
-}
+        let mut env = StarlarkEnvironment::new_with_exe()?;
+        // `-` performs ad-hoc signing, which doesn't require a certificate.
+        env.eval("exe.set_macos_signing('-')")?;
 
-#[cfg(test)]
-mod tests {
-    use {super::super::testutil::*, super::*, crate::python_distributions::PYTHON_DISTRIBUTIONS};
+        let exe = env.eval("exe")?;
+        let mut exe = exe.downcast_mut::<PythonExecutable>().unwrap().unwrap();
+
+        let context = BuildContext {
+            logger,
+            host_triple: crate::project_building::HOST.to_string(),
+            target_triple: crate::project_building::HOST.to_string(),
+            release: false,
+            opt_level: "0".to_string(),
+            output_path: temp_dir.path().to_path_buf(),
+            type_values: &env.type_values,
+        };
+
+        let exe_path = match exe.build(&context)?.run_mode {
+            RunMode::Path { path } => path,
+            RunMode::None => panic!("executable should be runnable"),
+        };
+
+        assert!(drain.messages.lock().unwrap().iter().any(|m| m
+            == &format!(
+                "running codesign --force --sign - --options runtime {}",
+                exe_path.display()
+            )));
+
+        let status = std::process::Command::new("codesign")
+            .arg("--verify")
+            .arg(&exe_path)
+            .status()?;
+        assert!(status.success());
+
+        // Signing with an unknown identity fails.
+        let signing = MacOsSigningConfig {
+            identity: "pyoxidizer-test-nonexistent-identity".to_string(),
+            entitlements_path: None,
+            hardened_runtime: true,
+        };
+        assert!(signing.sign(&context.logger, &exe_path).is_err());
+
+        Ok(())
+    }
 
     #[test]
-    fn test_default_values() -> Result<()> {
+    fn test_set_build_environment_variable() -> Result<()> {
         let mut env = StarlarkEnvironment::new_with_exe()?;
-        let exe = env.eval("exe")?;
 
-        assert_eq!(exe.get_type(), "PythonExecutable");
+        assert!(env
+            .eval("exe.set_build_environment_variable('', 'value')")
+            .is_err());
+        assert!(env
+            .eval("exe.set_build_environment_variable('1FOO', 'value')")
+            .is_err());
+        assert!(env
+            .eval("exe.set_build_environment_variable('FOO=BAR', 'value')")
+            .is_err());
+        assert!(env
+            .eval("exe.set_build_environment_variable('RUSTFLAGS', 42)")
+            .is_err());
 
+        env.eval("exe.set_build_environment_variable('RUSTFLAGS', '-C target-cpu=x86-64')")?;
+        env.eval("exe.set_build_environment_variable('RUSTFLAGS', '-C target-cpu=native')")?;
+        env.eval("exe.set_build_environment_variable('CARGO_TARGET_X86_64_UNKNOWN_LINUX_GNU_LINKER', 'clang')")?;
+
+        let exe = env.eval("exe")?;
         let exe = exe.downcast_ref::<PythonExecutable>().unwrap();
-        assert!(exe
-            .exe
-            .iter_resources()
-            .any(|(_, r)| r.in_memory_source.is_some()));
-        assert!(exe
-            .exe
-            .iter_resources()
-            .all(|(_, r)| r.in_memory_resources.is_none()));
+        assert_eq!(
+            exe.exe
+                .build_environment_variables()
+                .iter()
+                .map(|(k, v)| (k.as_str(), v.as_str()))
+                .collect::<Vec<_>>(),
+            vec![
+                ("CARGO_TARGET_X86_64_UNKNOWN_LINUX_GNU_LINKER", "clang"),
+                ("RUSTFLAGS", "-C target-cpu=native"),
+            ]
+        );
 
         Ok(())
     }
 
     #[test]
-    fn test_no_sources() -> Result<()> {
-        let mut env = StarlarkEnvironment::new()?;
+    fn test_add_frozen_module() -> Result<()> {
+        let logger = crate::testutil::get_logger()?;
+        let mut env = StarlarkEnvironment::new_with_exe()?;
 
-        env.eval("dist = default_python_distribution()")?;
-        env.eval("policy = dist.make_python_packaging_policy()")?;
-        env.eval("policy.include_distribution_sources = False")?;
+        assert!(env.eval("exe.add_frozen_module('', 'x = 1')").is_err());
+        assert!(env
+            .eval("exe.add_frozen_module('foo..bar', 'x = 1')")
+            .is_err());
+        assert!(env.eval("exe.add_frozen_module('1foo', 'x = 1')").is_err());
+        assert!(env.eval("exe.add_frozen_module('foo', 'def (')").is_err());
 
-        let exe = env.eval("dist.to_python_executable('testapp', packaging_policy=policy)")?;
+        env.eval("exe.add_frozen_module('myapp.frozen', 'x = 1')")?;
+        env.eval("exe.add_frozen_module('myapp', '', is_package=True)")?;
 
-        assert_eq!(exe.get_type(), "PythonExecutable");
+        let exe = env.eval("exe")?;
+        let exe = exe.downcast_ref::<PythonExecutable>().unwrap();
+        let context = exe.exe.to_embedded_python_context(&logger, "0")?;
+
+        let code = context
+            .config
+            .to_oxidized_python_interpreter_config_rs(None)?;
+        assert!(code.as_str().contains("extra_frozen_modules: Some(vec![pyembed::FrozenModule { name: std::ffi::CString::new(r#\"myapp\"#).unwrap(),"));
+        assert!(code.as_str().contains(
+            "pyembed::FrozenModule { name: std::ffi::CString::new(r#\"myapp.frozen\"#).unwrap(),"
+        ));
+        assert!(code.as_str().contains("is_package: true"));
+        assert!(!code.as_str().contains("r#\"foo\"#"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_replace_module_source() -> Result<()> {
+        let logger = crate::testutil::get_logger()?;
+        let mut env = StarlarkEnvironment::new_with_exe()?;
 
+        assert!(env
+            .eval("exe.replace_module_source('myapp_version', 'x = 1')")
+            .is_err());
+
+        env.eval("exe.add_python_resource(exe.make_python_module_source('myapp_version', 'VERSION = \"dev-version\"'))")?;
+        env.eval("exe.replace_module_source('myapp_version', 'VERSION = \"1.2.3-release\"')")?;
+
+        let exe = env.eval("exe")?;
         let exe = exe.downcast_ref::<PythonExecutable>().unwrap();
-        assert!(exe
+
+        let (_, resource) = exe
             .exe
             .iter_resources()
-            .all(|(_, r)| r.in_memory_source.is_none()));
+            .find(|(name, _)| *name == "myapp_version")
+            .unwrap();
+        assert_eq!(
+            resource.in_memory_source,
+            Some(DataLocation::Memory(
+                b"VERSION = \"1.2.3-release\"".to_vec()
+            ))
+        );
+
+        let context = exe.exe.to_embedded_python_context(&logger, "0")?;
+        let contains = |needle: &[u8]| {
+            context
+                .resources
+                .windows(needle.len())
+                .any(|window| window == needle)
+        };
+        assert!(contains(b"1.2.3-release"));
+        assert!(!contains(b"dev-version"));
 
         Ok(())
     }
 
     #[test]
-    fn test_make_python_module_source() -> Result<()> {
+    fn test_set_packed_resources_compression() -> Result<()> {
         let mut env = StarlarkEnvironment::new_with_exe()?;
-        let m = env.eval("exe.make_python_module_source('foo', 'import bar')")?;
 
-        assert_eq!(m.get_type(), PythonModuleSourceValue::TYPE);
-        assert_eq!(m.get_attr("name").unwrap().to_str(), "foo");
-        assert_eq!(m.get_attr("source").unwrap().to_str(), "import bar");
-        assert_eq!(m.get_attr("is_package").unwrap().to_bool(), false);
+        let level = |env: &mut StarlarkEnvironment| -> Result<Option<i32>> {
+            let exe = env.eval("exe")?;
+            let exe = exe.downcast_ref::<PythonExecutable>().unwrap();
+
+            Ok(exe.exe.packed_resources_compression_level())
+        };
+
+        assert_eq!(level(&mut env)?, None);
+
+        assert!(env.eval("exe.set_packed_resources_compression(0)").is_err());
+        assert!(env
+            .eval("exe.set_packed_resources_compression(23)")
+            .is_err());
+        assert!(env
+            .eval("exe.set_packed_resources_compression('3')")
+            .is_err());
+
+        env.eval("exe.set_packed_resources_compression(19)")?;
+        assert_eq!(level(&mut env)?, Some(19));
+
+        env.eval("exe.set_packed_resources_compression(None)")?;
+        assert_eq!(level(&mut env)?, None);
 
         Ok(())
     }
 
     #[test]
-    fn test_make_python_module_source_callback() -> Result<()> {
-        let mut env = StarlarkEnvironment::new()?;
-        env.eval("dist = default_python_distribution()")?;
-        env.eval("policy = dist.make_python_packaging_policy()")?;
-        env.eval(
-            "def my_func(policy, resource):\n    resource.add_source = True\n    resource.add_bytecode_optimization_level_two = True\n",
-        )?;
-        env.eval("policy.register_resource_callback(my_func)")?;
-        env.eval("exe = dist.to_python_executable('testapp', packaging_policy = policy)")?;
+    fn test_set_windows_metadata() -> Result<()> {
+        let temp_dir = tempdir::TempDir::new("pyoxidizer-test")?;
+        let icon_path = temp_dir.path().join("app.ico");
+        std::fs::write(&icon_path, b"icon")?;
 
-        let m = env.eval("exe.make_python_module_source('foo', 'import bar')")?;
+        let mut env = StarlarkEnvironment::new_with_exe()?;
 
-        assert_eq!(m.get_type(), PythonModuleSourceValue::TYPE);
-        assert_eq!(m.get_attr("name").unwrap().to_str(), "foo");
-        assert_eq!(m.get_attr("source").unwrap().to_str(), "import bar");
-        assert_eq!(m.get_attr("is_package").unwrap().to_bool(), false);
-        assert_eq!(m.get_attr("add_source").unwrap().to_bool(), true);
+        assert!(env
+            .eval("exe.set_windows_metadata(file_version='1.2.3.4.5')")
+            .is_err());
+        assert!(env
+            .eval("exe.set_windows_metadata(file_version='1.x')")
+            .is_err());
+        assert!(env
+            .eval("exe.set_windows_metadata(icon_path='does-not-exist.ico')")
+            .is_err());
+
+        env.eval(&format!(
+            "exe.set_windows_metadata(file_version='1.2.3', product_name='My App', icon_path={:?})",
+            icon_path.display().to_string()
+        ))?;
+
+        let exe = env.eval("exe")?;
+        let exe = exe.downcast_ref::<PythonExecutable>().unwrap();
         assert_eq!(
-            m.get_attr("add_bytecode_optimization_level_two")
-                .unwrap()
-                .to_bool(),
-            true
+            exe.exe.windows_resource_metadata(),
+            &WindowsResourceMetadata {
+                file_version: Some("1.2.3".to_string()),
+                product_name: Some("My App".to_string()),
+                icon_path: Some(icon_path),
+            }
         );
 
         Ok(())
     }
 
     #[test]
-    fn test_pip_download_pyflakes() -> Result<()> {
-        for target_triple in PYTHON_DISTRIBUTIONS.all_target_triples() {
-            let mut env = StarlarkEnvironment::new()?;
-            env.set_target_triple(target_triple)?;
+    fn test_post_build_callback() -> Result<()> {
+        let temp_dir = tempdir::TempDir::new("pyoxidizer-test")?;
+        let logger = crate::testutil::get_logger()?;
 
-            env.eval("dist = default_python_distribution()")?;
-            env.eval("exe = dist.to_python_executable('testapp')")?;
+        let mut env = StarlarkEnvironment::new_with_exe()?;
 
-            let resources = env.eval("exe.pip_download(['pyflakes==2.2.0'])")?;
+        let exe_path = temp_dir.path().join(executable_filename(
+            "testapp",
+            crate::project_building::HOST,
+        ));
+        env.eval(&format!(
+            "EXPECTED_PATH = {:?}",
+            exe_path.display().to_string()
+        ))?;
+        env.eval(
+            "def check_path(path):\n    if path != EXPECTED_PATH:\n        fail('unexpected path: ' + path)",
+        )?;
+        env.eval("def failing_callback(path):\n    fail('callback failed')")?;
 
-            assert_eq!(resources.get_type(), "list");
+        assert!(env.eval("exe.register_post_build_callback('foo')").is_err());
 
-            let raw_it = resources.iter().unwrap();
-            let mut it = raw_it.iter();
+        env.eval("exe.register_post_build_callback(check_path)")?;
 
-            let v = it.next().unwrap();
-            assert_eq!(v.get_type(), PythonModuleSourceValue::TYPE);
-            let x = v.downcast_ref::<PythonModuleSourceValue>().unwrap();
-            assert!(x.inner.package().starts_with("pyflakes"));
+        let exe = env.eval("exe")?;
+        let failing_callback = env.eval("failing_callback")?;
+
+        let context = BuildContext {
+            logger,
+            host_triple: crate::project_building::HOST.to_string(),
+            target_triple: crate::project_building::HOST.to_string(),
+            release: false,
+            opt_level: "0".to_string(),
+            output_path: temp_dir.path().to_path_buf(),
+            type_values: &env.type_values,
+        };
+
+        let mut exe = exe.downcast_mut::<PythonExecutable>().unwrap().unwrap();
+        assert_eq!(exe.post_build_callbacks.len(), 1);
+
+        match exe.build(&context)?.run_mode {
+            RunMode::Path { path } => assert_eq!(path, exe_path),
+            RunMode::None => panic!("executable should be runnable"),
         }
 
+        // Errors from callbacks fail the build. Callbacks also run when the
+        // existing executable is reused.
+        exe.starlark_register_post_build_callback(&failing_callback)
+            .unwrap();
+
+        let err = exe.build(&context).unwrap_err().to_string();
+        assert!(err
+            .as_str()
+            .contains("error calling post-build callback failing_callback(path)"));
+
         Ok(())
     }
 
     #[test]
-    fn test_pip_install_simple() -> Result<()> {
+    fn test_filter_resources_from_files_regexes() -> Result<()> {
+        let mut env = StarlarkEnvironment::new_with_exe()?;
+
+        env.eval("exe.add_python_resource(exe.make_python_module_source('mypkg', '', True))")?;
+        env.eval("exe.add_python_resource(exe.make_python_module_source('mypkg.foo', ''))")?;
+        env.eval("exe.add_python_resource(exe.make_python_module_source('mypkg.bar', ''))")?;
+        env.eval("exe.add_python_resource(exe.make_python_module_source('otherpkg', ''))")?;
+
+        let err = env
+            .eval("exe.filter_resources_from_files(regexes=['^mypkg('])")
+            .unwrap_err();
+        assert!(err
+            .to_string()
+            .as_str()
+            .contains("invalid regular expression ^mypkg("));
+
+        env.eval(r"exe.filter_resources_from_files(regexes=['^mypkg\\.'])")?;
+
+        let exe = env.eval("exe")?;
+        let exe = exe.downcast_ref::<PythonExecutable>().unwrap();
+
+        let names = exe
+            .exe
+            .iter_resources()
+            .map(|(name, _)| name.clone())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            names,
+            vec!["mypkg.bar".to_string(), "mypkg.foo".to_string()]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_drop_test_packages() -> Result<()> {
         let mut env = StarlarkEnvironment::new()?;
 
+        // Only resources added below can belong to test packages.
         env.eval("dist = default_python_distribution()")?;
         env.eval("policy = dist.make_python_packaging_policy()")?;
         env.eval("policy.include_distribution_sources = False")?;
+        env.eval("policy.include_distribution_resources = False")?;
         env.eval("exe = dist.to_python_executable('testapp', packaging_policy = policy)")?;
 
-        let resources = env.eval("exe.pip_install(['pyflakes==2.1.1'])")?;
-        assert_eq!(resources.get_type(), "list");
+        env.eval("exe.add_python_resource(exe.make_python_module_source('foo', '', True))")?;
+        env.eval("exe.add_python_resource(exe.make_python_module_source('foo.core', ''))")?;
+        env.eval("exe.add_python_resource(exe.make_python_module_source('foo.tests', '', True))")?;
+        env.eval("exe.add_python_resource(exe.make_python_module_source('foo.tests.test_x', ''))")?;
+        env.eval("exe.add_python_resource(exe.make_python_module_source('foo.checks', ''))")?;
 
-        let raw_it = resources.iter().unwrap();
-        let mut it = raw_it.iter();
+        assert!(env
+            .eval("exe.drop_test_packages(package_names='tests')")
+            .is_err());
 
-        let v = it.next().unwrap();
-        assert_eq!(v.get_type(), PythonModuleSourceValue::TYPE);
-        let x = v.downcast_ref::<PythonModuleSourceValue>().unwrap();
-        assert_eq!(x.inner.name, "pyflakes");
-        assert!(x.inner.is_package);
+        env.eval_assert("exe.drop_test_packages() == 2")?;
+
+        let names = |env: &mut StarlarkEnvironment| -> Result<Vec<String>> {
+            let exe = env.eval("exe")?;
+            let exe = exe.downcast_ref::<PythonExecutable>().unwrap();
+
+            Ok(exe
+                .exe
+                .iter_resources()
+                .map(|(name, _)| name.clone())
+                .filter(|name| name.starts_with("foo."))
+                .collect())
+        };
+
+        assert_eq!(
+            names(&mut env)?,
+            vec!["foo.checks".to_string(), "foo.core".to_string()]
+        );
+
+        env.eval_assert("exe.drop_test_packages(package_names=['checks']) == 1")?;
+        assert_eq!(names(&mut env)?, vec!["foo.core".to_string()]);
 
         Ok(())
     }
 
     #[test]
-    fn test_read_package_root_simple() -> Result<()> {
+    fn test_prune_unused_stdlib() -> Result<()> {
+        let mut env = StarlarkEnvironment::new_with_exe()?;
+
+        env.eval(
+            "exe.add_python_resource(exe.make_python_module_source('myapp', 'import json\\n'))",
+        )?;
+
+        assert!(env.eval("exe.prune_unused_stdlib('myapp')").is_err());
+        assert!(env.eval("exe.prune_unused_stdlib(['missing'])").is_err());
+
+        let names = |env: &mut StarlarkEnvironment| -> Result<BTreeSet<String>> {
+            let exe = env.eval("exe")?;
+            let exe = exe.downcast_ref::<PythonExecutable>().unwrap();
+
+            Ok(exe
+                .exe
+                .iter_resources()
+                .map(|(name, _)| name.clone())
+                .collect())
+        };
+
+        // Large packages unrelated to json.
+        let before = names(&mut env)?;
+        let unrelated = ["curses", "idlelib", "sqlite3", "turtledemo"]
+            .iter()
+            .filter(|package| before.contains(**package))
+            .map(|package| package.to_string())
+            .collect::<Vec<_>>();
+        assert!(!unrelated.is_empty());
+
+        let res = env.eval("exe.prune_unused_stdlib(['myapp'])")?;
+        assert_eq!(res.get_type(), "dict");
+        env.eval_assert("exe.prune_unused_stdlib(['myapp'])['removed'] == []")?;
+
+        let removed = res
+            .at(Value::from("removed"))
+            .unwrap()
+            .iter()
+            .unwrap()
+            .iter()
+            .map(|x| x.to_string())
+            .collect::<BTreeSet<_>>();
+        assert!(
+            res.at(Value::from("bytes_saved"))
+                .unwrap()
+                .to_int()
+                .unwrap()
+                > 0
+        );
+
+        let names = names(&mut env)?;
+
+        for package in &unrelated {
+            assert!(removed.contains(package));
+            assert!(!names.contains(package));
+            assert!(!names
+                .iter()
+                .any(|name| name.starts_with(&format!("{}.", package))));
+        }
+
+        for name in &[
+            "myapp",
+            "json",
+            "json.decoder",
+            "encodings",
+            "encodings.utf_8",
+        ] {
+            assert!(names.contains(*name), "{} retained", name);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_add_duplicate_resource() -> Result<()> {
         let temp_dir = tempdir::TempDir::new("pyoxidizer-test")?;
+        let root = temp_dir.path().display().to_string();
+        std::fs::write(temp_dir.path().join("foo.py"), "import baz")?;
 
-        let root = temp_dir.path();
-        std::fs::create_dir(root.join("bar"))?;
-        let bar_init = root.join("bar").join("__init__.py");
-        std::fs::write(&bar_init, "# bar")?;
+        let drain = CapturingDrain::default();
 
-        let foo_path = root.join("foo.py");
-        std::fs::write(&foo_path, "# foo")?;
+        let mut env = StarlarkEnvironment::new_with_exe()?;
+        env.set_logger(slog::Logger::root(drain.clone(), slog::o!()))?;
 
-        let baz_path = root.join("baz.py");
-        std::fs::write(&baz_path, "# baz")?;
+        env.eval("exe.add_python_resource(exe.make_python_module_source('foo', 'import bar'))")?;
+        env.eval("exe.add_python_resource(exe.make_python_module_source('foo', 'import bar'))")?;
+        assert!(!drain
+            .messages
+            .lock()
+            .unwrap()
+            .iter()
+            .any(|m| m.as_str().contains("replaces different content")));
 
-        std::fs::create_dir(root.join("extra"))?;
-        let extra_path = root.join("extra").join("__init__.py");
-        std::fs::write(&extra_path, "# extra")?;
+        env.eval(&format!(
+            "exe.add_python_resources(exe.read_package_root({:?}, ['foo']))",
+            root
+        ))?;
+        let expected = format!(
+            "resource foo from {} replaces different content from {}",
+            root,
+            std::env::current_dir()?.join("dummy").display()
+        );
+        assert!(drain
+            .messages
+            .lock()
+            .unwrap()
+            .iter()
+            .any(|m| m == &expected));
+
+        Ok(())
+    }
 
+    #[test]
+    fn test_add_duplicate_resource_strict() -> Result<()> {
         let mut env = StarlarkEnvironment::new()?;
         env.eval("dist = default_python_distribution()")?;
         env.eval("policy = dist.make_python_packaging_policy()")?;
-        env.eval("policy.include_distribution_sources = False")?;
+        env.eval("policy.error_on_duplicate_resources = True")?;
         env.eval("exe = dist.to_python_executable('testapp', packaging_policy = policy)")?;
 
-        let resources = env.eval(&format!(
-            "exe.read_package_root(\"{}\", packages=['foo', 'bar'])",
-            root.display()
-        ))?;
-
-        assert_eq!(resources.get_type(), "list");
-        assert_eq!(resources.length().unwrap(), 2);
-
-        let raw_it = resources.iter().unwrap();
-        let mut it = raw_it.iter();
-
-        let v = it.next().unwrap();
-        assert_eq!(v.get_type(), PythonModuleSourceValue::TYPE);
-        let x = v.downcast_ref::<PythonModuleSourceValue>().unwrap();
-        assert_eq!(x.inner.name, "bar");
-        assert!(x.inner.is_package);
-        assert_eq!(x.inner.source.resolve().unwrap(), b"# bar");
-
-        let v = it.next().unwrap();
-        assert_eq!(v.get_type(), PythonModuleSourceValue::TYPE);
-        let x = v.downcast_ref::<PythonModuleSourceValue>().unwrap();
-        assert_eq!(x.inner.name, "foo");
-        assert!(!x.inner.is_package);
-        assert_eq!(x.inner.source.resolve().unwrap(), b"# foo");
+        env.eval("exe.add_python_resource(exe.make_python_module_source('foo', 'import bar'))")?;
+        let err = env
+            .eval("exe.add_python_resource(exe.make_python_module_source('foo', 'import baz'))")
+            .unwrap_err();
+        assert!(err
+            .to_string()
+            .as_str()
+            .contains("replaces different content"));
 
         Ok(())
     }
@@ -0,0 +1,111 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use {
+    super::{
+        python_executable::PythonExecutable,
+        target::{BuildContext, BuildTarget, ResolvedTarget, RunMode},
+    },
+    crate::app_packaging::resource::FileContent,
+    anyhow::{anyhow, Context, Result},
+    slog::warn,
+    starlark::values::{Mutable, TypedValue, Value},
+    std::path::{Path, PathBuf},
+};
+
+/// A gzip compressed tarball of a Python executable and its extra files.
+pub struct PythonExecutableTarball {
+    pub exe: PythonExecutable,
+
+    /// Directory prefix to add to paths in the archive.
+    pub path_prefix: Option<String>,
+}
+
+impl TypedValue for PythonExecutableTarball {
+    type Holder = Mutable<PythonExecutableTarball>;
+    const TYPE: &'static str = "PythonExecutableTarball";
+
+    fn values_for_descendant_check_and_freeze<'a>(
+        &'a self,
+    ) -> Box<dyn Iterator<Item = Value> + 'a> {
+        self.exe.values_for_descendant_check_and_freeze()
+    }
+}
+
+impl BuildTarget for PythonExecutableTarball {
+    fn build(&mut self, context: &BuildContext) -> Result<ResolvedTarget> {
+        let (target, extra_files) = self.exe.build_with_extra_files(context)?;
+
+        let exe_path = match &target.run_mode {
+            RunMode::Path { path } => path.clone(),
+            RunMode::None => return Err(anyhow!("executable build did not produce a path")),
+        };
+        let exe_name = exe_path
+            .file_name()
+            .ok_or_else(|| anyhow!("{} has no file name", exe_path.display()))?;
+
+        let exe_content = FileContent {
+            data: std::fs::read(&exe_path).context(format!("reading {}", exe_path.display()))?,
+            executable: true,
+        };
+
+        let mut entries = vec![(PathBuf::from(exe_name), &exe_content)];
+        entries.extend(
+            extra_files
+                .entries()
+                .map(|(path, content)| (path.clone(), content)),
+        );
+
+        let tarball_path = context
+            .output_path
+            .join(format!("{}.tar.gz", self.exe.exe.name()));
+
+        warn!(
+            &context.logger,
+            "writing tarball to {}",
+            tarball_path.display()
+        );
+        write_tarball(&tarball_path, self.path_prefix.as_deref(), entries)
+            .context(format!("writing {}", tarball_path.display()))?;
+
+        Ok(ResolvedTarget {
+            run_mode: RunMode::None,
+            output_path: context.output_path.clone(),
+            timings: target.timings,
+        })
+    }
+}
+
+/// Write files to a gzip compressed tarball.
+///
+/// Entries are written in the order given with deterministic metadata.
+/// Executable files have mode 0755. Other files have mode 0644.
+fn write_tarball<'a>(
+    path: &Path,
+    path_prefix: Option<&str>,
+    entries: impl IntoIterator<Item = (PathBuf, &'a FileContent)>,
+) -> Result<()> {
+    let fh = std::fs::File::create(path)?;
+    let encoder = flate2::write::GzEncoder::new(fh, flate2::Compression::default());
+    let mut builder = tar::Builder::new(encoder);
+
+    for (entry_path, content) in entries {
+        let archive_path = match path_prefix {
+            Some(prefix) => Path::new(prefix).join(entry_path),
+            None => entry_path,
+        };
+
+        let mut header = tar::Header::new_gnu();
+        header.set_entry_type(tar::EntryType::Regular);
+        header.set_size(content.data.len() as u64);
+        header.set_mode(if content.executable { 0o755 } else { 0o644 });
+        header.set_mtime(0);
+
+        builder.append_data(&mut header, &archive_path, content.data.as_slice())?;
+    }
+
+    builder.into_inner()?.finish()?;
+
+    Ok(())
+}
@@ -3,7 +3,11 @@
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.
 
 use {
-    super::util::{ToOptional, ToValue, TryToOptional},
+    super::env::{get_context, EnvironmentContext},
+    super::util::{
+        optional_bool_arg, optional_list_arg, optional_str_arg, required_env_name_arg,
+        required_list_arg, required_str_arg, ToOptional, ToValue, TryToOptional,
+    },
     crate::py_packaging::config::EmbeddedPythonConfig,
     python_packaging::{
         interpreter::{
@@ -12,16 +16,63 @@ use {
         },
         resource::BytecodeOptimizationLevel,
     },
-    starlark::values::{
-        error::{
-            RuntimeError, UnsupportedOperation, ValueError, INCORRECT_PARAMETER_TYPE_ERROR_CODE,
+    starlark::{
+        environment::TypeValues,
+        starlark_fun, starlark_module, starlark_parse_param_type, starlark_signature,
+        starlark_signature_extraction, starlark_signatures,
+        values::{
+            error::{
+                RuntimeError, UnsupportedOperation, ValueError, INCORRECT_PARAMETER_TYPE_ERROR_CODE,
+            },
+            none::NoneType,
+            {Mutable, TypedValue, Value, ValueResult},
         },
-        none::NoneType,
-        {Mutable, TypedValue, Value, ValueResult},
     },
-    std::convert::TryFrom,
+    std::{convert::TryFrom, os::raw::c_ulong, path::PathBuf},
 };
 
+/// Maximum hash seed value accepted by Python.
+///
+/// This mirrors the range accepted by the `PYTHONHASHSEED` environment variable.
+const MAX_HASH_SEED: i64 = 4_294_967_295;
+
+/// Error handlers Python can use for stdio streams.
+const STDIO_ERROR_HANDLERS: &[&str] = &[
+    "backslashreplace",
+    "ignore",
+    "namereplace",
+    "replace",
+    "strict",
+    "surrogateescape",
+    "surrogatepass",
+    "xmlcharrefreplace",
+];
+
+/// Whether a string is syntactically valid as a Python codec name.
+fn is_valid_encoding_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.')
+}
+
+/// Whether a string is a valid `$ORIGIN` relative path for fault handler output.
+///
+/// The path must begin with `$ORIGIN/` and be followed by a relative path
+/// that doesn't traverse to parent directories or contain other `$` tokens.
+fn is_valid_fault_handler_path(path: &str) -> bool {
+    match path.strip_prefix("$ORIGIN/") {
+        Some(rest) => {
+            !rest.is_empty()
+                && !rest.contains('$')
+                && std::path::Path::new(rest)
+                    .components()
+                    .all(|c| matches!(c, std::path::Component::Normal(_)))
+        }
+        None => false,
+    }
+}
+
 impl ToValue for PythonInterpreterProfile {
     fn to_value(&self) -> Value {
         Value::from(self.to_string())
@@ -95,6 +146,12 @@ impl TryToOptional<BytecodeOptimizationLevel> for Value {
     fn try_to_optional(&self) -> Result<Option<BytecodeOptimizationLevel>, ValueError> {
         if self.get_type() == "NoneType" {
             Ok(None)
+        } else if self.get_type() != "int" {
+            Err(ValueError::from(RuntimeError {
+                code: INCORRECT_PARAMETER_TYPE_ERROR_CODE,
+                message: format!("expected int or None; got {}", self.get_type()),
+                label: "PythonInterpreterConfig.optimization_level".to_string(),
+            }))
         } else {
             match self.to_int()? {
                 0 => Ok(Some(BytecodeOptimizationLevel::Zero)),
@@ -148,6 +205,7 @@ impl TypedValue for PythonInterpreterConfigValue {
             "isolated" => self.inner.config.isolated.to_value(),
             "legacy_windows_fs_encoding" => self.inner.config.legacy_windows_fs_encoding.to_value(),
             "parse_argv" => self.inner.config.parse_argv.to_value(),
+            "argv" => self.inner.config.argv.to_value(),
             "use_environment" => self.inner.config.use_environment.to_value(),
             "utf8_mode" => self.inner.config.utf8_mode.to_value(),
             "base_exec_prefix" => self.inner.config.base_exec_prefix.to_value(),
@@ -203,6 +261,10 @@ impl TypedValue for PythonInterpreterConfigValue {
             "sys_meipass" => Value::from(self.inner.sys_meipass),
             "terminfo_resolution" => self.inner.terminfo_resolution.to_value(),
             "write_modules_directory_env" => self.inner.write_modules_directory_env.to_value(),
+            "multiprocessing_auto_dispatch" => {
+                Value::from(self.inner.multiprocessing_auto_dispatch)
+            }
+            "fault_handler_path" => self.inner.fault_handler_path.to_value(),
             "run_mode" => self.inner.run_mode.to_value(),
             attr => {
                 return Err(ValueError::OperationNotSupported {
@@ -227,6 +289,7 @@ impl TypedValue for PythonInterpreterConfigValue {
             "isolated" => true,
             "legacy_windows_fs_encoding" => true,
             "parse_argv" => true,
+            "argv" => true,
             "use_environment" => true,
             "utf8_mode" => true,
             "base_exec_prefix" => true,
@@ -282,6 +345,8 @@ impl TypedValue for PythonInterpreterConfigValue {
             "sys_meipass" => true,
             "terminfo_resolution" => true,
             "write_modules_directory_env" => true,
+            "multiprocessing_auto_dispatch" => true,
+            "fault_handler_path" => true,
             "run_mode" => true,
             _ => false,
         })
@@ -320,40 +385,42 @@ impl TypedValue for PythonInterpreterConfigValue {
                 self.inner.config.configure_locale = value.to_optional();
             }
             "coerce_c_locale" => {
-                self.inner.config.coerce_c_locale = if value.get_type() == "NoneType" {
-                    None
-                } else {
-                    Some(
-                        CoerceCLocale::try_from(value.to_string().as_str()).map_err(|e| {
-                            ValueError::from(RuntimeError {
-                                code: INCORRECT_PARAMETER_TYPE_ERROR_CODE,
-                                message: e,
-                                label: format!("{}.{}", Self::TYPE, attribute),
-                            })
-                        })?,
-                    )
+                self.inner.config.coerce_c_locale = match optional_str_arg(attribute, &value)? {
+                    Some(value) => Some(CoerceCLocale::try_from(value.as_str()).map_err(|e| {
+                        ValueError::from(RuntimeError {
+                            code: INCORRECT_PARAMETER_TYPE_ERROR_CODE,
+                            message: e,
+                            label: format!("{}.{}", Self::TYPE, attribute),
+                        })
+                    })?),
+                    None => None,
                 };
             }
             "coerce_c_locale_warn" => {
-                self.inner.config.coerce_c_locale_warn = value.to_optional();
+                self.inner.config.coerce_c_locale_warn = optional_bool_arg(attribute, &value)?;
             }
             "development_mode" => {
-                self.inner.config.development_mode = value.to_optional();
+                self.inner.config.development_mode = optional_bool_arg(attribute, &value)?;
             }
             "isolated" => {
                 self.inner.config.isolated = value.to_optional();
             }
             "legacy_windows_fs_encoding" => {
-                self.inner.config.legacy_windows_fs_encoding = value.to_optional();
+                self.inner.config.legacy_windows_fs_encoding =
+                    optional_bool_arg(attribute, &value)?;
             }
             "parse_argv" => {
-                self.inner.config.parse_argv = value.to_optional();
+                self.inner.config.parse_argv = optional_bool_arg(attribute, &value)?;
+            }
+            "argv" => {
+                optional_list_arg(attribute, "string", &value)?;
+                self.inner.config.argv = value.try_to_optional()?;
             }
             "use_environment" => {
                 self.inner.config.use_environment = value.to_optional();
             }
             "utf8_mode" => {
-                self.inner.config.utf8_mode = value.to_optional();
+                self.inner.config.utf8_mode = optional_bool_arg(attribute, &value)?;
             }
             "base_exec_prefix" => {
                 self.inner.config.base_exec_prefix = value.to_optional();
@@ -365,7 +432,7 @@ impl TypedValue for PythonInterpreterConfigValue {
                 self.inner.config.base_prefix = value.to_optional();
             }
             "buffered_stdio" => {
-                self.inner.config.buffered_stdio = value.to_optional();
+                self.inner.config.buffered_stdio = optional_bool_arg(attribute, &value)?;
             }
             "bytes_warning" => {
                 self.inner.config.bytes_warning = if value.get_type() == "NoneType" {
@@ -383,34 +450,35 @@ impl TypedValue for PythonInterpreterConfigValue {
                 };
             }
             "check_hash_pycs_mode" => {
-                self.inner.config.check_hash_pycs_mode = if value.get_type() == "NoneType" {
-                    None
-                } else {
-                    Some(
-                        CheckHashPYCsMode::try_from(value.to_string().as_str()).map_err(|e| {
+                self.inner.config.check_hash_pycs_mode = match optional_str_arg(attribute, &value)?
+                {
+                    Some(mode) => {
+                        Some(CheckHashPYCsMode::try_from(mode.as_str()).map_err(|e| {
                             ValueError::from(RuntimeError {
                                 code: INCORRECT_PARAMETER_TYPE_ERROR_CODE,
                                 message: e,
                                 label: format!("{}.{}", Self::TYPE, attribute),
                             })
-                        })?,
-                    )
+                        })?)
+                    }
+                    None => None,
                 };
             }
             "configure_c_stdio" => {
-                self.inner.config.configure_c_stdio = value.to_optional();
+                self.inner.config.configure_c_stdio = optional_bool_arg(attribute, &value)?;
             }
             "dump_refs" => {
-                self.inner.config.dump_refs = value.to_optional();
+                self.inner.config.dump_refs = optional_bool_arg(attribute, &value)?;
             }
             "exec_prefix" => {
                 self.inner.config.exec_prefix = value.to_optional();
             }
             "executable" => {
-                self.inner.config.executable = value.to_optional();
+                self.inner.config.executable =
+                    optional_str_arg(attribute, &value)?.map(PathBuf::from);
             }
             "fault_handler" => {
-                self.inner.config.fault_handler = value.to_optional();
+                self.inner.config.fault_handler = optional_bool_arg(attribute, &value)?;
             }
             "filesystem_encoding" => {
                 self.inner.config.filesystem_encoding = value.to_optional();
@@ -419,7 +487,24 @@ impl TypedValue for PythonInterpreterConfigValue {
                 self.inner.config.filesystem_errors = value.to_optional();
             }
             "hash_seed" => {
-                self.inner.config.hash_seed = value.try_to_optional()?;
+                self.inner.config.hash_seed = if value.get_type() == "NoneType" {
+                    None
+                } else {
+                    let seed = value.to_int()?;
+
+                    if seed < 0 || seed > MAX_HASH_SEED {
+                        return Err(ValueError::from(RuntimeError {
+                            code: INCORRECT_PARAMETER_TYPE_ERROR_CODE,
+                            message: format!(
+                                "hash_seed must be between 0 and {}; got {}",
+                                MAX_HASH_SEED, seed
+                            ),
+                            label: format!("{}.{}", Self::TYPE, attribute),
+                        }));
+                    }
+
+                    Some(seed as c_ulong)
+                };
             }
             "home" => {
                 self.inner.config.home = value.to_optional();
@@ -428,19 +513,19 @@ impl TypedValue for PythonInterpreterConfigValue {
                 self.inner.config.import_time = value.to_optional();
             }
             "inspect" => {
-                self.inner.config.inspect = value.to_optional();
+                self.inner.config.inspect = optional_bool_arg(attribute, &value)?;
             }
             "install_signal_handlers" => {
-                self.inner.config.install_signal_handlers = value.to_optional();
+                self.inner.config.install_signal_handlers = optional_bool_arg(attribute, &value)?;
             }
             "interactive" => {
-                self.inner.config.interactive = value.to_optional();
+                self.inner.config.interactive = optional_bool_arg(attribute, &value)?;
             }
             "legacy_windows_stdio" => {
-                self.inner.config.legacy_windows_stdio = value.to_optional();
+                self.inner.config.legacy_windows_stdio = optional_bool_arg(attribute, &value)?;
             }
             "malloc_stats" => {
-                self.inner.config.malloc_stats = value.to_optional();
+                self.inner.config.malloc_stats = optional_bool_arg(attribute, &value)?;
             }
             "module_search_paths" => {
                 self.inner.config.module_search_paths = value.try_to_optional()?;
@@ -460,19 +545,30 @@ impl TypedValue for PythonInterpreterConfigValue {
                 self.inner.config.parser_debug = value.to_optional();
             }
             "pathconfig_warnings" => {
-                self.inner.config.pathconfig_warnings = value.to_optional();
+                self.inner.config.pathconfig_warnings = optional_bool_arg(attribute, &value)?;
             }
             "prefix" => {
                 self.inner.config.prefix = value.to_optional();
             }
             "program_name" => {
-                self.inner.config.program_name = value.to_optional();
+                self.inner.config.program_name =
+                    optional_str_arg(attribute, &value)?.map(PathBuf::from);
             }
             "pycache_prefix" => {
-                self.inner.config.pycache_prefix = value.to_optional();
+                let prefix = optional_str_arg(attribute, &value)?;
+
+                if prefix.as_deref() == Some("") {
+                    return Err(ValueError::from(RuntimeError {
+                        code: INCORRECT_PARAMETER_TYPE_ERROR_CODE,
+                        message: "pycache_prefix must not be empty".to_string(),
+                        label: format!("{}.{}", Self::TYPE, attribute),
+                    }));
+                }
+
+                self.inner.config.pycache_prefix = prefix.map(PathBuf::from);
             }
             "python_path_env" => {
-                self.inner.config.python_path_env = value.to_optional();
+                self.inner.config.python_path_env = optional_str_arg(attribute, &value)?;
             }
             "quiet" => {
                 self.inner.config.quiet = value.to_optional();
@@ -487,25 +583,53 @@ impl TypedValue for PythonInterpreterConfigValue {
                 self.inner.config.run_module = value.to_optional();
             }
             "show_alloc_count" => {
-                self.inner.config.show_alloc_count = value.to_optional();
+                self.inner.config.show_alloc_count = optional_bool_arg(attribute, &value)?;
             }
             "show_ref_count" => {
-                self.inner.config.show_ref_count = value.to_optional();
+                self.inner.config.show_ref_count = optional_bool_arg(attribute, &value)?;
             }
             "site_import" => {
-                self.inner.config.site_import = value.to_optional();
+                self.inner.config.site_import = optional_bool_arg(attribute, &value)?;
             }
             "skip_first_source_line" => {
                 self.inner.config.skip_first_source_line = value.to_optional();
             }
             "stdio_encoding" => {
-                self.inner.config.stdio_encoding = value.to_optional();
+                let encoding = optional_str_arg(attribute, &value)?;
+
+                if let Some(encoding) = &encoding {
+                    if !is_valid_encoding_name(encoding) {
+                        return Err(ValueError::from(RuntimeError {
+                            code: INCORRECT_PARAMETER_TYPE_ERROR_CODE,
+                            message: format!("invalid encoding name: {}", encoding),
+                            label: format!("{}.{}", Self::TYPE, attribute),
+                        }));
+                    }
+                }
+
+                self.inner.config.stdio_encoding = encoding;
             }
             "stdio_errors" => {
-                self.inner.config.stdio_errors = value.to_optional();
+                let errors = optional_str_arg(attribute, &value)?;
+
+                if let Some(errors) = &errors {
+                    if !STDIO_ERROR_HANDLERS.contains(&errors.as_str()) {
+                        return Err(ValueError::from(RuntimeError {
+                            code: INCORRECT_PARAMETER_TYPE_ERROR_CODE,
+                            message: format!(
+                                "invalid stdio_errors value: {}; must be one of {}",
+                                errors,
+                                STDIO_ERROR_HANDLERS.join(", ")
+                            ),
+                            label: format!("{}.{}", Self::TYPE, attribute),
+                        }));
+                    }
+                }
+
+                self.inner.config.stdio_errors = errors;
             }
             "tracemalloc" => {
-                self.inner.config.tracemalloc = value.to_optional();
+                self.inner.config.tracemalloc = optional_bool_arg(attribute, &value)?;
             }
             "user_site_directory" => {
                 self.inner.config.user_site_directory = value.to_optional();
@@ -560,8 +684,39 @@ impl TypedValue for PythonInterpreterConfigValue {
             "write_modules_directory_env" => {
                 self.inner.write_modules_directory_env = value.to_optional();
             }
+            "multiprocessing_auto_dispatch" => {
+                let enabled = value.to_bool();
+
+                if enabled && self.inner.run_mode == PythonRunMode::None {
+                    return Err(ValueError::from(RuntimeError {
+                        code: INCORRECT_PARAMETER_TYPE_ERROR_CODE,
+                        message: "multiprocessing_auto_dispatch cannot be enabled when run_mode is 'none'".to_string(),
+                        label: format!("{}.{}", Self::TYPE, attribute),
+                    }));
+                }
+
+                self.inner.multiprocessing_auto_dispatch = enabled;
+            }
+            "fault_handler_path" => {
+                let path = optional_str_arg(attribute, &value)?;
+
+                if let Some(path) = &path {
+                    if !is_valid_fault_handler_path(path) {
+                        return Err(ValueError::from(RuntimeError {
+                            code: INCORRECT_PARAMETER_TYPE_ERROR_CODE,
+                            message: format!(
+                                "invalid fault_handler_path {}; must be $ORIGIN/ followed by a relative path",
+                                path
+                            ),
+                            label: format!("{}.{}", Self::TYPE, attribute),
+                        }));
+                    }
+                }
+
+                self.inner.fault_handler_path = path;
+            }
             "run_mode" => {
-                self.inner.run_mode =
+                let run_mode =
                     PythonRunMode::try_from(value.to_string().as_str()).map_err(|e| {
                         ValueError::from(RuntimeError {
                             code: INCORRECT_PARAMETER_TYPE_ERROR_CODE,
@@ -569,6 +724,16 @@ impl TypedValue for PythonInterpreterConfigValue {
                             label: format!("{}.{}", Self::TYPE, attribute),
                         })
                     })?;
+
+                if run_mode == PythonRunMode::None && self.inner.multiprocessing_auto_dispatch {
+                    return Err(ValueError::from(RuntimeError {
+                        code: INCORRECT_PARAMETER_TYPE_ERROR_CODE,
+                        message: "run_mode cannot be 'none' when multiprocessing_auto_dispatch is enabled".to_string(),
+                        label: format!("{}.{}", Self::TYPE, attribute),
+                    }));
+                }
+
+                self.inner.run_mode = run_mode;
             }
             attr => {
                 return Err(ValueError::OperationNotSupported {
@@ -583,9 +748,204 @@ impl TypedValue for PythonInterpreterConfigValue {
     }
 }
 
+// Starlark methods.
+impl PythonInterpreterConfigValue {
+    /// PythonInterpreterConfig.append_module_search_path(path)
+    pub fn starlark_append_module_search_path(&mut self, path: &Value) -> ValueResult {
+        let path = required_str_arg("path", path)?;
+
+        self.inner
+            .config
+            .module_search_paths
+            .get_or_insert_with(Vec::new)
+            .push(PathBuf::from(path));
+
+        // Automatically enable filesystem importer if module search paths
+        // are registered.
+        self.inner.filesystem_importer = true;
+
+        Ok(Value::new(NoneType::None))
+    }
+
+    /// PythonInterpreterConfig.clear_module_search_paths()
+    pub fn starlark_clear_module_search_paths(&mut self) -> ValueResult {
+        self.inner.config.module_search_paths = None;
+
+        Ok(Value::new(NoneType::None))
+    }
+
+    /// PythonInterpreterConfig.enable_filesystem_importer(search_paths)
+    pub fn starlark_enable_filesystem_importer(&mut self, search_paths: &Value) -> ValueResult {
+        required_list_arg("search_paths", "string", search_paths)?;
+
+        let search_paths = search_paths
+            .iter()?
+            .iter()
+            .map(|x| PathBuf::from(x.to_string()))
+            .collect::<Vec<_>>();
+
+        if search_paths.is_empty() {
+            return Err(ValueError::from(RuntimeError {
+                code: INCORRECT_PARAMETER_TYPE_ERROR_CODE,
+                message: "search_paths must contain at least one path".to_string(),
+                label: "enable_filesystem_importer()".to_string(),
+            }));
+        }
+
+        self.inner.config.module_search_paths = Some(search_paths);
+        self.inner.filesystem_importer = true;
+
+        Ok(Value::new(NoneType::None))
+    }
+
+    /// PythonInterpreterConfig.add_warn_option(value)
+    pub fn starlark_add_warn_option(&mut self, value: &Value) -> ValueResult {
+        let value = required_str_arg("value", value)?;
+
+        self.inner
+            .config
+            .warn_options
+            .get_or_insert_with(Vec::new)
+            .push(value);
+
+        Ok(Value::new(NoneType::None))
+    }
+
+    /// PythonInterpreterConfig.add_x_option(value)
+    pub fn starlark_add_x_option(&mut self, value: &Value) -> ValueResult {
+        let value = required_str_arg("value", value)?;
+
+        let key = value.splitn(2, '=').next().unwrap();
+        if key.is_empty() || key.contains(char::is_whitespace) {
+            return Err(ValueError::from(RuntimeError {
+                code: INCORRECT_PARAMETER_TYPE_ERROR_CODE,
+                message: format!(
+                    "invalid X option {}; must be of the form key or key=value",
+                    value
+                ),
+                label: "add_x_option()".to_string(),
+            }));
+        }
+
+        self.inner
+            .config
+            .x_options
+            .get_or_insert_with(Vec::new)
+            .push(value);
+
+        Ok(Value::new(NoneType::None))
+    }
+
+    /// PythonInterpreterConfig.set_environment_variable(name, value)
+    pub fn starlark_set_environment_variable(
+        &mut self,
+        name: &Value,
+        value: &Value,
+    ) -> ValueResult {
+        let name = required_env_name_arg("name", name)?;
+        let value = required_str_arg("value", value)?;
+
+        let variables = &mut self.inner.environment_variables;
+
+        match variables.iter_mut().find(|(existing, _)| existing == &name) {
+            Some(entry) => entry.1 = value,
+            None => variables.push((name, value)),
+        }
+
+        Ok(Value::new(NoneType::None))
+    }
+
+    /// PythonInterpreterConfig.set_terminfo_resolution_file(path)
+    pub fn starlark_set_terminfo_resolution_file(
+        &mut self,
+        type_values: &TypeValues,
+        path: &Value,
+    ) -> ValueResult {
+        let path = required_str_arg("path", path)?;
+
+        let raw_context = get_context(type_values)?;
+        let context = raw_context
+            .downcast_ref::<EnvironmentContext>()
+            .ok_or(ValueError::IncorrectParameterType)?;
+
+        let path = context.cwd.join(path);
+
+        let metadata = std::fs::metadata(&path).map_err(|e| {
+            ValueError::from(RuntimeError {
+                code: "RUNTIME_ERROR",
+                message: format!("unable to read terminfo file {}: {}", path.display(), e),
+                label: "set_terminfo_resolution_file()".to_string(),
+            })
+        })?;
+
+        if !metadata.is_file() || metadata.len() == 0 {
+            return Err(ValueError::from(RuntimeError {
+                code: "RUNTIME_ERROR",
+                message: format!("terminfo file {} is empty", path.display()),
+                label: "set_terminfo_resolution_file()".to_string(),
+            }));
+        }
+
+        self.inner.terminfo_resolution = TerminfoResolution::Static(path.display().to_string());
+
+        Ok(Value::new(NoneType::None))
+    }
+}
+
+starlark_module! { python_interpreter_config_module =>
+    PythonInterpreterConfig.append_module_search_path(this, path) {
+        match this.clone().downcast_mut::<PythonInterpreterConfigValue>()? {
+            Some(mut config) => config.starlark_append_module_search_path(&path),
+            None => Err(ValueError::IncorrectParameterType),
+        }
+    }
+
+    PythonInterpreterConfig.clear_module_search_paths(this) {
+        match this.clone().downcast_mut::<PythonInterpreterConfigValue>()? {
+            Some(mut config) => config.starlark_clear_module_search_paths(),
+            None => Err(ValueError::IncorrectParameterType),
+        }
+    }
+
+    PythonInterpreterConfig.enable_filesystem_importer(this, search_paths) {
+        match this.clone().downcast_mut::<PythonInterpreterConfigValue>()? {
+            Some(mut config) => config.starlark_enable_filesystem_importer(&search_paths),
+            None => Err(ValueError::IncorrectParameterType),
+        }
+    }
+
+    PythonInterpreterConfig.add_warn_option(this, value) {
+        match this.clone().downcast_mut::<PythonInterpreterConfigValue>()? {
+            Some(mut config) => config.starlark_add_warn_option(&value),
+            None => Err(ValueError::IncorrectParameterType),
+        }
+    }
+
+    PythonInterpreterConfig.add_x_option(this, value) {
+        match this.clone().downcast_mut::<PythonInterpreterConfigValue>()? {
+            Some(mut config) => config.starlark_add_x_option(&value),
+            None => Err(ValueError::IncorrectParameterType),
+        }
+    }
+
+    PythonInterpreterConfig.set_environment_variable(this, name, value) {
+        match this.clone().downcast_mut::<PythonInterpreterConfigValue>()? {
+            Some(mut config) => config.starlark_set_environment_variable(&name, &value),
+            None => Err(ValueError::IncorrectParameterType),
+        }
+    }
+
+    PythonInterpreterConfig.set_terminfo_resolution_file(env env, this, path) {
+        match this.clone().downcast_mut::<PythonInterpreterConfigValue>()? {
+            Some(mut config) => config.starlark_set_terminfo_resolution_file(&env, &path),
+            None => Err(ValueError::IncorrectParameterType),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use {super::super::testutil::*, anyhow::Result};
+    use {super::super::testutil::*, super::*, anyhow::Result};
 
     // TODO instantiating a new distribution every call is expensive. Can we cache this?
     fn get_env() -> Result<StarlarkEnvironment> {
@@ -609,6 +969,22 @@ mod tests {
         env.eval("config.config_profile = 'isolated'")?;
         env.eval_assert("config.config_profile == 'isolated'")?;
 
+        assert!(env.eval("config.config_profile = 'invalid'").is_err());
+        assert!(env.eval("config.config_profile = 42").is_err());
+        env.eval_assert("config.config_profile == 'isolated'")?;
+
+        env.eval("config.config_profile = 'python'")?;
+        let config = env.eval("config")?;
+        let config = config
+            .downcast_ref::<PythonInterpreterConfigValue>()
+            .unwrap();
+        let code = config
+            .inner
+            .to_oxidized_python_interpreter_config_rs(None)?;
+        assert!(code
+            .as_str()
+            .contains("profile: pyembed::PythonInterpreterProfile::Python,"));
+
         Ok(())
     }
 
@@ -660,6 +1036,66 @@ mod tests {
 
         env.eval_assert("config.coerce_c_locale == None")?;
 
+        assert!(env.eval("config.coerce_c_locale = 'POSIX'").is_err());
+        assert!(env.eval("config.coerce_c_locale = True").is_err());
+
+        env.eval("config.coerce_c_locale = 'C'")?;
+        env.eval_assert("config.coerce_c_locale == 'C'")?;
+
+        let config = env.eval("config")?;
+        let config = config
+            .downcast_ref::<PythonInterpreterConfigValue>()
+            .unwrap();
+        let code = config
+            .inner
+            .to_oxidized_python_interpreter_config_rs(None)?;
+        assert!(code
+            .as_str()
+            .contains("coerce_c_locale: Some(pyembed::CoerceCLocale::C),"));
+
+        env.eval("config.coerce_c_locale = 'LC_CTYPE'")?;
+        env.eval_assert("config.coerce_c_locale == 'LC_CTYPE'")?;
+
+        let config = env.eval("config")?;
+        let config = config
+            .downcast_ref::<PythonInterpreterConfigValue>()
+            .unwrap();
+        let code = config
+            .inner
+            .to_oxidized_python_interpreter_config_rs(None)?;
+        assert!(code
+            .as_str()
+            .contains("coerce_c_locale: Some(pyembed::CoerceCLocale::LCCtype),"));
+
+        env.eval("config.coerce_c_locale = None")?;
+        env.eval_assert("config.coerce_c_locale == None")?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_coerce_c_locale_warn() -> Result<()> {
+        let mut env = get_env()?;
+
+        env.eval_assert("config.coerce_c_locale_warn == None")?;
+
+        assert!(env.eval("config.coerce_c_locale_warn = 'yes'").is_err());
+
+        env.eval("config.coerce_c_locale_warn = True")?;
+        env.eval_assert("config.coerce_c_locale_warn == True")?;
+
+        let config = env.eval("config")?;
+        let config = config
+            .downcast_ref::<PythonInterpreterConfigValue>()
+            .unwrap();
+        let code = config
+            .inner
+            .to_oxidized_python_interpreter_config_rs(None)?;
+        assert!(code.as_str().contains("coerce_c_locale_warn: Some(true),"));
+
+        env.eval("config.coerce_c_locale_warn = None")?;
+        env.eval_assert("config.coerce_c_locale_warn == None")?;
+
         Ok(())
     }
 
@@ -669,6 +1105,23 @@ mod tests {
 
         env.eval_assert("config.development_mode == None")?;
 
+        assert!(env.eval("config.development_mode = 1").is_err());
+
+        env.eval("config.development_mode = True")?;
+        env.eval_assert("config.development_mode == True")?;
+
+        let config = env.eval("config")?;
+        let config = config
+            .downcast_ref::<PythonInterpreterConfigValue>()
+            .unwrap();
+        let code = config
+            .inner
+            .to_oxidized_python_interpreter_config_rs(None)?;
+        assert!(code.as_str().contains("development_mode: Some(true),"));
+
+        env.eval("config.development_mode = None")?;
+        env.eval_assert("config.development_mode == None")?;
+
         Ok(())
     }
 
@@ -687,6 +1140,25 @@ mod tests {
 
         env.eval_assert("config.legacy_windows_fs_encoding == None")?;
 
+        assert!(env.eval("config.legacy_windows_fs_encoding = 1").is_err());
+
+        env.eval("config.legacy_windows_fs_encoding = True")?;
+        env.eval_assert("config.legacy_windows_fs_encoding == True")?;
+
+        let config = env.eval("config")?;
+        let config = config
+            .downcast_ref::<PythonInterpreterConfigValue>()
+            .unwrap();
+        let code = config
+            .inner
+            .to_oxidized_python_interpreter_config_rs(None)?;
+        assert!(code
+            .as_str()
+            .contains("legacy_windows_fs_encoding: Some(true),"));
+
+        env.eval("config.legacy_windows_fs_encoding = False")?;
+        env.eval_assert("config.legacy_windows_fs_encoding == False")?;
+
         Ok(())
     }
 
@@ -696,6 +1168,49 @@ mod tests {
 
         env.eval_assert("config.parse_argv == None")?;
 
+        assert!(env.eval("config.parse_argv = 1").is_err());
+
+        env.eval("config.parse_argv = False")?;
+        env.eval_assert("config.parse_argv == False")?;
+
+        let config = env.eval("config")?;
+        let config = config
+            .downcast_ref::<PythonInterpreterConfigValue>()
+            .unwrap();
+        let code = config
+            .inner
+            .to_oxidized_python_interpreter_config_rs(None)?;
+        assert!(code.as_str().contains("parse_argv: Some(false),"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_argv() -> Result<()> {
+        let mut env = get_env()?;
+
+        env.eval_assert("config.argv == None")?;
+
+        assert!(env.eval("config.argv = 'myapp'").is_err());
+        assert!(env.eval("config.argv = ['myapp', 42]").is_err());
+
+        env.eval("config.argv = ['myapp', '--serve', 'hello \"#world']")?;
+        env.eval_assert("config.argv == ['myapp', '--serve', 'hello \"#world']")?;
+
+        let config = env.eval("config")?;
+        let config = config
+            .downcast_ref::<PythonInterpreterConfigValue>()
+            .unwrap();
+        let code = config
+            .inner
+            .to_oxidized_python_interpreter_config_rs(None)?;
+        assert!(code.as_str().contains(
+            "argv: Some(vec![std::ffi::OsString::from(\"myapp\"), std::ffi::OsString::from(\"--serve\"), std::ffi::OsString::from(\"hello \\\"#world\")]),"
+        ));
+
+        env.eval("config.argv = None")?;
+        env.eval_assert("config.argv == None")?;
+
         Ok(())
     }
 
@@ -714,6 +1229,34 @@ mod tests {
 
         env.eval_assert("config.utf8_mode == None")?;
 
+        assert!(env.eval("config.utf8_mode = 1").is_err());
+
+        env.eval("config.utf8_mode = True")?;
+        env.eval_assert("config.utf8_mode == True")?;
+
+        let config = env.eval("config")?;
+        let config = config
+            .downcast_ref::<PythonInterpreterConfigValue>()
+            .unwrap();
+        let code = config
+            .inner
+            .to_oxidized_python_interpreter_config_rs(None)?;
+        assert!(code.as_str().contains("utf8_mode: Some(true),"));
+
+        env.eval("config.utf8_mode = False")?;
+
+        let config = env.eval("config")?;
+        let config = config
+            .downcast_ref::<PythonInterpreterConfigValue>()
+            .unwrap();
+        let code = config
+            .inner
+            .to_oxidized_python_interpreter_config_rs(None)?;
+        assert!(code.as_str().contains("utf8_mode: Some(false),"));
+
+        env.eval("config.utf8_mode = None")?;
+        env.eval_assert("config.utf8_mode == None")?;
+
         Ok(())
     }
 
@@ -750,6 +1293,26 @@ mod tests {
 
         env.eval_assert("config.buffered_stdio == None")?;
 
+        assert!(env.eval("config.buffered_stdio = 1").is_err());
+
+        env.eval("config.buffered_stdio = False")?;
+        env.eval_assert("config.buffered_stdio == False")?;
+
+        let config = env.eval("config")?;
+        let config = config
+            .downcast_ref::<PythonInterpreterConfigValue>()
+            .unwrap();
+        let code = config
+            .inner
+            .to_oxidized_python_interpreter_config_rs(None)?;
+        assert!(code.as_str().contains("buffered_stdio: Some(false),"));
+
+        env.eval("config.buffered_stdio = True")?;
+        env.eval_assert("config.buffered_stdio == True")?;
+
+        env.eval("config.buffered_stdio = None")?;
+        env.eval_assert("config.buffered_stdio == None")?;
+
         Ok(())
     }
 
@@ -777,6 +1340,35 @@ mod tests {
 
         env.eval_assert("config.check_hash_pycs_mode == None")?;
 
+        assert!(env
+            .eval("config.check_hash_pycs_mode = 'sometimes'")
+            .is_err());
+        assert!(env.eval("config.check_hash_pycs_mode = True").is_err());
+
+        for (mode, variant) in &[
+            ("always", "Always"),
+            ("default", "Default"),
+            ("never", "Never"),
+        ] {
+            env.eval(&format!("config.check_hash_pycs_mode = '{}'", mode))?;
+            env.eval_assert(&format!("config.check_hash_pycs_mode == '{}'", mode))?;
+
+            let config = env.eval("config")?;
+            let config = config
+                .downcast_ref::<PythonInterpreterConfigValue>()
+                .unwrap();
+            let code = config
+                .inner
+                .to_oxidized_python_interpreter_config_rs(None)?;
+            assert!(code.as_str().contains(&format!(
+                "check_hash_pycs_mode: Some(pyembed::CheckHashPYCsMode::{}),",
+                variant
+            )));
+        }
+
+        env.eval("config.check_hash_pycs_mode = None")?;
+        env.eval_assert("config.check_hash_pycs_mode == None")?;
+
         Ok(())
     }
 
@@ -786,6 +1378,26 @@ mod tests {
 
         env.eval_assert("config.configure_c_stdio == None")?;
 
+        assert!(env.eval("config.configure_c_stdio = 1").is_err());
+
+        env.eval("config.configure_c_stdio = True")?;
+        env.eval_assert("config.configure_c_stdio == True")?;
+
+        let config = env.eval("config")?;
+        let config = config
+            .downcast_ref::<PythonInterpreterConfigValue>()
+            .unwrap();
+        let code = config
+            .inner
+            .to_oxidized_python_interpreter_config_rs(None)?;
+        assert!(code.as_str().contains("configure_c_stdio: Some(true),"));
+
+        env.eval("config.configure_c_stdio = False")?;
+        env.eval_assert("config.configure_c_stdio == False")?;
+
+        env.eval("config.configure_c_stdio = None")?;
+        env.eval_assert("config.configure_c_stdio == None")?;
+
         Ok(())
     }
 
@@ -795,6 +1407,23 @@ mod tests {
 
         env.eval_assert("config.dump_refs == None")?;
 
+        assert!(env.eval("config.dump_refs = 1").is_err());
+
+        env.eval("config.dump_refs = True")?;
+        env.eval_assert("config.dump_refs == True")?;
+
+        let config = env.eval("config")?;
+        let config = config
+            .downcast_ref::<PythonInterpreterConfigValue>()
+            .unwrap();
+        let code = config
+            .inner
+            .to_oxidized_python_interpreter_config_rs(None)?;
+        assert!(code.as_str().contains("dump_refs: Some(true),"));
+
+        env.eval("config.dump_refs = False")?;
+        env.eval_assert("config.dump_refs == False")?;
+
         Ok(())
     }
 
@@ -813,6 +1442,25 @@ mod tests {
 
         env.eval_assert("config.executable == None")?;
 
+        assert!(env.eval("config.executable = 42").is_err());
+
+        env.eval("config.executable = '$ORIGIN/myapp'")?;
+        env.eval_assert("config.executable == '$ORIGIN/myapp'")?;
+
+        let config = env.eval("config")?;
+        let config = config
+            .downcast_ref::<PythonInterpreterConfigValue>()
+            .unwrap();
+        let code = config
+            .inner
+            .to_oxidized_python_interpreter_config_rs(None)?;
+        assert!(code
+            .as_str()
+            .contains("executable: Some(std::path::PathBuf::from(r#\"$ORIGIN/myapp\"#)),"));
+
+        env.eval("config.executable = None")?;
+        env.eval_assert("config.executable == None")?;
+
         Ok(())
     }
 
@@ -822,6 +1470,23 @@ mod tests {
 
         env.eval_assert("config.fault_handler == None")?;
 
+        assert!(env.eval("config.fault_handler = 'true'").is_err());
+
+        env.eval("config.fault_handler = True")?;
+        env.eval_assert("config.fault_handler == True")?;
+
+        let config = env.eval("config")?;
+        let config = config
+            .downcast_ref::<PythonInterpreterConfigValue>()
+            .unwrap();
+        let code = config
+            .inner
+            .to_oxidized_python_interpreter_config_rs(None)?;
+        assert!(code.as_str().contains("fault_handler: Some(true),"));
+
+        env.eval("config.fault_handler = None")?;
+        env.eval_assert("config.fault_handler == None")?;
+
         Ok(())
     }
 
@@ -849,6 +1514,35 @@ mod tests {
 
         env.eval_assert("config.hash_seed == None")?;
 
+        env.eval("config.hash_seed = 0")?;
+        env.eval_assert("config.hash_seed == 0")?;
+
+        // An explicit seed takes precedence over PYTHONHASHSEED, so it is
+        // preserved regardless of whether the environment is consulted.
+        env.eval("config.use_environment = True")?;
+        env.eval_assert("config.hash_seed == 0")?;
+
+        let config = env.eval("config")?;
+        let config = config
+            .downcast_ref::<PythonInterpreterConfigValue>()
+            .unwrap();
+        let code = config
+            .inner
+            .to_oxidized_python_interpreter_config_rs(None)?;
+        assert!(code.as_str().contains("hash_seed: Some(0),"));
+        assert!(code.as_str().contains("use_environment: Some(true),"));
+
+        env.eval("config.hash_seed = 4294967295")?;
+        env.eval_assert("config.hash_seed == 4294967295")?;
+
+        assert!(env.eval("config.hash_seed = -1").is_err());
+        assert!(env.eval("config.hash_seed = 4294967296").is_err());
+        assert!(env.eval("config.hash_seed = 'foo'").is_err());
+        env.eval_assert("config.hash_seed == 4294967295")?;
+
+        env.eval("config.hash_seed = None")?;
+        env.eval_assert("config.hash_seed == None")?;
+
         Ok(())
     }
 
@@ -876,6 +1570,23 @@ mod tests {
 
         env.eval_assert("config.inspect == None")?;
 
+        assert!(env.eval("config.inspect = 1").is_err());
+
+        env.eval("config.inspect = True")?;
+        env.eval_assert("config.inspect == True")?;
+
+        let config = env.eval("config")?;
+        let config = config
+            .downcast_ref::<PythonInterpreterConfigValue>()
+            .unwrap();
+        let code = config
+            .inner
+            .to_oxidized_python_interpreter_config_rs(None)?;
+        assert!(code.as_str().contains("inspect: Some(true),"));
+
+        env.eval("config.inspect = None")?;
+        env.eval_assert("config.inspect == None")?;
+
         Ok(())
     }
 
@@ -885,6 +1596,25 @@ mod tests {
 
         env.eval_assert("config.install_signal_handlers == None")?;
 
+        assert!(env.eval("config.install_signal_handlers = 0").is_err());
+
+        env.eval("config.install_signal_handlers = False")?;
+        env.eval_assert("config.install_signal_handlers == False")?;
+
+        let config = env.eval("config")?;
+        let config = config
+            .downcast_ref::<PythonInterpreterConfigValue>()
+            .unwrap();
+        let code = config
+            .inner
+            .to_oxidized_python_interpreter_config_rs(None)?;
+        assert!(code
+            .as_str()
+            .contains("install_signal_handlers: Some(false),"));
+
+        env.eval("config.install_signal_handlers = None")?;
+        env.eval_assert("config.install_signal_handlers == None")?;
+
         Ok(())
     }
 
@@ -894,6 +1624,23 @@ mod tests {
 
         env.eval_assert("config.interactive == None")?;
 
+        assert!(env.eval("config.interactive = 1").is_err());
+
+        env.eval("config.interactive = True")?;
+        env.eval_assert("config.interactive == True")?;
+
+        let config = env.eval("config")?;
+        let config = config
+            .downcast_ref::<PythonInterpreterConfigValue>()
+            .unwrap();
+        let code = config
+            .inner
+            .to_oxidized_python_interpreter_config_rs(None)?;
+        assert!(code.as_str().contains("interactive: Some(true),"));
+
+        env.eval("config.interactive = None")?;
+        env.eval_assert("config.interactive == None")?;
+
         Ok(())
     }
 
@@ -903,6 +1650,23 @@ mod tests {
 
         env.eval_assert("config.legacy_windows_stdio == None")?;
 
+        assert!(env.eval("config.legacy_windows_stdio = 1").is_err());
+
+        env.eval("config.legacy_windows_stdio = True")?;
+        env.eval_assert("config.legacy_windows_stdio == True")?;
+
+        let config = env.eval("config")?;
+        let config = config
+            .downcast_ref::<PythonInterpreterConfigValue>()
+            .unwrap();
+        let code = config
+            .inner
+            .to_oxidized_python_interpreter_config_rs(None)?;
+        assert!(code.as_str().contains("legacy_windows_stdio: Some(true),"));
+
+        env.eval("config.legacy_windows_stdio = False")?;
+        env.eval_assert("config.legacy_windows_stdio == False")?;
+
         Ok(())
     }
 
@@ -912,6 +1676,23 @@ mod tests {
 
         env.eval_assert("config.malloc_stats == None")?;
 
+        assert!(env.eval("config.malloc_stats = 1").is_err());
+
+        env.eval("config.malloc_stats = True")?;
+        env.eval_assert("config.malloc_stats == True")?;
+
+        let config = env.eval("config")?;
+        let config = config
+            .downcast_ref::<PythonInterpreterConfigValue>()
+            .unwrap();
+        let code = config
+            .inner
+            .to_oxidized_python_interpreter_config_rs(None)?;
+        assert!(code.as_str().contains("malloc_stats: Some(true),"));
+
+        env.eval("config.malloc_stats = False")?;
+        env.eval_assert("config.malloc_stats == False")?;
+
         Ok(())
     }
 
@@ -934,6 +1715,34 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_append_module_search_path() -> Result<()> {
+        let mut env = get_env()?;
+
+        env.eval("config.append_module_search_path('$ORIGIN/lib')")?;
+        env.eval("config.append_module_search_path('$ORIGIN/extra')")?;
+        env.eval_assert("config.module_search_paths == ['$ORIGIN/lib', '$ORIGIN/extra']")?;
+        env.eval_assert("config.filesystem_importer == True")?;
+
+        assert!(env.eval("config.append_module_search_path(42)").is_err());
+
+        let config = env.eval("config")?;
+        let config = config
+            .downcast_ref::<PythonInterpreterConfigValue>()
+            .unwrap();
+        let code = config
+            .inner
+            .to_oxidized_python_interpreter_config_rs(None)?;
+        assert!(code.as_str().contains(
+            "module_search_paths: Some(vec![std::path::PathBuf::from(\"$ORIGIN/lib\"), std::path::PathBuf::from(\"$ORIGIN/extra\")]),"
+        ));
+
+        env.eval("config.clear_module_search_paths()")?;
+        env.eval_assert("config.module_search_paths == None")?;
+
+        Ok(())
+    }
+
     #[test]
     fn test_optimization_level() -> Result<()> {
         let mut env = get_env()?;
@@ -949,6 +1758,36 @@ mod tests {
         env.eval("config.optimization_level = 2")?;
         env.eval_assert("config.optimization_level == 2")?;
 
+        let config = env.eval("config")?;
+        let config = config
+            .downcast_ref::<PythonInterpreterConfigValue>()
+            .unwrap();
+        let code = config
+            .inner
+            .to_oxidized_python_interpreter_config_rs(None)?;
+        assert!(code
+            .as_str()
+            .contains("optimization_level: Some(pyembed::BytecodeOptimizationLevel::Two),"));
+
+        env.eval("config.optimization_level = 1")?;
+        let config = env.eval("config")?;
+        let config = config
+            .downcast_ref::<PythonInterpreterConfigValue>()
+            .unwrap();
+        let code = config
+            .inner
+            .to_oxidized_python_interpreter_config_rs(None)?;
+        assert!(code
+            .as_str()
+            .contains("optimization_level: Some(pyembed::BytecodeOptimizationLevel::One),"));
+
+        assert!(env.eval("config.optimization_level = 3").is_err());
+        assert!(env.eval("config.optimization_level = '2'").is_err());
+        assert!(env.eval("config.optimization_level = True").is_err());
+
+        env.eval("config.optimization_level = None")?;
+        env.eval_assert("config.optimization_level == None")?;
+
         Ok(())
     }
 
@@ -967,6 +1806,29 @@ mod tests {
 
         env.eval_assert("config.pathconfig_warnings == None")?;
 
+        assert!(env.eval("config.pathconfig_warnings = 1").is_err());
+
+        for value in &[false, true] {
+            env.eval(&format!(
+                "config.pathconfig_warnings = {}",
+                if *value { "True" } else { "False" }
+            ))?;
+
+            let config = env.eval("config")?;
+            let config = config
+                .downcast_ref::<PythonInterpreterConfigValue>()
+                .unwrap();
+            let code = config
+                .inner
+                .to_oxidized_python_interpreter_config_rs(None)?;
+            assert!(code
+                .as_str()
+                .contains(&format!("pathconfig_warnings: Some({}),", value)));
+        }
+
+        env.eval("config.pathconfig_warnings = None")?;
+        env.eval_assert("config.pathconfig_warnings == None")?;
+
         Ok(())
     }
 
@@ -985,6 +1847,25 @@ mod tests {
 
         env.eval_assert("config.program_name == None")?;
 
+        assert!(env.eval("config.program_name = True").is_err());
+
+        env.eval("config.program_name = '/opt/myapp/bin/myapp'")?;
+        env.eval_assert("config.program_name == '/opt/myapp/bin/myapp'")?;
+
+        let config = env.eval("config")?;
+        let config = config
+            .downcast_ref::<PythonInterpreterConfigValue>()
+            .unwrap();
+        let code = config
+            .inner
+            .to_oxidized_python_interpreter_config_rs(None)?;
+        assert!(code.as_str().contains(
+            "program_name: Some(std::path::PathBuf::from(r#\"/opt/myapp/bin/myapp\"#)),"
+        ));
+
+        env.eval("config.program_name = None")?;
+        env.eval_assert("config.program_name == None")?;
+
         Ok(())
     }
 
@@ -994,6 +1875,26 @@ mod tests {
 
         env.eval_assert("config.pycache_prefix == None")?;
 
+        assert!(env.eval("config.pycache_prefix = ''").is_err());
+        assert!(env.eval("config.pycache_prefix = True").is_err());
+
+        env.eval("config.pycache_prefix = '/var/cache/myapp'")?;
+        env.eval_assert("config.pycache_prefix == '/var/cache/myapp'")?;
+
+        let config = env.eval("config")?;
+        let config = config
+            .downcast_ref::<PythonInterpreterConfigValue>()
+            .unwrap();
+        let code = config
+            .inner
+            .to_oxidized_python_interpreter_config_rs(None)?;
+        assert!(code
+            .as_str()
+            .contains("pycache_prefix: Some(std::path::PathBuf::from(r#\"/var/cache/myapp\"#)),"));
+
+        env.eval("config.pycache_prefix = None")?;
+        env.eval_assert("config.pycache_prefix == None")?;
+
         Ok(())
     }
 
@@ -1003,6 +1904,25 @@ mod tests {
 
         env.eval_assert("config.python_path_env == None")?;
 
+        assert!(env.eval("config.python_path_env = 42").is_err());
+
+        env.eval("config.python_path_env = 'lib:site-packages'")?;
+        env.eval_assert("config.python_path_env == 'lib:site-packages'")?;
+
+        let config = env.eval("config")?;
+        let config = config
+            .downcast_ref::<PythonInterpreterConfigValue>()
+            .unwrap();
+        let code = config
+            .inner
+            .to_oxidized_python_interpreter_config_rs(None)?;
+        assert!(code
+            .as_str()
+            .contains("python_path_env: Some(r#\"lib:site-packages\"#.to_string()),"));
+
+        env.eval("config.python_path_env = None")?;
+        env.eval_assert("config.python_path_env == None")?;
+
         Ok(())
     }
 
@@ -1048,6 +1968,23 @@ mod tests {
 
         env.eval_assert("config.show_alloc_count == None")?;
 
+        assert!(env.eval("config.show_alloc_count = 1").is_err());
+
+        env.eval("config.show_alloc_count = True")?;
+        env.eval_assert("config.show_alloc_count == True")?;
+
+        let config = env.eval("config")?;
+        let config = config
+            .downcast_ref::<PythonInterpreterConfigValue>()
+            .unwrap();
+        let code = config
+            .inner
+            .to_oxidized_python_interpreter_config_rs(None)?;
+        assert!(code.as_str().contains("show_alloc_count: Some(true),"));
+
+        env.eval("config.show_alloc_count = False")?;
+        env.eval_assert("config.show_alloc_count == False")?;
+
         Ok(())
     }
 
@@ -1057,6 +1994,23 @@ mod tests {
 
         env.eval_assert("config.show_ref_count == None")?;
 
+        assert!(env.eval("config.show_ref_count = 1").is_err());
+
+        env.eval("config.show_ref_count = True")?;
+        env.eval_assert("config.show_ref_count == True")?;
+
+        let config = env.eval("config")?;
+        let config = config
+            .downcast_ref::<PythonInterpreterConfigValue>()
+            .unwrap();
+        let code = config
+            .inner
+            .to_oxidized_python_interpreter_config_rs(None)?;
+        assert!(code.as_str().contains("show_ref_count: Some(true),"));
+
+        env.eval("config.show_ref_count = False")?;
+        env.eval_assert("config.show_ref_count == False")?;
+
         Ok(())
     }
 
@@ -1066,6 +2020,29 @@ mod tests {
 
         env.eval_assert("config.site_import == None")?;
 
+        assert!(env.eval("config.site_import = 1").is_err());
+
+        for value in &[false, true] {
+            env.eval(&format!(
+                "config.site_import = {}",
+                if *value { "True" } else { "False" }
+            ))?;
+
+            let config = env.eval("config")?;
+            let config = config
+                .downcast_ref::<PythonInterpreterConfigValue>()
+                .unwrap();
+            let code = config
+                .inner
+                .to_oxidized_python_interpreter_config_rs(None)?;
+            assert!(code
+                .as_str()
+                .contains(&format!("site_import: Some({}),", value)));
+        }
+
+        env.eval("config.site_import = None")?;
+        env.eval_assert("config.site_import == None")?;
+
         Ok(())
     }
 
@@ -1084,6 +2061,27 @@ mod tests {
 
         env.eval_assert("config.stdio_encoding == None")?;
 
+        assert!(env.eval("config.stdio_encoding = ''").is_err());
+        assert!(env.eval("config.stdio_encoding = 'utf-8:strict'").is_err());
+        assert!(env.eval("config.stdio_encoding = True").is_err());
+
+        env.eval("config.stdio_encoding = 'utf-8'")?;
+        env.eval_assert("config.stdio_encoding == 'utf-8'")?;
+
+        let config = env.eval("config")?;
+        let config = config
+            .downcast_ref::<PythonInterpreterConfigValue>()
+            .unwrap();
+        let code = config
+            .inner
+            .to_oxidized_python_interpreter_config_rs(None)?;
+        assert!(code
+            .as_str()
+            .contains("stdio_encoding: Some(r#\"utf-8\"#.to_string()),"));
+
+        env.eval("config.stdio_encoding = None")?;
+        env.eval_assert("config.stdio_encoding == None")?;
+
         Ok(())
     }
 
@@ -1093,6 +2091,23 @@ mod tests {
 
         env.eval_assert("config.stdio_errors == None")?;
 
+        assert!(env.eval("config.stdio_errors = 'bogus'").is_err());
+        assert!(env.eval("config.stdio_errors = True").is_err());
+
+        env.eval("config.stdio_errors = 'backslashreplace'")?;
+        env.eval_assert("config.stdio_errors == 'backslashreplace'")?;
+
+        let config = env.eval("config")?;
+        let config = config
+            .downcast_ref::<PythonInterpreterConfigValue>()
+            .unwrap();
+        let code = config
+            .inner
+            .to_oxidized_python_interpreter_config_rs(None)?;
+        assert!(code
+            .as_str()
+            .contains("stdio_errors: Some(r#\"backslashreplace\"#.to_string()),"));
+
         Ok(())
     }
 
@@ -1102,6 +2117,23 @@ mod tests {
 
         env.eval_assert("config.tracemalloc == None")?;
 
+        assert!(env.eval("config.tracemalloc = 1").is_err());
+
+        env.eval("config.tracemalloc = True")?;
+        env.eval_assert("config.tracemalloc == True")?;
+
+        let config = env.eval("config")?;
+        let config = config
+            .downcast_ref::<PythonInterpreterConfigValue>()
+            .unwrap();
+        let code = config
+            .inner
+            .to_oxidized_python_interpreter_config_rs(None)?;
+        assert!(code.as_str().contains("tracemalloc: Some(true),"));
+
+        env.eval("config.tracemalloc = False")?;
+        env.eval_assert("config.tracemalloc == False")?;
+
         Ok(())
     }
 
@@ -1129,6 +2161,24 @@ mod tests {
 
         env.eval_assert("config.warn_options == None")?;
 
+        env.eval("config.add_warn_option('ignore::DeprecationWarning')")?;
+        env.eval("config.add_warn_option('error')")?;
+        env.eval("config.add_warn_option('ignore:\"#:UserWarning')")?;
+        env.eval_assert(
+            "config.warn_options == ['ignore::DeprecationWarning', 'error', 'ignore:\"#:UserWarning']",
+        )?;
+
+        let config = env.eval("config")?;
+        let config = config
+            .downcast_ref::<PythonInterpreterConfigValue>()
+            .unwrap();
+        let code = config
+            .inner
+            .to_oxidized_python_interpreter_config_rs(None)?;
+        assert!(code.as_str().contains(
+            "warn_options: Some(vec![\"ignore::DeprecationWarning\".to_string(), \"error\".to_string(), \"ignore:\\\"#:UserWarning\".to_string()]),"
+        ));
+
         Ok(())
     }
 
@@ -1147,6 +2197,26 @@ mod tests {
 
         env.eval_assert("config.x_options == None")?;
 
+        env.eval("config.add_x_option('utf8')")?;
+        env.eval("config.add_x_option('importtime=1')")?;
+        env.eval_assert("config.x_options == ['utf8', 'importtime=1']")?;
+
+        assert!(env.eval("config.add_x_option('')").is_err());
+        assert!(env.eval("config.add_x_option('=1')").is_err());
+        assert!(env.eval("config.add_x_option('foo bar')").is_err());
+        env.eval_assert("config.x_options == ['utf8', 'importtime=1']")?;
+
+        let config = env.eval("config")?;
+        let config = config
+            .downcast_ref::<PythonInterpreterConfigValue>()
+            .unwrap();
+        let code = config
+            .inner
+            .to_oxidized_python_interpreter_config_rs(None)?;
+        assert!(code.as_str().contains(
+            "x_options: Some(vec![\"utf8\".to_string(), \"importtime=1\".to_string()]),"
+        ));
+
         Ok(())
     }
 
@@ -1162,6 +2232,9 @@ mod tests {
         env.eval("config.raw_allocator = 'rust'")?;
         env.eval_assert("config.raw_allocator == 'rust'")?;
 
+        env.eval("config.raw_allocator = 'snmalloc'")?;
+        env.eval_assert("config.raw_allocator == 'snmalloc'")?;
+
         Ok(())
     }
 
@@ -1183,6 +2256,37 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_enable_filesystem_importer() -> Result<()> {
+        let mut env = get_env()?;
+
+        assert!(env.eval("config.enable_filesystem_importer([])").is_err());
+        assert!(env
+            .eval("config.enable_filesystem_importer(['foo', 42])")
+            .is_err());
+        env.eval_assert("config.filesystem_importer == False")?;
+        env.eval_assert("config.module_search_paths == None")?;
+
+        env.eval("config.module_search_paths = ['old']")?;
+        env.eval("config.enable_filesystem_importer(['$ORIGIN/lib'])")?;
+        env.eval_assert("config.filesystem_importer == True")?;
+        env.eval_assert("config.module_search_paths == ['$ORIGIN/lib']")?;
+
+        let config = env.eval("config")?;
+        let config = config
+            .downcast_ref::<PythonInterpreterConfigValue>()
+            .unwrap();
+        let code = config
+            .inner
+            .to_oxidized_python_interpreter_config_rs(None)?;
+        assert!(code.as_str().contains(
+            "module_search_paths: Some(vec![std::path::PathBuf::from(\"$ORIGIN/lib\")]),"
+        ));
+        assert!(code.as_str().contains("filesystem_importer: true,"));
+
+        Ok(())
+    }
+
     #[test]
     fn test_argvb() -> Result<()> {
         let mut env = get_env()?;
@@ -1225,6 +2329,55 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_set_terminfo_resolution_file() -> Result<()> {
+        let temp_dir = tempdir::TempDir::new("pyoxidizer-test")?;
+        let terminfo_path = temp_dir.path().join("terminfo");
+        std::fs::write(&terminfo_path, b"\x1a\x01\x00\xff")?;
+        let empty_path = temp_dir.path().join("empty");
+        std::fs::write(&empty_path, "")?;
+
+        let mut env = get_env()?;
+
+        assert!(env
+            .eval(&format!(
+                "config.set_terminfo_resolution_file('{}')",
+                temp_dir.path().join("missing").display()
+            ))
+            .is_err());
+        assert!(env
+            .eval(&format!(
+                "config.set_terminfo_resolution_file('{}')",
+                empty_path.display()
+            ))
+            .is_err());
+
+        // Relative paths are resolved against the context's directory.
+        env.eval(&format!(
+            "set_cwd({:?})",
+            temp_dir.path().display().to_string()
+        ))?;
+        env.eval("config.set_terminfo_resolution_file('terminfo')")?;
+        env.eval_assert(&format!(
+            "config.terminfo_resolution == 'static:{}'",
+            terminfo_path.display()
+        ))?;
+
+        let config = env.eval("config")?;
+        let config = config
+            .downcast_ref::<PythonInterpreterConfigValue>()
+            .unwrap();
+        let code = config
+            .inner
+            .to_oxidized_python_interpreter_config_rs(None)?;
+        assert!(code.as_str().contains(&format!(
+            "terminfo_resolution: pyembed::TerminfoResolution::Static({:?}.to_string()),",
+            terminfo_path.display().to_string()
+        )));
+
+        Ok(())
+    }
+
     #[test]
     fn test_write_modules_directory_env() -> Result<()> {
         let mut env = get_env()?;
@@ -1234,6 +2387,51 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_fault_handler_path() -> Result<()> {
+        let mut env = get_env()?;
+
+        env.eval_assert("config.fault_handler_path == None")?;
+
+        assert!(env.eval("config.fault_handler_path = True").is_err());
+        assert!(env.eval("config.fault_handler_path = ''").is_err());
+        assert!(env
+            .eval("config.fault_handler_path = 'faults.log'")
+            .is_err());
+        assert!(env
+            .eval("config.fault_handler_path = '/var/log/faults.log'")
+            .is_err());
+        assert!(env.eval("config.fault_handler_path = '$ORIGIN/'").is_err());
+        assert!(env
+            .eval("config.fault_handler_path = '$ORIGIN/../faults.log'")
+            .is_err());
+        assert!(env
+            .eval("config.fault_handler_path = '$ORIGIN/$HOME/faults.log'")
+            .is_err());
+        env.eval_assert("config.fault_handler_path == None")?;
+
+        env.eval("config.fault_handler = True")?;
+        env.eval("config.fault_handler_path = '$ORIGIN/logs/faults.log'")?;
+        env.eval_assert("config.fault_handler_path == '$ORIGIN/logs/faults.log'")?;
+
+        let config = env.eval("config")?;
+        let config = config
+            .downcast_ref::<PythonInterpreterConfigValue>()
+            .unwrap();
+        let code = config
+            .inner
+            .to_oxidized_python_interpreter_config_rs(None)?;
+        assert!(code.as_str().contains("fault_handler: Some(true),"));
+        assert!(code
+            .as_str()
+            .contains("fault_handler_path: Some(r#\"$ORIGIN/logs/faults.log\"#.to_string()),"));
+
+        env.eval("config.fault_handler_path = None")?;
+        env.eval_assert("config.fault_handler_path == None")?;
+
+        Ok(())
+    }
+
     #[test]
     fn test_run_mode() -> Result<()> {
         let mut env = get_env()?;
@@ -1252,6 +2450,88 @@ mod tests {
         env.eval("config.run_mode = 'file:path'")?;
         env.eval_assert("config.run_mode == 'file:path'")?;
 
+        env.eval("config.run_mode = 'resource:foo'")?;
+        env.eval_assert("config.run_mode == 'resource:foo'")?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_set_environment_variable() -> Result<()> {
+        let mut env = get_env()?;
+
+        assert!(env
+            .eval("config.set_environment_variable('', 'value')")
+            .is_err());
+        assert!(env
+            .eval("config.set_environment_variable('1FOO', 'value')")
+            .is_err());
+        assert!(env
+            .eval("config.set_environment_variable('FOO=BAR', 'value')")
+            .is_err());
+        assert!(env
+            .eval("config.set_environment_variable('FOO', 42)")
+            .is_err());
+
+        env.eval("config.set_environment_variable('SSL_CERT_FILE', '/etc/cacert.pem')")?;
+        env.eval("config.set_environment_variable('_MYAPP_MODE', 'frozen')")?;
+        env.eval("config.set_environment_variable('SSL_CERT_FILE', '$ORIGIN/cacert.pem')")?;
+
+        let config = env.eval("config")?;
+        let config = config
+            .downcast_ref::<PythonInterpreterConfigValue>()
+            .unwrap();
+        assert_eq!(
+            config.inner.environment_variables,
+            vec![
+                (
+                    "SSL_CERT_FILE".to_string(),
+                    "$ORIGIN/cacert.pem".to_string()
+                ),
+                ("_MYAPP_MODE".to_string(), "frozen".to_string()),
+            ]
+        );
+
+        let code = config
+            .inner
+            .to_oxidized_python_interpreter_config_rs(None)?;
+        assert!(code
+            .as_str()
+            .contains("(\"SSL_CERT_FILE\".to_string(), \"$ORIGIN/cacert.pem\".to_string())"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_multiprocessing_auto_dispatch() -> Result<()> {
+        let mut env = get_env()?;
+
+        env.eval_assert("config.multiprocessing_auto_dispatch == False")?;
+
+        env.eval("config.multiprocessing_auto_dispatch = True")?;
+        env.eval_assert("config.multiprocessing_auto_dispatch == True")?;
+
+        let config = env.eval("config")?;
+        let config = config
+            .downcast_ref::<PythonInterpreterConfigValue>()
+            .unwrap();
+        let code = config
+            .inner
+            .to_oxidized_python_interpreter_config_rs(None)?;
+        assert!(code
+            .as_str()
+            .contains("multiprocessing_auto_dispatch: true,"));
+
+        assert!(env.eval("config.run_mode = 'none'").is_err());
+        env.eval_assert("config.run_mode == 'repl'")?;
+
+        env.eval("config.multiprocessing_auto_dispatch = False")?;
+        env.eval("config.run_mode = 'none'")?;
+        assert!(env
+            .eval("config.multiprocessing_auto_dispatch = True")
+            .is_err());
+        env.eval_assert("config.multiprocessing_auto_dispatch == False")?;
+
         Ok(())
     }
 }
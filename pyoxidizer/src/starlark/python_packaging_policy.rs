@@ -5,7 +5,7 @@
 use {
     super::{
         python_resource::ResourceCollectionContext,
-        util::{required_str_arg, required_type_arg},
+        util::{required_list_arg, required_str_arg, required_type_arg},
     },
     linked_hash_map::LinkedHashMap,
     python_packaging::{
@@ -33,6 +33,9 @@ pub struct PythonPackagingPolicyValue {
 
     /// Starlark functions to influence PythonResourceAddCollectionContext creation.
     derive_context_callbacks: Vec<Value>,
+
+    /// Names of attributes explicitly set from Starlark, in the order first set.
+    set_attributes: Vec<String>,
 }
 
 impl PythonPackagingPolicyValue {
@@ -40,6 +43,14 @@ impl PythonPackagingPolicyValue {
         Self {
             inner,
             derive_context_callbacks: vec![],
+            set_attributes: vec![],
+        }
+    }
+
+    /// Record that an attribute was explicitly set.
+    fn record_set_attribute(&mut self, attribute: &str) {
+        if !self.set_attributes.iter().any(|x| x == attribute) {
+            self.set_attributes.push(attribute.to_string());
         }
     }
 
@@ -111,12 +122,21 @@ impl TypedValue for PythonPackagingPolicyValue {
             "allow_in_memory_shared_library_loading" => {
                 Value::from(self.inner.allow_in_memory_shared_library_loading())
             }
+            "apply_pth_files" => Value::from(self.inner.apply_pth_files()),
+            "bytecode_only" => Value::from(self.inner.bytecode_only()),
             "bytecode_optimize_level_zero" => {
                 Value::from(self.inner.bytecode_optimize_level_zero())
             }
             "bytecode_optimize_level_one" => Value::from(self.inner.bytecode_optimize_level_one()),
             "bytecode_optimize_level_two" => Value::from(self.inner.bytecode_optimize_level_two()),
+            "error_on_duplicate_resources" => {
+                Value::from(self.inner.error_on_duplicate_resources())
+            }
+            "excluded_extension_modules" => Value::from(self.inner.excluded_extension_modules()),
             "extension_module_filter" => Value::from(self.inner.extension_module_filter().as_ref()),
+            "include_distribution_metadata" => {
+                Value::from(self.inner.include_distribution_metadata())
+            }
             "include_distribution_sources" => {
                 Value::from(self.inner.include_distribution_sources())
             }
@@ -127,6 +147,7 @@ impl TypedValue for PythonPackagingPolicyValue {
                 Value::from(self.inner.include_non_distribution_sources())
             }
             "include_test" => Value::from(self.inner.include_test()),
+            "kept_distribution_metadata" => Value::from(self.inner.kept_distribution_metadata()),
             "preferred_extension_module_variants" => {
                 Value::try_from(self.inner.preferred_extension_module_variants().clone())?
             }
@@ -135,6 +156,7 @@ impl TypedValue for PythonPackagingPolicyValue {
                 Some(location) => Value::from(location.to_string()),
                 None => Value::from(NoneType::None),
             },
+            "strip_docstrings" => Value::from(self.inner.strip_docstrings()),
             attr => {
                 return Err(ValueError::OperationNotSupported {
                     op: UnsupportedOperation::GetAttr(attr.to_string()),
@@ -150,17 +172,24 @@ impl TypedValue for PythonPackagingPolicyValue {
     fn has_attr(&self, attribute: &str) -> Result<bool, ValueError> {
         Ok(match attribute {
             "allow_in_memory_shared_library_loading" => true,
+            "apply_pth_files" => true,
+            "bytecode_only" => true,
             "bytecode_optimize_level_zero" => true,
             "bytecode_optimize_level_one" => true,
             "bytecode_optimize_level_two" => true,
+            "error_on_duplicate_resources" => true,
+            "excluded_extension_modules" => true,
             "extension_module_filter" => true,
+            "include_distribution_metadata" => true,
             "include_distribution_sources" => true,
             "include_distribution_resources" => true,
             "include_non_distribution_sources" => true,
             "include_test" => true,
+            "kept_distribution_metadata" => true,
             "preferred_extension_module_variants" => true,
             "resources_location" => true,
             "resources_location_fallback" => true,
+            "strip_docstrings" => true,
             _ => false,
         })
     }
@@ -171,6 +200,12 @@ impl TypedValue for PythonPackagingPolicyValue {
                 self.inner
                     .set_allow_in_memory_shared_library_loading(value.to_bool());
             }
+            "apply_pth_files" => {
+                self.inner.set_apply_pth_files(value.to_bool());
+            }
+            "bytecode_only" => {
+                self.inner.set_bytecode_only(value.to_bool());
+            }
             "bytecode_optimize_level_zero" => {
                 self.inner.set_bytecode_optimize_level_zero(value.to_bool());
             }
@@ -180,6 +215,28 @@ impl TypedValue for PythonPackagingPolicyValue {
             "bytecode_optimize_level_two" => {
                 self.inner.set_bytecode_optimize_level_two(value.to_bool());
             }
+            "error_on_duplicate_resources" => {
+                self.inner.set_error_on_duplicate_resources(value.to_bool());
+            }
+            "excluded_extension_modules" => {
+                required_list_arg(attribute, "string", &value)?;
+
+                let patterns = value
+                    .to_vec()?
+                    .iter()
+                    .map(|x| x.to_string())
+                    .collect::<Vec<_>>();
+
+                self.inner
+                    .set_excluded_extension_modules(patterns)
+                    .map_err(|e| {
+                        ValueError::from(RuntimeError {
+                            code: "PYOXIDIZER_BUILD",
+                            message: e.to_string(),
+                            label: format!("{}.{}", Self::TYPE, attribute),
+                        })
+                    })?;
+            }
             "extension_module_filter" => {
                 let filter =
                     ExtensionModuleFilter::try_from(value.to_string().as_str()).map_err(|e| {
@@ -192,6 +249,10 @@ impl TypedValue for PythonPackagingPolicyValue {
 
                 self.inner.set_extension_module_filter(filter);
             }
+            "include_distribution_metadata" => {
+                self.inner
+                    .set_include_distribution_metadata(value.to_bool());
+            }
             "include_distribution_sources" => {
                 self.inner.set_include_distribution_sources(value.to_bool());
             }
@@ -206,6 +267,25 @@ impl TypedValue for PythonPackagingPolicyValue {
             "include_test" => {
                 self.inner.set_include_test(value.to_bool());
             }
+            "kept_distribution_metadata" => {
+                required_list_arg(attribute, "string", &value)?;
+
+                let patterns = value
+                    .to_vec()?
+                    .iter()
+                    .map(|x| x.to_string())
+                    .collect::<Vec<_>>();
+
+                self.inner
+                    .set_kept_distribution_metadata(patterns)
+                    .map_err(|e| {
+                        ValueError::from(RuntimeError {
+                            code: "PYOXIDIZER_BUILD",
+                            message: e.to_string(),
+                            label: format!("{}.{}", Self::TYPE, attribute),
+                        })
+                    })?;
+            }
             "resources_location" => {
                 self.inner.set_resources_location(
                     ConcreteResourceLocation::try_from(value.to_string().as_str()).map_err(
@@ -246,6 +326,9 @@ impl TypedValue for PythonPackagingPolicyValue {
                     ));
                 }
             }
+            "strip_docstrings" => {
+                self.inner.set_strip_docstrings(value.to_bool());
+            }
             attr => {
                 return Err(ValueError::OperationNotSupported {
                     op: UnsupportedOperation::SetAttr(attr.to_string()),
@@ -255,6 +338,8 @@ impl TypedValue for PythonPackagingPolicyValue {
             }
         }
 
+        self.record_set_attribute(attribute);
+
         Ok(())
     }
 }
@@ -279,12 +364,50 @@ impl PythonPackagingPolicyValue {
 
         self.inner
             .set_preferred_extension_module_variant(&name, &value);
+        self.record_set_attribute("preferred_extension_module_variants");
 
         Ok(Value::from(NoneType::None))
     }
+
+    fn starlark_merge(&self, other: &Value) -> ValueResult {
+        required_type_arg("other", Self::TYPE, other)?;
+        let other = other.downcast_ref::<PythonPackagingPolicyValue>().unwrap();
+
+        let mut merged = self.clone();
+
+        for attribute in &other.set_attributes {
+            match attribute.as_str() {
+                // Variants are merged by extension name.
+                "preferred_extension_module_variants" => {
+                    for (name, value) in other.inner.preferred_extension_module_variants() {
+                        merged
+                            .inner
+                            .set_preferred_extension_module_variant(name, value);
+                    }
+                    merged.record_set_attribute(attribute);
+                }
+                _ => {
+                    merged.set_attr(attribute, other.get_attr(attribute)?)?;
+                }
+            }
+        }
+
+        merged
+            .derive_context_callbacks
+            .extend(other.derive_context_callbacks.iter().cloned());
+
+        Ok(Value::new(merged))
+    }
 }
 
 starlark_module! { python_packaging_policy_module =>
+    PythonPackagingPolicy.merge(this, other) {
+        match this.clone().downcast_ref::<PythonPackagingPolicyValue>() {
+            Some(policy) => policy.starlark_merge(&other),
+            None => Err(ValueError::IncorrectParameterType),
+        }
+    }
+
     PythonPackagingPolicy.register_resource_callback(this, func) {
         match this.clone().downcast_mut::<PythonPackagingPolicyValue>()? {
             Some(mut policy) => policy.starlark_register_resource_callback(&func),
@@ -303,8 +426,15 @@ starlark_module! { python_packaging_policy_module =>
 #[cfg(test)]
 mod tests {
     use {
-        super::super::python_distribution::PythonDistribution, super::super::testutil::*, super::*,
+        super::super::python_distribution::PythonDistribution,
+        super::super::python_executable::PythonExecutable,
+        super::super::testutil::*,
+        super::*,
         anyhow::Result,
+        python_packaging::resource::{
+            DataLocation, PythonPackageDistributionResource,
+            PythonPackageDistributionResourceFlavor, PythonResource,
+        },
     };
 
     #[test]
@@ -472,6 +602,40 @@ mod tests {
         )?;
         assert!(value.to_bool());
 
+        // apply_pth_files
+        let value = env.eval("policy.apply_pth_files")?;
+        assert_eq!(value.get_type(), "bool");
+        assert!(!value.to_bool());
+
+        let value = env.eval("policy.apply_pth_files = True; policy.apply_pth_files")?;
+        assert!(value.to_bool());
+
+        // bytecode_only
+        let value = env.eval("policy.bytecode_only")?;
+        assert_eq!(value.get_type(), "bool");
+        assert!(!value.to_bool());
+
+        let value = env.eval("policy.bytecode_only = True; policy.bytecode_only")?;
+        assert!(value.to_bool());
+
+        // error_on_duplicate_resources
+        let value = env.eval("policy.error_on_duplicate_resources")?;
+        assert_eq!(value.get_type(), "bool");
+        assert!(!value.to_bool());
+
+        let value = env.eval(
+            "policy.error_on_duplicate_resources = True; policy.error_on_duplicate_resources",
+        )?;
+        assert!(value.to_bool());
+
+        // strip_docstrings
+        let value = env.eval("policy.strip_docstrings")?;
+        assert_eq!(value.get_type(), "bool");
+        assert!(!value.to_bool());
+
+        let value = env.eval("policy.strip_docstrings = True; policy.strip_docstrings")?;
+        assert!(value.to_bool());
+
         Ok(())
     }
 
@@ -496,6 +660,100 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_excluded_extension_modules() -> Result<()> {
+        let mut env = StarlarkEnvironment::new()?;
+
+        env.eval("dist = default_python_distribution()")?;
+        env.eval("policy = dist.make_python_packaging_policy()")?;
+
+        env.eval_assert("policy.excluded_extension_modules == []")?;
+        assert!(env
+            .eval("policy.excluded_extension_modules = '_sqlite3'")
+            .is_err());
+        assert!(env
+            .eval("policy.excluded_extension_modules = ['[invalid']")
+            .is_err());
+
+        env.eval("policy.excluded_extension_modules = ['_sqlite*']")?;
+        env.eval_assert("policy.excluded_extension_modules == ['_sqlite*']")?;
+
+        let exe = env.eval("dist.to_python_executable('testapp')")?;
+        let exe = exe.downcast_ref::<PythonExecutable>().unwrap();
+        assert!(exe.exe.iter_resources().any(|(name, _)| name == "_sqlite3"));
+
+        let exe = env.eval("dist.to_python_executable('testapp', packaging_policy=policy)")?;
+        let exe = exe.downcast_ref::<PythonExecutable>().unwrap();
+        assert!(!exe.exe.iter_resources().any(|(name, _)| name == "_sqlite3"));
+        assert!(exe.exe.iter_resources().any(|(name, _)| name == "_io"));
+
+        // Extension modules required by the interpreter can't be excluded.
+        env.eval("policy.excluded_extension_modules = ['_io']")?;
+        assert!(env
+            .eval("dist.to_python_executable('testapp', packaging_policy=policy)")
+            .is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_distribution_metadata() -> Result<()> {
+        let mut env = StarlarkEnvironment::new()?;
+
+        env.eval("dist = default_python_distribution()")?;
+        env.eval("policy = dist.make_python_packaging_policy()")?;
+
+        env.eval_assert("policy.include_distribution_metadata == True")?;
+        env.eval_assert(
+            "policy.kept_distribution_metadata == ['METADATA', 'PKG-INFO', 'entry_points.txt']",
+        )?;
+        assert!(env
+            .eval("policy.kept_distribution_metadata = 'METADATA'")
+            .is_err());
+        assert!(env
+            .eval("policy.kept_distribution_metadata = ['[invalid']")
+            .is_err());
+
+        let resource = |name: &str| -> PythonResource<'static> {
+            PythonPackageDistributionResource {
+                location: PythonPackageDistributionResourceFlavor::DistInfo,
+                package: "foo".to_string(),
+                version: "1.0".to_string(),
+                name: name.to_string(),
+                data: DataLocation::Memory(vec![]),
+            }
+            .into()
+        };
+
+        let included = |env: &mut StarlarkEnvironment, name: &str| -> Result<bool> {
+            let policy_value = env.eval("policy")?;
+            let policy = policy_value
+                .downcast_ref::<PythonPackagingPolicyValue>()
+                .unwrap();
+
+            Ok(policy
+                .inner
+                .derive_add_collection_context(&resource(name))
+                .include)
+        };
+
+        assert!(included(&mut env, "RECORD")?);
+        assert!(included(&mut env, "METADATA")?);
+
+        env.eval("policy.include_distribution_metadata = False")?;
+        assert!(!included(&mut env, "RECORD")?);
+        assert!(!included(&mut env, "INSTALLER")?);
+        assert!(included(&mut env, "METADATA")?);
+        assert!(included(&mut env, "entry_points.txt")?);
+
+        env.eval("policy.kept_distribution_metadata = ['METADATA', '*.txt']")?;
+        assert!(!included(&mut env, "RECORD")?);
+        assert!(included(&mut env, "METADATA")?);
+        assert!(included(&mut env, "top_level.txt")?);
+
+        Ok(())
+    }
+
     #[test]
     fn test_register_resource_callback() -> Result<()> {
         let mut env = StarlarkEnvironment::new()?;
@@ -518,4 +776,71 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_merge() -> Result<()> {
+        let mut env = StarlarkEnvironment::new()?;
+
+        env.eval("dist = default_python_distribution()")?;
+        env.eval("base = dist.make_python_packaging_policy()")?;
+        env.eval("base.bytecode_only = True")?;
+        env.eval("base.include_test = False")?;
+        env.eval("base.excluded_extension_modules = ['_ssl']")?;
+        env.eval("base.set_preferred_extension_module_variant('foo', 'base')")?;
+        env.eval("base.set_preferred_extension_module_variant('bar', 'base')")?;
+        env.eval("def base_func(policy, resource):\n    resource.add_bytecode_optimization_level_one = True\n")?;
+        env.eval("base.register_resource_callback(base_func)")?;
+
+        env.eval("other = dist.make_python_packaging_policy()")?;
+        env.eval("other.include_test = True")?;
+        env.eval("other.excluded_extension_modules = ['_sqlite*']")?;
+        env.eval("other.set_preferred_extension_module_variant('foo', 'other')")?;
+        env.eval("def other_func(policy, resource):\n    if resource.add_bytecode_optimization_level_one:\n        resource.add_bytecode_optimization_level_two = True\n")?;
+        env.eval("other.register_resource_callback(other_func)")?;
+
+        assert!(env.eval("base.merge('foo')").is_err());
+
+        env.eval("merged = base.merge(other)")?;
+
+        // Attributes not set on `other` come from `base`.
+        env.eval_assert("merged.bytecode_only == True")?;
+        env.eval_assert("merged.resources_location == base.resources_location")?;
+        // Attributes set on `other` override `base`. Lists are replaced.
+        env.eval_assert("merged.include_test == True")?;
+        env.eval_assert("merged.excluded_extension_modules == ['_sqlite*']")?;
+        // Preferred variants are merged by name.
+        env.eval_assert("merged.preferred_extension_module_variants['foo'] == 'other'")?;
+        env.eval_assert("merged.preferred_extension_module_variants['bar'] == 'base'")?;
+
+        // Inputs are unchanged.
+        env.eval_assert("base.include_test == False")?;
+        env.eval_assert("base.excluded_extension_modules == ['_ssl']")?;
+        env.eval_assert("other.bytecode_only == False")?;
+
+        {
+            let merged_value = env.eval("merged")?;
+            let merged = merged_value
+                .downcast_ref::<PythonPackagingPolicyValue>()
+                .unwrap();
+            assert_eq!(
+                merged
+                    .derive_context_callbacks
+                    .iter()
+                    .map(|x| x.to_str())
+                    .collect::<Vec<_>>(),
+                vec![
+                    "base_func(policy, resource)",
+                    "other_func(policy, resource)"
+                ]
+            );
+        }
+
+        // Callbacks run in order: `other_func` sees what `base_func` did.
+        env.eval("exe = dist.to_python_executable('testapp', packaging_policy=merged)")?;
+        env.eval("m = exe.make_python_module_source('foo', '')")?;
+        env.eval_assert("m.add_bytecode_optimization_level_one == True")?;
+        env.eval_assert("m.add_bytecode_optimization_level_two == True")?;
+
+        Ok(())
+    }
 }
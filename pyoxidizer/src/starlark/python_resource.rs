@@ -23,7 +23,10 @@ use {
             {Mutable, TypedValue, Value, ValueResult},
         },
     },
-    std::convert::{TryFrom, TryInto},
+    std::{
+        convert::{TryFrom, TryInto},
+        path::{Component, Path, PathBuf},
+    },
 };
 
 #[derive(Clone, Debug)]
@@ -231,6 +234,8 @@ pub trait ResourceCollectionContext {
 pub struct PythonModuleSourceValue {
     pub inner: PythonModuleSource,
     pub add_context: Option<PythonResourceAddCollectionContext>,
+    /// Describes where the resource was obtained from.
+    pub origin: Option<String>,
 }
 
 impl PythonModuleSourceValue {
@@ -238,6 +243,7 @@ impl PythonModuleSourceValue {
         Self {
             inner: module,
             add_context: None,
+            origin: None,
         }
     }
 }
@@ -340,6 +346,8 @@ impl TypedValue for PythonModuleSourceValue {
 pub struct PythonPackageResourceValue {
     pub inner: PythonPackageResource,
     pub add_context: Option<PythonResourceAddCollectionContext>,
+    /// Describes where the resource was obtained from.
+    pub origin: Option<String>,
 }
 
 impl PythonPackageResourceValue {
@@ -347,6 +355,7 @@ impl PythonPackageResourceValue {
         Self {
             inner: resource,
             add_context: None,
+            origin: None,
         }
     }
 }
@@ -391,6 +400,14 @@ impl TypedValue for PythonPackageResourceValue {
             "is_stdlib" => Value::from(self.inner.is_stdlib),
             "package" => Value::new(self.inner.leaf_package.clone()),
             "name" => Value::new(self.inner.relative_name.clone()),
+            "add_install_relative_path" => match self
+                .add_context
+                .as_ref()
+                .and_then(|context| context.install_relative_path.as_ref())
+            {
+                Some(path) => Value::new(path.display().to_string()),
+                None => Value::from(NoneType::None),
+            },
             // TODO expose raw data
             attr => {
                 return if self.add_collection_context_attrs().contains(&attr) {
@@ -413,21 +430,76 @@ impl TypedValue for PythonPackageResourceValue {
             "is_stdlib" => true,
             "package" => true,
             "name" => true,
+            "add_install_relative_path" => true,
             // TODO expose raw data
             attr => self.add_collection_context_attrs().contains(&attr),
         })
     }
 
     fn set_attr(&mut self, attribute: &str, value: Value) -> Result<(), ValueError> {
-        self.set_attr_add_collection_context(attribute, value)
+        match attribute {
+            "add_install_relative_path" => {
+                let path = match value.get_type() {
+                    "NoneType" => None,
+                    "string" => Some(validate_install_relative_path(&value.to_str())?),
+                    t => {
+                        return Err(ValueError::from(RuntimeError {
+                            code: INCORRECT_PARAMETER_TYPE_ERROR_CODE,
+                            message: format!("expected string or None; got {}", t),
+                            label: format!("{}.{}", Self::TYPE, attribute),
+                        }))
+                    }
+                };
+
+                match self.add_context.as_mut() {
+                    Some(context) => {
+                        context.install_relative_path = path;
+                        Ok(())
+                    }
+                    None => Err(ValueError::from(RuntimeError {
+                        code: "PYOXIDIZER",
+                        message: "attempting to set a collection context attribute on an object without a context".to_string(),
+                        label: "setattr()".to_string(),
+                    })),
+                }
+            }
+            _ => self.set_attr_add_collection_context(attribute, value),
+        }
     }
 }
 
+/// Validate a path to install a resource at, relative to the binary.
+///
+/// The path must be relative, non-empty, and not contain `.` or `..`
+/// components.
+fn validate_install_relative_path(value: &str) -> Result<PathBuf, ValueError> {
+    let path = Path::new(value);
+
+    if value.is_empty()
+        || !path
+            .components()
+            .all(|component| matches!(component, Component::Normal(_)))
+    {
+        return Err(ValueError::from(RuntimeError {
+            code: INCORRECT_PARAMETER_TYPE_ERROR_CODE,
+            message: format!(
+                "install path must be a normalized relative path; got {}",
+                value
+            ),
+            label: "add_install_relative_path".to_string(),
+        }));
+    }
+
+    Ok(path.components().collect())
+}
+
 /// Starlark `Value` wrapper for `PythonPackageDistributionResource`.
 #[derive(Debug, Clone)]
 pub struct PythonPackageDistributionResourceValue {
     pub inner: PythonPackageDistributionResource,
     pub add_context: Option<PythonResourceAddCollectionContext>,
+    /// Describes where the resource was obtained from.
+    pub origin: Option<String>,
 }
 
 impl PythonPackageDistributionResourceValue {
@@ -435,6 +507,7 @@ impl PythonPackageDistributionResourceValue {
         Self {
             inner: resource,
             add_context: None,
+            origin: None,
         }
     }
 }
@@ -520,6 +593,8 @@ impl TypedValue for PythonPackageDistributionResourceValue {
 pub struct PythonExtensionModuleValue {
     pub inner: PythonExtensionModule,
     pub add_context: Option<PythonResourceAddCollectionContext>,
+    /// Describes where the resource was obtained from.
+    pub origin: Option<String>,
 }
 
 impl PythonExtensionModuleValue {
@@ -527,6 +602,7 @@ impl PythonExtensionModuleValue {
         Self {
             inner: em,
             add_context: None,
+            origin: None,
         }
     }
 }
@@ -610,10 +686,12 @@ pub fn python_resource_to_value(
     call_stack: &mut CallStack,
     resource: &PythonResource,
     policy: &PythonPackagingPolicyValue,
+    origin: &str,
 ) -> ValueResult {
     match resource {
         PythonResource::ModuleSource(sm) => {
             let mut m = PythonModuleSourceValue::new(sm.clone().into_owned());
+            m.origin = Some(origin.to_string());
             policy.apply_to_resource(type_values, call_stack, &mut m)?;
 
             Ok(Value::new(m))
@@ -621,6 +699,7 @@ pub fn python_resource_to_value(
 
         PythonResource::PackageResource(data) => {
             let mut r = PythonPackageResourceValue::new(data.clone().into_owned());
+            r.origin = Some(origin.to_string());
             policy.apply_to_resource(type_values, call_stack, &mut r)?;
 
             Ok(Value::new(r))
@@ -628,6 +707,7 @@ pub fn python_resource_to_value(
 
         PythonResource::PackageDistributionResource(resource) => {
             let mut r = PythonPackageDistributionResourceValue::new(resource.clone().into_owned());
+            r.origin = Some(origin.to_string());
             policy.apply_to_resource(type_values, call_stack, &mut r)?;
 
             Ok(Value::new(r))
@@ -635,10 +715,11 @@ pub fn python_resource_to_value(
 
         PythonResource::ExtensionModule(em) => {
             let mut em = PythonExtensionModuleValue::new(em.clone().into_owned());
-            policy.apply_to_resource(type_values, call_stack,&mut em)?;
+            em.origin = Some(origin.to_string());
+            policy.apply_to_resource(type_values, call_stack, &mut em)?;
 
             Ok(Value::new(em))
-        },
+        }
 
         _ => {
             panic!("incompatible PythonResource variant passed; did you forget to filter through is_resource_starlark_compatible()?")
@@ -646,6 +727,23 @@ pub fn python_resource_to_value(
     }
 }
 
+/// Describe the type of a `PythonResource` for use in messages.
+///
+/// Types having a Starlark representation are described by their Starlark type name.
+pub fn python_resource_type_name(resource: &PythonResource) -> &'static str {
+    match resource {
+        PythonResource::ModuleSource(_) => "PythonModuleSource",
+        PythonResource::ModuleBytecodeRequest(_) => "PythonModuleBytecodeRequest",
+        PythonResource::ModuleBytecode(_) => "PythonModuleBytecode",
+        PythonResource::PackageResource(_) => "PythonPackageResource",
+        PythonResource::PackageDistributionResource(_) => "PythonPackageDistributionResource",
+        PythonResource::ExtensionModule(_) => "PythonExtensionModule",
+        PythonResource::EggFile(_) => "PythonEggFile",
+        PythonResource::PathExtension(_) => "PythonPathExtension",
+        PythonResource::FileData(_) => "FileData",
+    }
+}
+
 /// Attempt to resolve the `PythonResourceAddCollectionContext` for a Value.
 pub fn add_context_for_value(
     value: &Value,
@@ -0,0 +1,60 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use {
+    super::{
+        python_executable::PythonExecutable,
+        target::{BuildContext, BuildTarget, ResolvedTarget, RunMode},
+    },
+    anyhow::{anyhow, Context, Result},
+    slog::warn,
+    starlark::values::{Mutable, TypedValue, Value},
+};
+
+/// A Python executable that loads its resources from a standalone bundle file.
+///
+/// The bundle is written next to the executable.
+pub struct PythonResourcesBundle {
+    /// The executable, configured to write its resources to the bundle.
+    pub exe: PythonExecutable,
+
+    /// Filename of the bundle.
+    pub filename: String,
+}
+
+impl TypedValue for PythonResourcesBundle {
+    type Holder = Mutable<PythonResourcesBundle>;
+    const TYPE: &'static str = "PythonResourcesBundle";
+
+    fn values_for_descendant_check_and_freeze<'a>(
+        &'a self,
+    ) -> Box<dyn Iterator<Item = Value> + 'a> {
+        self.exe.values_for_descendant_check_and_freeze()
+    }
+}
+
+impl BuildTarget for PythonResourcesBundle {
+    fn build(&mut self, context: &BuildContext) -> Result<ResolvedTarget> {
+        let (target, extra_files) = self.exe.build_with_extra_files(context)?;
+
+        let exe_dir = match &target.run_mode {
+            RunMode::Path { path } => path
+                .parent()
+                .ok_or_else(|| anyhow!("{} has no parent directory", path.display()))?
+                .to_path_buf(),
+            RunMode::None => return Err(anyhow!("executable build did not produce a path")),
+        };
+
+        warn!(
+            &context.logger,
+            "writing resources bundle to {}",
+            exe_dir.join(&self.filename).display()
+        );
+        extra_files
+            .write_to_path(&exe_dir)
+            .context(format!("writing files to {}", exe_dir.display()))?;
+
+        Ok(target)
+    }
+}
@@ -3,12 +3,23 @@
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.
 
 use {
-    anyhow::{anyhow, Result},
-    std::path::PathBuf,
+    anyhow::{anyhow, Context, Result},
+    serde::Serialize,
+    slog::info,
+    starlark::environment::TypeValues,
+    std::{
+        collections::BTreeMap,
+        path::PathBuf,
+        time::{Duration, Instant},
+    },
 };
 
+/// Filename of the file describing a built target.
+pub const BUILD_RESULT_FILENAME: &str = "build-result.json";
+
 /// How a resolved target can be run.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
 pub enum RunMode {
     /// Target cannot be run.
     None,
@@ -16,17 +27,53 @@ pub enum RunMode {
     Path { path: PathBuf },
 }
 
+/// Durations of the phases of a build, in seconds, keyed by phase name.
+pub type BuildTimings = BTreeMap<String, f64>;
+
+/// Log how long a build phase started at `start` took.
+///
+/// Returns the elapsed time.
+pub fn log_phase_duration(logger: &slog::Logger, phase: &str, start: Instant) -> Duration {
+    let elapsed = start.elapsed();
+    info!(logger, "{} took {:.3}s", phase, elapsed.as_secs_f64());
+
+    elapsed
+}
+
 /// Represents a resolved target.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct ResolvedTarget {
     /// How the built target can be run.
     pub run_mode: RunMode,
 
     /// Where build artifacts are stored on the filesystem.
     pub output_path: PathBuf,
+
+    /// How long phases of the build took.
+    ///
+    /// Only targets instrumenting their build record timings.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub timings: BuildTimings,
 }
 
 impl ResolvedTarget {
+    /// Serialize this instance to JSON.
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    /// Write a JSON file describing this instance into its output path.
+    ///
+    /// Returns the path to the written file.
+    pub fn write_build_result(&self) -> Result<PathBuf> {
+        let path = self.output_path.join(BUILD_RESULT_FILENAME);
+
+        std::fs::write(&path, self.to_json()?)
+            .with_context(|| format!("writing {}", path.display()))?;
+
+        Ok(path)
+    }
+
     pub fn run(&self) -> Result<()> {
         match &self.run_mode {
             RunMode::None => Ok(()),
@@ -48,7 +95,7 @@ impl ResolvedTarget {
 /// Describes context that a target is built in.
 ///
 /// This is used to pass metadata to the `BuildTarget::build()` method.
-pub struct BuildContext {
+pub struct BuildContext<'a> {
     /// Logger where messages can be written.
     pub logger: slog::Logger,
 
@@ -68,6 +115,9 @@ pub struct BuildContext {
 
     /// Where generated files should be written.
     pub output_path: PathBuf,
+
+    /// Starlark type values used to call Starlark functions during building.
+    pub type_values: &'a TypeValues,
 }
 
 /// Trait that indicates a type can be resolved as a target.
@@ -75,3 +125,52 @@ pub trait BuildTarget {
     /// Build the target, resolving it
     fn build(&mut self, context: &BuildContext) -> Result<ResolvedTarget>;
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_json() -> Result<()> {
+        let temp_dir = tempdir::TempDir::new("pyoxidizer-test")?;
+        let exe_path = temp_dir.path().join("myapp");
+
+        let target = ResolvedTarget {
+            run_mode: RunMode::Path {
+                path: exe_path.clone(),
+            },
+            output_path: temp_dir.path().to_path_buf(),
+            timings: BuildTimings::new(),
+        };
+
+        let json_path = target.write_build_result()?;
+        assert_eq!(json_path, temp_dir.path().join("build-result.json"));
+
+        let value: serde_json::Value = serde_json::from_slice(&std::fs::read(&json_path)?)?;
+        assert_eq!(
+            value,
+            serde_json::json!({
+                "run_mode": {
+                    "type": "path",
+                    "path": exe_path.display().to_string(),
+                },
+                "output_path": temp_dir.path().display().to_string(),
+            })
+        );
+
+        let mut target = ResolvedTarget {
+            run_mode: RunMode::None,
+            output_path: temp_dir.path().to_path_buf(),
+            timings: BuildTimings::new(),
+        };
+
+        let value: serde_json::Value = serde_json::from_str(&target.to_json()?)?;
+        assert_eq!(value["run_mode"], serde_json::json!({"type": "none"}));
+
+        target.timings.insert("rust_compile".to_string(), 1.5);
+        let value: serde_json::Value = serde_json::from_str(&target.to_json()?)?;
+        assert_eq!(value["timings"], serde_json::json!({"rust_compile": 1.5}));
+
+        Ok(())
+    }
+}
@@ -5,6 +5,7 @@
 use {
     super::env::{get_context, global_environment, EnvironmentContext},
     crate::logging::PrintlnDrain,
+    crate::py_packaging::distribution::DistributionFlavorRegistry,
     anyhow::{anyhow, Result},
     codemap::CodeMap,
     codemap_diagnostic::{Diagnostic, Emitter},
@@ -17,6 +18,8 @@ use {
     },
 };
 
+pub use crate::testutil::CapturingDrain;
+
 /// A Starlark execution environment.
 ///
 /// Provides convenience wrappers for common functionality.
@@ -50,6 +53,9 @@ impl StarlarkEnvironment {
             "0",
             None,
             false,
+            false,
+            None,
+            false,
         )?;
 
         let (env, type_values) = global_environment(&context)
@@ -143,6 +149,71 @@ impl StarlarkEnvironment {
 
         Ok(())
     }
+
+    /// Replace the logger used by the environment.
+    pub fn set_logger(&mut self, logger: slog::Logger) -> Result<()> {
+        let raw_context = get_context(&self.type_values).unwrap();
+        let mut context = raw_context
+            .downcast_mut::<EnvironmentContext>()
+            .unwrap()
+            .unwrap();
+
+        context.logger = logger;
+
+        Ok(())
+    }
+
+    /// Set whether the environment forbids network access.
+    pub fn set_offline(&mut self, offline: bool) -> Result<()> {
+        let raw_context = get_context(&self.type_values).unwrap();
+        let mut context = raw_context
+            .downcast_mut::<EnvironmentContext>()
+            .unwrap()
+            .unwrap();
+
+        context.offline = offline;
+
+        Ok(())
+    }
+
+    /// Set whether evaluation only validates the configuration.
+    pub fn set_validate_only(&mut self, validate_only: bool) -> Result<()> {
+        let raw_context = get_context(&self.type_values).unwrap();
+        let mut context = raw_context
+            .downcast_mut::<EnvironmentContext>()
+            .unwrap()
+            .unwrap();
+
+        context.validate_only = validate_only;
+
+        Ok(())
+    }
+
+    /// Set the hosts Python distributions may be downloaded from.
+    pub fn set_allowed_distribution_hosts(&mut self, hosts: Option<Vec<String>>) -> Result<()> {
+        let raw_context = get_context(&self.type_values).unwrap();
+        let mut context = raw_context
+            .downcast_mut::<EnvironmentContext>()
+            .unwrap()
+            .unwrap();
+
+        context.allowed_distribution_hosts = hosts;
+
+        Ok(())
+    }
+
+    /// Set the distribution flavors available in addition to built-in flavors.
+    pub fn set_distribution_flavors(&mut self, flavors: DistributionFlavorRegistry) -> Result<()> {
+        let raw_context = get_context(&self.type_values).unwrap();
+        let mut context = raw_context
+            .downcast_mut::<EnvironmentContext>()
+            .unwrap()
+            .unwrap();
+
+        context.distribution_flavors = flavors;
+
+        Ok(())
+    }
 }
 
 pub fn starlark_ok(snippet: &str) -> Value {
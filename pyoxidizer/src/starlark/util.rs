@@ -8,7 +8,7 @@ use {
         none::NoneType,
         Value,
     },
-    std::{os::raw::c_ulong, path::PathBuf},
+    std::{ffi::OsString, os::raw::c_ulong, path::PathBuf},
 };
 
 pub fn required_type_arg(arg_name: &str, arg_type: &str, value: &Value) -> Result<(), ValueError> {
@@ -45,6 +45,35 @@ pub fn required_str_arg(name: &str, value: &Value) -> Result<String, ValueError>
     }
 }
 
+/// Obtain an environment variable name from a required string argument.
+///
+/// Names must consist of letters, digits, and underscores and not start with
+/// a digit.
+pub fn required_env_name_arg(name: &str, value: &Value) -> Result<String, ValueError> {
+    let value = required_str_arg(name, value)?;
+
+    let valid = match value.chars().next() {
+        Some(c) => {
+            (c.is_ascii_alphabetic() || c == '_')
+                && value.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+        }
+        None => false,
+    };
+
+    if valid {
+        Ok(value)
+    } else {
+        Err(ValueError::from(RuntimeError {
+            code: INCORRECT_PARAMETER_TYPE_ERROR_CODE,
+            message: format!(
+                "invalid environment variable name {}; must consist of letters, digits, and underscores and not start with a digit",
+                value
+            ),
+            label: format!("invalid {}", name),
+        }))
+    }
+}
+
 pub fn optional_str_arg(name: &str, value: &Value) -> Result<Option<String>, ValueError> {
     match value.get_type() {
         "NoneType" => Ok(None),
@@ -262,6 +291,20 @@ impl ToValue for Option<Vec<String>> {
     }
 }
 
+impl ToValue for Option<Vec<OsString>> {
+    fn to_value(&self) -> Value {
+        match self {
+            Some(value) => Value::from(
+                value
+                    .iter()
+                    .map(|x| x.to_string_lossy().to_string())
+                    .collect::<Vec<_>>(),
+            ),
+            None => Value::from(NoneType::None),
+        }
+    }
+}
+
 impl ToValue for Option<Vec<PathBuf>> {
     fn to_value(&self) -> Value {
         match self {
@@ -314,25 +357,32 @@ pub trait TryToOptional<T> {
     fn try_to_optional(&self) -> Result<Option<T>, ValueError>;
 }
 
-impl TryToOptional<c_ulong> for Value {
-    fn try_to_optional(&self) -> Result<Option<c_ulong>, ValueError> {
+impl TryToOptional<Vec<String>> for Value {
+    fn try_to_optional(&self) -> Result<Option<Vec<String>>, ValueError> {
         if self.get_type() == "NoneType" {
             Ok(None)
         } else {
-            Ok(Some(self.to_int()? as c_ulong))
+            let values = self.to_vec()?;
+
+            Ok(Some(
+                values.iter().map(|x| x.to_string()).collect::<Vec<_>>(),
+            ))
         }
     }
 }
 
-impl TryToOptional<Vec<String>> for Value {
-    fn try_to_optional(&self) -> Result<Option<Vec<String>>, ValueError> {
+impl TryToOptional<Vec<OsString>> for Value {
+    fn try_to_optional(&self) -> Result<Option<Vec<OsString>>, ValueError> {
         if self.get_type() == "NoneType" {
             Ok(None)
         } else {
             let values = self.to_vec()?;
 
             Ok(Some(
-                values.iter().map(|x| x.to_string()).collect::<Vec<_>>(),
+                values
+                    .iter()
+                    .map(|x| OsString::from(x.to_string()))
+                    .collect::<Vec<_>>(),
             ))
         }
     }
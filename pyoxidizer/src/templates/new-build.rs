@@ -13,4 +13,31 @@ fn main() {
     } else {
         panic!("unable to find build artifacts generated by pyembed crate");
     }
+
+    embed_windows_resources();
+}
+
+/// Embed Windows resources (version info, icon) defined by PyOxidizer.
+#[cfg(windows)]
+fn embed_windows_resources() {
+    println!("cargo:rerun-if-env-changed=PYOXIDIZER_WINDOWS_RESOURCE_SCRIPT");
+    println!("cargo:rerun-if-env-changed=PYOXIDIZER_WINDOWS_ICON");
+
+    if std::env::var("CARGO_CFG_TARGET_OS").as_deref() != Ok("windows") {
+        return;
+    }
+
+    if let Ok(script) = std::env::var("PYOXIDIZER_WINDOWS_RESOURCE_SCRIPT") {
+        println!("cargo:rerun-if-changed={}", script);
+
+        if let Ok(icon) = std::env::var("PYOXIDIZER_WINDOWS_ICON") {
+            println!("cargo:rerun-if-changed={}", icon);
+        }
+
+        embed_resource::compile(script);
+    }
 }
+
+/// The embed-resource crate is only available when building on Windows.
+#[cfg(not(windows))]
+fn embed_windows_resources() {}
@@ -4,7 +4,9 @@
 
 use {
     crate::logging::PrintlnDrain,
-    crate::py_packaging::distribution::{DistributionFlavor, PythonDistributionLocation},
+    crate::py_packaging::distribution::{
+        DistributionFlavor, HttpProxyConfig, PythonDistributionLocation,
+    },
     crate::py_packaging::standalone_distribution::StandaloneDistribution,
     crate::python_distributions::PYTHON_DISTRIBUTIONS,
     anyhow::{anyhow, Result},
@@ -26,6 +28,27 @@ pub fn get_logger() -> Result<slog::Logger> {
     ))
 }
 
+/// A slog Drain that records messages in memory.
+#[derive(Clone, Default)]
+pub struct CapturingDrain {
+    pub messages: Arc<Mutex<Vec<String>>>,
+}
+
+impl slog::Drain for CapturingDrain {
+    type Ok = ();
+    type Err = slog::Never;
+
+    fn log(
+        &self,
+        record: &slog::Record,
+        _values: &slog::OwnedKVList,
+    ) -> Result<Self::Ok, Self::Err> {
+        self.messages.lock().unwrap().push(record.msg().to_string());
+
+        Ok(())
+    }
+}
+
 lazy_static! {
     pub static ref DEFAULT_DISTRIBUTION_TEMP_DIR: tempdir::TempDir =
         tempdir::TempDir::new("pyoxidizer-test").expect("unable to create temp directory");
@@ -55,7 +78,10 @@ pub fn get_distribution(
 
     if !lock.deref_mut().contains_key(location) {
         let dist = Arc::new(Box::new(StandaloneDistribution::from_location(
-            &logger, &location, &dest_path,
+            &logger,
+            &location,
+            &dest_path,
+            &HttpProxyConfig::from_env(),
         )?));
 
         lock.deref_mut().insert(location.clone(), dist);
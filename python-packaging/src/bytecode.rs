@@ -9,10 +9,12 @@ use {
     anyhow::{anyhow, Result},
     byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt},
     std::{
+        collections::BTreeMap,
         fs::File,
         io::{BufRead, BufReader, Read, Write},
         path::{Path, PathBuf},
         process,
+        sync::{Arc, Mutex},
     },
 };
 
@@ -31,6 +33,24 @@ pub trait PythonBytecodeCompiler {
         optimize: BytecodeOptimizationLevel,
         output_mode: CompileMode,
     ) -> Result<Vec<u8>>;
+
+    /// Compile Python source into bytecode at multiple optimization levels.
+    ///
+    /// Returns bytecode keyed by optimization level. The default
+    /// implementation calls `compile()` for each level. Implementations
+    /// may override this to avoid parsing the source multiple times.
+    fn compile_levels(
+        &mut self,
+        source: &[u8],
+        filename: &str,
+        levels: &[BytecodeOptimizationLevel],
+        output_mode: CompileMode,
+    ) -> Result<BTreeMap<BytecodeOptimizationLevel, Vec<u8>>> {
+        levels
+            .iter()
+            .map(|level| Ok((*level, self.compile(source, filename, *level, output_mode)?)))
+            .collect()
+    }
 }
 
 /// An entity to perform Python bytecode compilation.
@@ -44,6 +64,7 @@ pub struct BytecodeCompiler {
 }
 
 /// Output mode for BytecodeCompiler.
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum CompileMode {
     /// Emit just Python bytecode.
     Bytecode,
@@ -53,6 +74,17 @@ pub enum CompileMode {
     PycUncheckedHash,
 }
 
+impl CompileMode {
+    /// The value of this mode in the bytecode compiler protocol.
+    fn as_protocol_bytes(&self) -> &'static [u8] {
+        match self {
+            CompileMode::Bytecode => b"bytecode",
+            CompileMode::PycCheckedHash => b"pyc-checked-hash",
+            CompileMode::PycUncheckedHash => b"pyc-unchecked-hash",
+        }
+    }
+}
+
 impl BytecodeCompiler {
     /// Create a bytecode compiler using a Python executable.
     ///
@@ -123,11 +155,7 @@ impl PythonBytecodeCompiler for BytecodeCompiler {
         stdin.write_all(b"\n")?;
         stdin.write_all(i32::from(optimize).to_string().as_bytes())?;
         stdin.write_all(b"\n")?;
-        stdin.write_all(match output_mode {
-            CompileMode::Bytecode => b"bytecode",
-            CompileMode::PycCheckedHash => b"pyc-checked-hash",
-            CompileMode::PycUncheckedHash => b"pyc-unchecked-hash",
-        })?;
+        stdin.write_all(output_mode.as_protocol_bytes())?;
         stdin.write_all(b"\n")?;
         stdin.write_all(filename.as_bytes())?;
         stdin.write_all(source)?;
@@ -137,13 +165,79 @@ impl PythonBytecodeCompiler for BytecodeCompiler {
         reader.read_line(&mut len_s)?;
 
         let len_s = len_s.trim_end();
-        let bytecode_len = len_s.parse::<u64>().unwrap();
+        let bytecode_len = len_s.parse::<u64>().map_err(|_| {
+            anyhow!(
+                "unable to compile {}; the bytecode compiler did not produce bytecode",
+                filename
+            )
+        })?;
 
         let mut bytecode: Vec<u8> = Vec::new();
         reader.take(bytecode_len).read_to_end(&mut bytecode)?;
 
         Ok(bytecode)
     }
+
+    fn compile_levels(
+        &mut self,
+        source: &[u8],
+        filename: &str,
+        levels: &[BytecodeOptimizationLevel],
+        output_mode: CompileMode,
+    ) -> Result<BTreeMap<BytecodeOptimizationLevel, Vec<u8>>> {
+        if levels.is_empty() {
+            return Ok(BTreeMap::new());
+        }
+
+        let stdin = self.command.stdin.as_mut().expect("failed to get stdin");
+        let stdout = self.command.stdout.as_mut().expect("failed to get stdout");
+
+        let mut reader = BufReader::new(stdout);
+
+        let levels_s = levels
+            .iter()
+            .map(|level| i32::from(*level).to_string())
+            .collect::<Vec<_>>()
+            .join(",");
+
+        stdin.write_all(b"compile_levels\n")?;
+        stdin.write_all(filename.len().to_string().as_bytes())?;
+        stdin.write_all(b"\n")?;
+        stdin.write_all(source.len().to_string().as_bytes())?;
+        stdin.write_all(b"\n")?;
+        stdin.write_all(levels_s.as_bytes())?;
+        stdin.write_all(b"\n")?;
+        stdin.write_all(output_mode.as_protocol_bytes())?;
+        stdin.write_all(b"\n")?;
+        stdin.write_all(filename.as_bytes())?;
+        stdin.write_all(source)?;
+        stdin.flush()?;
+
+        let mut res = BTreeMap::new();
+
+        // Results are emitted in the order levels were requested.
+        for level in levels {
+            let mut len_s = String::new();
+            reader.read_line(&mut len_s)?;
+
+            let len_s = len_s.trim_end();
+            let bytecode_len = len_s.parse::<u64>().map_err(|_| {
+                anyhow!(
+                    "unable to compile {}; the bytecode compiler did not produce bytecode",
+                    filename
+                )
+            })?;
+
+            let mut bytecode: Vec<u8> = Vec::new();
+            (&mut reader)
+                .take(bytecode_len)
+                .read_to_end(&mut bytecode)?;
+
+            res.insert(*level, bytecode);
+        }
+
+        Ok(res)
+    }
 }
 
 impl Drop for BytecodeCompiler {
@@ -156,6 +250,97 @@ impl Drop for BytecodeCompiler {
     }
 }
 
+/// Apply a function to items using a pool of bytecode compilers.
+///
+/// Up to `jobs` compilers are created by calling `new_compiler`. Each is
+/// owned by a worker thread taking items from a shared queue, so at most
+/// `jobs` compilers are in use at any time. Results are returned in the
+/// order of `items`. If `f` fails, the first error is returned.
+pub fn map_with_compilers<T, R, C, F>(
+    items: Vec<T>,
+    jobs: usize,
+    new_compiler: C,
+    f: F,
+) -> Result<Vec<R>>
+where
+    T: Send + 'static,
+    R: Send + 'static,
+    C: Fn() -> Result<Box<dyn PythonBytecodeCompiler + Send>> + Send + Sync + 'static,
+    F: Fn(&mut dyn PythonBytecodeCompiler, T) -> Result<R> + Send + Sync + 'static,
+{
+    if jobs == 0 {
+        return Err(anyhow!("number of bytecode compilers must be at least 1"));
+    }
+
+    let count = items.len();
+    let queue = Arc::new(Mutex::new(items.into_iter().enumerate()));
+    let results = Arc::new(Mutex::new(
+        (0..count).map(|_| None).collect::<Vec<Option<R>>>(),
+    ));
+    let new_compiler = Arc::new(new_compiler);
+    let f = Arc::new(f);
+
+    let handles = (0..jobs.min(count))
+        .map(|_| {
+            let queue = queue.clone();
+            let results = results.clone();
+            let new_compiler = new_compiler.clone();
+            let f = f.clone();
+
+            std::thread::spawn(move || -> Result<()> {
+                let mut compiler = new_compiler()?;
+
+                loop {
+                    let next = queue.lock().unwrap().next();
+
+                    let (index, item) = match next {
+                        Some(next) => next,
+                        None => return Ok(()),
+                    };
+
+                    match f(compiler.as_mut(), item) {
+                        Ok(result) => {
+                            results.lock().unwrap()[index] = Some(result);
+                        }
+                        Err(err) => {
+                            // Prevent other workers from taking more items.
+                            queue.lock().unwrap().by_ref().for_each(drop);
+                            return Err(err);
+                        }
+                    }
+                }
+            })
+        })
+        .collect::<Vec<_>>();
+
+    let mut error = None;
+    for handle in handles {
+        let res = handle
+            .join()
+            .map_err(|_| anyhow!("bytecode compiler thread panicked"))?;
+
+        if let Err(err) = res {
+            if error.is_none() {
+                error = Some(err);
+            }
+        }
+    }
+
+    if let Some(err) = error {
+        return Err(err);
+    }
+
+    let results = Arc::try_unwrap(results)
+        .map_err(|_| anyhow!("bytecode compiler results still referenced"))?
+        .into_inner()
+        .map_err(|_| anyhow!("bytecode compiler results lock poisoned"))?;
+
+    results
+        .into_iter()
+        .map(|result| result.ok_or_else(|| anyhow!("bytecode compiler did not produce a result")))
+        .collect()
+}
+
 /// How to write out a .pyc bytecode header.
 #[derive(Debug, Clone, Copy)]
 pub enum BytecodeHeaderMode {
@@ -196,7 +381,120 @@ pub fn compute_bytecode_header(magic_number: u32, mode: BytecodeHeaderMode) -> R
 
 #[cfg(test)]
 mod tests {
-    use super::*;
+    use {
+        super::*,
+        std::sync::atomic::{AtomicUsize, Ordering},
+    };
+
+    struct CountingCompiler {
+        active: Arc<AtomicUsize>,
+        max_active: Arc<AtomicUsize>,
+    }
+
+    impl PythonBytecodeCompiler for CountingCompiler {
+        fn get_magic_number(&self) -> u32 {
+            42
+        }
+
+        fn compile(
+            &mut self,
+            source: &[u8],
+            _filename: &str,
+            _optimize: BytecodeOptimizationLevel,
+            _output_mode: CompileMode,
+        ) -> Result<Vec<u8>> {
+            let active = self.active.fetch_add(1, Ordering::SeqCst) + 1;
+            self.max_active.fetch_max(active, Ordering::SeqCst);
+            std::thread::sleep(std::time::Duration::from_millis(5));
+            self.active.fetch_sub(1, Ordering::SeqCst);
+
+            Ok(source.to_vec())
+        }
+    }
+
+    #[test]
+    fn test_map_with_compilers() -> Result<()> {
+        let created = Arc::new(AtomicUsize::new(0));
+        let active = Arc::new(AtomicUsize::new(0));
+        let max_active = Arc::new(AtomicUsize::new(0));
+
+        let new_compiler = {
+            let created = created.clone();
+            let active = active.clone();
+            let max_active = max_active.clone();
+
+            move || -> Result<Box<dyn PythonBytecodeCompiler + Send>> {
+                created.fetch_add(1, Ordering::SeqCst);
+                Ok(Box::new(CountingCompiler {
+                    active: active.clone(),
+                    max_active: max_active.clone(),
+                }))
+            }
+        };
+
+        let items = (0..32).map(|i| format!("item{}", i)).collect::<Vec<_>>();
+
+        let res = map_with_compilers(items.clone(), 3, new_compiler, |compiler, item: String| {
+            compiler.compile(
+                item.as_bytes(),
+                &item,
+                BytecodeOptimizationLevel::Zero,
+                CompileMode::Bytecode,
+            )
+        })?;
+
+        assert_eq!(
+            res,
+            items
+                .iter()
+                .map(|item| item.as_bytes().to_vec())
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(created.load(Ordering::SeqCst), 3);
+        assert!(max_active.load(Ordering::SeqCst) <= 3);
+
+        // Compilers aren't created for jobs without items.
+        created.store(0, Ordering::SeqCst);
+        let new_compiler = {
+            let created = created.clone();
+            move || -> Result<Box<dyn PythonBytecodeCompiler + Send>> {
+                created.fetch_add(1, Ordering::SeqCst);
+                Ok(Box::new(CountingCompiler {
+                    active: Arc::new(AtomicUsize::new(0)),
+                    max_active: Arc::new(AtomicUsize::new(0)),
+                }))
+            }
+        };
+        map_with_compilers(vec![1, 2], 8, new_compiler, |_, item: i32| Ok(item))?;
+        assert_eq!(created.load(Ordering::SeqCst), 2);
+
+        assert!(map_with_compilers(
+            vec![1],
+            0,
+            || -> Result<Box<dyn PythonBytecodeCompiler + Send>> { Err(anyhow!("unused")) },
+            |_, item: i32| Ok(item)
+        )
+        .is_err());
+
+        assert!(map_with_compilers(
+            vec![1, 2, 3],
+            2,
+            || -> Result<Box<dyn PythonBytecodeCompiler + Send>> {
+                Ok(Box::new(CountingCompiler {
+                    active: Arc::new(AtomicUsize::new(0)),
+                    max_active: Arc::new(AtomicUsize::new(0)),
+                }))
+            },
+            |_, item: i32| if item == 2 {
+                Err(anyhow!("failed"))
+            } else {
+                Ok(item)
+            }
+        )
+        .is_err());
+
+        Ok(())
+    }
 
     #[test]
     fn test_header() -> Result<()> {
@@ -7,6 +7,7 @@
 # When invoked, we start a server that listens for commands. We then
 # react to those commands and send results to the caller.
 
+import ast
 import importlib._bootstrap_external
 import importlib.util
 import marshal
@@ -25,6 +26,50 @@ stdin = sys.__stdin__.buffer
 stdout = sys.__stdout__.buffer
 
 
+def decode_source(source):
+    """Decode source bytes to str, honoring PEP 263 encoding declarations.
+
+    Returns a tuple of the bytes used for hashing and the decoded str.
+    """
+    # Default source encoding is UTF-8. But per PEP 263, the first or second
+    # line of source can match a regular expression to define a custom
+    # encoding. We need to detect custom encodings and use it to decode
+    # the passed bytes to str.
+    encoding = "utf-8"
+
+    for line in source.splitlines()[0:2]:
+        m = RE_CODING.match(line)
+        if m:
+            encoding = m.group(1).decode("ascii")
+            break
+
+    # Someone has set us up the BOM! According to PEP 263 the file should
+    # be interpreted as UTF-8.
+    if source.startswith(b"\xef\xbb\xbf"):
+        encoding = "utf-8"
+        source = source[3:]
+
+    return source, source.decode(encoding)
+
+
+def code_to_output(code, source_bytes, output_mode):
+    """Serialize a code object according to the requested output mode."""
+    if output_mode == b"bytecode":
+        return marshal.dumps(code)
+    elif output_mode == b"pyc-checked-hash":
+        source_hash = importlib.util.source_hash(source_bytes)
+        return importlib._bootstrap_external._code_to_hash_pyc(
+            code, source_hash, checked=True,
+        )
+    elif output_mode == b"pyc-unchecked-hash":
+        source_hash = importlib.util.source_hash(source_bytes)
+        return importlib._bootstrap_external._code_to_hash_pyc(
+            code, source_hash, checked=False,
+        )
+    else:
+        raise Exception("unknown output mode: %s" % output_mode)
+
+
 while True:
     command = stdin.readline().rstrip()
 
@@ -48,47 +93,42 @@ while True:
 
         name = os.fsdecode(name)
 
-        # Default source encoding is UTF-8. But per PEP 263, the first or second
-        # line of source can match a regular expression to define a custom
-        # encoding. We need to detect custom encodings and use it to decode
-        # the passed bytes to str.
-        encoding = "utf-8"
-
-        for line in source.splitlines()[0:2]:
-            m = RE_CODING.match(line)
-            if m:
-                encoding = m.group(1).decode("ascii")
-                break
-
-        # Someone has set us up the BOM! According to PEP 263 the file should
-        # be interpreted as UTF-8.
-        if source.startswith(b"\xef\xbb\xbf"):
-            encoding = "utf-8"
-            source = source[3:]
-
-        source_bytes = source
-        source = source.decode(encoding)
+        source_bytes, source = decode_source(source)
 
         code = compile(source, name, "exec", optimize=optimize_level)
-        bytecode = marshal.dumps(code)
-
-        if output_mode == b"bytecode":
-            out = bytecode
-        elif output_mode == b"pyc-checked-hash":
-            source_hash = importlib.util.source_hash(source_bytes)
-            out = importlib._bootstrap_external._code_to_hash_pyc(
-                code, source_hash, checked=True,
-            )
-        elif output_mode == b"pyc-unchecked-hash":
-            source_hash = importlib.util.source_hash(source_bytes)
-            out = importlib._bootstrap_external._code_to_hash_pyc(
-                code, source_hash, checked=False,
-            )
-        else:
-            raise Exception("unknown output mode: %s" % output_mode)
+        out = code_to_output(code, source_bytes, output_mode)
 
         stdout.write(b"%d\n" % len(out))
         stdout.write(out)
+        stdout.flush()
+    elif command == b"compile_levels":
+        name_len = stdin.readline().rstrip()
+        source_len = stdin.readline().rstrip()
+        optimize_levels = stdin.readline().rstrip()
+        output_mode = stdin.readline().rstrip()
+
+        name_len = int(name_len)
+        source_len = int(source_len)
+        optimize_levels = [int(x) for x in optimize_levels.split(b",")]
+
+        name = stdin.read(name_len)
+        source = stdin.read(source_len)
+
+        name = os.fsdecode(name)
+
+        source_bytes, source = decode_source(source)
+
+        # Parse once and derive code objects for each optimization level
+        # from the same AST.
+        tree = ast.parse(source, name)
+
+        for optimize_level in optimize_levels:
+            code = compile(tree, name, "exec", optimize=optimize_level)
+            out = code_to_output(code, source_bytes, output_mode)
+
+            stdout.write(b"%d\n" % len(out))
+            stdout.write(out)
+
         stdout.flush()
     else:
         raise Exception("invalid command: %s" % command)
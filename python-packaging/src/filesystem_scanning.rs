@@ -127,6 +127,17 @@ impl<'a> PythonResourceIterator<'a> {
         }
     }
 
+    /// Treat named packages as PEP 420 namespace packages.
+    ///
+    /// Namespace packages don't have an `__init__` module. So files directly
+    /// in their directories can't otherwise be attributed to a package and
+    /// would not be emitted as package resources.
+    pub fn with_namespace_packages(mut self, packages: &[String]) -> Self {
+        self.seen_packages.extend(packages.iter().cloned());
+
+        self
+    }
+
     fn resolve_data_location(&self, path: &Path) -> DataLocation {
         match self.path_content_overrides.get(path) {
             Some(location) => location.clone(),
@@ -445,6 +456,7 @@ impl<'a> PythonResourceIterator<'a> {
             Some("pth") => PathItem::PythonResource(
                 PythonPathExtension {
                     data: self.resolve_data_location(path),
+                    path_resources: vec![],
                 }
                 .into(),
             ),
@@ -1249,7 +1261,8 @@ mod tests {
         assert_eq!(
             resources[0],
             PythonPathExtension {
-                data: DataLocation::Path(pth_path)
+                data: DataLocation::Path(pth_path),
+                path_resources: vec![],
             }
             .into()
         );
@@ -1573,6 +1586,41 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_namespace_package_resources() -> Result<()> {
+        let td = tempdir::TempDir::new("pyoxidizer-test")?;
+        let tp = td.path();
+
+        let ns_path = tp.join("ns");
+        let sub_path = ns_path.join("sub");
+        create_dir_all(&sub_path)?;
+
+        write(ns_path.join("data.txt"), "data")?;
+        write(sub_path.join("__init__.py"), "")?;
+
+        let resources = PythonResourceIterator::new(tp, DEFAULT_CACHE_TAG, &DEFAULT_SUFFIXES)
+            .collect::<Result<Vec<_>>>()?;
+        assert_eq!(resources.len(), 1);
+
+        let resources = PythonResourceIterator::new(tp, DEFAULT_CACHE_TAG, &DEFAULT_SUFFIXES)
+            .with_namespace_packages(&["ns".to_string()])
+            .collect::<Result<Vec<_>>>()?;
+        assert_eq!(resources.len(), 2);
+        assert_eq!(
+            resources[1],
+            PythonPackageResource {
+                leaf_package: "ns".to_string(),
+                relative_name: "data.txt".to_string(),
+                data: DataLocation::Path(ns_path.join("data.txt")),
+                is_stdlib: false,
+                is_test: false,
+            }
+            .into()
+        );
+
+        Ok(())
+    }
+
     #[test]
     fn test_memory_resources() -> Result<()> {
         let inputs = vec![
@@ -71,6 +71,12 @@ pub enum PythonRunMode {
     /// a char* and we want the constructor of this type to worry about
     /// the type coercion.
     File { path: PathBuf },
+    /// Run a Python module embedded in the binary as the main module.
+    ///
+    /// Unlike `Module`, the module is never imported from the filesystem:
+    /// interpreter initialization fails if the oxidized importer is disabled
+    /// or if its resources don't contain the module.
+    Resource { name: String },
 }
 
 impl ToString for PythonRunMode {
@@ -81,6 +87,7 @@ impl ToString for PythonRunMode {
             Self::Module { module } => format!("module:{}", module),
             Self::Eval { code } => format!("eval:{}", code),
             Self::File { path } => format!("file:{}", path.display()),
+            Self::Resource { name } => format!("resource:{}", name),
         }
     }
 }
@@ -112,6 +119,9 @@ impl TryFrom<&str> for PythonRunMode {
                     "file" => Ok(Self::File {
                         path: PathBuf::from(suffix),
                     }),
+                    "resource" => Ok(Self::Resource {
+                        name: suffix.to_string(),
+                    }),
                     _ => Err(format!("{} is not a valid Python run mode", value)),
                 }
             }
@@ -169,6 +179,8 @@ pub enum MemoryAllocatorBackend {
     Jemalloc,
     /// Use Rust's global allocator.
     Rust,
+    /// Use snmalloc.
+    Snmalloc,
 }
 
 impl ToString for MemoryAllocatorBackend {
@@ -177,6 +189,7 @@ impl ToString for MemoryAllocatorBackend {
             Self::System => "system",
             Self::Jemalloc => "jemalloc",
             Self::Rust => "rust",
+            Self::Snmalloc => "snmalloc",
         }
         .to_string()
     }
@@ -190,6 +203,7 @@ impl TryFrom<&str> for MemoryAllocatorBackend {
             "system" => Ok(Self::System),
             "jemalloc" => Ok(Self::Jemalloc),
             "rust" => Ok(Self::Rust),
+            "snmalloc" => Ok(Self::Snmalloc),
             _ => Err(format!("{} is not a valid memory allocator backend", value)),
         }
     }
@@ -229,6 +243,13 @@ impl PythonRawAllocator {
             ..PythonRawAllocator::default()
         }
     }
+
+    pub fn snmalloc() -> Self {
+        Self {
+            backend: MemoryAllocatorBackend::Snmalloc,
+            ..PythonRawAllocator::default()
+        }
+    }
 }
 
 impl Default for PythonRawAllocator {
@@ -23,3 +23,6 @@ pub mod resource_collection;
 
 #[cfg(feature = "wheel")]
 pub mod wheel;
+
+#[cfg(feature = "zip")]
+pub mod zip_scanning;
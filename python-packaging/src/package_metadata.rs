@@ -5,7 +5,7 @@
 /*! Working with Python package metadata (i.e. .pkg-info directories) */
 
 use {
-    anyhow::{Context, Result},
+    anyhow::{anyhow, Context, Result},
     mailparse::parse_mail,
 };
 
@@ -62,6 +62,70 @@ impl PythonPackageMetadata {
     }
 }
 
+/// Represents an entry point defined in an entry_points.txt file.
+#[derive(Clone, Debug, PartialEq)]
+pub struct EntryPoint {
+    /// Group the entry point belongs to. e.g. `console_scripts`.
+    pub group: String,
+    /// Name of the entry point.
+    pub name: String,
+    /// Module the entry point references.
+    pub module: String,
+    /// Dotted attribute within `module` the entry point references, if any.
+    pub attr: Option<String>,
+}
+
+/// Parse entry points from data in an entry_points.txt file.
+///
+/// Extras declared by entry points are ignored.
+pub fn parse_entry_points(data: &[u8]) -> Result<Vec<EntryPoint>> {
+    let text = std::str::from_utf8(data).context("entry points data is not UTF-8")?;
+
+    let mut group = None;
+    let mut entry_points = vec![];
+
+    for line in text.lines().map(|line| line.trim()) {
+        if line.is_empty() || line.starts_with('#') || line.starts_with(';') {
+            continue;
+        }
+
+        if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            group = Some(name.trim().to_string());
+            continue;
+        }
+
+        let group = group
+            .as_ref()
+            .ok_or_else(|| anyhow!("entry point defined outside of a group: {}", line))?;
+
+        let mut parts = line.splitn(2, '=');
+        let name = parts.next().unwrap().trim();
+        let value = parts
+            .next()
+            .ok_or_else(|| anyhow!("invalid entry point definition: {}", line))?;
+
+        // Strip extras. e.g. `module:attr [extra1,extra2]`.
+        let value = value.split('[').next().unwrap().trim();
+
+        let mut parts = value.splitn(2, ':');
+        let module = parts.next().unwrap().trim();
+        let attr = parts.next().map(|attr| attr.trim().to_string());
+
+        if name.is_empty() || module.is_empty() {
+            return Err(anyhow!("invalid entry point definition: {}", line));
+        }
+
+        entry_points.push(EntryPoint {
+            group: group.clone(),
+            name: name.to_string(),
+            module: module.to_string(),
+            attr,
+        });
+    }
+
+    Ok(entry_points)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -99,4 +163,56 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_parse_entry_points() -> Result<()> {
+        let data = concat!(
+            "[console_scripts]\n",
+            "black = black:patched_main\n",
+            "blackd = blackd:patched_main [d]\n",
+            "\n",
+            "# comment\n",
+            "[foo.plugins]\n",
+            "bar=foo.bar\n",
+            "baz = foo.baz:Baz.create\n",
+        )
+        .as_bytes();
+
+        let entry_points = parse_entry_points(data)?;
+
+        assert_eq!(
+            entry_points,
+            vec![
+                EntryPoint {
+                    group: "console_scripts".to_string(),
+                    name: "black".to_string(),
+                    module: "black".to_string(),
+                    attr: Some("patched_main".to_string()),
+                },
+                EntryPoint {
+                    group: "console_scripts".to_string(),
+                    name: "blackd".to_string(),
+                    module: "blackd".to_string(),
+                    attr: Some("patched_main".to_string()),
+                },
+                EntryPoint {
+                    group: "foo.plugins".to_string(),
+                    name: "bar".to_string(),
+                    module: "foo.bar".to_string(),
+                    attr: None,
+                },
+                EntryPoint {
+                    group: "foo.plugins".to_string(),
+                    name: "baz".to_string(),
+                    module: "foo.baz".to_string(),
+                    attr: Some("Baz.create".to_string()),
+                },
+            ]
+        );
+
+        assert!(parse_entry_points(b"foo = bar:baz\n").is_err());
+        assert!(parse_entry_points(b"[console_scripts]\nfoo\n").is_err());
+
+        Ok(())
+    }
 }
@@ -13,7 +13,8 @@ use {
         resource::{PythonExtensionModule, PythonExtensionModuleVariants, PythonResource},
         resource_collection::PythonResourceAddCollectionContext,
     },
-    anyhow::Result,
+    anyhow::{anyhow, Result},
+    glob::Pattern,
     std::{collections::HashMap, convert::TryFrom, iter::FromIterator},
 };
 
@@ -51,6 +52,13 @@ impl AsRef<str> for ExtensionModuleFilter {
     }
 }
 
+/// Distribution metadata files kept by default when metadata is excluded.
+///
+/// These are the files `importlib.metadata` reads to find distributions,
+/// their versions, and their entry points.
+pub const DEFAULT_KEPT_DISTRIBUTION_METADATA: &[&str] =
+    &["METADATA", "PKG-INFO", "entry_points.txt"];
+
 /// Defines how Python resources should be packaged.
 #[derive(Clone, Debug, PartialEq)]
 pub struct PythonPackagingPolicy {
@@ -60,6 +68,9 @@ pub struct PythonPackagingPolicy {
     /// Preferred variants of extension modules.
     preferred_extension_module_variants: HashMap<String, String>,
 
+    /// Glob patterns of extension module names that should never be included.
+    excluded_extension_modules: Vec<Pattern>,
+
     /// Where resources should be placed/loaded from by default.
     resources_location: ConcreteResourceLocation,
 
@@ -87,6 +98,15 @@ pub struct PythonPackagingPolicy {
     /// Whether to include test files.
     include_test: bool,
 
+    /// Whether to include all package distribution metadata files.
+    ///
+    /// If false, only distribution resources matching
+    /// `kept_distribution_metadata` are included.
+    include_distribution_metadata: bool,
+
+    /// Glob patterns of distribution metadata file names to always include.
+    kept_distribution_metadata: Vec<Pattern>,
+
     /// Mapping of target triple to list of extensions that don't work for that triple.
     ///
     /// Policy constructors can populate this with known broken extensions to
@@ -101,6 +121,27 @@ pub struct PythonPackagingPolicy {
 
     /// Whether to write Python bytecode at optimization level 2.
     bytecode_optimize_level_two: bool,
+
+    /// Whether adding a resource that replaces a different resource is an error.
+    error_on_duplicate_resources: bool,
+
+    /// Whether resources in directories referenced by collected .pth files are packaged.
+    ///
+    /// If false, .pth files are dropped.
+    apply_pth_files: bool,
+
+    /// Whether Python modules are only added as bytecode.
+    ///
+    /// If true, module source is never added, regardless of per-resource
+    /// settings.
+    bytecode_only: bool,
+
+    /// Whether Python modules are only added as optimization level 2 bytecode.
+    ///
+    /// Level 2 bytecode has docstrings removed. If true, module source and
+    /// bytecode at other optimization levels is never added, regardless of
+    /// per-resource settings.
+    strip_docstrings: bool,
 }
 
 impl Default for PythonPackagingPolicy {
@@ -108,6 +149,7 @@ impl Default for PythonPackagingPolicy {
         PythonPackagingPolicy {
             extension_module_filter: ExtensionModuleFilter::All,
             preferred_extension_module_variants: HashMap::new(),
+            excluded_extension_modules: vec![],
             resources_location: ConcreteResourceLocation::InMemory,
             resources_location_fallback: None,
             allow_in_memory_shared_library_loading: false,
@@ -115,10 +157,19 @@ impl Default for PythonPackagingPolicy {
             include_non_distribution_sources: true,
             include_distribution_resources: false,
             include_test: false,
+            include_distribution_metadata: true,
+            kept_distribution_metadata: DEFAULT_KEPT_DISTRIBUTION_METADATA
+                .iter()
+                .map(|x| Pattern::new(x).expect("default patterns are valid"))
+                .collect(),
             broken_extensions: HashMap::new(),
             bytecode_optimize_level_zero: true,
             bytecode_optimize_level_one: false,
             bytecode_optimize_level_two: false,
+            error_on_duplicate_resources: false,
+            apply_pth_files: false,
+            bytecode_only: false,
+            strip_docstrings: false,
         }
     }
 }
@@ -150,6 +201,80 @@ impl PythonPackagingPolicy {
             .insert(extension.to_string(), variant.to_string());
     }
 
+    /// Obtain glob patterns of extension module names that are excluded.
+    pub fn excluded_extension_modules(&self) -> Vec<String> {
+        self.excluded_extension_modules
+            .iter()
+            .map(|pattern| pattern.as_str().to_string())
+            .collect()
+    }
+
+    /// Set glob patterns of extension module names that are excluded.
+    ///
+    /// Excluded extension modules are never added, even if requested.
+    pub fn set_excluded_extension_modules(&mut self, patterns: Vec<String>) -> Result<()> {
+        self.excluded_extension_modules = patterns
+            .iter()
+            .map(|pattern| {
+                Pattern::new(pattern)
+                    .map_err(|e| anyhow!("invalid extension module pattern {}: {}", pattern, e))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(())
+    }
+
+    /// Whether an extension module is excluded by this policy.
+    pub fn is_extension_module_excluded(&self, name: &str) -> bool {
+        self.excluded_extension_modules
+            .iter()
+            .any(|pattern| pattern.matches(name))
+    }
+
+    /// Whether to include all package distribution metadata files.
+    pub fn include_distribution_metadata(&self) -> bool {
+        self.include_distribution_metadata
+    }
+
+    /// Set whether to include all package distribution metadata files.
+    pub fn set_include_distribution_metadata(&mut self, include: bool) {
+        self.include_distribution_metadata = include;
+    }
+
+    /// Obtain glob patterns of distribution metadata files that are always included.
+    pub fn kept_distribution_metadata(&self) -> Vec<String> {
+        self.kept_distribution_metadata
+            .iter()
+            .map(|pattern| pattern.as_str().to_string())
+            .collect()
+    }
+
+    /// Set glob patterns of distribution metadata files that are always included.
+    ///
+    /// Patterns are matched against the file name within the `.dist-info`
+    /// or `.egg-info` directory.
+    pub fn set_kept_distribution_metadata(&mut self, patterns: Vec<String>) -> Result<()> {
+        self.kept_distribution_metadata = patterns
+            .iter()
+            .map(|pattern| {
+                Pattern::new(pattern).map_err(|e| {
+                    anyhow!("invalid distribution metadata pattern {}: {}", pattern, e)
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(())
+    }
+
+    /// Whether a distribution metadata file is included by this policy.
+    pub fn is_distribution_metadata_included(&self, name: &str) -> bool {
+        self.include_distribution_metadata
+            || self
+                .kept_distribution_metadata
+                .iter()
+                .any(|pattern| pattern.matches(name))
+    }
+
     /// Obtain the primary location for added resources.
     pub fn resources_location(&self) -> &ConcreteResourceLocation {
         &self.resources_location
@@ -250,6 +375,85 @@ impl PythonPackagingPolicy {
         self.bytecode_optimize_level_two = value;
     }
 
+    /// Whether adding a resource that replaces a different resource is an error.
+    pub fn error_on_duplicate_resources(&self) -> bool {
+        self.error_on_duplicate_resources
+    }
+
+    /// Set whether adding a resource that replaces a different resource is an error.
+    pub fn set_error_on_duplicate_resources(&mut self, value: bool) {
+        self.error_on_duplicate_resources = value;
+    }
+
+    /// Whether resources in directories referenced by collected .pth files are packaged.
+    pub fn apply_pth_files(&self) -> bool {
+        self.apply_pth_files
+    }
+
+    /// Set whether resources in directories referenced by collected .pth files are packaged.
+    pub fn set_apply_pth_files(&mut self, value: bool) {
+        self.apply_pth_files = value;
+    }
+
+    /// Whether Python modules are only added as bytecode.
+    pub fn bytecode_only(&self) -> bool {
+        self.bytecode_only
+    }
+
+    /// Set whether Python modules are only added as bytecode.
+    pub fn set_bytecode_only(&mut self, value: bool) {
+        self.bytecode_only = value;
+    }
+
+    /// Apply the bytecode only setting to a `PythonResourceAddCollectionContext`.
+    ///
+    /// If bytecode only mode is enabled, source is not stored and bytecode
+    /// at optimization level 0 is added if no other bytecode is being added.
+    /// Otherwise this is a no-op.
+    ///
+    /// The context should be for a Python module source.
+    pub fn enforce_bytecode_only(&self, add_context: &mut PythonResourceAddCollectionContext) {
+        if !self.bytecode_only {
+            return;
+        }
+
+        add_context.store_source = false;
+
+        if !add_context.optimize_level_zero
+            && !add_context.optimize_level_one
+            && !add_context.optimize_level_two
+        {
+            add_context.optimize_level_zero = true;
+        }
+    }
+
+    /// Whether Python modules are only added as optimization level 2 bytecode.
+    pub fn strip_docstrings(&self) -> bool {
+        self.strip_docstrings
+    }
+
+    /// Set whether Python modules are only added as optimization level 2 bytecode.
+    pub fn set_strip_docstrings(&mut self, value: bool) {
+        self.strip_docstrings = value;
+    }
+
+    /// Apply the docstring stripping setting to a `PythonResourceAddCollectionContext`.
+    ///
+    /// If docstring stripping is enabled, source is not stored and only
+    /// bytecode at optimization level 2 is added. Otherwise this is a no-op.
+    ///
+    /// The context should be for a Python module source.
+    pub fn enforce_strip_docstrings(&self, add_context: &mut PythonResourceAddCollectionContext) {
+        if !self.strip_docstrings {
+            return;
+        }
+
+        add_context.store_source = false;
+        add_context.optimize_level_zero = false;
+        add_context.optimize_level_one = false;
+        add_context.optimize_level_two = true;
+    }
+
     /// Obtain broken extensions for a target triple.
     pub fn broken_extensions_for_triple(&self, target_triple: &str) -> Option<&Vec<String>> {
         self.broken_extensions.get(target_triple)
@@ -292,7 +496,7 @@ impl PythonPackagingPolicy {
         let location = self.resources_location.clone();
         let location_fallback = self.resources_location_fallback.clone();
 
-        PythonResourceAddCollectionContext {
+        let mut add_context = PythonResourceAddCollectionContext {
             include,
             location,
             location_fallback,
@@ -300,7 +504,15 @@ impl PythonPackagingPolicy {
             optimize_level_zero: self.bytecode_optimize_level_zero,
             optimize_level_one: self.bytecode_optimize_level_one,
             optimize_level_two: self.bytecode_optimize_level_two,
+            install_relative_path: None,
+        };
+
+        if let PythonResource::ModuleSource(_) = resource {
+            self.enforce_bytecode_only(&mut add_context);
+            self.enforce_strip_docstrings(&mut add_context);
         }
+
+        add_context
     }
 
     /// Determine if a Python resource is applicable to the current policy.
@@ -331,10 +543,13 @@ impl PythonPackagingPolicy {
                     true
                 }
             }
-            PythonResource::PackageDistributionResource(_) => true,
+            PythonResource::PackageDistributionResource(resource) => {
+                self.is_distribution_metadata_included(&resource.name)
+            }
             PythonResource::ExtensionModule(_) => false,
             PythonResource::PathExtension(_) => false,
             PythonResource::EggFile(_) => false,
+            PythonResource::FileData(_) => true,
         }
     }
 
@@ -360,6 +575,17 @@ impl PythonPackagingPolicy {
                 continue;
             }
 
+            if self.is_extension_module_excluded(name) {
+                if variants.iter().any(|em| em.is_minimally_required()) {
+                    return Err(anyhow!(
+                        "extension module {} cannot be excluded because it is required by the Python interpreter",
+                        name
+                    ));
+                }
+
+                continue;
+            }
+
             // Always add minimally required extension modules, because things don't
             // work if we don't do this.
             let ext_variants =
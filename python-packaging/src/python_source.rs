@@ -4,11 +4,21 @@
 
 /*! Utility functions related to Python source code. */
 
-use {anyhow::Result, lazy_static::lazy_static};
+use {
+    anyhow::Result,
+    lazy_static::lazy_static,
+    std::{borrow::Cow, collections::BTreeSet},
+};
 
 lazy_static! {
     static ref RE_CODING: regex::bytes::Regex =
         regex::bytes::Regex::new(r"^[ \t\f]*#.*?coding[:=][ \t]*([-_.a-zA-Z0-9]+)").unwrap();
+    static ref RE_IMPORT: regex::Regex = regex::Regex::new(r"^import\s+(.*)$").unwrap();
+    static ref RE_FROM_IMPORT: regex::Regex =
+        regex::Regex::new(r"^from\s*(\.*)\s*([\w.]*)\s+import\s+(.*)$").unwrap();
+    static ref RE_DYNAMIC_IMPORT: regex::Regex =
+        regex::Regex::new(r#"(?:__import__|import_module)\(\s*['"]([A-Za-z_][\w.]*)['"]"#).unwrap();
+    static ref RE_DOTTED_NAME: regex::Regex = regex::Regex::new(r"^[\w]+(?:\.[\w]+)*$").unwrap();
 }
 
 /// Derive the source encoding from Python source code.
@@ -36,6 +46,11 @@ pub fn has_dunder_file(source: &[u8]) -> Result<bool> {
     // We can't just look for b"__file__ because the source file may be in
     // encodings like UTF-16. So we need to decode to Unicode first then look for
     // the code points.
+    Ok(decode_source(source).contains("__file__"))
+}
+
+/// Decode Python source code to Unicode using its declared encoding.
+fn decode_source(source: &[u8]) -> Cow<str> {
     let encoding = python_source_encoding(source);
 
     let encoder = match encoding_rs::Encoding::for_label(&encoding) {
@@ -45,5 +60,245 @@ pub fn has_dunder_file(source: &[u8]) -> Result<bool> {
 
     let (source, ..) = encoder.decode(source);
 
-    Ok(source.contains("__file__"))
+    source
+}
+
+/// Split Python source code into logical lines.
+///
+/// Comments are removed and string literals are replaced by empty literals,
+/// so the result is only suitable for coarse analysis.
+fn logical_lines(source: &str) -> Vec<String> {
+    let chars = source.replace("\r\n", "\n").chars().collect::<Vec<_>>();
+
+    let mut lines = vec![];
+    let mut current = String::new();
+    let mut depth = 0usize;
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+
+        match c {
+            '#' => {
+                while i < chars.len() && chars[i] != '\n' {
+                    i += 1;
+                }
+                continue;
+            }
+            '\'' | '"' => {
+                let is_quote = |j: usize| chars.get(j) == Some(&c);
+                let triple = is_quote(i + 1) && is_quote(i + 2);
+                let quote_len = if triple { 3 } else { 1 };
+
+                i += quote_len;
+                while i < chars.len() {
+                    if chars[i] == '\\' {
+                        i += 2;
+                        continue;
+                    }
+                    if chars[i] == c && (!triple || (is_quote(i + 1) && is_quote(i + 2))) {
+                        i += quote_len;
+                        break;
+                    }
+                    if chars[i] == '\n' && !triple {
+                        break;
+                    }
+                    i += 1;
+                }
+
+                current.push_str("\"\"");
+                continue;
+            }
+            '\\' if chars.get(i + 1) == Some(&'\n') => {
+                current.push(' ');
+                i += 2;
+                continue;
+            }
+            '(' | '[' | '{' => depth += 1,
+            ')' | ']' | '}' => depth = depth.saturating_sub(1),
+            '\n' | ';' if depth == 0 => {
+                lines.push(std::mem::take(&mut current));
+                i += 1;
+                continue;
+            }
+            '\n' => {
+                current.push(' ');
+                i += 1;
+                continue;
+            }
+            _ => {}
+        }
+
+        current.push(c);
+        i += 1;
+    }
+
+    lines.push(current);
+
+    lines
+}
+
+/// Obtain the names bound by a comma delimited list of import targets.
+fn import_targets(targets: &str) -> Vec<&str> {
+    targets
+        .split(',')
+        .filter_map(|target| {
+            let target = target.trim_matches(|c: char| c.is_whitespace() || c == '(' || c == ')');
+            let name = target.split_whitespace().next()?;
+
+            if RE_DOTTED_NAME.is_match(name) {
+                Some(name)
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+/// Resolve the package a relative import at `level` refers to.
+///
+/// Returns `None` if the import goes beyond the top-level package.
+fn resolve_relative_package(module_name: &str, is_package: bool, level: usize) -> Option<String> {
+    let mut parts = module_name.split('.').collect::<Vec<_>>();
+
+    if !is_package {
+        parts.pop();
+    }
+
+    if level > parts.len() {
+        return None;
+    }
+
+    parts.truncate(parts.len() - (level - 1));
+
+    Some(parts.join("."))
+}
+
+/// Find the names of modules imported by Python source code.
+///
+/// `module_name` and `is_package` describe the module the source belongs to
+/// and are used to resolve relative imports.
+///
+/// This is a static analysis. Every `import` statement is considered, even
+/// if it is conditional. For `from X import Y` statements, both `X` and
+/// `X.Y` are returned, as `Y` may be a submodule. Calls to `__import__()` or
+/// `importlib.import_module()` with a string literal are also recognized.
+/// Other dynamic imports are not detected.
+pub fn find_imports(source: &[u8], module_name: &str, is_package: bool) -> BTreeSet<String> {
+    let source = decode_source(source);
+
+    let mut imports = RE_DYNAMIC_IMPORT
+        .captures_iter(&source)
+        .map(|caps| caps[1].to_string())
+        .collect::<BTreeSet<_>>();
+
+    for line in logical_lines(&source) {
+        // Simple statements can follow a compound statement header on the
+        // same line. e.g. `try: import foo`.
+        let statements = std::iter::once(line.as_str())
+            .chain(line.match_indices(':').map(|(i, _)| &line[i + 1..]))
+            .map(|s| s.trim());
+
+        for statement in statements {
+            if let Some(caps) = RE_IMPORT.captures(statement) {
+                imports.extend(import_targets(&caps[1]).into_iter().map(|x| x.to_string()));
+            } else if let Some(caps) = RE_FROM_IMPORT.captures(statement) {
+                let level = caps[1].len();
+                let mut base = caps[2].to_string();
+
+                if level > 0 {
+                    let package = match resolve_relative_package(module_name, is_package, level) {
+                        Some(package) => package,
+                        None => continue,
+                    };
+
+                    base = if base.is_empty() {
+                        package
+                    } else {
+                        format!("{}.{}", package, base)
+                    };
+                }
+
+                if !RE_DOTTED_NAME.is_match(&base) {
+                    continue;
+                }
+
+                for target in import_targets(&caps[3]) {
+                    if !target.contains('.') {
+                        imports.insert(format!("{}.{}", base, target));
+                    }
+                }
+
+                imports.insert(base);
+            }
+        }
+    }
+
+    imports
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn imports(source: &str, module_name: &str, is_package: bool) -> Vec<String> {
+        find_imports(source.as_bytes(), module_name, is_package)
+            .into_iter()
+            .collect()
+    }
+
+    #[test]
+    fn test_find_imports() {
+        assert_eq!(
+            imports(
+                "import os, sys as system\nimport xml.dom\nfrom json import (\n    dumps,\n    loads as l,\n)\n",
+                "foo",
+                false
+            ),
+            vec!["json", "json.dumps", "json.loads", "os", "sys", "xml.dom"]
+        );
+
+        assert_eq!(
+            imports(
+                "try: import a\nexcept ImportError: import b; import c\nfrom d import *\n",
+                "foo",
+                false
+            ),
+            vec!["a", "b", "c", "d"]
+        );
+
+        assert_eq!(
+            imports(
+                "# import a\ns = 'import b'\nt = \"\"\"\nimport c\n\"\"\"\nx = \\\n  1\n",
+                "foo",
+                false
+            ),
+            Vec::<String>::new()
+        );
+
+        assert_eq!(
+            imports(
+                "m = importlib.import_module('a.b')\nn = __import__(\"c\")\n",
+                "foo",
+                false
+            ),
+            vec!["a.b", "c"]
+        );
+    }
+
+    #[test]
+    fn test_find_imports_relative() {
+        assert_eq!(
+            imports("from . import a\nfrom .b import c\n", "foo.bar", false),
+            vec!["foo", "foo.a", "foo.b", "foo.b.c"]
+        );
+        assert_eq!(
+            imports("from . import a\nfrom ..b import c\n", "foo.bar", true),
+            vec!["foo.b", "foo.b.c", "foo.bar", "foo.bar.a"]
+        );
+        assert_eq!(
+            imports("from .. import a\n", "foo", false),
+            Vec::<String>::new()
+        );
+    }
 }
@@ -47,7 +47,7 @@ impl DataLocation {
 }
 
 /// An optimization level for Python bytecode.
-#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
 pub enum BytecodeOptimizationLevel {
     Zero,
     One,
@@ -733,14 +733,90 @@ impl PythonEggFile {
 pub struct PythonPathExtension {
     /// Content of the .pth file.
     pub data: DataLocation,
+
+    /// Resources in the directories the .pth file adds to `sys.path`.
+    ///
+    /// Relative entries are relative to the directory containing the .pth
+    /// file. So this is populated by whatever collects the file, if at all.
+    pub path_resources: Vec<PythonResource<'static>>,
+}
+
+/// An entry in a .pth file.
+#[derive(Clone, Debug, PartialEq)]
+pub enum PythonPathExtensionEntry {
+    /// A path to add to `sys.path`, as written in the file.
+    Path(PathBuf),
+    /// A line of code `site` executes when processing the file.
+    Code(String),
 }
 
 impl PythonPathExtension {
     pub fn to_memory(&self) -> Result<Self> {
         Ok(Self {
             data: self.data.to_memory()?,
+            path_resources: self
+                .path_resources
+                .iter()
+                .map(|r| r.to_memory())
+                .collect::<Result<Vec<_>>>()?,
         })
     }
+
+    /// Parse the entries of the .pth file.
+    ///
+    /// Lines are interpreted like `site.addpackage()` does: blank lines and
+    /// comments are skipped and lines starting with `import` are code.
+    /// Relative paths are not resolved.
+    pub fn entries(&self) -> Result<Vec<PythonPathExtensionEntry>> {
+        let data = self.data.resolve()?;
+        let data = String::from_utf8(data).context("decoding .pth file")?;
+
+        Ok(data
+            .lines()
+            .filter(|line| !line.trim().is_empty() && !line.starts_with('#'))
+            .map(|line| {
+                if line.starts_with("import ") || line.starts_with("import\t") {
+                    PythonPathExtensionEntry::Code(line.to_string())
+                } else {
+                    PythonPathExtensionEntry::Path(PathBuf::from(line.trim_end()))
+                }
+            })
+            .collect())
+    }
+}
+
+/// Represents an arbitrary file not associated with a Python package.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FileData {
+    /// Relative path the file should be made available at.
+    pub path: PathBuf,
+
+    /// Content of the file.
+    pub data: DataLocation,
+
+    /// Whether the file should be marked as executable.
+    pub executable: bool,
+}
+
+impl FileData {
+    pub fn to_memory(&self) -> Result<Self> {
+        Ok(Self {
+            path: self.path.clone(),
+            data: self.data.to_memory()?,
+            executable: self.executable,
+        })
+    }
+
+    /// The path of the file normalized to use `/` as the directory separator.
+    ///
+    /// This is used as the resource name.
+    pub fn path_string(&self) -> String {
+        self.path
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/")
+    }
 }
 
 /// Represents a resource that can be read by Python somehow.
@@ -762,6 +838,8 @@ pub enum PythonResource<'a> {
     EggFile(Cow<'a, PythonEggFile>),
     /// A path extension.
     PathExtension(Cow<'a, PythonPathExtension>),
+    /// An arbitrary file.
+    FileData(Cow<'a, FileData>),
 }
 
 impl<'a> PythonResource<'a> {
@@ -780,6 +858,7 @@ impl<'a> PythonResource<'a> {
             PythonResource::ExtensionModule(em) => em.name.clone(),
             PythonResource::EggFile(_) => "".to_string(),
             PythonResource::PathExtension(_) => "".to_string(),
+            PythonResource::FileData(f) => f.path_string(),
         }
     }
 
@@ -793,6 +872,7 @@ impl<'a> PythonResource<'a> {
             PythonResource::ExtensionModule(em) => &em.name,
             PythonResource::EggFile(_) => return false,
             PythonResource::PathExtension(_) => return false,
+            PythonResource::FileData(_) => return false,
         };
 
         for package in packages {
@@ -819,6 +899,7 @@ impl<'a> PythonResource<'a> {
             PythonResource::ExtensionModule(m) => m.to_memory()?.into(),
             PythonResource::EggFile(e) => e.to_memory()?.into(),
             PythonResource::PathExtension(e) => e.to_memory()?.into(),
+            PythonResource::FileData(f) => f.to_memory()?.into(),
         })
     }
 }
@@ -919,12 +1000,45 @@ impl<'a> From<&'a PythonPathExtension> for PythonResource<'a> {
     }
 }
 
+impl<'a> From<FileData> for PythonResource<'a> {
+    fn from(f: FileData) -> Self {
+        PythonResource::FileData(Cow::Owned(f))
+    }
+}
+
+impl<'a> From<&'a FileData> for PythonResource<'a> {
+    fn from(f: &'a FileData) -> Self {
+        PythonResource::FileData(Cow::Borrowed(f))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
     const DEFAULT_CACHE_TAG: &str = "cpython-37";
 
+    #[test]
+    fn test_path_extension_entries() -> Result<()> {
+        let pth = PythonPathExtension {
+            data: DataLocation::Memory(
+                b"# comment\n\n/opt/app/lib\nvendor  \nimport foo; foo.install()\n".to_vec(),
+            ),
+            path_resources: vec![],
+        };
+
+        assert_eq!(
+            pth.entries()?,
+            vec![
+                PythonPathExtensionEntry::Path(PathBuf::from("/opt/app/lib")),
+                PythonPathExtensionEntry::Path(PathBuf::from("vendor")),
+                PythonPathExtensionEntry::Code("import foo; foo.install()".to_string()),
+            ]
+        );
+
+        Ok(())
+    }
+
     #[test]
     fn test_is_in_packages() {
         let source = PythonResource::ModuleSource(Cow::Owned(PythonModuleSource {
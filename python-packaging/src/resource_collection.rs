@@ -7,16 +7,18 @@
 use {
     crate::{
         bytecode::{
-            compute_bytecode_header, BytecodeHeaderMode, CompileMode, PythonBytecodeCompiler,
+            compute_bytecode_header, map_with_compilers, BytecodeHeaderMode, CompileMode,
+            PythonBytecodeCompiler,
         },
         libpython::LibPythonBuildContext,
         location::{AbstractResourceLocation, ConcreteResourceLocation},
         module_util::{packages_from_module_name, resolve_path_for_module},
         python_source::has_dunder_file,
         resource::{
-            BytecodeOptimizationLevel, DataLocation, PythonExtensionModule, PythonModuleBytecode,
-            PythonModuleBytecodeFromSource, PythonModuleSource, PythonPackageDistributionResource,
-            PythonPackageResource, PythonResource, SharedLibrary,
+            BytecodeOptimizationLevel, DataLocation, FileData, PythonExtensionModule,
+            PythonModuleBytecode, PythonModuleBytecodeFromSource, PythonModuleSource,
+            PythonPackageDistributionResource, PythonPackageResource, PythonResource,
+            SharedLibrary,
         },
     },
     anyhow::{anyhow, Result},
@@ -26,7 +28,7 @@ use {
         collections::{BTreeMap, BTreeSet, HashMap},
         convert::TryFrom,
         iter::FromIterator,
-        path::PathBuf,
+        path::{Path, PathBuf},
     },
 };
 
@@ -81,9 +83,125 @@ pub struct PrePackagedResource {
     pub is_frozen_module: bool,
     pub is_extension_module: bool,
     pub is_shared_library: bool,
+    pub is_file_data: bool,
+    pub file_executable: bool,
+    pub in_memory_file_data: Option<DataLocation>,
+    // (path, data)
+    pub relative_path_file_data: Option<(PathBuf, DataLocation)>,
+}
+
+/// Compile bytecode for optimization levels whose bytecode derives from source.
+///
+/// Levels sharing the same source are compiled together so the source is
+/// only parsed once.
+fn compile_from_source(
+    compiler: &mut dyn PythonBytecodeCompiler,
+    name: &str,
+    providers: &[(
+        BytecodeOptimizationLevel,
+        Option<&PythonModuleBytecodeProvider>,
+    )],
+    output_mode: CompileMode,
+) -> Result<BTreeMap<BytecodeOptimizationLevel, Vec<u8>>> {
+    let mut sources: Vec<(&DataLocation, Vec<BytecodeOptimizationLevel>)> = vec![];
+
+    for (level, provider) in providers {
+        if let Some(PythonModuleBytecodeProvider::FromSource(location)) = provider {
+            match sources.iter_mut().find(|(l, _)| *l == location) {
+                Some((_, levels)) => levels.push(*level),
+                None => sources.push((location, vec![*level])),
+            }
+        }
+    }
+
+    let mut res = BTreeMap::new();
+
+    for (location, levels) in sources {
+        res.extend(compiler.compile_levels(&location.resolve()?, name, &levels, output_mode)?);
+    }
+
+    Ok(res)
+}
+
+/// Remove compiled bytecode for an optimization level from a map.
+fn take_compiled(
+    compiled: &mut BTreeMap<BytecodeOptimizationLevel, Vec<u8>>,
+    name: &str,
+    level: BytecodeOptimizationLevel,
+) -> Result<Vec<u8>> {
+    compiled
+        .remove(&level)
+        .ok_or_else(|| anyhow!("bytecode for {} at {:?} was not compiled", name, level))
 }
 
 impl PrePackagedResource {
+    /// Obtain all locations of data referenced by this instance.
+    ///
+    /// Includes locations of source that bytecode will be compiled from.
+    pub fn data_locations(&self) -> Vec<&DataLocation> {
+        fn provider_location(provider: &PythonModuleBytecodeProvider) -> &DataLocation {
+            match provider {
+                PythonModuleBytecodeProvider::Provided(location) => location,
+                PythonModuleBytecodeProvider::FromSource(location) => location,
+            }
+        }
+
+        let mut res = vec![];
+
+        res.extend(self.in_memory_source.iter());
+        for provider in [
+            &self.in_memory_bytecode,
+            &self.in_memory_bytecode_opt1,
+            &self.in_memory_bytecode_opt2,
+        ]
+        .iter()
+        {
+            res.extend(provider.iter().map(provider_location));
+        }
+        res.extend(self.in_memory_extension_module_shared_library.iter());
+        for resources in [
+            &self.in_memory_resources,
+            &self.in_memory_distribution_resources,
+        ]
+        .iter()
+        {
+            res.extend(resources.iter().flat_map(|m| m.values()));
+        }
+        res.extend(self.in_memory_shared_library.iter());
+        res.extend(self.relative_path_module_source.iter().map(|(_, l)| l));
+        for entry in [
+            &self.relative_path_bytecode,
+            &self.relative_path_bytecode_opt1,
+            &self.relative_path_bytecode_opt2,
+        ]
+        .iter()
+        {
+            res.extend(
+                entry
+                    .iter()
+                    .map(|(_, _, provider)| provider_location(provider)),
+            );
+        }
+        res.extend(
+            self.relative_path_extension_module_shared_library
+                .iter()
+                .map(|(_, l)| l),
+        );
+        for resources in [
+            &self.relative_path_package_resources,
+            &self.relative_path_distribution_resources,
+        ]
+        .iter()
+        {
+            res.extend(resources.iter().flat_map(|m| m.values().map(|(_, l)| l)));
+        }
+        res.extend(self.relative_path_shared_library.iter().map(|(_, _, l)| l));
+        res.extend(self.in_memory_file_data.iter());
+        res.extend(self.relative_path_file_data.iter().map(|(_, l)| l));
+
+        res
+    }
+
     /// Convert the instance to a `Resource`.
     ///
     /// This will compile bytecode from source code using the specified compiler.
@@ -95,6 +213,45 @@ impl PrePackagedResource {
     ) -> Result<(Resource<'a, u8>, Vec<FileInstall>)> {
         let mut installs = Vec::new();
 
+        let mut in_memory_compiled = compile_from_source(
+            compiler,
+            &self.name,
+            &[
+                (
+                    BytecodeOptimizationLevel::Zero,
+                    self.in_memory_bytecode.as_ref(),
+                ),
+                (
+                    BytecodeOptimizationLevel::One,
+                    self.in_memory_bytecode_opt1.as_ref(),
+                ),
+                (
+                    BytecodeOptimizationLevel::Two,
+                    self.in_memory_bytecode_opt2.as_ref(),
+                ),
+            ],
+            CompileMode::Bytecode,
+        )?;
+        let mut relative_path_compiled = compile_from_source(
+            compiler,
+            &self.name,
+            &[
+                (
+                    BytecodeOptimizationLevel::Zero,
+                    self.relative_path_bytecode.as_ref().map(|(_, _, p)| p),
+                ),
+                (
+                    BytecodeOptimizationLevel::One,
+                    self.relative_path_bytecode_opt1.as_ref().map(|(_, _, p)| p),
+                ),
+                (
+                    BytecodeOptimizationLevel::Two,
+                    self.relative_path_bytecode_opt2.as_ref().map(|(_, _, p)| p),
+                ),
+            ],
+            CompileMode::PycUncheckedHash,
+        )?;
+
         let resource = Resource {
             flavor: ResourceFlavor::None,
             name: Cow::Owned(self.name.clone()),
@@ -109,12 +266,11 @@ impl PrePackagedResource {
                 Some(PythonModuleBytecodeProvider::Provided(location)) => {
                     Some(Cow::Owned(location.resolve()?))
                 }
-                Some(PythonModuleBytecodeProvider::FromSource(location)) => {
-                    Some(Cow::Owned(compiler.compile(
-                        &location.resolve()?,
+                Some(PythonModuleBytecodeProvider::FromSource(_)) => {
+                    Some(Cow::Owned(take_compiled(
+                        &mut in_memory_compiled,
                         &self.name,
                         BytecodeOptimizationLevel::Zero,
-                        CompileMode::Bytecode,
                     )?))
                 }
                 None => None,
@@ -123,12 +279,11 @@ impl PrePackagedResource {
                 Some(PythonModuleBytecodeProvider::Provided(location)) => {
                     Some(Cow::Owned(location.resolve()?))
                 }
-                Some(PythonModuleBytecodeProvider::FromSource(location)) => {
-                    Some(Cow::Owned(compiler.compile(
-                        &location.resolve()?,
+                Some(PythonModuleBytecodeProvider::FromSource(_)) => {
+                    Some(Cow::Owned(take_compiled(
+                        &mut in_memory_compiled,
                         &self.name,
                         BytecodeOptimizationLevel::One,
-                        CompileMode::Bytecode,
                     )?))
                 }
                 None => None,
@@ -137,12 +292,11 @@ impl PrePackagedResource {
                 Some(PythonModuleBytecodeProvider::Provided(location)) => {
                     Some(Cow::Owned(location.resolve()?))
                 }
-                Some(PythonModuleBytecodeProvider::FromSource(location)) => {
-                    Some(Cow::Owned(compiler.compile(
-                        &location.resolve()?,
+                Some(PythonModuleBytecodeProvider::FromSource(_)) => {
+                    Some(Cow::Owned(take_compiled(
+                        &mut in_memory_compiled,
                         &self.name,
                         BytecodeOptimizationLevel::Two,
-                        CompileMode::Bytecode,
                     )?))
                 }
                 None => None,
@@ -214,11 +368,10 @@ impl PrePackagedResource {
                 installs.push((
                     path.clone(),
                     DataLocation::Memory(match provider {
-                        PythonModuleBytecodeProvider::FromSource(location) => compiler.compile(
-                            &location.resolve()?,
+                        PythonModuleBytecodeProvider::FromSource(_) => take_compiled(
+                            &mut relative_path_compiled,
                             &self.name,
                             BytecodeOptimizationLevel::Zero,
-                            CompileMode::PycUncheckedHash,
                         )?,
                         PythonModuleBytecodeProvider::Provided(location) => {
                             let mut data = compute_bytecode_header(
@@ -254,11 +407,10 @@ impl PrePackagedResource {
                 installs.push((
                     path.clone(),
                     DataLocation::Memory(match provider {
-                        PythonModuleBytecodeProvider::FromSource(location) => compiler.compile(
-                            &location.resolve()?,
+                        PythonModuleBytecodeProvider::FromSource(_) => take_compiled(
+                            &mut relative_path_compiled,
                             &self.name,
                             BytecodeOptimizationLevel::One,
-                            CompileMode::PycUncheckedHash,
                         )?,
                         PythonModuleBytecodeProvider::Provided(location) => {
                             let mut data = compute_bytecode_header(
@@ -294,11 +446,10 @@ impl PrePackagedResource {
                 installs.push((
                     path.clone(),
                     DataLocation::Memory(match provider {
-                        PythonModuleBytecodeProvider::FromSource(location) => compiler.compile(
-                            &location.resolve()?,
+                        PythonModuleBytecodeProvider::FromSource(_) => take_compiled(
+                            &mut relative_path_compiled,
                             &self.name,
                             BytecodeOptimizationLevel::Two,
-                            CompileMode::PycUncheckedHash,
                         )?,
                         PythonModuleBytecodeProvider::Provided(location) => {
                             let mut data = compute_bytecode_header(
@@ -357,6 +508,20 @@ impl PrePackagedResource {
             is_frozen_module: self.is_frozen_module,
             is_extension_module: self.is_extension_module,
             is_shared_library: self.is_shared_library,
+            is_file_data: self.is_file_data,
+            file_executable: self.file_executable,
+            in_memory_file_data: if let Some(location) = &self.in_memory_file_data {
+                Some(Cow::Owned(location.resolve()?))
+            } else {
+                None
+            },
+            relative_path_file_data: if let Some((path, location)) = &self.relative_path_file_data {
+                installs.push((path.clone(), location.clone(), self.file_executable));
+
+                Some(Cow::Owned(path.clone()))
+            } else {
+                None
+            },
         };
 
         if let Some((prefix, filename, location)) = &self.relative_path_shared_library {
@@ -544,6 +709,14 @@ pub struct PythonResourceAddCollectionContext {
 
     /// Whether to store Python bytecode for optimization level 2.
     pub optimize_level_two: bool,
+
+    /// Explicit path to install the resource at when it is added to a
+    /// filesystem-relative location.
+    ///
+    /// The path is relative to the location the binary is installed. It
+    /// replaces the path derived from the location's prefix and the resource
+    /// name. Only honored for `PythonPackageResource`.
+    pub install_relative_path: Option<PathBuf>,
 }
 
 impl PythonResourceAddCollectionContext {
@@ -556,6 +729,7 @@ impl PythonResourceAddCollectionContext {
         self.optimize_level_zero = other.optimize_level_zero;
         self.optimize_level_one = other.optimize_level_one;
         self.optimize_level_two = other.optimize_level_two;
+        self.install_relative_path = other.install_relative_path.clone();
     }
 }
 
@@ -612,6 +786,13 @@ pub struct PythonResourceCollector {
 
     /// Named resources that have been collected.
     resources: BTreeMap<String, PrePackagedResource>,
+
+    /// Where collected resources came from, keyed by resource full name.
+    ///
+    /// Populated via `set_resource_origin()`. Origins are free-form
+    /// descriptions used for diagnostics.
+    resource_origins: BTreeMap<String, String>,
+
     /// Bytecode cache tag to use for compiled bytecode modules.
     cache_tag: String,
 }
@@ -635,6 +816,7 @@ impl PythonResourceCollector {
             allowed_extension_module_locations,
             allow_new_builtin_extension_modules,
             resources: BTreeMap::new(),
+            resource_origins: BTreeMap::new(),
             cache_tag: cache_tag.to_string(),
         }
     }
@@ -679,6 +861,121 @@ impl PythonResourceCollector {
         Box::new(self.resources.iter())
     }
 
+    /// Record where a resource came from.
+    ///
+    /// `name` is the full name of the resource, as returned by
+    /// `PythonResource.full_name()`.
+    pub fn set_resource_origin(&mut self, name: &str, origin: &str) {
+        self.resource_origins
+            .insert(name.to_string(), origin.to_string());
+    }
+
+    /// Obtain the recorded origin of a resource.
+    pub fn resource_origin(&self, name: &str) -> Option<&str> {
+        self.resource_origins.get(name).map(|x| x.as_str())
+    }
+
+    /// Whether a resource with the same name but different content has been collected.
+    ///
+    /// Only content that has already been collected is compared. e.g. a
+    /// collected module without source does not conflict with a module
+    /// source. Resource types not holding comparable content never conflict.
+    pub fn is_conflicting_resource(&self, resource: &PythonResource) -> Result<bool> {
+        fn differs(existing: Option<&DataLocation>, new: &DataLocation) -> Result<bool> {
+            Ok(match existing {
+                Some(existing) => existing.resolve()? != new.resolve()?,
+                None => false,
+            })
+        }
+
+        match resource {
+            PythonResource::ModuleSource(module) => {
+                if let Some(entry) = self.resources.get(&module.name) {
+                    Ok(differs(entry.in_memory_source.as_ref(), &module.source)?
+                        || differs(
+                            entry
+                                .relative_path_module_source
+                                .as_ref()
+                                .map(|(_, location)| location),
+                            &module.source,
+                        )?)
+                } else {
+                    Ok(false)
+                }
+            }
+            PythonResource::PackageResource(resource) => {
+                if let Some(entry) = self.resources.get(&resource.leaf_package) {
+                    Ok(differs(
+                        entry
+                            .in_memory_resources
+                            .as_ref()
+                            .and_then(|x| x.get(&resource.relative_name)),
+                        &resource.data,
+                    )? || differs(
+                        entry
+                            .relative_path_package_resources
+                            .as_ref()
+                            .and_then(|x| x.get(&resource.relative_name))
+                            .map(|(_, location)| location),
+                        &resource.data,
+                    )?)
+                } else {
+                    Ok(false)
+                }
+            }
+            PythonResource::PackageDistributionResource(resource) => {
+                if let Some(entry) = self.resources.get(&resource.package) {
+                    Ok(differs(
+                        entry
+                            .in_memory_distribution_resources
+                            .as_ref()
+                            .and_then(|x| x.get(&resource.name)),
+                        &resource.data,
+                    )? || differs(
+                        entry
+                            .relative_path_distribution_resources
+                            .as_ref()
+                            .and_then(|x| x.get(&resource.name))
+                            .map(|(_, location)| location),
+                        &resource.data,
+                    )?)
+                } else {
+                    Ok(false)
+                }
+            }
+            PythonResource::ExtensionModule(em) => {
+                match (self.resources.get(&em.name), &em.shared_library) {
+                    (Some(entry), Some(library)) => Ok(differs(
+                        entry.in_memory_extension_module_shared_library.as_ref(),
+                        library,
+                    )? || differs(
+                        entry
+                            .relative_path_extension_module_shared_library
+                            .as_ref()
+                            .map(|(_, location)| location),
+                        library,
+                    )?),
+                    _ => Ok(false),
+                }
+            }
+            PythonResource::FileData(file) => {
+                if let Some(entry) = self.resources.get(&file.path_string()) {
+                    Ok(differs(entry.in_memory_file_data.as_ref(), &file.data)?
+                        || differs(
+                            entry
+                                .relative_path_file_data
+                                .as_ref()
+                                .map(|(_, location)| location),
+                            &file.data,
+                        )?)
+                } else {
+                    Ok(false)
+                }
+            }
+            _ => Ok(false),
+        }
+    }
+
     /// Add Python module source with a specific location.
     pub fn add_python_module_source(
         &mut self,
@@ -772,6 +1069,67 @@ impl PythonResourceCollector {
         Ok(())
     }
 
+    /// Replace the source code of an already added Python module.
+    ///
+    /// Source stored in memory or in a relative path is replaced with
+    /// `source`. Bytecode derived from the module's source is recompiled
+    /// from `source` when resources are compiled. Bytecode that was
+    /// provided as-is is left untouched.
+    ///
+    /// Errors if no module source or source-derived bytecode has been added
+    /// for `name`.
+    pub fn replace_python_module_source(&mut self, name: &str, source: Vec<u8>) -> Result<()> {
+        let entry = self
+            .resources
+            .get_mut(name)
+            .filter(|entry| entry.is_module)
+            .ok_or_else(|| anyhow!("Python module {} has not been added", name))?;
+
+        let location = DataLocation::Memory(source);
+        let mut replaced = false;
+
+        if let Some(source) = &mut entry.in_memory_source {
+            *source = location.clone();
+            replaced = true;
+        }
+        if let Some((_, source)) = &mut entry.relative_path_module_source {
+            *source = location.clone();
+            replaced = true;
+        }
+
+        for provider in vec![
+            entry.in_memory_bytecode.as_mut(),
+            entry.in_memory_bytecode_opt1.as_mut(),
+            entry.in_memory_bytecode_opt2.as_mut(),
+            entry
+                .relative_path_bytecode
+                .as_mut()
+                .map(|(_, _, provider)| provider),
+            entry
+                .relative_path_bytecode_opt1
+                .as_mut()
+                .map(|(_, _, provider)| provider),
+            entry
+                .relative_path_bytecode_opt2
+                .as_mut()
+                .map(|(_, _, provider)| provider),
+        ]
+        .into_iter()
+        .flatten()
+        {
+            if let PythonModuleBytecodeProvider::FromSource(source) = provider {
+                *source = location.clone();
+                replaced = true;
+            }
+        }
+
+        if replaced {
+            Ok(())
+        } else {
+            Err(anyhow!("Python module {} has no source to replace", name))
+        }
+    }
+
     /// Add Python module bytecode to the specified location.
     pub fn add_python_module_bytecode(
         &mut self,
@@ -993,6 +1351,19 @@ impl PythonResourceCollector {
         &mut self,
         resource: &PythonPackageResource,
         location: &ConcreteResourceLocation,
+    ) -> Result<()> {
+        self.add_python_package_resource_at(resource, location, None)
+    }
+
+    /// Add resource data to a given location, optionally at an explicit path.
+    ///
+    /// `install_relative_path` overrides the path of resources added to a
+    /// filesystem-relative location.
+    fn add_python_package_resource_at(
+        &mut self,
+        resource: &PythonPackageResource,
+        location: &ConcreteResourceLocation,
+        install_relative_path: Option<&Path>,
     ) -> Result<()> {
         self.check_policy(location.into())?;
 
@@ -1024,13 +1395,18 @@ impl PythonResourceCollector {
                     entry.relative_path_package_resources = Some(BTreeMap::new());
                 }
 
+                let path = match install_relative_path {
+                    Some(path) => path.to_path_buf(),
+                    None => resource.resolve_path(prefix),
+                };
+
                 entry
                     .relative_path_package_resources
                     .as_mut()
                     .unwrap()
                     .insert(
                         resource.relative_name.clone(),
-                        (resource.resolve_path(prefix), resource.data.clone()),
+                        (path, resource.data.clone()),
                     );
             }
         }
@@ -1051,11 +1427,22 @@ impl PythonResourceCollector {
             return Ok(());
         }
 
-        self.add_python_resource_with_locations(
-            &resource.into(),
+        let install_relative_path = add_context.install_relative_path.as_deref();
+
+        match self.add_python_package_resource_at(
+            resource,
             &add_context.location,
-            &add_context.location_fallback,
-        )
+            install_relative_path,
+        ) {
+            Ok(()) => Ok(()),
+            Err(err) => {
+                if let Some(location) = &add_context.location_fallback {
+                    self.add_python_package_resource_at(resource, location, install_relative_path)
+                } else {
+                    Err(err)
+                }
+            }
+        }
     }
 
     /// Add a Python package distribution resource to a given location.
@@ -1434,6 +1821,60 @@ impl PythonResourceCollector {
         Ok(())
     }
 
+    /// Add arbitrary file data to a given location.
+    pub fn add_file_data(
+        &mut self,
+        file: &FileData,
+        location: &ConcreteResourceLocation,
+    ) -> Result<()> {
+        self.check_policy(location.into())?;
+
+        let name = file.path_string();
+
+        let entry = self
+            .resources
+            .entry(name.clone())
+            .or_insert_with(|| PrePackagedResource {
+                name,
+                ..PrePackagedResource::default()
+            });
+
+        entry.is_file_data = true;
+        entry.file_executable = file.executable;
+
+        match location {
+            ConcreteResourceLocation::InMemory => {
+                entry.in_memory_file_data = Some(file.data.clone());
+            }
+            ConcreteResourceLocation::RelativePath(prefix) => {
+                entry.relative_path_file_data =
+                    Some((PathBuf::from(prefix).join(&file.path), file.data.clone()));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Add arbitrary file data using an add context.
+    ///
+    /// The fields from the context will be respected. This includes not doing
+    /// anything if `include` is false.
+    pub fn add_file_data_with_context(
+        &mut self,
+        file: &FileData,
+        add_context: &PythonResourceAddCollectionContext,
+    ) -> Result<()> {
+        if !add_context.include {
+            return Ok(());
+        }
+
+        self.add_python_resource_with_locations(
+            &file.into(),
+            &add_context.location,
+            &add_context.location_fallback,
+        )
+    }
+
     fn add_python_resource_with_locations(
         &mut self,
         resource: &PythonResource,
@@ -1501,6 +1942,16 @@ impl PythonResourceCollector {
                     }
                 }
             }
+            PythonResource::FileData(file) => match self.add_file_data(file, location) {
+                Ok(()) => Ok(()),
+                Err(err) => {
+                    if let Some(location) = fallback_location {
+                        self.add_file_data(file, location)
+                    } else {
+                        Err(err)
+                    }
+                }
+            },
             _ => Err(anyhow!("PythonResource variant not yet supported")),
         }
     }
@@ -1578,6 +2029,46 @@ impl PythonResourceCollector {
             extra_files,
         })
     }
+
+    /// Compiles resources into a finalized collection using multiple compilers.
+    ///
+    /// This behaves like `compile_resources()` except resources are converted
+    /// concurrently by up to `jobs` bytecode compilers created by `new_compiler`.
+    pub fn compile_resources_parallel<C>(
+        &self,
+        jobs: usize,
+        new_compiler: C,
+    ) -> Result<CompiledResourcesCollection<'static>>
+    where
+        C: Fn() -> Result<Box<dyn PythonBytecodeCompiler + Send>> + Send + Sync + 'static,
+    {
+        let mut input_resources = self.resources.clone();
+        populate_parent_packages(&mut input_resources)?;
+
+        let converted = map_with_compilers(
+            input_resources.into_iter().collect::<Vec<_>>(),
+            jobs,
+            new_compiler,
+            |compiler, (name, resource): (String, PrePackagedResource)| {
+                let (entry, installs) = resource.to_resource(compiler)?;
+
+                Ok((name, entry, installs))
+            },
+        )?;
+
+        let mut resources = BTreeMap::new();
+        let mut extra_files = Vec::new();
+
+        for (name, entry, installs) in converted {
+            extra_files.extend(installs);
+            resources.insert(name, entry);
+        }
+
+        Ok(CompiledResourcesCollection {
+            resources,
+            extra_files,
+        })
+    }
 }
 
 #[cfg(test)]
@@ -1619,6 +2110,39 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_data_locations() {
+        let pre = PrePackagedResource {
+            is_module: true,
+            name: "module".to_string(),
+            in_memory_source: Some(DataLocation::Memory(b"source".to_vec())),
+            in_memory_bytecode_opt1: Some(PythonModuleBytecodeProvider::FromSource(
+                DataLocation::Path(PathBuf::from("/module.py")),
+            )),
+            relative_path_package_resources: Some(
+                vec![(
+                    "foo.txt".to_string(),
+                    (
+                        PathBuf::from("module/foo.txt"),
+                        DataLocation::Memory(b"foo".to_vec()),
+                    ),
+                )]
+                .into_iter()
+                .collect(),
+            ),
+            ..PrePackagedResource::default()
+        };
+
+        assert_eq!(
+            pre.data_locations(),
+            vec![
+                &DataLocation::Memory(b"source".to_vec()),
+                &DataLocation::Path(PathBuf::from("/module.py")),
+                &DataLocation::Memory(b"foo".to_vec()),
+            ]
+        );
+    }
+
     #[test]
     fn test_resource_conversion_basic() -> Result<()> {
         let mut compiler = FakeBytecodeCompiler { magic_number: 42 };
@@ -2726,6 +3250,167 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_replace_python_module_source() -> Result<()> {
+        let mut r = PythonResourceCollector::new(
+            vec![AbstractResourceLocation::InMemory],
+            vec![],
+            false,
+            DEFAULT_CACHE_TAG,
+        );
+
+        assert!(r.replace_python_module_source("foo", vec![1]).is_err());
+
+        let module = PythonModuleSource {
+            name: "foo".to_string(),
+            source: DataLocation::Memory(vec![42]),
+            is_package: false,
+            cache_tag: DEFAULT_CACHE_TAG.to_string(),
+            is_stdlib: false,
+            is_test: false,
+        };
+
+        r.add_python_module_source(&module, &ConcreteResourceLocation::InMemory)?;
+        r.add_python_module_bytecode_from_source(
+            &module.as_bytecode_module(BytecodeOptimizationLevel::Zero),
+            &ConcreteResourceLocation::InMemory,
+        )?;
+        r.add_python_module_bytecode(
+            &PythonModuleBytecode::new(
+                "foo",
+                BytecodeOptimizationLevel::One,
+                false,
+                DEFAULT_CACHE_TAG,
+                &[21],
+            ),
+            &ConcreteResourceLocation::InMemory,
+        )?;
+
+        r.replace_python_module_source("foo", vec![43])?;
+
+        let entry = r.resources.get("foo").unwrap();
+        assert_eq!(entry.in_memory_source, Some(DataLocation::Memory(vec![43])));
+        assert_eq!(
+            entry.in_memory_bytecode,
+            Some(PythonModuleBytecodeProvider::FromSource(
+                DataLocation::Memory(vec![43])
+            ))
+        );
+        assert_eq!(
+            entry.in_memory_bytecode_opt1,
+            Some(PythonModuleBytecodeProvider::Provided(
+                DataLocation::Memory(vec![21])
+            ))
+        );
+
+        let mut compiler = FakeBytecodeCompiler { magic_number: 42 };
+        let resources = r.compile_resources(&mut compiler)?;
+        let resource = resources.resources.get("foo").unwrap();
+        assert_eq!(resource.in_memory_source, Some(Cow::Owned(vec![43])));
+        assert_eq!(
+            resource.in_memory_bytecode,
+            Some(Cow::Owned(b"bc0\x2b".to_vec()))
+        );
+
+        r.add_python_module_bytecode(
+            &PythonModuleBytecode::new(
+                "bar",
+                BytecodeOptimizationLevel::Zero,
+                false,
+                DEFAULT_CACHE_TAG,
+                &[21],
+            ),
+            &ConcreteResourceLocation::InMemory,
+        )?;
+        assert!(r.replace_python_module_source("bar", vec![1]).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_compile_resources_parallel() -> Result<()> {
+        let mut r = PythonResourceCollector::new(
+            vec![
+                AbstractResourceLocation::InMemory,
+                AbstractResourceLocation::RelativePath,
+            ],
+            vec![],
+            false,
+            DEFAULT_CACHE_TAG,
+        );
+
+        for i in 0..10 {
+            let location = if i % 2 == 0 {
+                ConcreteResourceLocation::InMemory
+            } else {
+                ConcreteResourceLocation::RelativePath("lib".to_string())
+            };
+
+            r.add_python_module_bytecode_from_source(
+                &PythonModuleBytecodeFromSource {
+                    name: format!("pkg.mod{}", i),
+                    source: DataLocation::Memory(vec![i]),
+                    optimize_level: BytecodeOptimizationLevel::Zero,
+                    is_package: false,
+                    cache_tag: DEFAULT_CACHE_TAG.to_string(),
+                    is_stdlib: false,
+                    is_test: false,
+                },
+                &location,
+            )?;
+        }
+
+        let mut compiler = FakeBytecodeCompiler { magic_number: 42 };
+        let serial = r.compile_resources(&mut compiler)?;
+
+        let parallel = r.compile_resources_parallel(4, || {
+            Ok(Box::new(FakeBytecodeCompiler { magic_number: 42 })
+                as Box<dyn PythonBytecodeCompiler + Send>)
+        })?;
+
+        // Parent package is populated.
+        assert_eq!(parallel.resources.len(), 11);
+        assert_eq!(parallel.resources, serial.resources);
+        assert_eq!(parallel.extra_files, serial.extra_files);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_is_conflicting_resource() -> Result<()> {
+        let mut r = PythonResourceCollector::new(
+            vec![AbstractResourceLocation::InMemory],
+            vec![],
+            false,
+            DEFAULT_CACHE_TAG,
+        );
+
+        let module = PythonModuleSource {
+            name: "foo".to_string(),
+            source: DataLocation::Memory(vec![42]),
+            is_package: false,
+            cache_tag: DEFAULT_CACHE_TAG.to_string(),
+            is_stdlib: false,
+            is_test: false,
+        };
+        let different = PythonModuleSource {
+            source: DataLocation::Memory(vec![43]),
+            ..module.clone()
+        };
+
+        assert!(!r.is_conflicting_resource(&(&module).into())?);
+
+        r.add_python_module_source(&module, &ConcreteResourceLocation::InMemory)?;
+        r.set_resource_origin("foo", "first");
+
+        assert!(!r.is_conflicting_resource(&(&module).into())?);
+        assert!(r.is_conflicting_resource(&(&different).into())?);
+        assert_eq!(r.resource_origin("foo"), Some("first"));
+        assert_eq!(r.resource_origin("bar"), None);
+
+        Ok(())
+    }
+
     #[test]
     fn test_add_in_memory_source_module_parents() -> Result<()> {
         let mut r = PythonResourceCollector::new(
@@ -2905,6 +3590,7 @@ mod tests {
             optimize_level_zero: false,
             optimize_level_one: false,
             optimize_level_two: false,
+            install_relative_path: None,
         };
 
         // include=false is a noop.
@@ -3130,6 +3816,7 @@ mod tests {
             optimize_level_zero: false,
             optimize_level_one: false,
             optimize_level_two: false,
+            install_relative_path: None,
         };
 
         // include=false is a noop.
@@ -3326,6 +4013,7 @@ mod tests {
             optimize_level_zero: false,
             optimize_level_one: false,
             optimize_level_two: false,
+            install_relative_path: None,
         };
 
         // include=false is a noop.
@@ -3420,6 +4108,112 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_add_in_memory_file_data() -> Result<()> {
+        let mut r = PythonResourceCollector::new(
+            vec![AbstractResourceLocation::InMemory],
+            vec![],
+            false,
+            DEFAULT_CACHE_TAG,
+        );
+        r.add_file_data(
+            &FileData {
+                path: PathBuf::from("foo/bar.txt"),
+                data: DataLocation::Memory(vec![42]),
+                executable: false,
+            },
+            &ConcreteResourceLocation::InMemory,
+        )?;
+
+        assert_eq!(r.resources.len(), 1);
+        assert_eq!(
+            r.resources.get("foo/bar.txt"),
+            Some(&PrePackagedResource {
+                name: "foo/bar.txt".to_string(),
+                is_file_data: true,
+                in_memory_file_data: Some(DataLocation::Memory(vec![42])),
+                ..PrePackagedResource::default()
+            })
+        );
+
+        let mut compiler = FakeBytecodeCompiler { magic_number: 42 };
+
+        let resources = r.compile_resources(&mut compiler)?;
+
+        assert_eq!(resources.resources.len(), 1);
+        assert_eq!(
+            resources.resources.get("foo/bar.txt"),
+            Some(&Resource {
+                name: Cow::Owned("foo/bar.txt".to_string()),
+                is_file_data: true,
+                in_memory_file_data: Some(Cow::Owned(vec![42])),
+                ..Resource::default()
+            })
+        );
+        assert!(resources.extra_files.is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_add_relative_path_file_data() -> Result<()> {
+        let mut r = PythonResourceCollector::new(
+            vec![AbstractResourceLocation::RelativePath],
+            vec![],
+            false,
+            DEFAULT_CACHE_TAG,
+        );
+        r.add_file_data(
+            &FileData {
+                path: PathBuf::from("foo/bar.sh"),
+                data: DataLocation::Memory(vec![42]),
+                executable: true,
+            },
+            &ConcreteResourceLocation::RelativePath("prefix".to_string()),
+        )?;
+
+        assert_eq!(r.resources.len(), 1);
+        assert_eq!(
+            r.resources.get("foo/bar.sh"),
+            Some(&PrePackagedResource {
+                name: "foo/bar.sh".to_string(),
+                is_file_data: true,
+                file_executable: true,
+                relative_path_file_data: Some((
+                    PathBuf::from("prefix/foo/bar.sh"),
+                    DataLocation::Memory(vec![42])
+                )),
+                ..PrePackagedResource::default()
+            })
+        );
+
+        let mut compiler = FakeBytecodeCompiler { magic_number: 42 };
+
+        let resources = r.compile_resources(&mut compiler)?;
+
+        assert_eq!(resources.resources.len(), 1);
+        assert_eq!(
+            resources.resources.get("foo/bar.sh"),
+            Some(&Resource {
+                name: Cow::Owned("foo/bar.sh".to_string()),
+                is_file_data: true,
+                file_executable: true,
+                relative_path_file_data: Some(Cow::Owned(PathBuf::from("prefix/foo/bar.sh"))),
+                ..Resource::default()
+            })
+        );
+        assert_eq!(
+            resources.extra_files,
+            vec![(
+                PathBuf::from("prefix/foo/bar.sh"),
+                DataLocation::Memory(vec![42]),
+                true
+            ),]
+        );
+
+        Ok(())
+    }
+
     #[test]
     fn test_add_in_memory_package_resource() -> Result<()> {
         let mut r = PythonResourceCollector::new(
@@ -3579,6 +4373,7 @@ mod tests {
             optimize_level_zero: false,
             optimize_level_one: false,
             optimize_level_two: false,
+            install_relative_path: None,
         };
 
         // include=false is a noop.
@@ -3622,8 +4417,8 @@ mod tests {
                         resource.relative_name.clone(),
                         (
                             PathBuf::from("prefix")
-                                .join(resource.leaf_package)
-                                .join(resource.relative_name),
+                                .join(&resource.leaf_package)
+                                .join(&resource.relative_name),
                             resource.data.clone()
                         )
                     )]
@@ -3636,6 +4431,29 @@ mod tests {
 
         r.resources.clear();
 
+        // install_relative_path overrides the derived path.
+        add_context.install_relative_path = Some(PathBuf::from("custom/data/bar.txt"));
+        r.add_python_package_resource_with_context(&resource, &add_context)?;
+        assert_eq!(
+            r.resources.get(&resource.leaf_package),
+            Some(&PrePackagedResource {
+                is_module: true,
+                name: resource.leaf_package.clone(),
+                is_package: true,
+                relative_path_package_resources: Some(BTreeMap::from_iter(
+                    [(
+                        resource.relative_name.clone(),
+                        (PathBuf::from("custom/data/bar.txt"), resource.data.clone())
+                    )]
+                    .iter()
+                    .cloned()
+                )),
+                ..PrePackagedResource::default()
+            })
+        );
+
+        r.resources.clear();
+
         Ok(())
     }
 
@@ -3798,6 +4616,7 @@ mod tests {
             optimize_level_zero: false,
             optimize_level_one: false,
             optimize_level_two: false,
+            install_relative_path: None,
         };
 
         // include=false is a noop.
@@ -0,0 +1,203 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+/*!
+Scanning zip archives for Python resources.
+*/
+
+use {
+    crate::{
+        filesystem_scanning::PythonResourceIterator,
+        module_util::PythonModuleSuffixes,
+        resource::{DataLocation, PythonResource},
+    },
+    anyhow::{Context, Result},
+    std::{
+        io::{Read, Seek},
+        path::{Component, Path, PathBuf},
+    },
+    zip::ZipArchive,
+};
+
+/// Find Python resources in a zip archive.
+///
+/// Entries are read into memory and classified as if the archive had been
+/// extracted to a directory.
+///
+/// If `strip_prefix` is defined, only entries under that path prefix are
+/// considered and the prefix is removed from their paths. This allows
+/// consuming archives having a top-level directory, such as source
+/// distributions.
+///
+/// Entries in `__pycache__` directories are ignored.
+pub fn find_python_resources_in_zip<'a, R>(
+    reader: R,
+    strip_prefix: Option<&str>,
+    cache_tag: &str,
+    suffixes: &PythonModuleSuffixes,
+) -> Result<Vec<PythonResource<'a>>>
+where
+    R: Read + Seek,
+{
+    let mut archive = ZipArchive::new(reader).context("reading zip archive")?;
+
+    let strip_prefix = strip_prefix.map(Path::new);
+
+    let mut inputs = Vec::new();
+
+    for i in 0..archive.len() {
+        let mut file = archive.by_index(i)?;
+
+        // Directory entries have a trailing slash.
+        if file.name().ends_with('/') {
+            continue;
+        }
+
+        let path = PathBuf::from(file.name());
+
+        let path = match strip_prefix {
+            Some(prefix) => match path.strip_prefix(prefix) {
+                Ok(path) => path.to_path_buf(),
+                Err(_) => continue,
+            },
+            None => path,
+        };
+
+        if path
+            .components()
+            .any(|c| c == Component::Normal("__pycache__".as_ref()))
+        {
+            continue;
+        }
+
+        let mut buffer = Vec::with_capacity(file.size() as usize);
+        file.read_to_end(&mut buffer)
+            .with_context(|| format!("reading {} from zip archive", file.name()))?;
+
+        inputs.push((path, DataLocation::Memory(buffer)));
+    }
+
+    // Archive order is arbitrary. Sort to mimic filesystem scanning and
+    // emit resources deterministically.
+    inputs.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+    PythonResourceIterator::from_data_locations(&inputs, cache_tag, suffixes)
+        .collect::<Result<Vec<_>>>()
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::resource::{PythonModuleSource, PythonPackageResource},
+        lazy_static::lazy_static,
+        std::io::{Cursor, Write},
+        zip::{write::FileOptions, ZipWriter},
+    };
+
+    const DEFAULT_CACHE_TAG: &str = "cpython-37";
+
+    lazy_static! {
+        static ref DEFAULT_SUFFIXES: PythonModuleSuffixes = PythonModuleSuffixes {
+            source: vec![".py".to_string()],
+            bytecode: vec![".pyc".to_string()],
+            debug_bytecode: vec![],
+            optimized_bytecode: vec![],
+            extension: vec![],
+        };
+    }
+
+    fn make_zip(files: &[(&str, &[u8])]) -> Result<Vec<u8>> {
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+
+        for (name, data) in files {
+            writer.start_file(*name, FileOptions::default())?;
+            writer.write_all(data)?;
+        }
+
+        Ok(writer.finish()?.into_inner())
+    }
+
+    #[test]
+    fn test_strip_prefix() -> Result<()> {
+        let data = make_zip(&[
+            ("foo-1.0/foo/bar/baz.py", b"baz"),
+            ("foo-1.0/foo/bar/__init__.py", b"bar"),
+            ("foo-1.0/foo/__pycache__/__init__.cpython-37.pyc", b"pyc"),
+            ("foo-1.0/foo/__init__.py", b"foo"),
+            ("foo-1.0/foo/data.txt", b"data"),
+            ("other/ignored.py", b"ignored"),
+        ])?;
+
+        let resources = find_python_resources_in_zip(
+            Cursor::new(data),
+            Some("foo-1.0"),
+            DEFAULT_CACHE_TAG,
+            &DEFAULT_SUFFIXES,
+        )?;
+
+        assert_eq!(
+            resources,
+            vec![
+                PythonModuleSource {
+                    name: "foo".to_string(),
+                    source: DataLocation::Memory(b"foo".to_vec()),
+                    is_package: true,
+                    cache_tag: DEFAULT_CACHE_TAG.to_string(),
+                    is_stdlib: false,
+                    is_test: false,
+                }
+                .into(),
+                PythonModuleSource {
+                    name: "foo.bar".to_string(),
+                    source: DataLocation::Memory(b"bar".to_vec()),
+                    is_package: true,
+                    cache_tag: DEFAULT_CACHE_TAG.to_string(),
+                    is_stdlib: false,
+                    is_test: false,
+                }
+                .into(),
+                PythonModuleSource {
+                    name: "foo.bar.baz".to_string(),
+                    source: DataLocation::Memory(b"baz".to_vec()),
+                    is_package: false,
+                    cache_tag: DEFAULT_CACHE_TAG.to_string(),
+                    is_stdlib: false,
+                    is_test: false,
+                }
+                .into(),
+                PythonPackageResource {
+                    leaf_package: "foo".to_string(),
+                    relative_name: "data.txt".to_string(),
+                    data: DataLocation::Memory(b"data".to_vec()),
+                    is_stdlib: false,
+                    is_test: false,
+                }
+                .into(),
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_no_strip_prefix() -> Result<()> {
+        let data = make_zip(&[
+            ("foo.py", b"foo"),
+            ("__pycache__/foo.cpython-37.pyc", b"pyc"),
+        ])?;
+
+        let resources = find_python_resources_in_zip(
+            Cursor::new(data),
+            None,
+            DEFAULT_CACHE_TAG,
+            &DEFAULT_SUFFIXES,
+        )?;
+
+        assert_eq!(resources.len(), 1);
+        assert_eq!(resources[0].full_name(), "foo");
+
+        Ok(())
+    }
+}
@@ -9,6 +9,17 @@ use std::{borrow::Cow, collections::HashMap, convert::TryFrom, iter::FromIterato
 /// Header value for version 2 of resources payload.
 pub const HEADER_V2: &[u8] = b"pyembed\x02";
 
+/// Header value for a zstd compressed resources payload.
+///
+/// The header is followed by a zstd frame holding an uncompressed payload,
+/// including its own header.
+pub const HEADER_ZSTD: &[u8] = b"pyembedz";
+
+/// Header value for version 1 of a standalone resources bundle file.
+///
+/// The header is followed by a resources payload, which may be compressed.
+pub const BUNDLE_HEADER_V1: &[u8] = b"pyoxbnd\x01";
+
 /// Defines the type of a resource.
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub enum ResourceFlavor {
@@ -149,6 +160,11 @@ pub enum ResourceField {
     IsFrozenModule = 0x18,
     IsExtensionModule = 0x19,
     IsSharedLibrary = 0x1a,
+    IsFileData = 0x1b,
+    FileExecutable = 0x1c,
+    InMemoryFileData = 0x1d,
+    RelativeFilesystemFileData = 0x1e,
+    BlobReference = 0x1f,
 }
 
 impl Into<u8> for ResourceField {
@@ -181,6 +197,11 @@ impl Into<u8> for ResourceField {
             ResourceField::IsFrozenModule => 0x18,
             ResourceField::IsExtensionModule => 0x19,
             ResourceField::IsSharedLibrary => 0x1a,
+            ResourceField::IsFileData => 0x1b,
+            ResourceField::FileExecutable => 0x1c,
+            ResourceField::InMemoryFileData => 0x1d,
+            ResourceField::RelativeFilesystemFileData => 0x1e,
+            ResourceField::BlobReference => 0x1f,
             ResourceField::EndOfEntry => 0xff,
         }
     }
@@ -218,6 +239,11 @@ impl TryFrom<u8> for ResourceField {
             0x18 => Ok(ResourceField::IsFrozenModule),
             0x19 => Ok(ResourceField::IsExtensionModule),
             0x1a => Ok(ResourceField::IsSharedLibrary),
+            0x1b => Ok(ResourceField::IsFileData),
+            0x1c => Ok(ResourceField::FileExecutable),
+            0x1d => Ok(ResourceField::InMemoryFileData),
+            0x1e => Ok(ResourceField::RelativeFilesystemFileData),
+            0x1f => Ok(ResourceField::BlobReference),
             0xff => Ok(ResourceField::EndOfEntry),
             _ => Err("invalid field type"),
         }
@@ -309,6 +335,20 @@ where
 
     /// Whether this resource defines a shared library.
     pub is_shared_library: bool,
+
+    /// Whether this resource defines arbitrary file data.
+    ///
+    /// If set, the resource name is the relative path of the file.
+    pub is_file_data: bool,
+
+    /// Whether the file data should be marked as executable.
+    pub file_executable: bool,
+
+    /// Content of a file to make available from memory.
+    pub in_memory_file_data: Option<Cow<'a, [X]>>,
+
+    /// Relative path to file containing arbitrary file data.
+    pub relative_path_file_data: Option<Cow<'a, Path>>,
 }
 
 impl<'a, X> Default for Resource<'a, X>
@@ -342,6 +382,10 @@ where
             is_frozen_module: false,
             is_extension_module: false,
             is_shared_library: false,
+            is_file_data: false,
+            file_executable: false,
+            in_memory_file_data: None,
+            relative_path_file_data: None,
         }
     }
 }
@@ -456,6 +500,16 @@ where
             is_frozen_module: self.is_frozen_module,
             is_extension_module: self.is_extension_module,
             is_shared_library: self.is_shared_library,
+            is_file_data: self.is_file_data,
+            file_executable: self.file_executable,
+            in_memory_file_data: self
+                .in_memory_file_data
+                .as_ref()
+                .map(|value| Cow::Owned(value.clone().into_owned())),
+            relative_path_file_data: self
+                .relative_path_file_data
+                .as_ref()
+                .map(|value| Cow::Owned(value.clone().into_owned())),
         }
     }
 }
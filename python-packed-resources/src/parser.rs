@@ -6,7 +6,8 @@
 
 use {
     super::data::{
-        BlobInteriorPadding, BlobSectionField, Resource, ResourceField, ResourceFlavor, HEADER_V2,
+        BlobInteriorPadding, BlobSectionField, Resource, ResourceField, ResourceFlavor,
+        BUNDLE_HEADER_V1, HEADER_V2, HEADER_ZSTD,
     },
     byteorder::{LittleEndian, ReadBytesExt},
     std::{
@@ -46,6 +47,8 @@ pub struct ResourceParserIterator<'a> {
     data: &'a [u8],
     reader: Cursor<&'a [u8]>,
     blob_sections: [Option<BlobSectionReadState>; 256],
+    blob_start_offset: usize,
+    pending_blob_reference: Option<usize>,
     claimed_resources_count: usize,
     read_resources_count: usize,
 }
@@ -61,12 +64,43 @@ impl<'a> ResourceParserIterator<'a> {
     /// This accepts a reference to the original blobs payload, an array of
     /// current blob section offsets, the resource field being accessed, and the
     /// length of the blob and returns a slice to that blob.
-    fn resolve_blob_data(&mut self, resource_field: ResourceField, length: usize) -> &'a [u8] {
+    ///
+    /// If the field was preceded by a blob reference, the referenced data is
+    /// returned and the blob section is not advanced.
+    ///
+    /// Err occurs if the blob lies outside the data being parsed.
+    fn resolve_blob_data(
+        &mut self,
+        resource_field: ResourceField,
+        length: usize,
+    ) -> Result<&'a [u8], &'static str> {
+        if let Some(offset) = self.pending_blob_reference.take() {
+            let start = self
+                .blob_start_offset
+                .checked_add(offset)
+                .ok_or("blob reference offset out of bounds")?;
+            let end = start
+                .checked_add(length)
+                .ok_or("blob reference offset out of bounds")?;
+
+            return self
+                .data
+                .get(start..end)
+                .ok_or("blob reference offset out of bounds");
+        }
+
         let mut state = self.blob_sections[resource_field as usize]
             .as_mut()
-            .expect("blob state not found");
+            .ok_or("blob state not found")?;
 
-        let blob = &self.data[state.offset..state.offset + length];
+        let end = state
+            .offset
+            .checked_add(length)
+            .ok_or("blob data out of bounds")?;
+        let blob = self
+            .data
+            .get(state.offset..end)
+            .ok_or("blob data out of bounds")?;
 
         let increment = match &state.interior_padding {
             BlobInteriorPadding::None => length,
@@ -75,25 +109,33 @@ impl<'a> ResourceParserIterator<'a> {
 
         state.offset += increment;
 
-        blob
+        Ok(blob)
     }
 
     #[cfg(unix)]
-    fn resolve_path(&mut self, resource_field: ResourceField, length: usize) -> Cow<'a, Path> {
-        let path_str = OsStr::from_bytes(self.resolve_blob_data(resource_field, length));
-        Cow::Borrowed(Path::new(path_str))
+    fn resolve_path(
+        &mut self,
+        resource_field: ResourceField,
+        length: usize,
+    ) -> Result<Cow<'a, Path>, &'static str> {
+        let path_str = OsStr::from_bytes(self.resolve_blob_data(resource_field, length)?);
+        Ok(Cow::Borrowed(Path::new(path_str)))
     }
 
     #[cfg(windows)]
-    fn resolve_path(&mut self, resource_field: ResourceField, length: usize) -> Cow<'a, Path> {
-        let raw = self.resolve_blob_data(resource_field, length);
+    fn resolve_path(
+        &mut self,
+        resource_field: ResourceField,
+        length: usize,
+    ) -> Result<Cow<'a, Path>, &'static str> {
+        let raw = self.resolve_blob_data(resource_field, length)?;
         let raw = unsafe { std::slice::from_raw_parts(raw.as_ptr() as *const u16, raw.len() / 2) };
 
         // There isn't an API that lets us get a OsStr from &[u16]. So we need to use
         // owned types.
         let path_string = OsString::from_wide(raw);
 
-        Cow::Owned(PathBuf::from(path_string))
+        Ok(Cow::Owned(PathBuf::from(path_string)))
     }
 
     fn parse_next(&mut self) -> Result<Option<Resource<'a, u8>>, &'static str> {
@@ -120,6 +162,7 @@ impl<'a> ResourceParserIterator<'a> {
                 }
                 ResourceField::StartOfEntry => {
                     self.read_resources_count += 1;
+                    self.pending_blob_reference = None;
                     current_resource = Resource::default();
                     current_resource_name = None;
                 }
@@ -153,7 +196,7 @@ impl<'a> ResourceParserIterator<'a> {
                         as usize;
 
                     let name = unsafe {
-                        std::str::from_utf8_unchecked(self.resolve_blob_data(field_type, l))
+                        std::str::from_utf8_unchecked(self.resolve_blob_data(field_type, l)?)
                     };
 
                     current_resource_name = Some(name);
@@ -173,7 +216,7 @@ impl<'a> ResourceParserIterator<'a> {
                         as usize;
 
                     current_resource.in_memory_source =
-                        Some(Cow::Borrowed(self.resolve_blob_data(field_type, l)));
+                        Some(Cow::Borrowed(self.resolve_blob_data(field_type, l)?));
                 }
                 ResourceField::InMemoryBytecode => {
                     let l = self
@@ -183,7 +226,7 @@ impl<'a> ResourceParserIterator<'a> {
                         as usize;
 
                     current_resource.in_memory_bytecode =
-                        Some(Cow::Borrowed(self.resolve_blob_data(field_type, l)));
+                        Some(Cow::Borrowed(self.resolve_blob_data(field_type, l)?));
                 }
                 ResourceField::InMemoryBytecodeOpt1 => {
                     let l = self
@@ -193,7 +236,7 @@ impl<'a> ResourceParserIterator<'a> {
                         as usize;
 
                     current_resource.in_memory_bytecode_opt1 =
-                        Some(Cow::Borrowed(self.resolve_blob_data(field_type, l)));
+                        Some(Cow::Borrowed(self.resolve_blob_data(field_type, l)?));
                 }
                 ResourceField::InMemoryBytecodeOpt2 => {
                     let l = self
@@ -203,7 +246,7 @@ impl<'a> ResourceParserIterator<'a> {
                         as usize;
 
                     current_resource.in_memory_bytecode_opt2 =
-                        Some(Cow::Borrowed(self.resolve_blob_data(field_type, l)));
+                        Some(Cow::Borrowed(self.resolve_blob_data(field_type, l)?));
                 }
                 ResourceField::InMemoryExtensionModuleSharedLibrary => {
                     let l = self
@@ -213,7 +256,7 @@ impl<'a> ResourceParserIterator<'a> {
                         as usize;
 
                     current_resource.in_memory_extension_module_shared_library =
-                        Some(Cow::Borrowed(self.resolve_blob_data(field_type, l)));
+                        Some(Cow::Borrowed(self.resolve_blob_data(field_type, l)?));
                 }
 
                 ResourceField::InMemoryResourcesData => {
@@ -234,7 +277,7 @@ impl<'a> ResourceParserIterator<'a> {
 
                         let resource_name = unsafe {
                             std::str::from_utf8_unchecked(
-                                self.resolve_blob_data(field_type, resource_name_length),
+                                self.resolve_blob_data(field_type, resource_name_length)?,
                             )
                         };
 
@@ -244,7 +287,7 @@ impl<'a> ResourceParserIterator<'a> {
                             .map_err(|_| "failed reading resource length")?
                             as usize;
 
-                        let resource_data = self.resolve_blob_data(field_type, resource_length);
+                        let resource_data = self.resolve_blob_data(field_type, resource_length)?;
 
                         resources
                             .insert(Cow::Borrowed(resource_name), Cow::Borrowed(resource_data));
@@ -271,7 +314,7 @@ impl<'a> ResourceParserIterator<'a> {
 
                         let name = unsafe {
                             std::str::from_utf8_unchecked(
-                                self.resolve_blob_data(field_type, name_length),
+                                self.resolve_blob_data(field_type, name_length)?,
                             )
                         };
 
@@ -280,7 +323,7 @@ impl<'a> ResourceParserIterator<'a> {
                                 "failed reading package distribution resource length"
                             })? as usize;
 
-                        let resource_data = self.resolve_blob_data(field_type, resource_length);
+                        let resource_data = self.resolve_blob_data(field_type, resource_length)?;
 
                         resources.insert(Cow::Borrowed(name), Cow::Borrowed(resource_data));
                     }
@@ -296,7 +339,7 @@ impl<'a> ResourceParserIterator<'a> {
                         as usize;
 
                     current_resource.in_memory_shared_library =
-                        Some(Cow::Borrowed(self.resolve_blob_data(field_type, l)));
+                        Some(Cow::Borrowed(self.resolve_blob_data(field_type, l)?));
                 }
 
                 ResourceField::SharedLibraryDependencyNames => {
@@ -316,7 +359,7 @@ impl<'a> ResourceParserIterator<'a> {
 
                         let name = unsafe {
                             std::str::from_utf8_unchecked(
-                                self.resolve_blob_data(field_type, name_length),
+                                self.resolve_blob_data(field_type, name_length)?,
                             )
                         };
 
@@ -333,7 +376,7 @@ impl<'a> ResourceParserIterator<'a> {
                         .map_err(|_| "failed reading Python module relative path length")?
                         as usize;
 
-                    let path = self.resolve_path(field_type, path_length);
+                    let path = self.resolve_path(field_type, path_length)?;
 
                     current_resource.relative_path_module_source = Some(path);
                 }
@@ -344,7 +387,7 @@ impl<'a> ResourceParserIterator<'a> {
                             "failed reading Python module bytecode relative path length"
                         })? as usize;
 
-                    let path = self.resolve_path(field_type, path_length);
+                    let path = self.resolve_path(field_type, path_length)?;
 
                     current_resource.relative_path_module_bytecode = Some(path);
                 }
@@ -354,7 +397,7 @@ impl<'a> ResourceParserIterator<'a> {
                         "failed reading Python module bytecode opt 1 relative path length"
                     })? as usize;
 
-                    let path = self.resolve_path(field_type, path_length);
+                    let path = self.resolve_path(field_type, path_length)?;
 
                     current_resource.relative_path_module_bytecode_opt1 = Some(path);
                 }
@@ -364,7 +407,7 @@ impl<'a> ResourceParserIterator<'a> {
                         "failed reading Python module bytecode opt 2 relative path length"
                     })? as usize;
 
-                    let path = self.resolve_path(field_type, path_length);
+                    let path = self.resolve_path(field_type, path_length)?;
 
                     current_resource.relative_path_module_bytecode_opt2 = Some(path);
                 }
@@ -374,7 +417,7 @@ impl<'a> ResourceParserIterator<'a> {
                         "failed reading Python extension module shared library relative path length"
                     })? as usize;
 
-                    let path = self.resolve_path(field_type, path_length);
+                    let path = self.resolve_path(field_type, path_length)?;
 
                     current_resource.relative_path_extension_module_shared_library = Some(path);
                 }
@@ -396,7 +439,7 @@ impl<'a> ResourceParserIterator<'a> {
 
                         let resource_name = unsafe {
                             std::str::from_utf8_unchecked(
-                                self.resolve_blob_data(field_type, resource_name_length),
+                                self.resolve_blob_data(field_type, resource_name_length)?,
                             )
                         };
 
@@ -406,7 +449,7 @@ impl<'a> ResourceParserIterator<'a> {
                             .map_err(|_| "failed reading resource path length")?
                             as usize;
 
-                        let path = self.resolve_path(field_type, path_length);
+                        let path = self.resolve_path(field_type, path_length)?;
 
                         resources.insert(Cow::Borrowed(resource_name), path);
                     }
@@ -430,7 +473,7 @@ impl<'a> ResourceParserIterator<'a> {
 
                         let name = unsafe {
                             std::str::from_utf8_unchecked(
-                                self.resolve_blob_data(field_type, name_length),
+                                self.resolve_blob_data(field_type, name_length)?,
                             )
                         };
 
@@ -440,7 +483,7 @@ impl<'a> ResourceParserIterator<'a> {
                             .map_err(|_| "failed reading package distribution path length")?
                             as usize;
 
-                        let path = self.resolve_path(field_type, path_length);
+                        let path = self.resolve_path(field_type, path_length)?;
 
                         resources.insert(Cow::Borrowed(name), path);
                     }
@@ -467,6 +510,47 @@ impl<'a> ResourceParserIterator<'a> {
                 ResourceField::IsSharedLibrary => {
                     current_resource.is_shared_library = true;
                 }
+
+                ResourceField::IsFileData => {
+                    current_resource.is_file_data = true;
+                }
+
+                ResourceField::FileExecutable => {
+                    current_resource.file_executable = true;
+                }
+
+                ResourceField::InMemoryFileData => {
+                    let l = self
+                        .reader
+                        .read_u64::<LittleEndian>()
+                        .map_err(|_| "failed reading in-memory file data length")?
+                        as usize;
+
+                    current_resource.in_memory_file_data =
+                        Some(Cow::Borrowed(self.resolve_blob_data(field_type, l)?));
+                }
+
+                ResourceField::RelativeFilesystemFileData => {
+                    let path_length = self
+                        .reader
+                        .read_u32::<LittleEndian>()
+                        .map_err(|_| "failed reading file data relative path length")?
+                        as usize;
+
+                    let path = self.resolve_path(field_type, path_length)?;
+
+                    current_resource.relative_path_file_data = Some(path);
+                }
+
+                ResourceField::BlobReference => {
+                    let offset = self
+                        .reader
+                        .read_u64::<LittleEndian>()
+                        .map_err(|_| "failed reading blob reference offset")?
+                        as usize;
+
+                    self.pending_blob_reference = Some(offset);
+                }
             }
         }
     }
@@ -499,11 +583,33 @@ pub fn load_resources<'a>(data: &'a [u8]) -> Result<ResourceParserIterator<'a>,
 
     if header == HEADER_V2 {
         load_resources_v2(&data[8..])
+    } else if header == HEADER_ZSTD {
+        Err("resources data is compressed and must be decompressed before parsing")
     } else {
         Err("unrecognized file format")
     }
 }
 
+/// Obtain the resources payload from a standalone resources bundle.
+///
+/// The returned slice is suitable for passing to `load_resources()`, after
+/// decompression if it is compressed.
+pub fn bundle_resources_data(data: &[u8]) -> Result<&[u8], &'static str> {
+    if data.len() < BUNDLE_HEADER_V1.len() {
+        return Err("error reading 8 byte bundle header");
+    }
+
+    let header = &data[0..8];
+
+    if header == BUNDLE_HEADER_V1 {
+        Ok(&data[8..])
+    } else if header.starts_with(&BUNDLE_HEADER_V1[0..7]) {
+        Err("unsupported resources bundle version")
+    } else {
+        Err("not a resources bundle")
+    }
+}
+
 fn load_resources_v2<'a>(data: &'a [u8]) -> Result<ResourceParserIterator<'a>, &'static str> {
     let mut reader = Cursor::new(data);
 
@@ -622,6 +728,8 @@ fn load_resources_v2<'a>(data: &'a [u8]) -> Result<ResourceParserIterator<'a>, &
         data,
         reader,
         blob_sections: blob_offsets,
+        blob_start_offset,
+        pending_blob_reference: None,
         claimed_resources_count: resources_count,
         read_resources_count: 0,
     })
@@ -655,6 +763,47 @@ mod tests {
         assert_eq!(res.err(), Some("unrecognized file format"));
     }
 
+    #[test]
+    fn test_compressed_header() {
+        let res = load_resources(b"pyembedz\x28\xb5\x2f\xfd");
+        assert_eq!(
+            res.err(),
+            Some("resources data is compressed and must be decompressed before parsing")
+        );
+    }
+
+    #[test]
+    fn test_bundle_header() {
+        assert_eq!(
+            bundle_resources_data(b"pyox").err(),
+            Some("error reading 8 byte bundle header")
+        );
+        assert_eq!(
+            bundle_resources_data(b"pyembed\x02").err(),
+            Some("not a resources bundle")
+        );
+        assert_eq!(
+            bundle_resources_data(b"pyoxbnd\x02").err(),
+            Some("unsupported resources bundle version")
+        );
+
+        let resource = Resource {
+            name: Cow::from("foo"),
+            ..Resource::default()
+        };
+
+        let mut data = BUNDLE_HEADER_V1.to_vec();
+        write_packed_resources_v2(&[resource], &mut data, None).unwrap();
+
+        let resources = load_resources(bundle_resources_data(&data).unwrap())
+            .unwrap()
+            .collect::<Result<Vec<Resource<u8>>, &'static str>>()
+            .unwrap();
+
+        assert_eq!(resources.len(), 1);
+        assert_eq!(resources[0].name, "foo");
+    }
+
     #[test]
     fn test_no_indices() {
         let data = b"pyembed\x02\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00";
@@ -1322,6 +1471,70 @@ mod tests {
     }
 
     #[allow(clippy::cognitive_complexity)]
+    #[test]
+    fn test_in_memory_file_data() {
+        let resource = Resource {
+            name: Cow::from("foo/bar.txt"),
+            is_file_data: true,
+            file_executable: true,
+            in_memory_file_data: Some(Cow::from(b"data".to_vec())),
+            ..Resource::default()
+        };
+
+        let mut data = Vec::new();
+        write_packed_resources_v2(&[resource], &mut data, None).unwrap();
+        let resources = load_resources(&data)
+            .unwrap()
+            .collect::<Result<Vec<Resource<u8>>, &'static str>>()
+            .unwrap();
+
+        assert_eq!(resources.len(), 1);
+
+        let entry = &resources[0];
+
+        assert_eq!(
+            entry,
+            &Resource {
+                name: Cow::Borrowed("foo/bar.txt"),
+                is_file_data: true,
+                file_executable: true,
+                in_memory_file_data: Some(Cow::Borrowed(&data[data.len() - 4..data.len()])),
+                ..Resource::default()
+            }
+        );
+    }
+
+    #[test]
+    fn test_relative_path_file_data() {
+        let resource = Resource {
+            name: Cow::from("foo/bar.txt"),
+            is_file_data: true,
+            relative_path_file_data: Some(Cow::from(Path::new("foo/bar.txt"))),
+            ..Resource::default()
+        };
+
+        let mut data = Vec::new();
+        write_packed_resources_v2(&[resource], &mut data, None).unwrap();
+        let resources = load_resources(&data)
+            .unwrap()
+            .collect::<Result<Vec<Resource<u8>>, &'static str>>()
+            .unwrap();
+
+        assert_eq!(resources.len(), 1);
+
+        let entry = &resources[0];
+
+        assert_eq!(
+            entry,
+            &Resource {
+                name: Cow::Borrowed("foo/bar.txt"),
+                is_file_data: true,
+                relative_path_file_data: Some(Cow::Borrowed(Path::new("foo/bar.txt"))),
+                ..Resource::default()
+            }
+        );
+    }
+
     #[test]
     fn test_all_fields() {
         let mut in_memory_resources = HashMap::new();
@@ -1378,6 +1591,10 @@ mod tests {
             is_frozen_module: true,
             is_extension_module: true,
             is_shared_library: true,
+            is_file_data: true,
+            file_executable: true,
+            in_memory_file_data: Some(Cow::from(b"filedata".to_vec())),
+            relative_path_file_data: Some(Cow::from(Path::new("file_data_path"))),
         };
 
         let mut data = Vec::new();
@@ -1483,6 +1700,16 @@ mod tests {
         assert!(entry.is_frozen_module);
         assert!(entry.is_extension_module);
         assert!(entry.is_shared_library);
+        assert!(entry.is_file_data);
+        assert!(entry.file_executable);
+        assert_eq!(
+            entry.in_memory_file_data.as_ref().unwrap().as_ref(),
+            b"filedata"
+        );
+        assert_eq!(
+            entry.relative_path_file_data,
+            Some(Cow::Borrowed(Path::new("file_data_path")))
+        );
     }
 
     #[test]
@@ -1511,4 +1738,54 @@ mod tests {
 
         assert_eq!(resources, loaded);
     }
+
+    #[test]
+    fn test_truncated_blob_data() {
+        let resource = Resource {
+            name: Cow::from("foo"),
+            in_memory_source: Some(Cow::from(b"import io".to_vec())),
+            ..Resource::default()
+        };
+
+        let mut data = Vec::new();
+        write_packed_resources_v2(&[resource], &mut data, None).unwrap();
+        data.truncate(data.len() - 4);
+
+        let mut res = load_resources(&data).unwrap();
+        assert_eq!(res.next(), Some(Err("blob data out of bounds")));
+    }
+
+    #[test]
+    fn test_duplicate_blobs() {
+        for padding in &[None, Some(BlobInteriorPadding::Null)] {
+            let resources: Vec<Resource<u8>> = vec![
+                Resource {
+                    name: Cow::from("foo"),
+                    in_memory_source: Some(Cow::from(b"import vendored".to_vec())),
+                    ..Resource::default()
+                },
+                Resource {
+                    name: Cow::from("vendor.foo"),
+                    in_memory_source: Some(Cow::from(b"import vendored".to_vec())),
+                    in_memory_bytecode: Some(Cow::from(b"fake bytecode".to_vec())),
+                    ..Resource::default()
+                },
+                Resource {
+                    name: Cow::from("data.bin"),
+                    is_file_data: true,
+                    in_memory_file_data: Some(Cow::from(b"import vendored".to_vec())),
+                    ..Resource::default()
+                },
+            ];
+
+            let mut data = Vec::new();
+            write_packed_resources_v2(&resources, &mut data, *padding).unwrap();
+            let loaded = load_resources(&data)
+                .unwrap()
+                .collect::<Result<Vec<Resource<u8>>, &'static str>>()
+                .unwrap();
+
+            assert_eq!(resources, loaded);
+        }
+    }
 }
@@ -10,7 +10,12 @@ use {
     },
     anyhow::{anyhow, Context, Result},
     byteorder::{LittleEndian, WriteBytesExt},
-    std::{collections::BTreeMap, convert::TryFrom, io::Write, path::Path},
+    std::{
+        collections::{BTreeMap, HashMap},
+        convert::TryFrom,
+        io::Write,
+        path::Path,
+    },
 };
 
 #[cfg(unix)]
@@ -46,6 +51,114 @@ fn path_to_bytes(p: &Path) -> Vec<u8> {
     }
 }
 
+/// Fields whose blob data is written in the order they appear in.
+///
+/// This is the order blob sections are laid out in the payload.
+const BLOB_FIELDS: &[ResourceField] = &[
+    ResourceField::ModuleName,
+    ResourceField::InMemorySource,
+    ResourceField::InMemoryBytecode,
+    ResourceField::InMemoryBytecodeOpt1,
+    ResourceField::InMemoryBytecodeOpt2,
+    ResourceField::InMemoryExtensionModuleSharedLibrary,
+    ResourceField::InMemoryResourcesData,
+    ResourceField::InMemoryDistributionResource,
+    ResourceField::InMemorySharedLibrary,
+    ResourceField::SharedLibraryDependencyNames,
+    ResourceField::RelativeFilesystemModuleSource,
+    ResourceField::RelativeFilesystemModuleBytecode,
+    ResourceField::RelativeFilesystemModuleBytecodeOpt1,
+    ResourceField::RelativeFilesystemModuleBytecodeOpt2,
+    ResourceField::RelativeFilesystemExtensionModuleSharedLibrary,
+    ResourceField::RelativeFilesystemPackageResources,
+    ResourceField::RelativeFilesystemDistributionResource,
+    ResourceField::InMemoryFileData,
+    ResourceField::RelativeFilesystemFileData,
+];
+
+/// Length of a blob reference in the resources index.
+///
+/// Field type + u64 offset.
+const BLOB_REFERENCE_INDEX_LENGTH: usize = 9;
+
+/// Obtain the data of a field consisting of a single blob that can be shared.
+fn shareable_blob<'b>(resource: &'b Resource<u8>, field: ResourceField) -> Option<&'b [u8]> {
+    let data = match field {
+        ResourceField::InMemorySource => &resource.in_memory_source,
+        ResourceField::InMemoryBytecode => &resource.in_memory_bytecode,
+        ResourceField::InMemoryBytecodeOpt1 => &resource.in_memory_bytecode_opt1,
+        ResourceField::InMemoryBytecodeOpt2 => &resource.in_memory_bytecode_opt2,
+        ResourceField::InMemoryExtensionModuleSharedLibrary => {
+            &resource.in_memory_extension_module_shared_library
+        }
+        ResourceField::InMemorySharedLibrary => &resource.in_memory_shared_library,
+        ResourceField::InMemoryFileData => &resource.in_memory_file_data,
+        _ => return None,
+    };
+
+    data.as_ref().map(|x| x.as_ref())
+}
+
+/// Find blobs whose content is identical to a blob written earlier.
+///
+/// Returns, for each resource, a mapping of fields to the offset of the
+/// identical blob relative to the start of blob data. These fields should
+/// reference the existing blob instead of storing another copy.
+///
+/// Blobs not larger than a reference in the index aren't deduplicated.
+fn find_duplicate_blobs<'a, T: AsRef<Resource<'a, u8>>>(
+    resources: &[T],
+    interior_padding: Option<BlobInteriorPadding>,
+) -> Vec<BTreeMap<ResourceField, u64>> {
+    let padding = match &interior_padding {
+        Some(padding) => *padding,
+        None => BlobInteriorPadding::None,
+    };
+
+    let mut references = vec![BTreeMap::new(); resources.len()];
+    let mut offsets: HashMap<&[u8], u64> = HashMap::new();
+    let mut offset = 0u64;
+
+    for field in BLOB_FIELDS {
+        for (i, resource) in resources.iter().enumerate() {
+            let resource = resource.as_ref();
+
+            if let Some(data) = shareable_blob(resource, *field) {
+                if data.len() > BLOB_REFERENCE_INDEX_LENGTH {
+                    if let Some(existing) = offsets.get(data) {
+                        references[i].insert(*field, *existing);
+                        continue;
+                    }
+
+                    offsets.insert(data, offset);
+                }
+            }
+
+            offset += (resource.field_blob_length(*field)
+                + resource.field_blob_interior_padding_length(*field, padding))
+                as u64;
+        }
+    }
+
+    references
+}
+
+/// Write a reference to existing blob data if the field has one.
+fn write_blob_reference<W: Write>(
+    dest: &mut W,
+    blob_references: &BTreeMap<ResourceField, u64>,
+    field: ResourceField,
+) -> Result<()> {
+    if let Some(offset) = blob_references.get(&field) {
+        dest.write_u8(ResourceField::BlobReference.into())
+            .context("writing blob reference field")?;
+        dest.write_u64::<LittleEndian>(*offset)
+            .context("writing blob reference offset")?;
+    }
+
+    Ok(())
+}
+
 #[derive(Debug)]
 struct BlobSection {
     resource_field: ResourceField,
@@ -129,6 +242,8 @@ where
             || self.relative_path_extension_module_shared_library.is_some()
             || self.relative_path_package_resources.is_some()
             || self.relative_path_distribution_resources.is_some()
+            || self.in_memory_file_data.is_some()
+            || self.relative_path_file_data.is_some()
     }
 
     /// Compute length of index entry for version 1 payload format.
@@ -246,6 +361,22 @@ where
             index += 1;
         }
 
+        if self.is_file_data {
+            index += 1;
+        }
+
+        if self.file_executable {
+            index += 1;
+        }
+
+        if self.in_memory_file_data.is_some() {
+            index += 9;
+        }
+
+        if self.relative_path_file_data.is_some() {
+            index += 5;
+        }
+
         // End of index entry.
         index += 1;
 
@@ -260,6 +391,7 @@ where
             ResourceField::EndOfIndex => 0,
             ResourceField::StartOfEntry => 0,
             ResourceField::EndOfEntry => 0,
+            ResourceField::BlobReference => 0,
             ResourceField::Flavor => 0,
             ResourceField::ModuleName => self.name.as_bytes().len(),
             ResourceField::IsPackage => 0,
@@ -393,6 +525,22 @@ where
             ResourceField::IsFrozenModule => 0,
             ResourceField::IsExtensionModule => 0,
             ResourceField::IsSharedLibrary => 0,
+            ResourceField::IsFileData => 0,
+            ResourceField::FileExecutable => 0,
+            ResourceField::InMemoryFileData => {
+                if let Some(data) = &self.in_memory_file_data {
+                    data.len()
+                } else {
+                    0
+                }
+            }
+            ResourceField::RelativeFilesystemFileData => {
+                if let Some(path) = &self.relative_path_file_data {
+                    path_bytes_length(path)
+                } else {
+                    0
+                }
+            }
         }
     }
 
@@ -406,6 +554,7 @@ where
             ResourceField::EndOfIndex => 0,
             ResourceField::StartOfEntry => 0,
             ResourceField::EndOfEntry => 0,
+            ResourceField::BlobReference => 0,
             ResourceField::Flavor => 0,
             ResourceField::ModuleName => 1,
             ResourceField::IsPackage => 0,
@@ -527,6 +676,22 @@ where
             ResourceField::IsFrozenModule => 0,
             ResourceField::IsExtensionModule => 0,
             ResourceField::IsSharedLibrary => 0,
+            ResourceField::IsFileData => 0,
+            ResourceField::FileExecutable => 0,
+            ResourceField::InMemoryFileData => {
+                if self.in_memory_file_data.is_some() {
+                    1
+                } else {
+                    0
+                }
+            }
+            ResourceField::RelativeFilesystemFileData => {
+                if self.relative_path_file_data.is_some() {
+                    1
+                } else {
+                    0
+                }
+            }
         };
 
         let overhead = match padding {
@@ -538,7 +703,14 @@ where
     }
 
     /// Write the version 1 index entry for a module instance.
-    pub fn write_index_v1<W: Write>(&self, dest: &mut W) -> Result<()> {
+    ///
+    /// `blob_references` maps fields to offsets of existing blob data the
+    /// field should resolve to instead of consuming data from its blob section.
+    pub fn write_index_v1<W: Write>(
+        &self,
+        dest: &mut W,
+        blob_references: &BTreeMap<ResourceField, u64>,
+    ) -> Result<()> {
         let name_len =
             u16::try_from(self.name.as_bytes().len()).context("converting name to u16")?;
 
@@ -569,6 +741,7 @@ where
         }
 
         if let Some(source) = &self.in_memory_source {
+            write_blob_reference(dest, blob_references, ResourceField::InMemorySource)?;
             let l =
                 u32::try_from(source.len()).context("converting in-memory source length to u32")?;
             dest.write_u8(ResourceField::InMemorySource.into())
@@ -578,6 +751,7 @@ where
         }
 
         if let Some(bytecode) = &self.in_memory_bytecode {
+            write_blob_reference(dest, blob_references, ResourceField::InMemoryBytecode)?;
             let l = u32::try_from(bytecode.len())
                 .context("converting in-memory bytecode length to u32")?;
             dest.write_u8(ResourceField::InMemoryBytecode.into())
@@ -587,6 +761,7 @@ where
         }
 
         if let Some(bytecode) = &self.in_memory_bytecode_opt1 {
+            write_blob_reference(dest, blob_references, ResourceField::InMemoryBytecodeOpt1)?;
             let l = u32::try_from(bytecode.len())
                 .context("converting in-memory bytecode opt 1 length to u32")?;
             dest.write_u8(ResourceField::InMemoryBytecodeOpt1.into())
@@ -596,6 +771,7 @@ where
         }
 
         if let Some(bytecode) = &self.in_memory_bytecode_opt2 {
+            write_blob_reference(dest, blob_references, ResourceField::InMemoryBytecodeOpt2)?;
             let l = u32::try_from(bytecode.len())
                 .context("converting in-memory bytecode opt 2 length to u32")?;
             dest.write_u8(ResourceField::InMemoryBytecodeOpt2.into())
@@ -605,6 +781,11 @@ where
         }
 
         if let Some(library) = &self.in_memory_extension_module_shared_library {
+            write_blob_reference(
+                dest,
+                blob_references,
+                ResourceField::InMemoryExtensionModuleSharedLibrary,
+            )?;
             let l = u32::try_from(library.len())
                 .context("converting in-memory library length to u32")?;
             dest.write_u8(ResourceField::InMemoryExtensionModuleSharedLibrary.into())
@@ -650,6 +831,7 @@ where
         }
 
         if let Some(library) = &self.in_memory_shared_library {
+            write_blob_reference(dest, blob_references, ResourceField::InMemorySharedLibrary)?;
             let l = u64::try_from(library.len())
                 .context("converting in-memory shared library length to u64")?;
             dest.write_u8(ResourceField::InMemorySharedLibrary.into())
@@ -784,6 +966,35 @@ where
                 .context("writing is_shared_library field")?;
         }
 
+        if self.is_file_data {
+            dest.write_u8(ResourceField::IsFileData.into())
+                .context("writing is_file_data field")?;
+        }
+
+        if self.file_executable {
+            dest.write_u8(ResourceField::FileExecutable.into())
+                .context("writing file_executable field")?;
+        }
+
+        if let Some(data) = &self.in_memory_file_data {
+            write_blob_reference(dest, blob_references, ResourceField::InMemoryFileData)?;
+            let l = u64::try_from(data.len())
+                .context("converting in-memory file data length to u64")?;
+            dest.write_u8(ResourceField::InMemoryFileData.into())
+                .context("writing in-memory file data field")?;
+            dest.write_u64::<LittleEndian>(l)
+                .context("writing in-memory file data length")?;
+        }
+
+        if let Some(path) = &self.relative_path_file_data {
+            let l = u32::try_from(path_bytes_length(path))
+                .context("converting file data relative path length to u32")?;
+            dest.write_u8(ResourceField::RelativeFilesystemFileData.into())
+                .context("writing relative path file data field")?;
+            dest.write_u32::<LittleEndian>(l)
+                .context("writing relative path file data length")?;
+        }
+
         dest.write_u8(ResourceField::EndOfEntry.into())
             .map_err(|_| anyhow!("error writing end of index entry"))?;
 
@@ -800,6 +1011,10 @@ pub fn write_packed_resources_v2<'a, T: AsRef<Resource<'a, u8>>, W: Write>(
 ) -> Result<()> {
     let mut blob_sections = BTreeMap::new();
 
+    // Identical blobs are only stored once. Subsequent occurrences reference
+    // the existing data.
+    let blob_references = find_duplicate_blobs(modules, interior_padding);
+
     let mut blob_section_count = 0;
     // 1 for end of index field.
     let mut blob_index_length = 1;
@@ -837,83 +1052,17 @@ pub fn write_packed_resources_v2<'a, T: AsRef<Resource<'a, u8>>, W: Write>(
         Ok(())
     };
 
-    for module in modules {
+    for (i, module) in modules.iter().enumerate() {
         let module = module.as_ref();
         module_index_length += module.index_v1_length();
 
-        process_field(&mut blob_sections, module, ResourceField::ModuleName);
-        process_field(&mut blob_sections, module, ResourceField::InMemorySource);
-        process_field(&mut blob_sections, module, ResourceField::InMemoryBytecode);
-        process_field(
-            &mut blob_sections,
-            module,
-            ResourceField::InMemoryBytecodeOpt1,
-        );
-        process_field(
-            &mut blob_sections,
-            module,
-            ResourceField::InMemoryBytecodeOpt2,
-        );
-        process_field(
-            &mut blob_sections,
-            module,
-            ResourceField::InMemoryExtensionModuleSharedLibrary,
-        );
-        process_field(
-            &mut blob_sections,
-            module,
-            ResourceField::InMemoryResourcesData,
-        );
-        process_field(
-            &mut blob_sections,
-            module,
-            ResourceField::InMemoryDistributionResource,
-        );
-        process_field(
-            &mut blob_sections,
-            module,
-            ResourceField::InMemorySharedLibrary,
-        );
-        process_field(
-            &mut blob_sections,
-            module,
-            ResourceField::SharedLibraryDependencyNames,
-        );
-        process_field(
-            &mut blob_sections,
-            module,
-            ResourceField::RelativeFilesystemModuleSource,
-        );
-        process_field(
-            &mut blob_sections,
-            module,
-            ResourceField::RelativeFilesystemModuleBytecode,
-        );
-        process_field(
-            &mut blob_sections,
-            module,
-            ResourceField::RelativeFilesystemModuleBytecodeOpt1,
-        );
-        process_field(
-            &mut blob_sections,
-            module,
-            ResourceField::RelativeFilesystemModuleBytecodeOpt2,
-        );
-        process_field(
-            &mut blob_sections,
-            module,
-            ResourceField::RelativeFilesystemExtensionModuleSharedLibrary,
-        );
-        process_field(
-            &mut blob_sections,
-            module,
-            ResourceField::RelativeFilesystemPackageResources,
-        );
-        process_field(
-            &mut blob_sections,
-            module,
-            ResourceField::RelativeFilesystemDistributionResource,
-        );
+        for field in BLOB_FIELDS {
+            if blob_references[i].contains_key(field) {
+                module_index_length += BLOB_REFERENCE_INDEX_LENGTH;
+            } else {
+                process_field(&mut blob_sections, module, *field);
+            }
+        }
     }
 
     for section in blob_sections.values() {
@@ -935,8 +1084,8 @@ pub fn write_packed_resources_v2<'a, T: AsRef<Resource<'a, u8>>, W: Write>(
     dest.write_u8(ResourceField::EndOfIndex.into())?;
 
     // Write the resources index.
-    for module in modules {
-        module.as_ref().write_index_v1(dest)?;
+    for (module, references) in modules.iter().zip(blob_references.iter()) {
+        module.as_ref().write_index_v1(dest, references)?;
     }
     dest.write_u8(ResourceField::EndOfIndex.into())?;
 
@@ -946,35 +1095,55 @@ pub fn write_packed_resources_v2<'a, T: AsRef<Resource<'a, u8>>, W: Write>(
         add_interior_padding(dest)?;
     }
 
-    for module in modules {
+    for (module, references) in modules.iter().zip(blob_references.iter()) {
+        if references.contains_key(&ResourceField::InMemorySource) {
+            continue;
+        }
+
         if let Some(data) = &module.as_ref().in_memory_source {
             dest.write_all(data)?;
             add_interior_padding(dest)?;
         }
     }
 
-    for module in modules {
+    for (module, references) in modules.iter().zip(blob_references.iter()) {
+        if references.contains_key(&ResourceField::InMemoryBytecode) {
+            continue;
+        }
+
         if let Some(data) = &module.as_ref().in_memory_bytecode {
             dest.write_all(data)?;
             add_interior_padding(dest)?;
         }
     }
 
-    for module in modules {
+    for (module, references) in modules.iter().zip(blob_references.iter()) {
+        if references.contains_key(&ResourceField::InMemoryBytecodeOpt1) {
+            continue;
+        }
+
         if let Some(data) = &module.as_ref().in_memory_bytecode_opt1 {
             dest.write_all(data)?;
             add_interior_padding(dest)?;
         }
     }
 
-    for module in modules {
+    for (module, references) in modules.iter().zip(blob_references.iter()) {
+        if references.contains_key(&ResourceField::InMemoryBytecodeOpt2) {
+            continue;
+        }
+
         if let Some(data) = &module.as_ref().in_memory_bytecode_opt2 {
             dest.write_all(data)?;
             add_interior_padding(dest)?;
         }
     }
 
-    for module in modules {
+    for (module, references) in modules.iter().zip(blob_references.iter()) {
+        if references.contains_key(&ResourceField::InMemoryExtensionModuleSharedLibrary) {
+            continue;
+        }
+
         if let Some(data) = &module.as_ref().in_memory_extension_module_shared_library {
             dest.write_all(data)?;
             add_interior_padding(dest)?;
@@ -1003,7 +1172,11 @@ pub fn write_packed_resources_v2<'a, T: AsRef<Resource<'a, u8>>, W: Write>(
         }
     }
 
-    for module in modules {
+    for (module, references) in modules.iter().zip(blob_references.iter()) {
+        if references.contains_key(&ResourceField::InMemorySharedLibrary) {
+            continue;
+        }
+
         if let Some(data) = &module.as_ref().in_memory_shared_library {
             dest.write_all(data)?;
             add_interior_padding(dest)?;
@@ -1079,6 +1252,24 @@ pub fn write_packed_resources_v2<'a, T: AsRef<Resource<'a, u8>>, W: Write>(
         }
     }
 
+    for (module, references) in modules.iter().zip(blob_references.iter()) {
+        if references.contains_key(&ResourceField::InMemoryFileData) {
+            continue;
+        }
+
+        if let Some(data) = &module.as_ref().in_memory_file_data {
+            dest.write_all(data)?;
+            add_interior_padding(dest)?;
+        }
+    }
+
+    for module in modules {
+        if let Some(path) = &module.as_ref().relative_path_file_data {
+            dest.write_all(&path_to_bytes(path))?;
+            add_interior_padding(dest)?;
+        }
+    }
+
     Ok(())
 }
 
@@ -1153,4 +1344,55 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_write_duplicate_blobs() -> Result<()> {
+        let source = |data: &[u8]| Resource {
+            flavor: ResourceFlavor::Module,
+            name: Cow::Owned("foo".to_string()),
+            in_memory_source: Some(Cow::Owned(data.to_vec())),
+            ..Resource::default()
+        };
+
+        let mut unique = Vec::new();
+        write_packed_resources_v2(
+            &[source(b"first copy of data"), source(b"other copy of data")],
+            &mut unique,
+            None,
+        )?;
+
+        let mut duplicate = Vec::new();
+        write_packed_resources_v2(
+            &[source(b"first copy of data"), source(b"first copy of data")],
+            &mut duplicate,
+            None,
+        )?;
+
+        // The second copy is replaced by a reference in the index.
+        assert_eq!(
+            duplicate.len(),
+            unique.len() - b"first copy of data".len() + BLOB_REFERENCE_INDEX_LENGTH
+        );
+
+        let references = find_duplicate_blobs(
+            &[source(b"first copy of data"), source(b"first copy of data")],
+            None,
+        );
+        assert!(references[0].is_empty());
+        // The first blob follows the 2 resource names.
+        assert_eq!(references[1].get(&ResourceField::InMemorySource), Some(&6));
+
+        // Blobs smaller than a reference are stored multiple times.
+        let mut small = Vec::new();
+        write_packed_resources_v2(&[source(b"small"), source(b"small")], &mut small, None)?;
+        let mut small_unique = Vec::new();
+        write_packed_resources_v2(
+            &[source(b"small"), source(b"other")],
+            &mut small_unique,
+            None,
+        )?;
+        assert_eq!(small.len(), small_unique.len());
+
+        Ok(())
+    }
 }